@@ -0,0 +1,21 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// Generate the C header for the ffi module, which the ffi tests compare with the one shipped in include/
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let config = cbindgen::Config::from_file("cbindgen.toml").expect("cbindgen.toml is valid");
+
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("the ffi module can be translated to C")
+        .write_to_file(std::path::Path::new(&out_dir).join("gcloud_ctx.h"));
+}
@@ -0,0 +1,311 @@
+/*
+ * C ABI for gcloud-ctx, available when the library is built with the `ffi` feature:
+ *
+ *     cargo rustc -p gcloud-ctx --release --features ffi --crate-type cdylib
+ *
+ * Functions which can fail return 0 on success and -1 on failure, or NULL if they return a pointer. After a failure,
+ * gctx_last_error() and gctx_last_error_code() describe what went wrong on the calling thread. Strings returned by the
+ * library must be released with gctx_string_free(), stores with gctx_store_free() and properties with
+ * gctx_properties_free().
+ *
+ * Generated from gcloud-ctx/src/ffi.rs by cbindgen, don't edit it by hand
+ */
+
+#ifndef GCLOUD_CTX_H
+#define GCLOUD_CTX_H
+
+/* A configuration store, only ever used through a pointer */
+typedef struct GctxStore GctxStore;
+
+/* Properties of a configuration, only ever used through a pointer */
+typedef struct GctxProperties GctxProperties;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Open the configuration store at the given location, or the default location if `location` is `NULL`
+ *
+ * Returns `NULL` on failure. The store must be released with [`gctx_store_free`]
+ *
+ * # Safety
+ *
+ * `location` must be `NULL` or a valid NUL terminated string
+ */
+GctxStore *gctx_store_open(const char *location);
+
+/**
+ * Release a store opened with [`gctx_store_open`]. Does nothing if `store` is `NULL`
+ *
+ * # Safety
+ *
+ * `store` must be `NULL` or a store returned by [`gctx_store_open`] which hasn't already been released
+ */
+void gctx_store_free(GctxStore *store);
+
+/**
+ * Get the names of every configuration in natural order, one per line
+ *
+ * Returns `NULL` on failure. The names must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`]
+ */
+char *gctx_store_list(const GctxStore *store);
+
+/**
+ * Get the name of the active configuration
+ *
+ * Returns `NULL` if no configuration is active, in which case [`gctx_last_error_code`] is `NO_ACTIVE_CONFIGURATION`.
+ * The name must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`]
+ */
+char *gctx_store_active(const GctxStore *store);
+
+/**
+ * Activate a configuration by name
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+ */
+int gctx_store_activate(GctxStore *store,
+                        const char *name);
+
+/**
+ * Create a configuration with the given properties, in the same INI format as a configuration file, e.g.
+ * `"[core]\nproject = my-project\n"`. `properties` may be `NULL` to create an empty configuration
+ *
+ * Fails if the configuration already exists, unless `overwrite` is non-zero. Returns `0` on success and `-1` on
+ * failure
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`], `name` must be a valid NUL terminated string and
+ * `properties` must be `NULL` or a valid NUL terminated string
+ */
+int gctx_store_create(GctxStore *store,
+                      const char *name,
+                      const char *properties,
+                      int overwrite);
+
+/**
+ * Describe the properties of a configuration in the same INI format as a configuration file
+ *
+ * Returns `NULL` on failure. The string must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+ */
+char *gctx_store_describe(const GctxStore *store,
+                          const char *name);
+
+/**
+ * Delete a configuration by name, which fails if it's the active configuration
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+ */
+int gctx_store_delete(GctxStore *store,
+                      const char *name);
+
+/**
+ * Get the properties of a configuration, so they can be read without parsing the configuration file
+ *
+ * Returns `NULL` on failure. The properties must be released with [`gctx_properties_free`]
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+ */
+GctxProperties *gctx_store_properties(const GctxStore *store,
+                                      const char *name);
+
+/**
+ * Get a single property of a configuration
+ *
+ * Returns `NULL` if the property isn't set, in which case [`gctx_last_error_code`] is `NULL`, or on failure. The
+ * value must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name`, `section` and `key` must be valid NUL
+ * terminated strings
+ */
+char *gctx_store_get_property(const GctxStore *store,
+                              const char *name,
+                              const char *section,
+                              const char *key);
+
+/**
+ * Set a single property of a configuration, rewriting it atomically
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name`, `section`, `key` and `value` must be valid NUL
+ * terminated strings
+ */
+int gctx_store_set_property(GctxStore *store,
+                            const char *name,
+                            const char *section,
+                            const char *key,
+                            const char *value);
+
+/**
+ * Remove a single property from a configuration, rewriting it atomically. Removing a property which isn't set
+ * succeeds
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `store` must be a store returned by [`gctx_store_open`] and `name`, `section` and `key` must be valid NUL
+ * terminated strings
+ */
+int gctx_store_unset_property(GctxStore *store,
+                              const char *name,
+                              const char *section,
+                              const char *key);
+
+/**
+ * Create an empty set of properties, e.g. to build up the properties of a new configuration
+ *
+ * The properties must be released with [`gctx_properties_free`]
+ */
+GctxProperties *gctx_properties_new(void);
+
+/**
+ * Release properties returned by the library. Does nothing if `properties` is `NULL`
+ *
+ * # Safety
+ *
+ * `properties` must be `NULL` or properties returned by the library which haven't already been released
+ */
+void gctx_properties_free(GctxProperties *properties);
+
+/**
+ * Get a property
+ *
+ * Returns `NULL` if the property isn't set, in which case [`gctx_last_error_code`] is `NULL`, or on failure. The
+ * value must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library and `section` and `key` must be valid NUL terminated
+ * strings
+ */
+char *gctx_properties_get(const GctxProperties *properties,
+                          const char *section,
+                          const char *key);
+
+/**
+ * Set a property, adding the section if needed
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library and `section`, `key` and `value` must be valid NUL
+ * terminated strings
+ */
+int gctx_properties_set(GctxProperties *properties,
+                        const char *section,
+                        const char *key,
+                        const char *value);
+
+/**
+ * Remove a property. Removing a property which isn't set succeeds
+ *
+ * Returns `0` on success and `-1` on failure
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library and `section` and `key` must be valid NUL terminated
+ * strings
+ */
+int gctx_properties_unset(GctxProperties *properties,
+                          const char *section,
+                          const char *key);
+
+/**
+ * Get the number of properties which are set, e.g. to read each one with [`gctx_properties_entry`]
+ *
+ * Returns `-1` on failure
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library
+ */
+int gctx_properties_count(const GctxProperties *properties);
+
+/**
+ * Get the section, key and value of the property at the given index, in the order they appear in the configuration
+ *
+ * Returns `0` on success and `-1` on failure, e.g. if the index is out of range. On success each of `section`, `key`
+ * and `value` which isn't `NULL` is set to a string which must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library and `section`, `key` and `value` must each be `NULL` or
+ * valid for writing a pointer
+ */
+int gctx_properties_entry(const GctxProperties *properties,
+                          size_t index,
+                          char **section,
+                          char **key,
+                          char **value);
+
+/**
+ * Format properties in the same INI format as a configuration file
+ *
+ * Returns `NULL` on failure. The string must be released with [`gctx_string_free`]
+ *
+ * # Safety
+ *
+ * `properties` must be properties returned by the library
+ */
+char *gctx_properties_to_string(const GctxProperties *properties);
+
+/**
+ * Release a string returned by the library. Does nothing if `s` is `NULL`
+ *
+ * # Safety
+ *
+ * `s` must be `NULL` or a string returned by the library which hasn't already been released
+ */
+void gctx_string_free(char *s);
+
+/**
+ * Message describing the last failure on the calling thread, or `NULL` if the last call succeeded
+ *
+ * The message is owned by the library and is only valid until the next call on the same thread, so it must not be
+ * released
+ */
+const char *gctx_last_error(void);
+
+/**
+ * Code of the last failure on the calling thread, e.g. `UNKNOWN_CONFIGURATION`, or `NULL` if the last call succeeded
+ *
+ * The code is owned by the library and is only valid until the next call on the same thread, so it must not be
+ * released
+ */
+const char *gctx_last_error_code(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GCLOUD_CTX_H */
@@ -0,0 +1,325 @@
+"""Python bindings for gcloud-ctx
+
+A thin wrapper over the C ABI of the Rust library, so that scripts manage gcloud configurations with exactly the same
+validated code paths as gctx. Build the shared library with:
+
+    cargo rustc -p gcloud-ctx --release --features ffi --crate-type cdylib
+
+and either put it next to this module or point the GCLOUD_CTX_LIBRARY environment variable at it:
+
+    from gcloud_ctx import ConfigurationStore, Properties
+
+    with ConfigurationStore() as store:
+        properties = Properties()
+        properties.set("core", "project", "my-project")
+
+        store.create("my-config", properties)
+        store.activate("my-config")
+        print(store.describe("my-config").get("core", "project"))
+"""
+
+import ctypes
+import functools
+import os
+import sys
+from pathlib import Path
+
+__all__ = ["ConfigurationStore", "GcloudCtxError", "Properties"]
+
+# Environment variable which overrides where the shared library is loaded from
+LIBRARY_ENV_VAR = "GCLOUD_CTX_LIBRARY"
+
+
+class GcloudCtxError(Exception):
+    """A gcloud-ctx operation failed
+
+    The code is the same as the Rust Error::code, e.g. UNKNOWN_CONFIGURATION, so it can be matched on reliably
+    """
+
+    def __init__(self, code, message):
+        super().__init__(message)
+        self.code = code
+        self.message = message
+
+
+class Properties:
+    """Properties of a configuration, grouped by section then key
+
+    The properties are held by the library, which reads and writes them exactly as gctx does, so comments and
+    formatting in configuration files are never mistaken for properties. Values are validated when they're written to
+    a configuration
+    """
+
+    def __init__(self, sections=None, library=None):
+        self._lib = _load(library)
+        self._properties = self._lib.gctx_properties_new()
+
+        for section, values in (sections or {}).items():
+            for key, value in values.items():
+                self.set(section, key, value)
+
+    @classmethod
+    def _wrap(cls, lib, properties):
+        """Take ownership of properties returned by the library"""
+        if not properties:
+            _raise(lib)
+
+        wrapped = cls.__new__(cls)
+        wrapped._lib = lib
+        wrapped._properties = properties
+        return wrapped
+
+    def __del__(self):
+        if getattr(self, "_properties", None):
+            self._lib.gctx_properties_free(self._properties)
+            self._properties = None
+
+    def get(self, section, key):
+        """Get a property, or None if it isn't set"""
+        value = self._lib.gctx_properties_get(self._properties, section.encode(), key.encode())
+        return _take_optional(self._lib, value)
+
+    def set(self, section, key, value):
+        """Set a property, adding the section if needed"""
+        result = self._lib.gctx_properties_set(self._properties, section.encode(), key.encode(), value.encode())
+        _check(self._lib, result)
+        return self
+
+    def unset(self, section, key):
+        """Remove a property, returning the previous value if there was one"""
+        previous = self.get(section, key)
+        _check(self._lib, self._lib.gctx_properties_unset(self._properties, section.encode(), key.encode()))
+        return previous
+
+    def sections(self):
+        """Copy of every property, grouped by section then key"""
+        count = self._lib.gctx_properties_count(self._properties)
+
+        if count < 0:
+            _raise(self._lib)
+
+        sections = {}
+
+        for index in range(count):
+            section, key, value = ctypes.c_void_p(), ctypes.c_void_p(), ctypes.c_void_p()
+            _check(
+                self._lib,
+                self._lib.gctx_properties_entry(
+                    self._properties, index, ctypes.byref(section), ctypes.byref(key), ctypes.byref(value)
+                ),
+            )
+
+            strings = [_take(self._lib, pointer.value) for pointer in (section, key, value)]
+            sections.setdefault(strings[0], {})[strings[1]] = strings[2]
+
+        return sections
+
+    def __eq__(self, other):
+        return isinstance(other, Properties) and self.sections() == other.sections()
+
+    def __repr__(self):
+        return "Properties({!r})".format(self.sections())
+
+    def __str__(self):
+        """Properties in the same INI format as a configuration file"""
+        return _take(self._lib, self._lib.gctx_properties_to_string(self._properties))
+
+
+class ConfigurationStore:
+    """A gcloud configuration store, opened at the given location or the default location
+
+    Close the store when it's no longer needed, e.g. by using it as a context manager
+    """
+
+    def __init__(self, location=None, library=None):
+        self._lib = _load(library)
+        encoded = None if location is None else os.fsencode(location)
+        self._store = self._lib.gctx_store_open(encoded)
+
+        if not self._store:
+            _raise(self._lib)
+
+    def close(self):
+        """Release the store, after which it can't be used"""
+        if self._store:
+            self._lib.gctx_store_free(self._store)
+            self._store = None
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        # the library may already be unloaded if the interpreter is shutting down
+        if getattr(self, "_store", None):
+            self.close()
+
+    def configurations(self):
+        """Names of every configuration in natural order"""
+        names = _take(self._lib, self._lib.gctx_store_list(self._handle()))
+        return names.split("\n") if names else []
+
+    def active(self):
+        """Name of the active configuration, or None if no configuration is active"""
+        active = self._lib.gctx_store_active(self._handle())
+
+        if not active:
+            if _last_error(self._lib)[0] == "NO_ACTIVE_CONFIGURATION":
+                return None
+            _raise(self._lib)
+
+        return _take(self._lib, active)
+
+    def activate(self, name):
+        """Activate a configuration by name"""
+        _check(self._lib, self._lib.gctx_store_activate(self._handle(), name.encode()))
+
+    def create(self, name, properties=None, overwrite=False):
+        """Create a configuration, failing if it already exists unless overwrite is true"""
+        encoded = None if properties is None else str(properties).encode()
+        _check(self._lib, self._lib.gctx_store_create(self._handle(), name.encode(), encoded, int(overwrite)))
+
+    def describe(self, name):
+        """Properties of a configuration"""
+        return Properties._wrap(self._lib, self._lib.gctx_store_properties(self._handle(), name.encode()))
+
+    def get_property(self, name, section, key):
+        """Get a single property of a configuration, or None if it isn't set"""
+        value = self._lib.gctx_store_get_property(self._handle(), name.encode(), section.encode(), key.encode())
+        return _take_optional(self._lib, value)
+
+    def set_property(self, name, section, key, value):
+        """Set a single property of a configuration"""
+        result = self._lib.gctx_store_set_property(
+            self._handle(), name.encode(), section.encode(), key.encode(), value.encode()
+        )
+        _check(self._lib, result)
+
+    def unset_property(self, name, section, key):
+        """Remove a single property from a configuration"""
+        result = self._lib.gctx_store_unset_property(self._handle(), name.encode(), section.encode(), key.encode())
+        _check(self._lib, result)
+
+    def delete(self, name):
+        """Delete a configuration, which fails if it's the active configuration"""
+        _check(self._lib, self._lib.gctx_store_delete(self._handle(), name.encode()))
+
+    def _handle(self):
+        if not self._store:
+            raise ValueError("the store has been closed")
+        return self._store
+
+
+def _check(lib, result):
+    if result != 0:
+        _raise(lib)
+
+
+def _take(lib, pointer):
+    """Copy a string returned by the library and release it"""
+    if not pointer:
+        _raise(lib)
+
+    try:
+        return ctypes.string_at(pointer).decode()
+    finally:
+        lib.gctx_string_free(pointer)
+
+
+def _take_optional(lib, pointer):
+    """Copy a string returned by the library, or return None if it returned NULL without failing"""
+    if not pointer and _last_error(lib)[0] is None:
+        return None
+
+    return _take(lib, pointer)
+
+
+def _last_error(lib):
+    code = lib.gctx_last_error_code()
+    message = lib.gctx_last_error()
+    return (code.decode() if code else None, message.decode() if message else None)
+
+
+def _raise(lib):
+    code, message = _last_error(lib)
+    raise GcloudCtxError(code or "UNKNOWN", message or "gcloud-ctx failed")
+
+
+def _library_names():
+    if sys.platform == "win32":
+        return ["gcloud_ctx.dll"]
+    if sys.platform == "darwin":
+        return ["libgcloud_ctx.dylib"]
+    return ["libgcloud_ctx.so"]
+
+
+def _find_library():
+    override = os.environ.get(LIBRARY_ENV_VAR)
+    if override:
+        return override
+
+    here = Path(__file__).resolve().parent
+    candidates = [here, here.parent.parent / "target" / "release", here.parent.parent / "target" / "debug"]
+
+    for directory in candidates:
+        for name in _library_names():
+            if (directory / name).exists():
+                return str(directory / name)
+
+    raise GcloudCtxError(
+        "LIBRARY_NOT_FOUND",
+        "Unable to find the gcloud-ctx library. Build it with 'cargo rustc -p gcloud-ctx --release --features ffi "
+        "--crate-type cdylib' or set {}".format(LIBRARY_ENV_VAR),
+    )
+
+
+@functools.lru_cache(maxsize=None)
+def _load(path=None):
+    lib = ctypes.CDLL(path or _find_library())
+
+    # strings returned by the library are kept as raw pointers so they can be released after copying
+    owned = ctypes.c_void_p
+    store = ctypes.c_void_p
+    properties = ctypes.c_void_p
+
+    signatures = {
+        "gctx_store_open": ([ctypes.c_char_p], store),
+        "gctx_store_free": ([store], None),
+        "gctx_store_list": ([store], owned),
+        "gctx_store_active": ([store], owned),
+        "gctx_store_activate": ([store, ctypes.c_char_p], ctypes.c_int),
+        "gctx_store_create": ([store, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_int], ctypes.c_int),
+        "gctx_store_describe": ([store, ctypes.c_char_p], owned),
+        "gctx_store_properties": ([store, ctypes.c_char_p], properties),
+        "gctx_store_get_property": ([store, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p], owned),
+        "gctx_store_set_property": (
+            [store, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p],
+            ctypes.c_int,
+        ),
+        "gctx_store_unset_property": ([store, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p], ctypes.c_int),
+        "gctx_store_delete": ([store, ctypes.c_char_p], ctypes.c_int),
+        "gctx_properties_new": ([], properties),
+        "gctx_properties_free": ([properties], None),
+        "gctx_properties_get": ([properties, ctypes.c_char_p, ctypes.c_char_p], owned),
+        "gctx_properties_set": ([properties, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p], ctypes.c_int),
+        "gctx_properties_unset": ([properties, ctypes.c_char_p, ctypes.c_char_p], ctypes.c_int),
+        "gctx_properties_count": ([properties], ctypes.c_int),
+        "gctx_properties_entry": (
+            [properties, ctypes.c_size_t, ctypes.POINTER(ctypes.c_void_p), ctypes.POINTER(ctypes.c_void_p),
+             ctypes.POINTER(ctypes.c_void_p)],
+            ctypes.c_int,
+        ),
+        "gctx_properties_to_string": ([properties], owned),
+        "gctx_string_free": ([owned], None),
+        "gctx_last_error": ([], ctypes.c_char_p),
+        "gctx_last_error_code": ([], ctypes.c_char_p),
+    }
+
+    for name, (argtypes, restype) in signatures.items():
+        function = getattr(lib, name)
+        function.argtypes = argtypes
+        function.restype = restype
+
+    return lib
@@ -0,0 +1,108 @@
+"""Tests for the Python bindings, which need the shared library to be built first:
+
+    cargo rustc -p gcloud-ctx --features ffi --crate-type cdylib
+    python -m unittest discover -s gcloud-ctx/python
+"""
+
+import os
+import tempfile
+import unittest
+from pathlib import Path
+
+from gcloud_ctx import ConfigurationStore, GcloudCtxError, Properties
+
+
+class StoreTestCase(unittest.TestCase):
+    """Runs each test against a fresh store in a temporary directory, keeping the state directory there too"""
+
+    def setUp(self):
+        tmp = tempfile.TemporaryDirectory()
+        self.addCleanup(tmp.cleanup)
+        self.location = Path(tmp.name) / "gcloud"
+
+        configurations = self.location / "configurations"
+        configurations.mkdir(parents=True)
+        (configurations / "config_foo").write_text("[core]\n; legacy = 1\nproject = my-project\n")
+        (self.location / "active_config").write_text("foo")
+
+        state = os.environ.get("GCTX_STATE_DIR")
+        os.environ["GCTX_STATE_DIR"] = str(Path(tmp.name) / "state")
+        self.addCleanup(_restore_env, "GCTX_STATE_DIR", state)
+
+        self.store = ConfigurationStore(self.location)
+        self.addCleanup(self.store.close)
+
+
+class TestConfigurationStore(StoreTestCase):
+    def test_comments_are_not_properties(self):
+        properties = self.store.describe("foo")
+
+        self.assertEqual(properties.sections(), {"core": {"project": "my-project"}})
+        self.assertIsNone(properties.get("core", "legacy"))
+        self.assertIsNone(self.store.get_property("foo", "core", "legacy"))
+
+    def test_get_set_and_unset_property(self):
+        self.assertEqual(self.store.get_property("foo", "core", "project"), "my-project")
+
+        self.store.set_property("foo", "core", "account", "me@example.com")
+        self.store.unset_property("foo", "core", "project")
+
+        self.assertEqual(self.store.describe("foo").sections(), {"core": {"account": "me@example.com"}})
+
+    def test_create_activate_and_delete(self):
+        self.store.create("bar", Properties({"core": {"project": "other-project"}}))
+        self.store.activate("bar")
+
+        self.assertEqual(self.store.configurations(), ["bar", "foo"])
+        self.assertEqual(self.store.active(), "bar")
+        self.assertEqual(self.store.get_property("bar", "core", "project"), "other-project")
+
+        self.store.delete("foo")
+        self.assertEqual(self.store.configurations(), ["bar"])
+
+    def test_failures_raise_with_the_error_code(self):
+        with self.assertRaises(GcloudCtxError) as raised:
+            self.store.get_property("unknown", "core", "project")
+        self.assertEqual(raised.exception.code, "UNKNOWN_CONFIGURATION")
+
+        with self.assertRaises(GcloudCtxError) as raised:
+            self.store.delete("foo")
+        self.assertEqual(raised.exception.code, "DELETE_ACTIVE_CONFIGURATION")
+
+    def test_closed_store_can_not_be_used(self):
+        self.store.close()
+
+        with self.assertRaises(ValueError):
+            self.store.configurations()
+
+
+class TestProperties(unittest.TestCase):
+    def test_get_set_and_unset(self):
+        properties = Properties().set("core", "project", "my-project")
+
+        self.assertEqual(properties.get("core", "project"), "my-project")
+        self.assertEqual(properties.unset("core", "project"), "my-project")
+        self.assertIsNone(properties.unset("core", "project"))
+        self.assertEqual(properties.sections(), {})
+
+    def test_sections_round_trip(self):
+        sections = {"core": {"project": "my-project", "account": "me@example.com"}, "compute": {"zone": "a-zone"}}
+
+        self.assertEqual(Properties(sections).sections(), sections)
+        self.assertEqual(Properties(sections), Properties(sections))
+
+    def test_str_is_formatted_by_the_library(self):
+        properties = Properties({"core": {"project": "my-project"}})
+
+        self.assertEqual(str(properties), "[core]\nproject=my-project\n")
+
+
+def _restore_env(name, value):
+    if value is None:
+        os.environ.pop(name, None)
+    else:
+        os.environ[name] = value
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,98 @@
+use crate::{Properties, PropertyChange};
+
+#[derive(Debug, Clone)]
+/// Desired state of a single configuration, see [`ConfigurationStore::apply`](crate::ConfigurationStore::apply)
+pub struct ConfigSpec {
+    /// Name of the configuration
+    name: String,
+
+    /// Properties the configuration should have
+    properties: Properties,
+}
+
+impl ConfigSpec {
+    /// Declare that a configuration with the given name should exist with exactly the given properties
+    pub fn new(name: impl Into<String>, properties: Properties) -> Self {
+        Self {
+            name: name.into(),
+            properties,
+        }
+    }
+
+    /// Name of the configuration
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Properties the configuration should have
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Options which control how a desired set of configurations is applied, see
+/// [`ConfigurationStore::apply`](crate::ConfigurationStore::apply)
+pub struct ApplyOptions {
+    /// Whether configurations which aren't declared are deleted
+    pub(crate) prune: bool,
+
+    /// Whether to only report the changes without making them
+    pub(crate) dry_run: bool,
+}
+
+impl ApplyOptions {
+    /// Set whether configurations which aren't declared are deleted, defaults to `false`
+    ///
+    /// The active configuration is never pruned, so applying fails if it isn't declared
+    pub fn prune(&mut self, prune: bool) -> &mut Self {
+        self.prune = prune;
+        self
+    }
+
+    /// Set whether to only report the changes which would be made without making them, defaults to `false`
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Change made to a single configuration when applying a desired set of configurations, see
+/// [`ConfigurationStore::apply`](crate::ConfigurationStore::apply)
+pub enum AppliedChange {
+    /// The configuration didn't exist and was created
+    Created(String),
+
+    /// The configuration existed with different properties and was updated
+    Updated {
+        /// Name of the configuration
+        name: String,
+
+        /// Differences between the previous and declared properties
+        changes: Vec<PropertyChange>,
+    },
+
+    /// The configuration already had the declared properties
+    Unchanged(String),
+
+    /// The configuration wasn't declared and was deleted
+    Pruned(String),
+}
+
+impl AppliedChange {
+    /// Name of the configuration which was changed
+    pub fn name(&self) -> &str {
+        match self {
+            AppliedChange::Created(name)
+            | AppliedChange::Updated { name, .. }
+            | AppliedChange::Unchanged(name)
+            | AppliedChange::Pruned(name) => name,
+        }
+    }
+
+    /// Did applying change the configuration?
+    pub fn is_change(&self) -> bool {
+        !matches!(self, AppliedChange::Unchanged(_))
+    }
+}
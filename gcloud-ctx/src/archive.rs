@@ -0,0 +1,367 @@
+use crate::{Error, Result};
+use std::{
+    io::{self, Read, Write},
+    time::SystemTime,
+};
+
+/// Size of a tar block, which headers and contents are padded to
+const BLOCK_SIZE: usize = 512;
+
+/// Largest file read from an archive, which is far bigger than any configuration but stops a corrupt or malicious
+/// header exhausting memory
+const MAX_ENTRY_SIZE: u64 = 1024 * 1024;
+
+/// Longest path which fits in the name field of a ustar header, longer ones are written in a PAX extended header
+const MAX_PATH_LEN: usize = 100;
+
+/// Type of a regular file entry
+const REGULAR_FILE: u8 = b'0';
+
+/// Type of a PAX extended header, which holds attributes such as a long path for the entry which follows it
+const PAX_HEADER: u8 = b'x';
+
+/// Path of the entry which holds the name of the active configuration
+pub(crate) const ACTIVE_ENTRY: &str = "active_config";
+
+/// Path prefix of the entries which hold configurations, followed by the name of the configuration
+pub(crate) const CONFIGURATION_PREFIX: &str = "configurations/config_";
+
+/// A file in an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    /// Path of the file within the archive
+    pub(crate) path: String,
+
+    /// Contents of the file
+    pub(crate) contents: Vec<u8>,
+}
+
+/// Write the entries as a tar archive in the POSIX ustar format, so that it can be inspected or unpacked with any
+/// tar tool
+///
+/// Paths which are too long for a ustar header are written in a PAX extended header before the entry, as GNU and BSD
+/// tar do
+pub(crate) fn write<W: Write>(entries: &[Entry], mut writer: W) -> Result<()> {
+    let modified = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+
+    for entry in entries {
+        let name = truncate(&entry.path);
+
+        if name.len() < entry.path.len() {
+            let records = pax_record("path", &entry.path);
+            let pax_name = truncate(&format!("PaxHeader/{}", name)).to_owned();
+
+            writer.write_all(&header(&pax_name, records.len(), PAX_HEADER, modified))?;
+            write_contents(&mut writer, &records)?;
+        }
+
+        writer.write_all(&header(name, entry.contents.len(), REGULAR_FILE, modified))?;
+        write_contents(&mut writer, &entry.contents)?;
+    }
+
+    // the end of an archive is marked by two empty blocks
+    writer.write_all(&[0; BLOCK_SIZE * 2])?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Read the regular files from a ustar archive, skipping anything else such as directories
+pub(crate) fn read<R: Read>(mut reader: R) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut header = [0; BLOCK_SIZE];
+
+    // path from a PAX extended header, which applies to the next entry
+    let mut long_path: Option<String> = None;
+
+    loop {
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| invalid("the archive ended unexpectedly"))?;
+
+        if header.iter().all(|&b| b == 0) {
+            return Ok(entries);
+        }
+
+        if &header[257..262] != b"ustar" {
+            return Err(invalid("only ustar archives are supported"));
+        }
+
+        if parse_octal(&header[148..156])? != checksum(&header) {
+            return Err(invalid("an entry has an incorrect checksum"));
+        }
+
+        let size = parse_octal(&header[124..136])?;
+        let padded = size
+            .checked_add(padding(size))
+            .ok_or_else(|| invalid("an entry is too large"))?;
+        let mut block = reader.by_ref().take(padded);
+
+        let kind = header[156];
+
+        // anything other than a regular file or its extended header, e.g. a directory, isn't needed
+        if !matches!(kind, REGULAR_FILE | PAX_HEADER | 0) {
+            long_path = None;
+            let skipped = io::copy(&mut block, &mut io::sink())?;
+
+            if skipped != padded {
+                return Err(invalid("the archive ended unexpectedly"));
+            }
+
+            continue;
+        }
+
+        if size > MAX_ENTRY_SIZE {
+            return Err(invalid("an entry is too large"));
+        }
+
+        // the size is bounded, so it's safe to read the whole entry into memory
+        let mut contents = Vec::new();
+        block.read_to_end(&mut contents)?;
+
+        if contents.len() as u64 != padded {
+            return Err(invalid("the archive ended unexpectedly"));
+        }
+
+        contents.truncate(size as usize);
+
+        if kind == PAX_HEADER {
+            long_path = parse_pax_path(&contents)?;
+            continue;
+        }
+
+        let mut path = parse_str(&header[345..500])?.to_owned();
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(parse_str(&header[..100])?);
+
+        let path = long_path.take().unwrap_or(path);
+
+        // archives made by tar tools often have paths relative to the current directory
+        let path = path.trim_start_matches("./").to_owned();
+
+        entries.push(Entry { path, contents });
+    }
+}
+
+/// Build the ustar header for an entry, whose name must fit in the name field
+fn header(name: &str, size: usize, kind: u8, modified: u64) -> [u8; BLOCK_SIZE] {
+    let mut header = [0; BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(format!("{:011o}\0", modified).as_bytes());
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    let checksum = checksum(&header);
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    header
+}
+
+/// Write the contents of an entry, padded to a whole block
+fn write_contents<W: Write>(mut writer: W, contents: &[u8]) -> Result<()> {
+    writer.write_all(contents)?;
+    writer.write_all(&vec![0; padding(contents.len() as u64) as usize])?;
+    Ok(())
+}
+
+/// Longest prefix of a path which fits in the name field of a ustar header
+fn truncate(path: &str) -> &str {
+    let mut len = path.len().min(MAX_PATH_LEN);
+
+    while !path.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    &path[..len]
+}
+
+/// A PAX extended header record, which starts with its own length in bytes, e.g. `30 path=configurations/config_a\n`
+fn pax_record(key: &str, value: &str) -> Vec<u8> {
+    let rest = format!(" {}={}\n", key, value);
+    let mut len = rest.len() + 1;
+
+    // adding the length can make it a digit longer
+    while len != rest.len() + len.to_string().len() {
+        len = rest.len() + len.to_string().len();
+    }
+
+    format!("{}{}", len, rest).into_bytes()
+}
+
+/// Find the path in the records of a PAX extended header, if it has one
+fn parse_pax_path(mut records: &[u8]) -> Result<Option<String>> {
+    let mut path = None;
+
+    while !records.is_empty() {
+        let record = records
+            .iter()
+            .position(|&b| b == b' ')
+            .and_then(|space| std::str::from_utf8(&records[..space]).ok())
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|&len| len <= records.len())
+            .map(|len| &records[..len])
+            .ok_or_else(|| invalid("an entry has an invalid extended header"))?;
+
+        records = &records[record.len()..];
+
+        let record = std::str::from_utf8(record).map_err(|_| invalid("an entry has an invalid extended header"))?;
+        let (_, attribute) = record.split_once(' ').unwrap_or_default();
+
+        if let Some(value) = attribute.strip_suffix('\n').and_then(|a| a.strip_prefix("path=")) {
+            path = Some(value.to_owned());
+        }
+    }
+
+    Ok(path)
+}
+
+/// Checksum of a header, which is the sum of its bytes with the checksum field itself counted as spaces
+fn checksum(header: &[u8; BLOCK_SIZE]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum()
+}
+
+/// Number of bytes needed to pad contents of the given length to a whole block
+fn padding(len: u64) -> u64 {
+    let block = BLOCK_SIZE as u64;
+    (block - len % block) % block
+}
+
+/// Parse a NUL or space terminated octal number from a header field
+fn parse_octal(field: &[u8]) -> Result<u64> {
+    let digits = parse_str(field)?.trim_matches(' ');
+
+    if digits.is_empty() {
+        return Ok(0);
+    }
+
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("an entry has an invalid header"))
+}
+
+/// Parse a NUL terminated string from a header field
+fn parse_str(field: &[u8]) -> Result<&str> {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    std::str::from_utf8(&field[..len]).map_err(|_| invalid("an entry has an invalid header"))
+}
+
+/// Explain why an archive can't be read or written
+fn invalid(reason: &str) -> Error {
+    Error::InvalidArchive(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_round_trip() {
+        let entries = vec![
+            Entry {
+                path: "configurations/config_foo".to_owned(),
+                contents: b"[core]\nproject=my-project\n".to_vec(),
+            },
+            Entry {
+                path: "active_config".to_owned(),
+                contents: b"foo".to_vec(),
+            },
+            Entry {
+                path: "configurations/config_empty".to_owned(),
+                contents: Vec::new(),
+            },
+        ];
+
+        let mut archive = Vec::new();
+        write(&entries, &mut archive).unwrap();
+
+        assert_eq!(archive.len() % BLOCK_SIZE, 0);
+        assert_eq!(read(archive.as_slice()).unwrap(), entries);
+    }
+
+    #[test]
+    pub fn test_read_rejects_invalid_archives() {
+        let entries = vec![Entry {
+            path: "active_config".to_owned(),
+            contents: b"foo".to_vec(),
+        }];
+        let mut archive = Vec::new();
+        write(&entries, &mut archive).unwrap();
+
+        let mut corrupted = archive.clone();
+        corrupted[0] = b'x';
+        assert!(matches!(read(corrupted.as_slice()), Err(Error::InvalidArchive(_))));
+
+        assert!(matches!(read(&archive[..700]), Err(Error::InvalidArchive(_))));
+        assert!(matches!(read(&b"not an archive"[..]), Err(Error::InvalidArchive(_))));
+    }
+
+    #[test]
+    pub fn test_read_rejects_oversized_entries() {
+        let entries = vec![Entry {
+            path: "configurations/config_foo".to_owned(),
+            contents: Vec::new(),
+        }];
+        let mut archive = Vec::new();
+        write(&entries, &mut archive).unwrap();
+
+        // claim an enormous size without providing the contents, which mustn't be allocated up front
+        archive[124..136].copy_from_slice(b"77777777777\0");
+        archive[148..156].copy_from_slice(b"        ");
+        let header: &[u8; BLOCK_SIZE] = archive[..BLOCK_SIZE].try_into().unwrap();
+        let checksum = format!("{:06o}\0 ", checksum(header));
+        archive[148..156].copy_from_slice(checksum.as_bytes());
+
+        assert!(matches!(
+            read(archive.as_slice()),
+            Err(Error::InvalidArchive(reason)) if reason == "an entry is too large"
+        ));
+    }
+
+    #[test]
+    pub fn test_round_trip_long_paths() {
+        let entries = vec![
+            Entry {
+                path: format!("{}{}", CONFIGURATION_PREFIX, "a".repeat(MAX_PATH_LEN)),
+                contents: b"[core]\nproject=my-project\n".to_vec(),
+            },
+            Entry {
+                path: "active_config".to_owned(),
+                contents: b"foo".to_vec(),
+            },
+        ];
+
+        let mut archive = Vec::new();
+        write(&entries, &mut archive).unwrap();
+
+        // extended header and its records, then the entry and its contents, then the other entry and the end marker
+        assert_eq!(archive.len(), BLOCK_SIZE * 8);
+        assert_eq!(archive[156], PAX_HEADER);
+        assert_eq!(read(archive.as_slice()).unwrap(), entries);
+    }
+
+    #[test]
+    pub fn test_pax_record_includes_its_own_length() {
+        assert_eq!(pax_record("path", "abc"), b"12 path=abc\n");
+
+        // 98 bytes without the length, which makes it 100 with two digits, so it needs three
+        let value = "a".repeat(91);
+        assert_eq!(pax_record("path", &value), format!("101 path={}\n", value).into_bytes());
+    }
+}
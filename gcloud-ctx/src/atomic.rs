@@ -0,0 +1,142 @@
+use crate::{FileContext, FileOperation, Result};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Maximum number of symlinks followed to find the file to write, as most OSes limit it
+const MAX_LINKS: usize = 40;
+
+/// Write a file so that readers, e.g. gcloud, only ever see the old or the new contents and never a partially
+/// written file, even if the process crashes part way through
+///
+/// The contents are written to a uniquely named hidden file alongside the target, so that concurrent writers never
+/// share it and the rename can't cross file systems, and then renamed over the target. The temporary file is removed
+/// if anything fails. Symlinks are followed so that the file they point to is replaced rather than the link itself,
+/// and the permissions of the replaced file are kept
+pub(crate) fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let target = resolve_links(path);
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = target
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(&format!(".{}.", file_name))
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .file_context(FileOperation::Create, dir)?;
+
+    if let Ok(metadata) = fs::metadata(&target) {
+        fs::set_permissions(temp.path(), metadata.permissions()).file_context(FileOperation::Write, temp.path())?;
+    }
+
+    temp.write_all(contents.as_ref())
+        .and_then(|_| temp.as_file().sync_all())
+        .file_context(FileOperation::Write, temp.path())?;
+
+    temp.persist(&target)
+        .map_err(|e| e.error)
+        .file_context(FileOperation::Write, &target)?;
+
+    Ok(())
+}
+
+/// Follow any symlinks to the file they ultimately point to, which may not exist yet
+fn resolve_links(path: &Path) -> PathBuf {
+    let mut path = path.to_path_buf();
+
+    for _ in 0..MAX_LINKS {
+        match fs::read_link(&path) {
+            // relative targets are relative to the directory containing the link
+            Ok(target) => path = path.parent().map_or_else(|| target.clone(), |dir| dir.join(&target)),
+            Err(_) => break,
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_write_replaces_contents_without_leaving_temp_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config_foo");
+        fs::write(&path, "[core]\nproject=old\n").unwrap();
+
+        write(&path, "[core]\nproject=new\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[core]\nproject=new\n");
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    pub fn test_write_removes_temp_file_on_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        // a directory can't be replaced by a file
+        let path = tmp.path().join("config_foo");
+        fs::create_dir(&path).unwrap();
+
+        assert!(write(&path, "contents").is_err());
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    pub fn test_concurrent_writes_never_share_a_temp_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config_foo");
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let path = &path;
+                scope.spawn(move || write(path, format!("[core]\nproject=project-{}\n", i)).unwrap());
+            }
+        });
+
+        // whichever write finished last, the file holds one complete version
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("[core]\nproject=project-") && contents.ends_with("\n"));
+        assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_write_follows_symlinks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("dotfiles").join("config_foo");
+        fs::create_dir(target.parent().unwrap()).unwrap();
+        fs::write(&target, "old").unwrap();
+
+        let link = tmp.path().join("config_foo");
+        std::os::unix::fs::symlink("dotfiles/config_foo", &link).unwrap();
+
+        write(&link, "new").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("config_foo");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        write(&path, "new").unwrap();
+
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{storage::Storage, FileContext, FileOperation, Properties, Result};
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A copy of a configuration file which was saved before it was overwritten or deleted
+pub struct Backup {
+    /// Name of the configuration which was backed up
+    name: String,
+
+    /// Path to the backup file
+    path: PathBuf,
+
+    /// When the backup was made
+    created: SystemTime,
+}
+
+impl Backup {
+    /// Name of the configuration which was backed up
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Path to the backup file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// When the backup was made
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// Account the backed up configuration used, if it had one and the backup can still be read
+    pub(crate) fn account(&self, storage: &Storage) -> Option<String> {
+        let contents = storage.read(&self.path).ok()?;
+        let properties = Properties::from_reader(contents.as_slice()).ok()?;
+        properties.core().account().map(str::to_owned)
+    }
+
+    /// Directory which holds the backups of the named configuration
+    pub(crate) fn directory(state: &Path, name: &str) -> PathBuf {
+        state.join("backups").join(format!("config_{}", name))
+    }
+
+    /// Copy a configuration file into the backup area
+    pub(crate) fn create(storage: &Storage, state: &Path, name: &str, source: &Path) -> Result<Self> {
+        let directory = Self::directory(state, name);
+        storage
+            .create_dir_all(&directory)
+            .file_context(FileOperation::Create, &directory)?;
+
+        let mut millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        // backups are named after their creation time, so nudge it along if two are made in the same millisecond
+        let path = loop {
+            let path = directory.join(millis.to_string());
+
+            if !storage.exists(&path) {
+                break path;
+            }
+
+            millis += 1;
+        };
+
+        storage.copy(source, &path).file_context(FileOperation::Copy, source)?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            path,
+            created: UNIX_EPOCH + Duration::from_millis(millis),
+        })
+    }
+
+    /// List the backups of the named configuration, newest first
+    pub(crate) fn list(storage: &Storage, state: &Path, name: &str) -> Result<Vec<Self>> {
+        let directory = Self::directory(state, name);
+
+        let entries = match storage.read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).file_context(FileOperation::Read, &directory),
+        };
+
+        let mut backups = Vec::new();
+
+        for entry in entries {
+            let entry = entry.file_context(FileOperation::Read, &directory)?;
+
+            let millis = match entry.file_name().to_str().and_then(|n| n.parse::<u64>().ok()) {
+                Some(millis) => millis,
+                None => continue, // not a backup
+            };
+
+            backups.push(Self {
+                name: name.to_owned(),
+                path: entry.path(),
+                created: UNIX_EPOCH + Duration::from_millis(millis),
+            });
+        }
+
+        backups.sort_by_key(|b| Reverse(b.created));
+
+        Ok(backups)
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{storage::Storage, ConfigurationStore, Result, DEFAULT_TRASH_RETENTION};
+use std::{path::PathBuf, time::Duration};
+
+#[derive(Debug, Clone)]
+/// Options for opening a [`ConfigurationStore`], e.g. to open a store or initialise it if it doesn't exist yet
+///
+/// ```rust
+/// # let tmp = tempfile::tempdir().unwrap();
+/// use gcloud_ctx::ConfigurationStore;
+///
+/// let store = ConfigurationStore::builder()
+///     .location(tmp.path().join("gcloud"))
+///     .create_if_missing(true)
+///     .open()?;
+///
+/// assert_eq!(store.active(), Some("default"));
+/// # Ok::<(), gcloud_ctx::Error>(())
+/// ```
+pub struct ConfigurationStoreBuilder {
+    /// Explicit location of the store, otherwise the default location is used
+    location: Option<PathBuf>,
+
+    /// Whether the default location respects the `CLOUDSDK_CONFIG` environment variable
+    follow_env: bool,
+
+    /// Whether to initialise the store if it doesn't exist yet
+    create_if_missing: bool,
+
+    /// Whether to open the store read-only
+    read_only: bool,
+
+    /// Whether a store without any configurations can be opened
+    tolerate_empty: bool,
+
+    /// Whether to create an active `default` configuration in a store without any configurations
+    bootstrap_default: bool,
+
+    /// Whether to defer scanning the configurations directory until the configurations are needed
+    lazy: bool,
+
+    /// Explicit location of the gcloud-ctx state for the store, otherwise the usual state directory is used
+    state_location: Option<PathBuf>,
+
+    /// How long deleted configurations are kept in the trash
+    trash_retention: Duration,
+}
+
+impl Default for ConfigurationStoreBuilder {
+    fn default() -> Self {
+        Self {
+            location: None,
+            follow_env: true,
+            create_if_missing: false,
+            read_only: false,
+            tolerate_empty: false,
+            bootstrap_default: false,
+            lazy: false,
+            state_location: None,
+            trash_retention: DEFAULT_TRASH_RETENTION,
+        }
+    }
+}
+
+impl ConfigurationStoreBuilder {
+    /// Open the store at the given location instead of the default location
+    pub fn location(&mut self, location: impl Into<PathBuf>) -> &mut Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set whether the default location respects the `CLOUDSDK_CONFIG` environment variable, defaults to `true`
+    ///
+    /// Has no effect if an explicit location is given
+    pub fn follow_env(&mut self, follow_env: bool) -> &mut Self {
+        self.follow_env = follow_env;
+        self
+    }
+
+    /// Set whether to initialise the store if it doesn't exist yet, defaults to `false`
+    ///
+    /// The store is initialised as [`ConfigurationStore::initialize`] does. Stores opened read-only are never created
+    pub fn create_if_missing(&mut self, create_if_missing: bool) -> &mut Self {
+        self.create_if_missing = create_if_missing;
+        self
+    }
+
+    /// Set whether to open the store read-only, see [`ConfigurationStore::into_read_only`], defaults to `false`
+    pub fn read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Set whether a store without any configurations can be opened, defaults to `false`
+    ///
+    /// Otherwise opening an empty store returns [`Error::NoConfigurationsFound`](crate::Error::NoConfigurationsFound)
+    pub fn tolerate_empty(&mut self, tolerate_empty: bool) -> &mut Self {
+        self.tolerate_empty = tolerate_empty;
+        self
+    }
+
+    /// Set whether to create an empty `default` configuration and make it active if the store doesn't have any
+    /// configurations, as `gcloud init` would, defaults to `false`
+    ///
+    /// Stores opened read-only are never bootstrapped
+    pub fn bootstrap_default(&mut self, bootstrap_default: bool) -> &mut Self {
+        self.bootstrap_default = bootstrap_default;
+        self
+    }
+
+    /// Set whether to defer scanning the configurations directory until the configurations are needed, see
+    /// [`ConfigurationStore::with_location_lazy`], defaults to `false`
+    ///
+    /// Lazy stores can't tell whether they're empty when they're opened, so they're never bootstrapped
+    pub fn lazy(&mut self, lazy: bool) -> &mut Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Keep the files gcloud-ctx writes about the store in the given directory instead of the shared state directory,
+    /// e.g. so that a throwaway store is entirely contained in one temporary directory
+    pub fn state_location(&mut self, state_location: impl Into<PathBuf>) -> &mut Self {
+        self.state_location = Some(state_location.into());
+        self
+    }
+
+    /// Set how long deleted configurations are kept in the trash, see [`ConfigurationStore::with_trash_retention`],
+    /// defaults to 30 days
+    pub fn trash_retention(&mut self, trash_retention: Duration) -> &mut Self {
+        self.trash_retention = trash_retention;
+        self
+    }
+
+    /// Open the store
+    pub fn open(&self) -> Result<ConfigurationStore> {
+        let location = match &self.location {
+            Some(location) => location.clone(),
+            None if self.follow_env => ConfigurationStore::default_location()?,
+            None => ConfigurationStore::os_default_location()?,
+        };
+
+        let storage = Storage::Disk;
+
+        if self.create_if_missing && !self.read_only && !storage.is_dir(&location.join("configurations")) {
+            ConfigurationStore::initialize_files(&storage, &location)?;
+        }
+
+        let store = if self.lazy {
+            ConfigurationStore::open_lazy(storage, location, self.read_only, self.state_location.clone())?
+        } else {
+            if self.bootstrap_default && !self.read_only {
+                ConfigurationStore::bootstrap(&storage, &location)?;
+            }
+
+            ConfigurationStore::open(
+                storage,
+                location,
+                self.tolerate_empty,
+                self.read_only,
+                self.state_location.clone(),
+            )?
+        };
+
+        Ok(store.with_trash_retention(self.trash_retention))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::fs;
+
+    #[test]
+    pub fn test_create_if_missing_initialises_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().join("gcloud");
+
+        let state = tmp.path().join("state");
+
+        let store = ConfigurationStore::builder()
+            .location(&location)
+            .state_location(&state)
+            .create_if_missing(true)
+            .open()
+            .unwrap();
+
+        assert_eq!(store.active(), Some("default"));
+        assert!(location.join("configurations/config_default").is_file());
+        assert_eq!(store.state_location(), state);
+    }
+
+    #[test]
+    pub fn test_read_only_never_creates_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().join("gcloud");
+
+        let result = ConfigurationStore::builder()
+            .location(&location)
+            .create_if_missing(true)
+            .read_only(true)
+            .open();
+
+        assert!(matches!(result, Err(Error::ConfigurationStoreNotFound(_))));
+        assert!(!location.exists());
+    }
+
+    #[test]
+    pub fn test_bootstrap_default_creates_active_configuration_in_empty_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+
+        let store = ConfigurationStore::builder()
+            .location(tmp.path())
+            .bootstrap_default(true)
+            .open()
+            .unwrap();
+
+        assert_eq!(store.active(), Some("default"));
+        assert_eq!(store.configurations().unwrap().len(), 1);
+    }
+
+    #[test]
+    pub fn test_bootstrap_default_leaves_existing_configurations() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("configurations/config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+
+        let store = ConfigurationStore::builder()
+            .location(tmp.path())
+            .bootstrap_default(true)
+            .open()
+            .unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert!(store.find_by_name("default").unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_tolerate_empty_opens_store_without_configurations() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("active_config"), "").unwrap();
+
+        let mut builder = ConfigurationStore::builder();
+        builder.location(tmp.path());
+
+        assert!(matches!(builder.open(), Err(Error::NoConfigurationsFound(_))));
+
+        let store = builder.tolerate_empty(true).read_only(true).open().unwrap();
+        assert!(store.configurations().unwrap().next().is_none());
+        assert!(store.is_read_only());
+    }
+}
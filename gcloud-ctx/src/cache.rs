@@ -0,0 +1,73 @@
+use crate::{storage::Storage, FileContext, FileOperation, Result, StateDirectory};
+use std::path::{Path, PathBuf};
+
+/// Cache of configuration names, so that shell completions and prompts don't need to scan the store
+///
+/// The cache is rewritten whenever a configuration is added, removed or renamed, and is considered stale if the
+/// configurations directory has been modified since, e.g. by gcloud itself
+#[derive(Debug)]
+pub struct NameCache;
+
+impl NameCache {
+    /// Path to the cache file for the store at the given location
+    pub fn path(location: &Path) -> Result<PathBuf> {
+        Ok(Self::file(&StateDirectory::for_store(location)?))
+    }
+
+    /// Path to the cache file within the state directory of a store
+    pub(crate) fn file(state: &Path) -> PathBuf {
+        state.join("cache").join("names")
+    }
+
+    /// Read the cached names for the store at the given location
+    ///
+    /// Returns `None` if the cache doesn't exist or is stale
+    pub fn read(location: &Path) -> Result<Option<Vec<String>>> {
+        Self::read_in(&Storage::Disk, &StateDirectory::for_store(location)?, location)
+    }
+
+    /// Read the cached names for the store at the given location from the given state directory
+    pub(crate) fn read_in(storage: &Storage, state: &Path, location: &Path) -> Result<Option<Vec<String>>> {
+        let path = Self::file(state);
+
+        let cached = match storage.metadata(&path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).file_context(FileOperation::Read, &path),
+        };
+
+        let configurations = location.join("configurations");
+        let changed = storage
+            .metadata(&configurations)
+            .and_then(|m| m.modified())
+            .file_context(FileOperation::Read, &configurations)?;
+
+        if changed > cached {
+            return Ok(None);
+        }
+
+        let names = storage
+            .read_to_string(&path)
+            .file_context(FileOperation::Read, &path)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        Ok(Some(names))
+    }
+
+    /// Write the names to the cache in the state directory of a store
+    pub(crate) fn write<'a>(storage: &Storage, state: &Path, names: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        let path = Self::file(state);
+
+        if let Some(parent) = path.parent() {
+            storage
+                .create_dir_all(parent)
+                .file_context(FileOperation::Create, parent)?;
+        }
+
+        let contents: String = names.into_iter().map(|name| format!("{}\n", name)).collect();
+        storage.write(&path, contents)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{InvalidEntry, InvalidEntryReason, Warning};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// How serious a finding is, see [`Finding::severity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// gcloud will still work, but probably not as intended
+    Warning,
+
+    /// gcloud or gcloud-ctx will fail, or silently ignore part of the store
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Problem found by checking the health of a store, see
+/// [`ConfigurationStore::check`](crate::ConfigurationStore::check)
+///
+/// New variants may be added in minor releases as more checks are added
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Finding {
+    /// `active_config` names a configuration which doesn't exist
+    DanglingActive(String),
+
+    /// A configuration file can't be parsed
+    Unparsable {
+        /// Name of the configuration
+        name: String,
+
+        /// Description of the problem
+        reason: String,
+    },
+
+    /// A file in the configurations directory doesn't have a valid configuration name, so it's ignored
+    InvalidName(PathBuf),
+
+    /// An entry in the configurations directory can't be a configuration, e.g. a directory
+    SkippedEntry(InvalidEntry),
+
+    /// A file or directory can't be read, e.g. because permission was denied
+    Unreadable {
+        /// Path to the file or directory
+        path: PathBuf,
+
+        /// Description of the problem
+        reason: String,
+    },
+
+    /// A file or directory is read-only, so gcloud and gcloud-ctx can't change it
+    ReadOnly(PathBuf),
+
+    /// The properties of a configuration are likely to be a mistake, e.g. the zone isn't in the region
+    Property {
+        /// Name of the configuration
+        name: String,
+
+        /// The problem with the properties
+        warning: Warning,
+    },
+}
+
+impl Finding {
+    /// How serious the finding is
+    pub fn severity(&self) -> Severity {
+        match self {
+            Finding::DanglingActive(_)
+            | Finding::Unparsable { .. }
+            | Finding::Unreadable { .. }
+            | Finding::ReadOnly(_) => Severity::Error,
+            Finding::SkippedEntry(entry) if entry.reason() == InvalidEntryReason::PermissionDenied => Severity::Error,
+            Finding::InvalidName(_) | Finding::SkippedEntry(_) | Finding::Property { .. } => Severity::Warning,
+        }
+    }
+
+    /// Path to the file the finding is about, if it's about a single file
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Finding::InvalidName(path) | Finding::Unreadable { path, .. } | Finding::ReadOnly(path) => Some(path),
+            Finding::SkippedEntry(entry) => Some(entry.path()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::DanglingActive(name) => write!(f, "The active configuration '{}' doesn't exist", name),
+            Finding::Unparsable { name, reason } => write!(f, "Configuration '{}' can't be parsed: {}", name, reason),
+            Finding::InvalidName(path) => write!(
+                f,
+                "'{}' isn't a valid configuration name, so it's ignored",
+                path.display()
+            ),
+            Finding::SkippedEntry(entry) => {
+                write!(f, "Skipped '{}' because {}", entry.path().display(), entry.reason())
+            }
+            Finding::Unreadable { path, reason } => write!(f, "Unable to read '{}': {}", path.display(), reason),
+            Finding::ReadOnly(path) => write!(f, "'{}' is read-only", path.display()),
+            Finding::Property { name, warning } => write!(f, "Configuration '{}': {}", name, warning),
+        }
+    }
+}
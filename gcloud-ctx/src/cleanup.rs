@@ -0,0 +1,131 @@
+use crate::{
+    storage::{Storage, StorageEntry},
+    FileContext, FileOperation, Result,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Kind of clutter which can be cleaned up, see [`ConfigurationStore::find_clutter`](crate::ConfigurationStore::find_clutter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClutterKind {
+    /// Backups other than the most recent one of each configuration, and every backup of configurations which were
+    /// deleted longer ago than the trash retention period
+    Backups,
+
+    /// gcloud-ctx metadata for configurations which no longer exist, e.g. because they were deleted with gcloud
+    OrphanedMetadata,
+
+    /// gcloud log files which are older than the requested age
+    StaleLogs,
+
+    /// Cached credentials in `legacy_credentials` for accounts which were only used by deleted configurations
+    UnusedCredentials,
+}
+
+impl fmt::Display for ClutterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ClutterKind::Backups => "old backups",
+            ClutterKind::OrphanedMetadata => "metadata for deleted configurations",
+            ClutterKind::StaleLogs => "old gcloud logs",
+            ClutterKind::UnusedCredentials => "credentials only used by deleted configurations",
+        };
+
+        f.write_str(description)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Files of one kind which can be removed to tidy up a configuration store
+pub struct Clutter {
+    /// Kind of clutter
+    kind: ClutterKind,
+
+    /// Files or directories to remove
+    paths: Vec<PathBuf>,
+
+    /// Size in bytes of each file or directory, in the same order as the paths
+    sizes: Vec<u64>,
+}
+
+impl Clutter {
+    /// Collect the given files or directories, returning `None` if there aren't any
+    pub(crate) fn new(storage: &Storage, kind: ClutterKind, mut paths: Vec<PathBuf>) -> Option<Self> {
+        if paths.is_empty() {
+            return None;
+        }
+
+        paths.sort();
+
+        let sizes = paths.iter().map(|path| storage.size(path)).collect();
+
+        Some(Self { kind, paths, sizes })
+    }
+
+    /// Kind of clutter
+    pub fn kind(&self) -> ClutterKind {
+        self.kind
+    }
+
+    /// Files or directories which would be removed
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Total size of the files in bytes
+    pub fn size(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+
+    /// Split into one piece of clutter per file or directory, e.g. to confirm removing each one separately
+    pub fn split(&self) -> Vec<Clutter> {
+        self.paths
+            .iter()
+            .zip(&self.sizes)
+            .map(|(path, size)| Self {
+                kind: self.kind,
+                paths: vec![path.clone()],
+                sizes: vec![*size],
+            })
+            .collect()
+    }
+
+    /// Remove the files, along with any directories which are left empty
+    pub(crate) fn remove(&self, storage: &Storage) -> Result<()> {
+        for path in &self.paths {
+            let result = if storage.is_dir(path) {
+                storage.remove_dir_all(path)
+            } else {
+                storage.remove_file(path)
+            };
+
+            match result {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(e).file_context(FileOperation::Delete, path);
+                }
+                _ => {}
+            }
+
+            // only succeeds if the directory is now empty, e.g. a day of gcloud logs
+            if let Some(parent) = path.parent() {
+                let _ = storage.remove_dir(parent);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// List the entries of a directory, treating a missing directory as empty
+pub(crate) fn entries(storage: &Storage, directory: &Path) -> Result<Vec<StorageEntry>> {
+    match storage.read_dir(directory) {
+        Ok(entries) => entries
+            .into_iter()
+            .collect::<std::io::Result<_>>()
+            .file_context(FileOperation::Read, directory),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).file_context(FileOperation::Read, directory),
+    }
+}
@@ -1,12 +1,40 @@
-use crate::{properties::Properties, Error, Result};
-use fs::File;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::{cmp::Ordering, collections::HashMap, fs, io::BufReader, path::PathBuf};
-
-lazy_static! {
-    static ref NAME_REGEX: Regex = Regex::new("^[a-z][-a-z0-9]*$").unwrap();
-}
+use crate::{
+    archive, cleanup,
+    ini::Document,
+    paths, patterns,
+    properties::Properties,
+    resolve::{edit_distance, max_typos},
+    storage::{EntryKind, Storage, StorageEntry, StoreLock},
+    AppliedChange, ApplyOptions, Backup, Clutter, ClutterKind, ConfigSpec, ConfigurationStoreBuilder, ConflictResolver,
+    DuplicateKeys, EffectiveProperties, Error, FileContext, FileOperation, Filter, Finding, History, HistoryEntry,
+    InvalidEntry, InvalidEntryReason, Journal, KeyOrder, LineEnding, MergeStrategy, Metadata, NameCache, NameMatch,
+    ReadOptions, Resolution, Result, StateDirectory, StoreEvent, Subscribers, Template, UndoableOperation, Warning,
+    WriteOptions,
+};
+use std::{
+    cmp::Ordering,
+    collections::{
+        btree_map,
+        hash_map::{DefaultHasher, RandomState},
+        BTreeMap,
+    },
+    fmt,
+    hash::{BuildHasher, Hasher},
+    io::{Read, Write},
+    iter::FusedIterator,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
+/// Environment variable which overrides the location of the configuration store
+const STORE_ENV_VAR: &str = "CLOUDSDK_CONFIG";
+
+/// How long deleted configurations are kept in the trash by default, see [`ConfigurationStore::list_trashed`]
+pub const DEFAULT_TRASH_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Name gcloud writes to `active_config` when no configuration is active
+const NO_ACTIVE_CONFIGURATION: &str = "NONE";
 
 #[derive(Debug, Clone)]
 /// Represents a gcloud named configuration
@@ -16,6 +44,15 @@ pub struct Configuration {
 
     /// Path to the configuration file
     path: PathBuf,
+
+    /// Properties of the configuration, loaded on first use and reset when the store modifies the configuration
+    properties: OnceLock<Properties>,
+
+    /// When the configuration was last activated, from the activation history of the store
+    last_activated: Option<SystemTime>,
+
+    /// Where the configuration file is kept, which is the storage of the store it came from
+    storage: Storage,
 }
 
 impl Configuration {
@@ -24,18 +61,89 @@ impl Configuration {
         &self.name
     }
 
+    /// Path to the configuration file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Properties of the configuration, which are loaded the first time they're needed and then cached
+    ///
+    /// The cache is reset when the configuration is modified through the store which owns it. Changes made by anything
+    /// else, e.g. gcloud itself, aren't seen until the store is reopened, see [`ConfigurationStore::describe`]
+    pub fn properties(&self) -> Result<&Properties> {
+        if let Some(properties) = self.properties.get() {
+            return Ok(properties);
+        }
+
+        let contents = self
+            .storage
+            .read(&self.path)
+            .file_context(FileOperation::Read, &self.path)?;
+        let properties = Properties::from_reader(contents.as_slice())?;
+
+        Ok(self.properties.get_or_init(|| properties))
+    }
+
+    /// When the configuration file was last modified, e.g. to find stale configurations
+    pub fn modified(&self) -> Result<SystemTime> {
+        self.storage
+            .metadata(&self.path)
+            .and_then(|m| m.modified())
+            .file_context(FileOperation::Read, &self.path)
+    }
+
+    /// When the configuration was last activated through gcloud-ctx, or `None` if it never has been
+    ///
+    /// This comes from the activation history, so activations by gcloud itself aren't included and only the most
+    /// recent activations are remembered, see [`ConfigurationStore::history`]
+    pub fn last_activated(&self) -> Option<SystemTime> {
+        self.last_activated
+    }
+
+    /// When the configuration file was created
+    ///
+    /// Not every platform and file system records this, in which case an [`Error::File`] is returned
+    pub fn created(&self) -> Result<SystemTime> {
+        self.storage
+            .metadata(&self.path)
+            .and_then(|m| m.created())
+            .file_context(FileOperation::Read, &self.path)
+    }
+
+    /// Fingerprint of the configuration contents, e.g. to find configurations which differ between stores
+    ///
+    /// The contents are normalised first, so formatting and the order of properties don't affect it
+    pub fn fingerprint(&self) -> Result<Fingerprint> {
+        let contents = self
+            .storage
+            .read_to_string(&self.path)
+            .file_context(FileOperation::Read, &self.path)?;
+        let mut document = Document::parse(&contents, &ReadOptions::default(), &mut Vec::new())?;
+        document.normalize();
+        document.strip_comments();
+
+        let mut normalized = Vec::new();
+        document.write(&mut normalized, WriteOptions::default().line_ending(LineEnding::Lf))?;
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&normalized);
+
+        Ok(Fingerprint(hasher.finish()))
+    }
+
     /// Is the given name a valid configuration name?
     ///
     /// Names must start with a lowercase ASCII character
     /// then zero or more ASCII alphanumerics and hyphens
     pub fn is_valid_name(name: &str) -> bool {
-        NAME_REGEX.is_match(name)
+        patterns::name(name)
     }
 }
 
 impl Ord for Configuration {
+    /// Configurations are in natural order, e.g. `env-2` comes before `env-10`
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.name.cmp(&other.name)
+        natural_cmp(&self.name, &other.name)
     }
 }
 
@@ -53,289 +161,4246 @@ impl PartialEq for Configuration {
 
 impl Eq for Configuration {}
 
-/// Action to perform when a naming conflict occurs
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum ConflictAction {
-    /// Abort the operation
-    Abort,
+/// Order in which configurations are listed
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Runs of digits are compared numerically, e.g. `env-2` comes before `env-10`
+    #[default]
+    Natural,
 
-    /// Overwrite the existing configuration
-    Overwrite,
+    /// Plain character order, e.g. `env-10` comes before `env-2`
+    Lexical,
 }
 
-impl From<bool> for ConflictAction {
-    fn from(value: bool) -> Self {
-        if value {
-            ConflictAction::Overwrite
+/// Compare names so that runs of digits are ordered numerically
+pub(crate) fn natural_cmp(left: &str, right: &str) -> Ordering {
+    let (mut a, mut b) = (left.as_bytes(), right.as_bytes());
+
+    while let (Some(x), Some(y)) = (a.first(), b.first()) {
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+
+            let a_digits = trim_leading_zeros(&a[..a_len]);
+            let b_digits = trim_leading_zeros(&b[..b_len]);
+
+            let ordering = a_digits.len().cmp(&b_digits.len()).then_with(|| a_digits.cmp(b_digits));
+
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+
+            a = &a[a_len..];
+            b = &b[b_len..];
         } else {
-            ConflictAction::Abort
+            if x != y {
+                return x.cmp(y);
+            }
+
+            a = &a[1..];
+            b = &b[1..];
         }
     }
+
+    // one is a prefix of the other, or they only differ by leading zeros
+    a.len().cmp(&b.len()).then_with(|| left.cmp(right))
 }
 
-#[derive(Debug)]
-/// Represents the store of gcloud configurations
-pub struct ConfigurationStore {
-    /// Location of the configuration store on disk
-    location: PathBuf,
+/// Strip leading zeros from a run of digits
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|c| **c == b'0').count();
+    &digits[zeros..]
+}
 
-    /// Path to the configurations sub-folder
-    configurations_path: PathBuf,
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Result of activating a configuration
+pub struct Activation {
+    /// Configuration which was active before, if there was one
+    previous: Option<String>,
 
-    /// Available configurations
-    configurations: HashMap<String, Configuration>,
+    /// Configuration which is now active
+    current: String,
+}
+
+impl Activation {
+    /// Configuration which was active before, if there was one
+    pub fn previous(&self) -> Option<&str> {
+        self.previous.as_deref()
+    }
+
+    /// Configuration which is now active
+    pub fn current(&self) -> &str {
+        &self.current
+    }
 
-    /// Name of the active configuration
-    active: String,
+    /// Whether the active configuration changed, i.e. it wasn't already active
+    pub fn changed(&self) -> bool {
+        self.previous.as_deref() != Some(self.current.as_str())
+    }
 }
 
-impl ConfigurationStore {
-    /// Opens the configuration store using the OS-specific defaults
-    ///
-    /// If the `CLOUDSDK_CONFIG` environment variable is set then this will be used, otherwise an
-    /// OS-specific default location will be used, as defined by the [dirs] crate, e.g.:
-    ///
-    /// - Windows: `%APPDATA%\gcloud`
-    /// - Linux: `~/.config/gcloud`
-    /// - Mac: `~/.config/gcloud` - note that this does not follow the Apple Developer Guidelines
-    ///
-    /// [dirs]: https://crates.io/crates/dirs
-    pub fn with_default_location() -> Result<Self> {
-        let gcloud_path: PathBuf = if let Ok(value) = std::env::var("CLOUDSDK_CONFIG") {
-            value.into()
-        } else {
-            let gcloud_path = if cfg!(target_os = "macos") {
-                dirs::home_dir()
-                    .ok_or(Error::ConfigurationDirectoryNotFound)?
-                    .join(".config")
-            } else {
-                dirs::config_dir().ok_or(Error::ConfigurationDirectoryNotFound)?
-            };
+/// What to do when deleting the active configuration, see [`ConfigurationStore::delete_with`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DeleteBehaviour {
+    /// Fail with [`Error::DeleteActiveConfiguration`]
+    #[default]
+    Refuse,
 
-            gcloud_path.join("gcloud")
-        };
+    /// Activate the given configuration first, then delete
+    ActivateFallback(String),
+}
 
-        Self::with_location(gcloud_path)
+#[derive(Debug, Clone)]
+/// A configuration which only exists in memory and is never written to the store, see
+/// [`ConfigurationStore::virtual_config`]
+pub struct VirtualConfiguration {
+    /// Name of the configuration
+    name: String,
+
+    /// Properties of the configuration
+    properties: Properties,
+}
+
+impl VirtualConfiguration {
+    /// Name of the configuration
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    /// Opens a configuration store at the given path
-    pub fn with_location(gcloud_path: PathBuf) -> Result<Self> {
-        if !gcloud_path.is_dir() {
-            return Err(Error::ConfigurationStoreNotFound(gcloud_path));
+    /// Properties of the configuration
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Environment variables which apply the configuration to a gcloud command, see [`Properties::env_vars`]
+    ///
+    /// Any properties which aren't set are taken from the active configuration by gcloud as usual
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.properties.env_vars()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A randomly generated configuration name, e.g. for short-lived configurations created by scripts
+pub struct GeneratedName(String);
+
+impl GeneratedName {
+    /// Length of the random suffix appended to the prefix
+    const SUFFIX_LENGTH: usize = 6;
+
+    /// Generate a name made from the given prefix and a short random suffix
+    pub fn new(prefix: &str) -> Result<Self> {
+        if !Configuration::is_valid_name(prefix) {
+            return Err(Error::InvalidName(prefix.to_owned()));
         }
 
-        let configurations_path = gcloud_path.join("configurations");
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
 
-        if !configurations_path.is_dir() {
-            return Err(Error::ConfigurationStoreNotFound(configurations_path));
+        // RandomState is seeded randomly per instance, which is plenty for avoiding collisions between names
+        let mut seed = RandomState::new().build_hasher().finish();
+        let mut suffix = String::with_capacity(Self::SUFFIX_LENGTH);
+
+        for _ in 0..Self::SUFFIX_LENGTH {
+            suffix.push(ALPHABET[(seed % ALPHABET.len() as u64) as usize] as char);
+            seed /= ALPHABET.len() as u64;
         }
 
-        let mut configurations: HashMap<String, Configuration> = HashMap::new();
+        Ok(GeneratedName(format!("{}-{}", prefix, suffix)))
+    }
 
-        for file in fs::read_dir(&configurations_path)? {
-            if file.is_err() {
-                // ignore files we're unable to read - e.g. permissions errors
-                continue;
-            }
+    /// The generated name
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
-            let file = file.unwrap();
-            let name = file.file_name();
-            let name = match name.to_str() {
-                Some(name) => name,
-                None => continue, // ignore files that aren't valid utf8
+impl fmt::Display for GeneratedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<GeneratedName> for String {
+    fn from(value: GeneratedName) -> Self {
+        value.0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// Fingerprint of the contents of a configuration, see [`Configuration::fingerprint`]
+///
+/// Fingerprints are only comparable when they were calculated by the same version of gcloud-ctx
+pub struct Fingerprint(u64);
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Name of a configuration, ordered naturally so that a [`ConfigurationMap`] is always in listing order
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NaturalName(String);
+
+impl Ord for NaturalName {
+    fn cmp(&self, other: &Self) -> Ordering {
+        natural_cmp(&self.0, &other.0)
+    }
+}
+
+impl PartialOrd for NaturalName {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Default)]
+/// Configurations keyed by name, kept in natural order so they can be listed without sorting
+pub(crate) struct ConfigurationMap(BTreeMap<NaturalName, Configuration>);
+
+impl ConfigurationMap {
+    /// Key for looking up a configuration by name
+    fn key(name: &str) -> NaturalName {
+        NaturalName(name.to_owned())
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&Configuration> {
+        self.0.get(&Self::key(name))
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Configuration> {
+        self.0.get_mut(&Self::key(name))
+    }
+
+    pub(crate) fn contains_key(&self, name: &str) -> bool {
+        self.0.contains_key(&Self::key(name))
+    }
+
+    pub(crate) fn insert(&mut self, name: String, configuration: Configuration) -> Option<Configuration> {
+        self.0.insert(NaturalName(name), configuration)
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) -> Option<Configuration> {
+        self.0.remove(&Self::key(name))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Configurations in natural order
+    pub(crate) fn values(&self) -> Configurations<'_> {
+        Configurations(self.0.values())
+    }
+
+    /// Names of the configurations in natural order
+    #[cfg(feature = "watch")]
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|name| name.0.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Iterator over the configurations in a store in natural order, see [`ConfigurationStore::configurations`]
+pub struct Configurations<'a>(btree_map::Values<'a, NaturalName, Configuration>);
+
+impl<'a> Iterator for Configurations<'a> {
+    type Item = &'a Configuration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Configurations<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for Configurations<'_> {}
+
+impl FusedIterator for Configurations<'_> {}
+
+#[derive(Debug, Default)]
+/// Contents of the configurations directory
+pub(crate) struct Scan {
+    /// Available configurations
+    configurations: ConfigurationMap,
+
+    /// Entries in the configurations directory which were skipped
+    invalid_entries: Vec<InvalidEntry>,
+}
+
+impl Scan {
+    /// Scan the configurations directory
+    pub(crate) fn read(storage: &Storage, configurations_path: &Path) -> Result<Self> {
+        let mut configurations = ConfigurationMap::default();
+        let mut invalid_entries: Vec<InvalidEntry> = Vec::new();
+
+        debug!("scanning {}", configurations_path.display());
+
+        for file in storage
+            .read_dir(configurations_path)
+            .file_context(FileOperation::Read, configurations_path)?
+        {
+            let file = match file {
+                Ok(file) => file,
+                Err(e) => {
+                    // the entry has no path if it can't be read, so report the directory instead
+                    debug!("skipped an entry in {} because {}", configurations_path.display(), e);
+                    invalid_entries.push(InvalidEntry {
+                        path: configurations_path.to_path_buf(),
+                        reason: InvalidEntryReason::unreadable(&e),
+                    });
+                    continue;
+                }
             };
-            let name = name.trim_start_matches("config_");
 
-            if !Configuration::is_valid_name(name) {
+            if let Some(reason) = ConfigurationStore::invalid_entry_reason(&file) {
+                debug!("skipped {} because {}", file.path().display(), reason);
+                invalid_entries.push(InvalidEntry {
+                    path: file.path(),
+                    reason,
+                });
                 continue;
             }
 
+            let file_name = file.file_name();
+            let name = match file_name.to_str().map(|name| name.trim_start_matches("config_")) {
+                Some(name) if Configuration::is_valid_name(name) => name,
+                name => {
+                    let reason = match name {
+                        Some(_) => InvalidEntryReason::InvalidName,
+                        None => InvalidEntryReason::NotUtf8,
+                    };
+
+                    debug!("skipped {} because {}", file.path().display(), reason);
+                    invalid_entries.push(InvalidEntry {
+                        path: file.path(),
+                        reason,
+                    });
+                    continue;
+                }
+            };
+
             configurations.insert(
                 name.to_owned(),
                 Configuration {
                     name: name.to_owned(),
                     path: file.path(),
+                    properties: OnceLock::new(),
+                    last_activated: None,
+                    storage: storage.clone(),
                 },
             );
         }
 
-        if configurations.is_empty() {
-            return Err(Error::NoConfigurationsFound(configurations_path));
-        }
-
-        let active = gcloud_path.join("active_config");
-        let active = fs::read_to_string(active)?;
-
-        Ok(ConfigurationStore {
-            location: gcloud_path,
-            configurations_path,
+        Ok(Self {
             configurations,
-            active,
+            invalid_entries,
         })
     }
 
-    /// Get the name of the currently active configuration
-    pub fn active(&self) -> &str {
-        &self.active
-    }
+    /// Record when each configuration was last activated, from the activation history in the given state directory
+    pub(crate) fn with_history(mut self, storage: &Storage, state: &Path) -> Result<Self> {
+        for entry in History::read(storage, state)? {
+            if let Some(configuration) = self.configurations.get_mut(entry.name()) {
+                configuration.last_activated = configuration.last_activated.max(Some(entry.activated()));
+            }
+        }
 
-    /// Get the collection of currently available configurations
-    pub fn configurations(&self) -> Vec<&Configuration> {
-        let mut value: Vec<&Configuration> = self.configurations.values().collect();
-        value.sort();
-        value
+        Ok(self)
     }
 
-    /// Check if the given configuration is active
-    pub fn is_active(&self, configuration: &Configuration) -> bool {
-        configuration.name == self.active
+    /// Names of the available configurations
+    #[cfg(feature = "watch")]
+    pub(crate) fn names(&self) -> std::collections::BTreeSet<String> {
+        self.configurations.names().map(str::to_owned).collect()
     }
+}
 
-    /// Activate a configuration by name
-    pub fn activate(&mut self, name: &str) -> Result<()> {
-        let configuration = self
-            .find_by_name(name)
-            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+#[derive(Debug)]
+/// Represents the store of gcloud configurations
+pub struct ConfigurationStore {
+    /// Location of the configuration store on disk
+    location: PathBuf,
 
-        let path = self.location.join("active_config");
-        std::fs::write(path, &configuration.name)?;
+    /// Path to the configurations sub-folder
+    configurations_path: PathBuf,
 
-        self.active = configuration.name.to_owned();
+    /// Location of the files gcloud-ctx keeps about the store, see [`StateDirectory`]
+    state: PathBuf,
 
-        Ok(())
-    }
+    /// Contents of the configurations directory, scanned on first use
+    scan: OnceLock<Scan>,
 
-    /// Copy an existing configuration, preserving all properties
-    pub fn copy(&mut self, src_name: &str, dest_name: &str, conflict: ConflictAction) -> Result<()> {
-        let src = self
-            .configurations
-            .get(src_name)
-            .ok_or_else(|| Error::UnknownConfiguration(src_name.to_owned()))?;
+    /// Name of the active configuration, if any
+    active: Option<String>,
 
-        if !Configuration::is_valid_name(dest_name) {
-            return Err(Error::InvalidName(dest_name.to_owned()));
-        }
+    /// Whether modifications are forbidden
+    read_only: bool,
 
-        if conflict == ConflictAction::Abort && self.configurations.contains_key(dest_name) {
-            return Err(Error::ExistingConfiguration(dest_name.to_owned()));
+    /// Whether pinned configurations are unpinned instead of failing when they're modified
+    unpin_first: bool,
+
+    /// Most recent backup made by this store before overwriting a configuration
+    last_backup: Option<Backup>,
+
+    /// How long deleted configurations are kept in the trash before they're clutter
+    trash_retention: Duration,
+
+    /// Callbacks which are told about changes made through the store
+    subscribers: Subscribers,
+
+    /// Where the files of the store and its state directory are kept
+    storage: Storage,
+}
+
+impl ConfigurationStore {
+    /// Opens the configuration store using the OS-specific defaults
+    ///
+    /// If the `CLOUDSDK_CONFIG` environment variable is set then this will be used, otherwise an
+    /// OS-specific default location will be used, as defined by the [dirs] crate, e.g.:
+    ///
+    /// - Windows: `%APPDATA%\gcloud`
+    /// - Linux: `~/.config/gcloud`
+    /// - Mac: `~/.config/gcloud` - note that this does not follow the Apple Developer Guidelines
+    ///
+    /// [dirs]: https://crates.io/crates/dirs
+    pub fn with_default_location() -> Result<Self> {
+        Self::with_location(Self::default_location()?)
+    }
+
+    /// Get the default location of the configuration store, as used by [`ConfigurationStore::with_default_location`]
+    pub fn default_location() -> Result<PathBuf> {
+        if let Ok(value) = std::env::var(STORE_ENV_VAR) {
+            return Ok(value.into());
         }
 
-        // just copy the file on disk so that any properties which aren't directly supported are maintained
-        let filename = self.configurations_path.join(format!("config_{}", dest_name));
-        fs::copy(&src.path, &filename)?;
+        Self::os_default_location()
+    }
 
-        let dest = Configuration {
-            name: dest_name.to_owned(),
-            path: filename,
+    /// Get the OS-specific default location of the configuration store, ignoring `CLOUDSDK_CONFIG`
+    pub(crate) fn os_default_location() -> Result<PathBuf> {
+        let gcloud_path = if cfg!(target_os = "macos") {
+            paths::home_dir()
+                .ok_or(Error::ConfigurationDirectoryNotFound)?
+                .join(".config")
+        } else {
+            paths::config_dir().ok_or(Error::ConfigurationDirectoryNotFound)?
         };
 
-        self.configurations.insert(dest_name.to_owned(), dest);
+        Ok(gcloud_path.join("gcloud"))
+    }
 
-        Ok(())
+    /// Create a configuration store at the given path with an empty `default` configuration, as `gcloud init` would
+    ///
+    /// Any existing configurations are left as they are, and the active configuration is only set if there wasn't one
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(location = %gcloud_path.display()))
+    )]
+    pub fn initialize(gcloud_path: PathBuf) -> Result<Self> {
+        Self::initialize_files(&Storage::Disk, &gcloud_path)?;
+        Self::with_location(gcloud_path)
     }
 
-    /// Create a new configuration
-    pub fn create(&mut self, name: &str, properties: &Properties, conflict: ConflictAction) -> Result<()> {
-        if !Configuration::is_valid_name(name) {
-            return Err(Error::InvalidName(name.to_owned()));
-        }
+    /// Create the files of a store as [`ConfigurationStore::initialize`] does, without opening it
+    pub(crate) fn initialize_files(storage: &Storage, gcloud_path: &Path) -> Result<()> {
+        let configurations_path = gcloud_path.join("configurations");
+        storage
+            .create_dir_all(&configurations_path)
+            .file_context(FileOperation::Create, &configurations_path)?;
+
+        let default = configurations_path.join("config_default");
 
-        if conflict == ConflictAction::Abort && self.configurations.contains_key(name) {
-            return Err(Error::ExistingConfiguration(name.to_owned()));
+        if !storage.exists(&default) {
+            storage.write(&default, "")?;
         }
 
-        let filename = self.configurations_path.join(format!("config_{}", name));
-        let file = File::create(&filename)?;
-        properties.to_writer(file)?;
+        let active = gcloud_path.join("active_config");
 
-        self.configurations.insert(
-            name.to_owned(),
-            Configuration {
-                name: name.to_owned(),
-                path: filename,
-            },
-        );
+        if !storage.exists(&active) {
+            storage.write(&active, "default")?;
+        }
 
         Ok(())
     }
 
-    /// Delete a configuration
-    pub fn delete(&mut self, name: &str) -> Result<()> {
-        let configuration = self
-            .find_by_name(name)
-            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+    /// Create an empty `default` configuration and make it active, as `gcloud init` would, but only if the store at
+    /// the given path doesn't have any configurations
+    pub(crate) fn bootstrap(storage: &Storage, gcloud_path: &Path) -> Result<()> {
+        let configurations_path = gcloud_path.join("configurations");
 
-        if self.is_active(configuration) {
-            return Err(Error::DeleteActiveConfiguration);
+        if !storage.is_dir(&configurations_path) {
+            return Err(Error::ConfigurationStoreNotFound(configurations_path));
         }
 
-        let path = &configuration.path;
-        fs::remove_file(&path)?;
+        if !Scan::read(storage, &configurations_path)?.configurations.is_empty() {
+            return Ok(());
+        }
 
-        self.configurations.remove(name);
+        storage.write(&configurations_path.join("config_default"), "")?;
+        storage.write(&gcloud_path.join("active_config"), "default")?;
 
         Ok(())
     }
 
-    /// Describe the properties in the given configuration
-    pub fn describe(&self, name: &str) -> Result<Properties> {
-        let configuration = self
-            .find_by_name(name)
-            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+    /// Opens a configuration store at the given path
+    ///
+    /// The configurations directory is scanned straight away, and the store must contain at least one configuration
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(location = %gcloud_path.display()))
+    )]
+    pub fn with_location(gcloud_path: PathBuf) -> Result<Self> {
+        Self::open(Storage::Disk, gcloud_path, false, false, None)
+    }
 
-        let path = &configuration.path;
-        let handle = File::open(path)?;
-        let reader = BufReader::new(handle);
+    /// Create a configuration which only exists in memory and is never written to any store, e.g. to run a single
+    /// command against a project without creating a configuration for it
+    pub fn virtual_config(name: &str, properties: Properties) -> Result<VirtualConfiguration> {
+        if !Configuration::is_valid_name(name) {
+            return Err(Error::InvalidName(name.to_owned()));
+        }
 
-        let properties = Properties::from_reader(reader)?;
+        Ok(VirtualConfiguration {
+            name: name.to_owned(),
+            properties,
+        })
+    }
 
-        Ok(properties)
+    /// Get a builder for opening a configuration store with options, e.g. to create it if it's missing
+    pub fn builder() -> ConfigurationStoreBuilder {
+        ConfigurationStoreBuilder::default()
     }
 
-    /// Rename a configuration
-    pub fn rename(&mut self, old_name: &str, new_name: &str, conflict: ConflictAction) -> Result<()> {
-        let src = self
-            .configurations
-            .get(old_name)
-            .ok_or_else(|| Error::UnknownConfiguration(old_name.to_owned()))?;
+    /// Opens a configuration store at the given path, scanning the configurations directory straight away
+    ///
+    /// Files written inside the store by earlier versions are moved to the state directory, unless the store is
+    /// being opened read-only
+    pub(crate) fn open(
+        storage: Storage,
+        gcloud_path: PathBuf,
+        tolerate_empty: bool,
+        read_only: bool,
+        state: Option<PathBuf>,
+    ) -> Result<Self> {
+        let store = Self::open_lazy(storage, gcloud_path, read_only, state)?;
+        let scan =
+            Scan::read(&store.storage, &store.configurations_path)?.with_history(&store.storage, &store.state)?;
 
-        let active = self.is_active(src);
+        if scan.configurations.is_empty() && !tolerate_empty {
+            return Err(Error::NoConfigurationsFound(store.configurations_path));
+        }
+
+        store.scan.set(scan).expect("the store hasn't been scanned yet");
+
+        Ok(store)
+    }
+
+    /// Opens a configuration store at the given path without scanning the configurations directory
+    ///
+    /// The directory is scanned the first time the configurations are needed, so callers which only need the
+    /// active configuration, e.g. shell prompts, stay fast even with many configurations. Any error whilst
+    /// scanning is returned by the first method which needs the configurations. Use
+    /// [`ConfigurationStore::builder`] to open a lazy store read-only, or [`ConfigurationStore::active_name_only`] if
+    /// only the name of the active configuration is needed
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(location = %gcloud_path.display()))
+    )]
+    pub fn with_location_lazy(gcloud_path: PathBuf) -> Result<Self> {
+        Self::open_lazy(Storage::Disk, gcloud_path, false, None)
+    }
 
-        if !Configuration::is_valid_name(new_name) {
-            return Err(Error::InvalidName(new_name.to_owned()));
+    /// Opens a configuration store at the given path without scanning it, keeping its state in the given directory or
+    /// the [`StateDirectory`] by default
+    ///
+    /// Files written inside the store by earlier versions are moved to the state directory, unless the store is
+    /// being opened read-only
+    pub(crate) fn open_lazy(
+        storage: Storage,
+        gcloud_path: PathBuf,
+        read_only: bool,
+        state: Option<PathBuf>,
+    ) -> Result<Self> {
+        if !storage.is_dir(&gcloud_path) {
+            return Err(Self::store_not_found(&storage, gcloud_path));
         }
 
-        if conflict == ConflictAction::Abort && self.configurations.contains_key(new_name) {
-            return Err(Error::ExistingConfiguration(new_name.to_owned()));
+        let configurations_path = gcloud_path.join("configurations");
+
+        if !storage.is_dir(&configurations_path) {
+            return Err(Error::ConfigurationStoreNotFound(configurations_path));
         }
 
-        let new_value = Configuration {
-            name: new_name.to_owned(),
-            path: src.path.with_file_name(format!("config_{}", new_name)),
+        let active = Self::parse_active(Self::read_active(&storage, &gcloud_path)?);
+
+        let state = match state {
+            Some(state) => state,
+            None => StateDirectory::for_store(&gcloud_path)?,
         };
 
-        std::fs::rename(&src.path, &new_value.path)?;
+        if !read_only {
+            StateDirectory::migrate(&storage, &gcloud_path, &state)?;
+        }
+
+        Ok(ConfigurationStore {
+            location: gcloud_path,
+            configurations_path,
+            state,
+            scan: OnceLock::new(),
+            active,
+            read_only,
+            unpin_first: false,
+            last_backup: None,
+            trash_retention: DEFAULT_TRASH_RETENTION,
+            subscribers: Subscribers::default(),
+            storage,
+        })
+    }
+
+    /// Read only the name of the active configuration from the store at the given path, without opening the store
+    ///
+    /// Nothing else is read or validated, not even whether the configuration exists, so this is the cheapest way to
+    /// show the active configuration, e.g. in a shell prompt which runs on every keystroke. Returns `None` if no
+    /// configuration is active
+    pub fn active_name_only(gcloud_path: &Path) -> Result<Option<String>> {
+        Ok(Self::parse_active(Self::read_active(&Storage::Disk, gcloud_path)?))
+    }
 
-        self.configurations.remove(old_name);
-        self.configurations.insert(new_name.to_owned(), new_value);
+    /// Read the name of the active configuration from the store at the given path
+    pub(crate) fn read_active(storage: &Storage, gcloud_path: &Path) -> Result<String> {
+        // other tools sometimes write the name with a trailing newline, which gcloud ignores
+        let active = gcloud_path.join("active_config");
+        let name = storage
+            .read_to_string(&active)
+            .file_context(FileOperation::Read, &active)?;
 
-        // check if the active configuration is the one being renamed
-        if active {
-            self.activate(new_name)?;
+        Ok(name.trim().to_owned())
+    }
+
+    /// Interpret the contents of `active_config`, where an empty file or `NONE` means no configuration is active
+    pub(crate) fn parse_active(name: String) -> Option<String> {
+        Some(name).filter(|name| !name.is_empty() && name != NO_ACTIVE_CONFIGURATION)
+    }
+
+    /// Explain why there's no configuration store at the given path, pointing out when the path came from
+    /// `CLOUDSDK_CONFIG` because that's a common misconfiguration
+    fn store_not_found(storage: &Storage, gcloud_path: PathBuf) -> Error {
+        match std::env::var_os(STORE_ENV_VAR) {
+            Some(value) if Path::new(&value) == gcloud_path => {
+                let variable = STORE_ENV_VAR.to_owned();
+                let value = value.to_string_lossy().into_owned();
+
+                if storage.exists(&gcloud_path) {
+                    Error::StoreEnvVarNotADirectory { variable, value }
+                } else {
+                    Error::StoreEnvVarMissing { variable, value }
+                }
+            }
+            _ => Error::ConfigurationStoreNotFound(gcloud_path),
+        }
+    }
+
+    /// Convert the store into one which returns [`Error::ReadOnlyStore`] from any operation that would modify it
+    pub fn into_read_only(self) -> Self {
+        Self {
+            read_only: true,
+            ..self
+        }
+    }
+
+    /// Check if the store was opened read-only
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Convert the store into one which unpins a pinned configuration before modifying it, rather than returning
+    /// [`Error::ConfigurationPinned`]
+    pub fn into_unpin_first(self) -> Self {
+        Self {
+            unpin_first: true,
+            ..self
+        }
+    }
+
+    /// Keep deleted configurations in the trash for the given time, after which
+    /// [`ConfigurationStore::find_clutter`] reports them as old backups, see [`ConfigurationStore::list_trashed`]
+    pub fn with_trash_retention(self, trash_retention: Duration) -> Self {
+        Self {
+            trash_retention,
+            ..self
+        }
+    }
+
+    /// Get the location of the configuration store on disk
+    pub fn location(&self) -> &Path {
+        &self.location
+    }
+
+    /// Get the location of the files gcloud-ctx keeps about the store, e.g. metadata, backups and caches
+    pub fn state_location(&self) -> &Path {
+        &self.state
+    }
+
+    /// Available configurations, scanning the configurations directory if it hasn't been already
+    ///
+    /// A failed scan isn't remembered, so the next call tries again
+    fn scan(&self) -> Result<&Scan> {
+        if let Some(scan) = self.scan.get() {
+            return Ok(scan);
+        }
+
+        let scan = Scan::read(&self.storage, &self.configurations_path)?.with_history(&self.storage, &self.state)?;
+
+        Ok(self.scan.get_or_init(|| scan))
+    }
+
+    /// Available configurations, for modification
+    fn configurations_mut(&mut self) -> Result<&mut ConfigurationMap> {
+        self.scan()?;
+        Ok(&mut self.scan.get_mut().expect("the store has been scanned").configurations)
+    }
+
+    /// Ensure the store can be modified
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnlyStore(self.location.clone()));
         }
 
         Ok(())
     }
 
-    /// Find a configuration by name
-    pub fn find_by_name(&self, name: &str) -> Option<&Configuration> {
-        self.configurations.get(&name.to_owned())
+    /// Take the advisory lock on the store, which is released when the returned lock is dropped
+    ///
+    /// Every modification holds the lock so that concurrent gcloud-ctx users, e.g. two gctx invocations, can't
+    /// interleave their writes. gcloud itself doesn't take the lock
+    fn lock(&self) -> Result<StoreLock> {
+        self.check_writable()?;
+        self.storage.lock(&self.state.join("lock"))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Ensure the named configuration can be modified, unpinning it first if the store allows it
+    fn check_unpinned(&self, name: &str) -> Result<()> {
+        let mut metadata = self.metadata(name)?;
+
+        if !metadata.is_pinned() {
+            return Ok(());
+        }
+
+        if !self.unpin_first {
+            return Err(Error::ConfigurationPinned(name.to_owned()));
+        }
+
+        metadata.set_pinned(false);
+        self.write_metadata(name, &metadata)
+    }
+
+    /// Check if a directory entry can't possibly be a configuration, e.g. because it's a sub-directory
+    fn invalid_entry_reason(entry: &StorageEntry) -> Option<InvalidEntryReason> {
+        match entry.kind() {
+            Ok(EntryKind::File) => None,
+            Ok(EntryKind::Directory) => Some(InvalidEntryReason::Directory),
+            Ok(EntryKind::Special) => Some(InvalidEntryReason::SpecialFile),
+            Err(e) => Some(InvalidEntryReason::unreadable(&e)),
+        }
+    }
+
+    /// Get the name of the currently active configuration, or `None` if no configuration is active
+    pub fn active(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Watch the store for changes made by any process, e.g. to keep a shell prompt or TUI in sync with gcloud
+    ///
+    /// The store itself isn't updated, so reopen it to see the changes
+    #[cfg(feature = "watch")]
+    pub fn watch(&self) -> Result<crate::StoreWatcher> {
+        crate::StoreWatcher::new(self)
+    }
+
+    /// Get the currently active configuration, which may not exist if `active_config` was changed by hand
+    pub fn active_configuration(&self) -> Result<&Configuration> {
+        let name = self.active().ok_or(Error::NoActiveConfiguration)?;
+
+        self.find_by_name(name)?
+            .ok_or_else(|| Error::ActiveConfigurationNotFound(name.to_owned()))
+    }
+
+    /// Iterate over the currently available configurations in natural order
+    ///
+    /// The configurations are kept in order, so listing them repeatedly doesn't allocate or sort
+    pub fn configurations(&self) -> Result<Configurations<'_>> {
+        Ok(self.scan()?.configurations.values())
+    }
+
+    /// Get the collection of currently available configurations in the given order
+    pub fn configurations_sorted(&self, order: SortOrder) -> Result<Vec<&Configuration>> {
+        let mut value: Vec<&Configuration> = self.configurations()?.collect();
+
+        if order == SortOrder::Lexical {
+            value.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        Ok(value)
+    }
+
+    /// Get the entries in the configurations directory which were skipped when the store was loaded, e.g.
+    /// directories, files whose names aren't valid configuration names and entries which couldn't be read
+    ///
+    /// Front-ends should warn about these, otherwise configurations the user expects to see silently disappear
+    pub fn diagnostics(&self) -> Result<&[InvalidEntry]> {
+        Ok(&self.scan()?.invalid_entries)
+    }
+
+    /// Get warnings about problems found when opening the store, e.g. skipped entries, see
+    /// [`ConfigurationStore::diagnostics`]
+    pub fn warnings(&self) -> Result<Vec<Warning>> {
+        Ok(self
+            .scan()?
+            .invalid_entries
+            .iter()
+            .map(|entry| Warning::SkippedEntry(entry.clone()))
+            .collect())
+    }
+
+    /// Check the health of the store, e.g. to build a `doctor` command or a CI check
+    ///
+    /// Reports an active configuration which doesn't exist, configurations which can't be read or parsed, entries in
+    /// the configurations directory which are ignored, directories gcloud can't write to and likely mistakes in the
+    /// properties of each configuration, e.g. a zone outside the region. Only fails if the configurations directory
+    /// can't be listed at all
+    pub fn check(&self) -> Result<Vec<Finding>> {
+        let mut findings = Vec::new();
+
+        if let Some(active) = self.active() {
+            if self.find_by_name(active)?.is_none() {
+                findings.push(Finding::DanglingActive(active.to_owned()));
+            }
+        }
+
+        // files are replaced atomically, so it's only the directories which need to be writable
+        for directory in [&self.location, &self.configurations_path] {
+            match self.storage.metadata(directory) {
+                Ok(metadata) if metadata.is_read_only() => findings.push(Finding::ReadOnly(directory.clone())),
+                Ok(_) => {}
+                Err(e) => findings.push(Finding::Unreadable {
+                    path: directory.clone(),
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        findings.extend(self.diagnostics()?.iter().cloned().map(|entry| match entry.reason() {
+            InvalidEntryReason::InvalidName => Finding::InvalidName(entry.path),
+            _ => Finding::SkippedEntry(entry),
+        }));
+
+        for configuration in self.configurations()? {
+            let name = &configuration.name;
+
+            match self.describe(name) {
+                Ok(properties) => findings.extend(properties.validate().into_iter().map(|warning| Finding::Property {
+                    name: name.clone(),
+                    warning,
+                })),
+                Err(e) => match e.io_error() {
+                    Some(source) => findings.push(Finding::Unreadable {
+                        path: configuration.path.clone(),
+                        reason: source.to_string(),
+                    }),
+                    None => findings.push(Finding::Unparsable {
+                        name: name.clone(),
+                        reason: e.to_string(),
+                    }),
+                },
+            }
+        }
+
+        Ok(findings)
+    }
+
+    /// Register a callback which is told about every configuration created, copied, activated, renamed or deleted
+    /// through this store, e.g. to log changes or keep other tools in sync
+    ///
+    /// Callbacks run after the change has been made, in the order they were registered. Changes made by anything
+    /// else, e.g. gcloud itself, aren't reported
+    pub fn subscribe(&mut self, callback: impl Fn(&StoreEvent) + Send + Sync + 'static) {
+        self.subscribers.add(Box::new(callback));
+    }
+
+    /// Check if the given configuration is active
+    pub fn is_active(&self, configuration: &Configuration) -> bool {
+        self.active() == Some(configuration.name.as_str())
+    }
+
+    /// Activate a configuration by name
+    ///
+    /// Returns the previously active configuration along with the new one, e.g. so that callers can undo the
+    /// activation
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn activate(&mut self, name: &str) -> Result<Activation> {
+        let _lock = self.lock()?;
+        let activation = self.write_active(name)?;
+
+        self.subscribers.notify(StoreEvent::Activated(activation.clone()));
+
+        Ok(activation)
+    }
+
+    /// Write the active configuration, without taking the lock
+    fn write_active(&mut self, name: &str) -> Result<Activation> {
+        let configuration = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+
+        let current = configuration.name.to_owned();
+        let activated = SystemTime::now();
+
+        self.storage.write(&self.location.join("active_config"), &current)?;
+        History::record(&self.storage, &self.state, &current, activated)?;
+
+        if let Some(configuration) = self.configurations_mut()?.get_mut(&current) {
+            configuration.last_activated = Some(activated);
+        }
+
+        let previous = self.active.replace(current.clone());
+
+        Ok(Activation { previous, current })
+    }
+
+    /// Leave the store with no active configuration, as gcloud does when `NONE` is activated
+    ///
+    /// Returns the previously active configuration, if there was one
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn deactivate(&mut self) -> Result<Option<String>> {
+        let _lock = self.lock()?;
+
+        self.storage
+            .write(&self.location.join("active_config"), NO_ACTIVE_CONFIGURATION)?;
+
+        let previous = self.active.take();
+
+        if let Some(previous) = &previous {
+            self.subscribers.notify(StoreEvent::Deactivated(previous.clone()));
+        }
+
+        Ok(previous)
+    }
+
+    /// Configurations which were activated, most recent first
+    ///
+    /// Every activation is recorded, up to the last 100, in the state directory of the store. Entries keep the name
+    /// the configuration had when it was activated, so they may name configurations which have since been renamed or
+    /// deleted
+    pub fn history(&self) -> Result<Vec<HistoryEntry>> {
+        let mut entries = History::read(&self.storage, &self.state)?;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// The most recently activated configuration other than the active one, e.g. to switch back to it
+    ///
+    /// Configurations which have since been deleted are skipped
+    pub fn previous(&self) -> Result<Option<&Configuration>> {
+        for entry in self.history()? {
+            if Some(entry.name()) == self.active() {
+                continue;
+            }
+
+            if let Some(configuration) = self.find_by_name(entry.name())? {
+                return Ok(Some(configuration));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Copy an existing configuration, preserving all properties
+    ///
+    /// Returns the copy, whose name differs from `dest_name` if the conflict was resolved by renaming,
+    /// or `None` if the copy was skipped
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(src_name = src_name, dest_name = dest_name))
+    )]
+    pub fn copy<C: ConflictResolver>(
+        &mut self,
+        src_name: &str,
+        dest_name: &str,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        self.copy_inner(src_name, dest_name, None, conflict)
+    }
+
+    /// Copy an existing configuration, setting the given properties on top of the original ones, e.g. to make a
+    /// configuration which is the same as another but for a different project
+    ///
+    /// Properties which aren't overridden are preserved, including ones which aren't directly supported. Returns
+    /// the same as [`ConfigurationStore::copy`]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(src_name = src_name, dest_name = dest_name))
+    )]
+    pub fn copy_with_overrides<C: ConflictResolver>(
+        &mut self,
+        src_name: &str,
+        dest_name: &str,
+        overrides: &Properties,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        self.copy_inner(src_name, dest_name, Some(overrides), conflict)
+    }
+
+    /// Copy an existing configuration, optionally applying overrides
+    fn copy_inner<C: ConflictResolver>(
+        &mut self,
+        src_name: &str,
+        dest_name: &str,
+        overrides: Option<&Properties>,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        let _lock = self.lock()?;
+
+        let src = self
+            .find_by_name(src_name)?
+            .ok_or_else(|| Error::UnknownConfiguration(src_name.to_owned()))?;
+        let src_path = src.path.clone();
+
+        let dest_name = match self.resolve_conflict(dest_name, &conflict)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let dest_name = dest_name.as_str();
+        self.check_unpinned(dest_name)?;
+
+        // just copy the file on disk so that any properties which aren't directly supported are maintained
+        let filename = self.configuration_path(dest_name);
+        let mut contents = self
+            .storage
+            .read(&src_path)
+            .file_context(FileOperation::Read, &src_path)?;
+
+        if let Some(overrides) = overrides {
+            let mut properties = Properties::from_reader(contents.as_slice())?;
+            properties.merge(overrides, MergeStrategy::OtherWins);
+
+            contents.clear();
+            properties.to_writer(&mut contents)?;
+        }
+
+        let backup = self.back_up(dest_name)?;
+        self.write_atomically(dest_name, &contents)?;
+        self.record_undo(Journal::new(
+            UndoableOperation::Overwritten(vec![dest_name.to_owned()]),
+            backup,
+        ))?;
+
+        // a copy of a pinned configuration is usually made so that it can be modified
+        let mut metadata = self.metadata(src_name)?;
+        metadata.set_pinned(false);
+        self.write_metadata(dest_name, &metadata)?;
+
+        let dest = Configuration {
+            name: dest_name.to_owned(),
+            path: filename,
+            properties: OnceLock::new(),
+            last_activated: None,
+            storage: self.storage.clone(),
+        };
+
+        self.configurations_mut()?.insert(dest_name.to_owned(), dest.clone());
+        self.write_name_cache()?;
+
+        self.subscribers.notify(StoreEvent::Copied {
+            from: src_name.to_owned(),
+            to: dest_name.to_owned(),
+        });
+
+        Ok(Some(dest))
+    }
+
+    /// Create a new configuration
+    ///
+    /// Returns the new configuration, whose name differs from `name` if the conflict was resolved by renaming,
+    /// or `None` if creating it was skipped
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn create<C: ConflictResolver>(
+        &mut self,
+        name: &str,
+        properties: &Properties,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        let _lock = self.lock()?;
+
+        let name = match self.resolve_conflict(name, &conflict)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let name = name.as_str();
+        self.check_unpinned(name)?;
+
+        let filename = self.configuration_path(name);
+        let mut contents = Vec::new();
+        properties.to_writer(&mut contents)?;
+        let backup = self.back_up(name)?;
+        self.write_atomically(name, &contents)?;
+        self.record_undo(Journal::new(
+            UndoableOperation::Overwritten(vec![name.to_owned()]),
+            backup,
+        ))?;
+
+        // metadata from any overwritten configuration no longer applies
+        self.write_metadata(name, &Metadata::default())?;
+
+        let configuration = Configuration {
+            name: name.to_owned(),
+            path: filename,
+            properties: OnceLock::new(),
+            last_activated: None,
+            storage: self.storage.clone(),
+        };
+
+        self.configurations_mut()?
+            .insert(name.to_owned(), configuration.clone());
+        self.write_name_cache()?;
+
+        self.subscribers.notify(StoreEvent::Created(name.to_owned()));
+
+        Ok(Some(configuration))
+    }
+
+    /// Delete a configuration
+    ///
+    /// The configuration is moved to the trash first, see [`ConfigurationStore::list_trashed`]
+    pub fn delete(&mut self, name: &str) -> Result<()> {
+        self.delete_with(name, DeleteBehaviour::Refuse)
+    }
+
+    /// Delete a configuration, choosing what happens if it's the active configuration
+    ///
+    /// With [`DeleteBehaviour::ActivateFallback`] the fallback is activated and the configuration deleted while
+    /// holding the store lock, so no other gctx process sees the store without an active configuration. If the
+    /// configuration can't be deleted then it's activated again
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn delete_with(&mut self, name: &str, behaviour: DeleteBehaviour) -> Result<()> {
+        let _lock = self.lock()?;
+
+        let configuration = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+
+        let fallback = match behaviour {
+            _ if !self.is_active(configuration) => None,
+            DeleteBehaviour::ActivateFallback(fallback) if fallback != name => Some(fallback),
+            _ => return Err(Error::DeleteActiveConfiguration),
+        };
+
+        // check everything which could fail before changing the active configuration
+        self.check_unpinned(name)?;
+
+        if let Some(fallback) = &fallback {
+            let activation = self.write_active(fallback)?;
+            self.subscribers.notify(StoreEvent::Activated(activation));
+        }
+
+        let backup = match self.remove(name) {
+            Ok(backup) => backup,
+            Err(e) => {
+                if fallback.is_some() {
+                    self.reactivate(name);
+                }
+
+                return Err(e);
+            }
+        };
+
+        let mut journal = Journal::new(UndoableOperation::Deleted(vec![name.to_owned()]), backup);
+        journal.active = fallback.map(|_| name.to_owned());
+        self.record_undo(journal)
+    }
+
+    /// Delete several configurations, e.g. those picked in a multi-select list
+    ///
+    /// See [`ConfigurationStore::delete_many_with`]
+    pub fn delete_many(&mut self, names: &[&str]) -> Result<Vec<(String, Result<()>)>> {
+        self.delete_many_with(names, DeleteBehaviour::Refuse)
+    }
+
+    /// Delete several configurations, choosing what happens if the active configuration is one of them
+    ///
+    /// Nothing is deleted if any of the names are unknown, or if the active configuration is included without a
+    /// fallback which is being kept. Otherwise every configuration is attempted, even if some fail, e.g. because
+    /// they're pinned, and the result of each is returned in the order given. If the active configuration can't be
+    /// deleted then it's activated again
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err, fields(names = ?names)))]
+    pub fn delete_many_with(
+        &mut self,
+        names: &[&str],
+        behaviour: DeleteBehaviour,
+    ) -> Result<Vec<(String, Result<()>)>> {
+        let _lock = self.lock()?;
+
+        for name in names {
+            if self.find_by_name(name)?.is_none() {
+                return Err(Error::UnknownConfiguration((*name).to_owned()));
+            }
+        }
+
+        let active = self.active().filter(|active| names.contains(active)).map(str::to_owned);
+
+        if let Some(active) = &active {
+            let fallback = match behaviour {
+                DeleteBehaviour::ActivateFallback(fallback) if !names.contains(&fallback.as_str()) => fallback,
+                _ => return Err(Error::DeleteActiveConfiguration),
+            };
+
+            // the fallback is only worth activating if the active configuration can actually be deleted
+            self.check_unpinned(active)?;
+
+            let activation = self.write_active(&fallback)?;
+            self.subscribers.notify(StoreEvent::Activated(activation));
+        }
+
+        let results: Vec<_> = names.iter().map(|name| (name.to_string(), self.remove(name))).collect();
+
+        let failed = |name: &String| results.iter().any(|(n, result)| n == name && result.is_err());
+        let active = match active {
+            Some(active) if failed(&active) => {
+                self.reactivate(&active);
+                None
+            }
+            active => active,
+        };
+
+        let mut deleted = Vec::new();
+        let mut backups = Vec::new();
+        let mut outcomes = Vec::new();
+
+        for (name, result) in results {
+            match result {
+                Ok(backup) => {
+                    deleted.push(name.clone());
+                    backups.extend(backup);
+                    outcomes.push((name, Ok(())));
+                }
+                Err(e) => outcomes.push((name, Err(e))),
+            }
+        }
+
+        let mut journal = Journal::new(UndoableOperation::Deleted(deleted), backups);
+        journal.active = active;
+        self.record_undo(journal)?;
+
+        Ok(outcomes)
+    }
+
+    /// Activate a configuration again after it couldn't be deleted, unless it was deleted part way through
+    ///
+    /// This is best effort because the error from the delete is more useful than any error from activating
+    fn reactivate(&mut self, name: &str) {
+        if !matches!(self.find_by_name(name), Ok(Some(_))) {
+            return;
+        }
+
+        if let Ok(activation) = self.write_active(name) {
+            self.subscribers.notify(StoreEvent::Activated(activation));
+        }
+    }
+
+    /// Delete a configuration which isn't active, without taking the lock
+    ///
+    /// The configuration is backed up first so that the delete can be undone, and the backup is returned
+    fn remove(&mut self, name: &str) -> Result<Option<Backup>> {
+        let path = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?
+            .path
+            .clone();
+
+        self.check_unpinned(name)?;
+
+        let backup = self.back_up(name)?;
+        self.storage
+            .remove_file(&path)
+            .file_context(FileOperation::Delete, &path)?;
+
+        self.configurations_mut()?.remove(name);
+        self.write_metadata(name, &Metadata::default())?;
+        self.write_name_cache()?;
+
+        self.subscribers.notify(StoreEvent::Deleted(name.to_owned()));
+
+        Ok(backup)
+    }
+
+    /// Make the store match a declared set of configurations, e.g. from a dotfile, creating and updating them as needed
+    /// and optionally deleting any which aren't declared
+    ///
+    /// Applying the same set again changes nothing, so it's safe to run repeatedly. The names, pinned configurations
+    /// and active configuration are all checked before anything changes, and the store lock is held from the checks
+    /// until the last change. Updated configurations keep their metadata. Updated and pruned configurations are backed
+    /// up, so [`ConfigurationStore::undo_last`] reverts them all at once, although created configurations are kept.
+    /// Returns the change to each configuration, with the declared ones first in the order given and then any pruned
+    /// ones in natural order
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(dry_run = options.dry_run))
+    )]
+    pub fn apply(&mut self, desired: &[ConfigSpec], options: &ApplyOptions) -> Result<Vec<AppliedChange>> {
+        let _lock = if options.dry_run { None } else { Some(self.lock()?) };
+
+        let mut changes = Vec::new();
+
+        for (i, spec) in desired.iter().enumerate() {
+            let name = spec.name();
+
+            if !Configuration::is_valid_name(name) {
+                return Err(Error::InvalidName(name.to_owned()));
+            }
+
+            if desired[..i].iter().any(|other| other.name() == name) {
+                return Err(Error::DuplicateConfigSpec(name.to_owned()));
+            }
+
+            let change = match self.find_by_name(name)? {
+                None => AppliedChange::Created(name.to_owned()),
+                Some(_) => match self.describe(name)?.diff(spec.properties()) {
+                    diff if diff.is_empty() => AppliedChange::Unchanged(name.to_owned()),
+                    diff => AppliedChange::Updated {
+                        name: name.to_owned(),
+                        changes: diff,
+                    },
+                },
+            };
+
+            changes.push(change);
+        }
+
+        if options.prune {
+            let pruned: Vec<String> = self
+                .configurations()?
+                .map(|configuration| configuration.name.clone())
+                .filter(|name| !desired.iter().any(|spec| spec.name() == name))
+                .collect();
+
+            if self
+                .active()
+                .is_some_and(|active| pruned.iter().any(|name| name == active))
+            {
+                return Err(Error::DeleteActiveConfiguration);
+            }
+
+            changes.extend(pruned.into_iter().map(AppliedChange::Pruned));
+        }
+
+        for change in &changes {
+            let modifies = matches!(change, AppliedChange::Updated { .. } | AppliedChange::Pruned(_));
+
+            if modifies && !self.unpin_first && self.metadata(change.name())?.is_pinned() {
+                return Err(Error::ConfigurationPinned(change.name().to_owned()));
+            }
+        }
+
+        if options.dry_run {
+            return Ok(changes);
+        }
+
+        let mut changed = Vec::new();
+        let mut backups = Vec::new();
+        let result = self.apply_changes(desired, &changes, &mut changed, &mut backups);
+
+        // recorded even if a change failed, so that the ones already made can be undone
+        self.record_undo(Journal::new(UndoableOperation::Applied(changed), backups))?;
+        result?;
+
+        Ok(changes)
+    }
+
+    /// Make the planned changes of [`ConfigurationStore::apply`] without taking the lock, recording the configurations
+    /// which were updated or pruned and their backups
+    fn apply_changes(
+        &mut self,
+        desired: &[ConfigSpec],
+        changes: &[AppliedChange],
+        changed: &mut Vec<String>,
+        backups: &mut Vec<Backup>,
+    ) -> Result<()> {
+        for (spec, change) in desired.iter().zip(changes) {
+            let mut contents = Vec::new();
+            spec.properties().to_writer(&mut contents)?;
+
+            match change {
+                AppliedChange::Created(name) => {
+                    self.write_imported(name, &contents)?;
+                }
+                AppliedChange::Updated { name, .. } => {
+                    self.check_unpinned(name)?;
+                    backups.extend(self.back_up(name)?);
+                    self.write_atomically(name, &contents)?;
+                    changed.push(name.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for change in changes {
+            if let AppliedChange::Pruned(name) = change {
+                backups.extend(self.remove(name)?);
+                changed.push(name.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describe the properties in the given configuration
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn describe(&self, name: &str) -> Result<Properties> {
+        let (properties, _) = self.describe_with_options(name, &ReadOptions::default())?;
+        Ok(properties)
+    }
+
+    /// Properties of the given configuration as gcloud will actually use them, with any `CLOUDSDK_*` environment
+    /// variables applied on top, see [`Properties::effective`]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn effective_properties(&self, name: &str) -> Result<EffectiveProperties> {
+        Ok(self.describe(name)?.effective())
+    }
+
+    /// Describe every configuration, reading them in parallel
+    ///
+    /// Results are in name order, and a configuration which fails to load doesn't prevent the others loading
+    pub fn describe_all(&self) -> Result<Vec<(&Configuration, Result<Properties>)>> {
+        let configurations: Vec<&Configuration> = self.configurations()?.collect();
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+        if threads == 1 || configurations.len() <= 1 {
+            return self.describe_all_sequential();
+        }
+
+        let chunk_size = configurations.len().div_ceil(threads).max(1);
+
+        Ok(std::thread::scope(|scope| {
+            let handles: Vec<_> = configurations
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().map(|c| (*c, self.describe(&c.name))).collect::<Vec<_>>())
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("describing configurations panicked"))
+                .collect()
+        }))
+    }
+
+    /// Describe every configuration on the calling thread, e.g. for callers which already run in parallel
+    ///
+    /// Results are the same as [`ConfigurationStore::describe_all`]
+    pub fn describe_all_sequential(&self) -> Result<Vec<(&Configuration, Result<Properties>)>> {
+        Ok(self.configurations()?.map(|c| (c, self.describe(&c.name))).collect())
+    }
+
+    /// Describe the properties in the given configuration using the given options
+    ///
+    /// Returns the properties along with any warnings encountered whilst reading them
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn describe_with_options(&self, name: &str, options: &ReadOptions) -> Result<(Properties, Vec<Warning>)> {
+        let configuration = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+
+        let path = &configuration.path;
+        let contents = self.storage.read(path).file_context(FileOperation::Read, path)?;
+
+        Properties::from_reader_with_options(contents.as_slice(), options)
+    }
+
+    /// Export the contents of a configuration as-is, e.g. to copy it to another machine with
+    /// [`ConfigurationStore::import`]
+    ///
+    /// Properties which aren't directly supported are preserved, along with comments and formatting
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn export<W: Write>(&self, name: &str, mut writer: W) -> Result<()> {
+        let configuration = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+
+        let path = &configuration.path;
+        let contents = self.storage.read(path).file_context(FileOperation::Read, path)?;
+        writer.write_all(&contents)?;
+
+        Ok(())
+    }
+
+    /// Import a configuration from the contents of a configuration file
+    ///
+    /// The contents are validated and then written as-is so that any properties which aren't directly
+    /// supported are maintained
+    ///
+    /// Returns the imported configuration, whose name differs from `name` if the conflict was resolved by renaming,
+    /// or `None` if the import was skipped
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn import<R: Read, C: ConflictResolver>(
+        &mut self,
+        name: &str,
+        mut reader: R,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        let _lock = self.lock()?;
+
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents)?;
+        Properties::from_reader(contents.as_slice())?;
+
+        let name = match self.resolve_conflict(name, &conflict)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        self.check_unpinned(&name)?;
+
+        let (configuration, backup) = self.write_imported(&name, &contents)?;
+        self.record_undo(Journal::new(UndoableOperation::Overwritten(vec![name]), backup))?;
+
+        Ok(Some(configuration))
+    }
+
+    /// Write an imported configuration whose name has already been resolved, without taking the lock
+    ///
+    /// Returns the configuration along with the backup of any configuration it overwrote
+    fn write_imported(&mut self, name: &str, contents: &[u8]) -> Result<(Configuration, Option<Backup>)> {
+        let filename = self.configuration_path(name);
+        let backup = self.back_up(name)?;
+        self.write_atomically(name, contents)?;
+
+        // metadata from any overwritten configuration no longer applies
+        self.write_metadata(name, &Metadata::default())?;
+
+        let configuration = Configuration {
+            name: name.to_owned(),
+            path: filename,
+            properties: OnceLock::new(),
+            last_activated: None,
+            storage: self.storage.clone(),
+        };
+
+        self.configurations_mut()?
+            .insert(name.to_owned(), configuration.clone());
+        self.write_name_cache()?;
+
+        self.subscribers.notify(StoreEvent::Created(name.to_owned()));
+
+        Ok((configuration, backup))
+    }
+
+    /// Export every configuration, along with which one is active, as a tar archive, e.g. to back up the store or
+    /// move it to a new machine with [`ConfigurationStore::import_archive`]
+    ///
+    /// Configurations are exported as-is, so properties which aren't directly supported are preserved
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn export_archive<W: Write>(&self, writer: W) -> Result<()> {
+        let mut entries = Vec::new();
+
+        for configuration in self.configurations()? {
+            let path = &configuration.path;
+
+            entries.push(archive::Entry {
+                path: format!("{}{}", archive::CONFIGURATION_PREFIX, configuration.name),
+                contents: self.storage.read(path).file_context(FileOperation::Read, path)?,
+            });
+        }
+
+        if let Some(active) = self.active() {
+            entries.push(archive::Entry {
+                path: archive::ACTIVE_ENTRY.to_owned(),
+                contents: active.as_bytes().to_vec(),
+            });
+        }
+
+        archive::write(&entries, writer)
+    }
+
+    /// Import every configuration in an archive made by [`ConfigurationStore::export_archive`], resolving any name
+    /// collisions with the given policy
+    ///
+    /// The whole archive is validated and every collision resolved before anything is imported, and the import
+    /// happens while holding the store lock so other processes never see it half done. The configuration which was
+    /// active when the archive was made is activated again, unless it was skipped or renamed because of a collision.
+    /// Returns the imported configurations
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn import_archive<R: Read, C: ConflictResolver>(
+        &mut self,
+        reader: R,
+        conflict: C,
+    ) -> Result<Vec<Configuration>> {
+        let mut active = None;
+        let mut configurations = Vec::new();
+
+        for entry in archive::read(reader)? {
+            if entry.path == archive::ACTIVE_ENTRY {
+                active = Self::parse_active(String::from_utf8_lossy(&entry.contents).trim().to_owned());
+            } else if let Some(name) = entry.path.strip_prefix(archive::CONFIGURATION_PREFIX) {
+                if !Configuration::is_valid_name(name) {
+                    return Err(Error::InvalidName(name.to_owned()));
+                }
+
+                Properties::from_reader(entry.contents.as_slice())?;
+                configurations.push((name.to_owned(), entry.contents));
+            }
+        }
+
+        let _lock = self.lock()?;
+        let mut resolved: Vec<(String, Vec<u8>)> = Vec::new();
+
+        for (name, contents) in configurations {
+            let name = match self.resolve_conflict(&name, &conflict)? {
+                Some(name) => name,
+                None => continue,
+            };
+
+            // two entries mustn't end up overwriting each other, e.g. one renamed to the name of another
+            if resolved.iter().any(|(other, _)| *other == name) {
+                return Err(Error::ExistingConfiguration(name));
+            }
+
+            self.check_unpinned(&name)?;
+            resolved.push((name, contents));
+        }
+
+        let mut imported = Vec::new();
+        let mut backups = Vec::new();
+
+        for (name, contents) in resolved {
+            let (configuration, backup) = self.write_imported(&name, &contents)?;
+            imported.push(configuration);
+            backups.extend(backup);
+        }
+
+        let overwritten = backups.iter().map(|backup| backup.name().to_owned()).collect();
+        self.record_undo(Journal::new(UndoableOperation::Overwritten(overwritten), backups))?;
+
+        if let Some(active) = active.filter(|active| imported.iter().any(|c| &c.name == active)) {
+            let activation = self.write_active(&active)?;
+            self.subscribers.notify(StoreEvent::Activated(activation));
+        }
+
+        Ok(imported)
+    }
+
+    /// Rewrite a configuration in a normalised form, with the properties in each section sorted, consistent
+    /// whitespace and the line endings from the given options
+    ///
+    /// Nothing else is lost, so sections keep their order regardless of the key order in the options, and comments
+    /// are kept with the section or property they belong to. Fails with [`Error::DuplicateKey`] rather than
+    /// dropping a property which is defined more than once. Returns whether the contents of the configuration changed
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn normalize(&self, name: &str, options: &WriteOptions) -> Result<bool> {
+        let _lock = self.lock()?;
+
+        let configuration = self
+            .find_by_name(name)?
+            .ok_or_else(|| Error::UnknownConfiguration(name.to_owned()))?;
+
+        self.check_unpinned(name)?;
+
+        let original = self
+            .storage
+            .read_to_string(&configuration.path)
+            .file_context(FileOperation::Read, &configuration.path)?;
+
+        let mut read_options = ReadOptions::default();
+        read_options.duplicate_keys(DuplicateKeys::Error);
+
+        let mut document = Document::parse(&original, &read_options, &mut Vec::new())?;
+        document.normalize();
+
+        let mut write_options = *options;
+        write_options.key_order(KeyOrder::Preserve);
+
+        let mut normalized = Vec::new();
+        document.write(&mut normalized, &write_options)?;
+
+        if normalized == original.as_bytes() {
+            return Ok(false);
+        }
+
+        // the property values are unchanged, so any cached properties are still valid
+        self.storage.write(&configuration.path, normalized)?;
+
+        Ok(true)
+    }
+
+    /// Rename a configuration
+    ///
+    /// Returns the renamed configuration, whose name differs from `new_name` if the conflict was resolved by renaming,
+    /// or `None` if the rename was skipped. Renaming a configuration to its own name changes nothing
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(old_name = old_name, new_name = new_name))
+    )]
+    pub fn rename<C: ConflictResolver>(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        let _lock = self.lock()?;
+
+        let src = self
+            .find_by_name(old_name)?
+            .ok_or_else(|| Error::UnknownConfiguration(old_name.to_owned()))?;
+
+        // otherwise the conflict resolution would overwrite the configuration with itself and reset its metadata
+        if old_name == new_name {
+            return Ok(Some(src.clone()));
+        }
+
+        let active = self.is_active(src);
+        let src_path = src.path.clone();
+        let last_activated = src.last_activated;
+
+        let new_name = match self.resolve_conflict(new_name, &conflict)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let new_name = new_name.as_str();
+        self.check_unpinned(old_name)?;
+        self.check_unpinned(new_name)?;
+
+        let new_value = Configuration {
+            name: new_name.to_owned(),
+            path: src_path.with_file_name(format!("config_{}", new_name)),
+            properties: OnceLock::new(),
+            last_activated,
+            storage: self.storage.clone(),
+        };
+
+        let backup = self.back_up(new_name)?;
+        self.storage
+            .rename(&src_path, &new_value.path)
+            .file_context(FileOperation::Rename, &src_path)?;
+
+        let mut journal = Journal::new(UndoableOperation::Overwritten(vec![new_name.to_owned()]), backup);
+        journal.active = active.then(|| old_name.to_owned());
+        journal.renamed = Some((old_name.to_owned(), new_name.to_owned()));
+        self.record_undo(journal)?;
+
+        let metadata = self.metadata(old_name)?;
+        self.write_metadata(new_name, &metadata)?;
+        self.write_metadata(old_name, &Metadata::default())?;
+
+        self.configurations_mut()?.remove(old_name);
+        self.configurations_mut()?
+            .insert(new_name.to_owned(), new_value.clone());
+        self.write_name_cache()?;
+
+        // check if the active configuration is the one being renamed
+        if active {
+            self.write_active(new_name)?;
+        }
+
+        self.subscribers.notify(StoreEvent::Renamed {
+            from: old_name.to_owned(),
+            to: new_name.to_owned(),
+        });
+
+        Ok(Some(new_value))
+    }
+
+    /// Decide the name to write a configuration with, resolving any conflict with an existing one
+    ///
+    /// Must be called while holding the lock. Returns `None` if the operation should be skipped
+    fn resolve_conflict(&self, name: &str, conflict: &impl ConflictResolver) -> Result<Option<String>> {
+        if !Configuration::is_valid_name(name) {
+            return Err(Error::InvalidName(name.to_owned()));
+        }
+
+        if !self.exists(name)? {
+            return Ok(Some(name.to_owned()));
+        }
+
+        match conflict.resolve(self, name)? {
+            Resolution::Abort => Err(Error::ExistingConfiguration(name.to_owned())),
+            Resolution::Overwrite => Ok(Some(name.to_owned())),
+            Resolution::Skip => Ok(None),
+            Resolution::Rename(new_name) if !Configuration::is_valid_name(&new_name) => {
+                Err(Error::InvalidName(new_name))
+            }
+            Resolution::Rename(new_name) if self.exists(&new_name)? => Err(Error::ExistingConfiguration(new_name)),
+            Resolution::Rename(new_name) => Ok(Some(new_name)),
+        }
+    }
+
+    /// Check whether a configuration exists, looking at the disk as well as the scan
+    ///
+    /// The scan may have been taken before another process created the configuration, so checking the disk while
+    /// holding the lock stops concurrent creates overwriting each other
+    fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.scan()?.configurations.contains_key(name) || self.storage.exists(&self.configuration_path(name)))
+    }
+
+    /// Path to the file for the named configuration
+    fn configuration_path(&self, name: &str) -> PathBuf {
+        self.configurations_path.join(format!("config_{}", name))
+    }
+
+    /// Get the gcloud-ctx metadata for a configuration
+    pub fn metadata(&self, name: &str) -> Result<Metadata> {
+        let path = self.metadata_path(name);
+
+        match self.storage.read(&path) {
+            Ok(contents) => Metadata::from_reader(contents.as_slice()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Metadata::default()),
+            Err(e) => Err(e).file_context(FileOperation::Read, &path),
+        }
+    }
+
+    /// Replace the gcloud-ctx metadata for a configuration
+    ///
+    /// Setting empty metadata removes any previously stored metadata
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn set_metadata(&mut self, name: &str, metadata: &Metadata) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write_metadata(name, metadata)
+    }
+
+    /// Names of the saved templates, in name order, see [`ConfigurationStore::save_template`]
+    pub fn templates(&self) -> Result<Vec<String>> {
+        let mut names: Vec<String> = cleanup::entries(&self.storage, &self.state.join("templates"))?
+            .into_iter()
+            .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("template_").map(str::to_owned))
+            .collect();
+
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Get a saved template
+    pub fn template(&self, name: &str) -> Result<Template> {
+        let path = self.template_path(name);
+
+        match self.storage.read(&path) {
+            Ok(contents) => Ok(Template::new(Properties::from_reader(contents.as_slice())?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::UnknownTemplate(name.to_owned())),
+            Err(e) => Err(e).file_context(FileOperation::Read, &path),
+        }
+    }
+
+    /// Save a template so that configurations can be created from it, replacing any template with the same name
+    ///
+    /// Templates are kept with the gcloud-ctx state for the store, so gcloud never sees them
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn save_template(&mut self, name: &str, template: &Template) -> Result<()> {
+        if !Configuration::is_valid_name(name) {
+            return Err(Error::InvalidName(name.to_owned()));
+        }
+
+        let _lock = self.lock()?;
+        let path = self.template_path(name);
+
+        if let Some(parent) = path.parent() {
+            self.storage
+                .create_dir_all(parent)
+                .file_context(FileOperation::Create, parent)?;
+        }
+
+        let mut contents = Vec::new();
+        template.properties().to_writer(&mut contents)?;
+
+        self.storage.write(&path, contents)
+    }
+
+    /// Delete a saved template
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn delete_template(&mut self, name: &str) -> Result<()> {
+        let _lock = self.lock()?;
+        let path = self.template_path(name);
+
+        match self.storage.remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Error::UnknownTemplate(name.to_owned())),
+            Err(e) => Err(e).file_context(FileOperation::Delete, &path),
+        }
+    }
+
+    /// Create a new configuration from a saved template, replacing its placeholders with the given variables
+    ///
+    /// Fails with [`Error::MissingTemplateVariable`] if a placeholder has no variable, otherwise the configuration
+    /// is created as [`ConfigurationStore::create`] does
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name, template = template))
+    )]
+    pub fn create_from_template<C: ConflictResolver>(
+        &mut self,
+        name: &str,
+        template: &str,
+        vars: &BTreeMap<String, String>,
+        conflict: C,
+    ) -> Result<Option<Configuration>> {
+        let properties = self.template(template)?.render(vars)?;
+        self.create(name, &properties, conflict)
+    }
+
+    /// Path to the file for the named template
+    fn template_path(&self, name: &str) -> PathBuf {
+        self.state.join("templates").join(format!("template_{}", name))
+    }
+
+    /// Pin a configuration so that modifying it returns [`Error::ConfigurationPinned`]
+    ///
+    /// Returns whether the configuration was pinned, i.e. `false` if it was already pinned
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn pin(&mut self, name: &str) -> Result<bool> {
+        self.set_pinned(name, true)
+    }
+
+    /// Unpin a configuration so that it can be modified again
+    ///
+    /// Returns whether the configuration was unpinned, i.e. `false` if it wasn't pinned
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn unpin(&mut self, name: &str) -> Result<bool> {
+        self.set_pinned(name, false)
+    }
+
+    /// Pin or unpin a configuration, returning whether that changed it
+    fn set_pinned(&mut self, name: &str, pinned: bool) -> Result<bool> {
+        let _lock = self.lock()?;
+
+        if self.find_by_name(name)?.is_none() {
+            return Err(Error::UnknownConfiguration(name.to_owned()));
+        }
+
+        let mut metadata = self.metadata(name)?;
+
+        if metadata.is_pinned() == pinned {
+            return Ok(false);
+        }
+
+        metadata.set_pinned(pinned);
+        self.write_metadata(name, &metadata)?;
+
+        Ok(true)
+    }
+
+    /// Write the gcloud-ctx metadata for a configuration, removing it if it's empty
+    fn write_metadata(&self, name: &str, metadata: &Metadata) -> Result<()> {
+        let path = self.metadata_path(name);
+
+        if metadata.is_empty() {
+            return match self.storage.remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e).file_context(FileOperation::Delete, &path),
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(parent) = path.parent() {
+            self.storage
+                .create_dir_all(parent)
+                .file_context(FileOperation::Create, parent)?;
+        }
+
+        let mut contents = Vec::new();
+        metadata.to_writer(&mut contents)?;
+
+        self.storage.write(&path, contents)
+    }
+
+    /// Path to the metadata file for the named configuration
+    fn metadata_path(&self, name: &str) -> PathBuf {
+        self.state.join("metadata").join(format!("config_{}", name))
+    }
+
+    /// Generate a name with the given prefix which isn't used by any existing configuration
+    pub fn generate_name(&self, prefix: &str) -> Result<GeneratedName> {
+        loop {
+            let name = GeneratedName::new(prefix)?;
+
+            if !self.scan()?.configurations.contains_key(name.as_str()) {
+                return Ok(name);
+            }
+        }
+    }
+
+    /// Rebuild the cache of configuration names used by shell completions and prompts
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn rebuild_name_cache(&self) -> Result<()> {
+        let _lock = self.lock()?;
+        self.write_name_cache()
+    }
+
+    /// Write the current configuration names to the name cache
+    fn write_name_cache(&self) -> Result<()> {
+        NameCache::write(
+            &self.storage,
+            &self.state,
+            self.configurations()?.map(|c| c.name.as_str()),
+        )
+    }
+
+    /// Set a single property of an existing configuration, returning the updated properties
+    ///
+    /// The configuration is rewritten atomically so gcloud never sees a partially written file
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name, section = section, key = key))
+    )]
+    pub fn set_property(&mut self, name: &str, section: &str, key: &str, value: &str) -> Result<Properties> {
+        self.update_properties(name, |properties| {
+            properties.set(section, key, value);
+        })
+    }
+
+    /// Remove a single property from an existing configuration, returning the updated properties
+    ///
+    /// The configuration is rewritten atomically so gcloud never sees a partially written file
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name, section = section, key = key))
+    )]
+    pub fn unset_property(&mut self, name: &str, section: &str, key: &str) -> Result<Properties> {
+        self.update_properties(name, |properties| {
+            properties.unset(section, key);
+        })
+    }
+
+    /// Load the properties of a configuration, modify them and atomically write them back
+    fn update_properties(&mut self, name: &str, update: impl FnOnce(&mut Properties)) -> Result<Properties> {
+        let _lock = self.lock()?;
+        self.check_unpinned(name)?;
+
+        let mut properties = self.describe(name)?;
+        update(&mut properties);
+
+        let mut contents = Vec::new();
+        properties.to_writer(&mut contents)?;
+        self.write_atomically(name, &contents)?;
+
+        Ok(properties)
+    }
+
+    /// Write the contents of a configuration file so that gcloud never sees a partially written file
+    ///
+    /// The temporary file starts with a `.` so it's never loaded as a configuration
+    fn write_atomically(&mut self, name: &str, contents: &[u8]) -> Result<()> {
+        self.storage.write(&self.configuration_path(name), contents)?;
+
+        if let Some(configuration) = self.configurations_mut()?.get_mut(name) {
+            configuration.properties = OnceLock::new();
+        }
+
+        Ok(())
+    }
+
+    /// Back up a configuration before it's overwritten, if it exists
+    ///
+    /// The original is copied rather than moved, so it's untouched if the overwrite fails
+    fn back_up(&mut self, name: &str) -> Result<Option<Backup>> {
+        // checked on disk in case another process created the configuration after the scan
+        let path = self.configuration_path(name);
+
+        if !self.storage.is_file(&path) {
+            return Ok(None);
+        }
+
+        let backup = Backup::create(&self.storage, &self.state, name, &path)?;
+        self.last_backup = Some(backup.clone());
+
+        Ok(Some(backup))
+    }
+
+    /// Record the last destructive operation so that it can be undone, if it overwrote or deleted anything
+    fn record_undo(&self, journal: Journal) -> Result<()> {
+        if journal.restore.is_empty() {
+            return Ok(());
+        }
+
+        journal.write(&self.storage, &self.state)
+    }
+
+    /// Undo the last operation which overwrote or deleted configurations, e.g. an accidental `--force` or delete
+    ///
+    /// The configurations are restored from the backups the operation made, and the configuration which was active
+    /// before the operation is activated again if the operation changed it. The current versions are backed up first,
+    /// so undoing can itself be reverted with [`ConfigurationStore::restore`]. Metadata such as tags isn't part of
+    /// the backups, so it isn't restored. Returns the operation which was undone
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn undo_last(&mut self) -> Result<UndoableOperation> {
+        let _lock = self.lock()?;
+
+        let journal = Journal::read(&self.storage, &self.state)?.ok_or(Error::NothingToUndo)?;
+        let mut restore = Vec::new();
+
+        // check and read everything before changing anything
+        if let Some((from, to)) = &journal.renamed {
+            self.check_unpinned(to)?;
+
+            if self.exists(from)? {
+                return Err(Error::ExistingConfiguration(from.to_owned()));
+            }
+        }
+
+        for (name, backup) in &journal.restore {
+            self.check_unpinned(name)?;
+            restore.push((
+                name,
+                self.storage.read(backup).file_context(FileOperation::Read, backup)?,
+            ));
+        }
+
+        if let Some((from, to)) = &journal.renamed {
+            let (from_path, to_path) = (self.configuration_path(from), self.configuration_path(to));
+            self.storage
+                .rename(&to_path, &from_path)
+                .file_context(FileOperation::Rename, &to_path)?;
+
+            let metadata = self.metadata(to)?;
+            self.write_metadata(from, &metadata)?;
+            self.write_metadata(to, &Metadata::default())?;
+
+            self.configurations_mut()?.remove(to);
+            self.track(from)?;
+        }
+
+        for (name, contents) in restore {
+            self.back_up(name)?;
+            self.write_atomically(name, &contents)?;
+            self.track(name)?;
+        }
+
+        for (_, backup) in &journal.restore {
+            self.storage
+                .remove_file(backup)
+                .file_context(FileOperation::Delete, backup)?;
+        }
+
+        Journal::clear(&self.storage, &self.state)?;
+
+        if let Some(active) = &journal.active {
+            if self.find_by_name(active)?.is_some() {
+                let activation = self.write_active(active)?;
+                self.subscribers.notify(StoreEvent::Activated(activation));
+            }
+        }
+
+        Ok(journal.operation)
+    }
+
+    /// Add a configuration which was written directly to disk, e.g. by restoring a backup, if it isn't known already
+    fn track(&mut self, name: &str) -> Result<()> {
+        if self.scan()?.configurations.contains_key(name) {
+            return Ok(());
+        }
+
+        let configuration = Configuration {
+            name: name.to_owned(),
+            path: self.configuration_path(name),
+            properties: OnceLock::new(),
+            last_activated: None,
+            storage: self.storage.clone(),
+        };
+        self.configurations_mut()?.insert(name.to_owned(), configuration);
+
+        self.write_name_cache()
+    }
+
+    /// Get the most recent backup made by this store before it overwrote or deleted a configuration
+    pub fn last_backup(&self) -> Option<&Backup> {
+        self.last_backup.as_ref()
+    }
+
+    /// List the backups of a configuration, newest first
+    pub fn backups(&self, name: &str) -> Result<Vec<Backup>> {
+        Backup::list(&self.storage, &self.state, name)
+    }
+
+    /// Configurations which were deleted and can still be restored with [`ConfigurationStore::restore`], most
+    /// recently deleted first
+    ///
+    /// Each one is the most recent backup of a configuration which no longer exists. They're kept for the trash
+    /// retention period, 30 days unless changed with [`ConfigurationStore::with_trash_retention`], after which
+    /// [`ConfigurationStore::find_clutter`] reports them as old backups
+    pub fn list_trashed(&self) -> Result<Vec<Backup>> {
+        let mut trashed = Vec::new();
+
+        for name in self.backed_up_names()? {
+            if self.find_by_name(&name)?.is_none() {
+                trashed.extend(Backup::list(&self.storage, &self.state, &name)?.into_iter().next());
+            }
+        }
+
+        trashed.sort_by_key(|backup| std::cmp::Reverse(backup.created()));
+
+        Ok(trashed)
+    }
+
+    /// Names of the configurations which have backups, whether or not they still exist
+    fn backed_up_names(&self) -> Result<Vec<String>> {
+        let names = cleanup::entries(&self.storage, &self.state.join("backups"))?
+            .into_iter()
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                file_name.to_str()?.strip_prefix("config_").map(str::to_owned)
+            })
+            .collect();
+
+        Ok(names)
+    }
+
+    /// Restore the most recent backup of a configuration
+    ///
+    /// The current version of the configuration, if there is one, is backed up first so that restoring can be undone
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, err, fields(name = name))
+    )]
+    pub fn restore(&mut self, name: &str) -> Result<Backup> {
+        let _lock = self.lock()?;
+
+        let backup = self
+            .backups(name)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::NoBackups(name.to_owned()))?;
+
+        self.check_unpinned(name)?;
+
+        let contents = self
+            .storage
+            .read(backup.path())
+            .file_context(FileOperation::Read, backup.path())?;
+        self.back_up(name)?;
+        self.write_atomically(name, &contents)?;
+        self.storage
+            .remove_file(backup.path())
+            .file_context(FileOperation::Delete, backup.path())?;
+        self.track(name)?;
+
+        Ok(backup)
+    }
+
+    /// Find files which can be removed to tidy up the store and its state directory, e.g. old backups and gcloud logs
+    ///
+    /// Log files are only included if they haven't been modified for `max_log_age`, and the backups of deleted
+    /// configurations once they've been in the trash for longer than the trash retention period. Credentials are only included
+    /// for accounts which the backups of deleted configurations used and no remaining configuration uses, so
+    /// credentials for accounts gcloud-ctx knows nothing about are never touched. Nothing is removed, see
+    /// [`ConfigurationStore::remove_clutter`]
+    ///
+    /// Fails if any configuration can't be read, because it may use credentials which would otherwise be removed
+    pub fn find_clutter(&self, max_log_age: Duration) -> Result<Vec<Clutter>> {
+        let mut backups = Vec::new();
+        let mut deleted_accounts = Vec::new();
+
+        let trash_cutoff = SystemTime::now()
+            .checked_sub(self.trash_retention)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        for name in self.backed_up_names()? {
+            let exists = self.find_by_name(&name)?.is_some();
+            let all = Backup::list(&self.storage, &self.state, &name)?;
+            let trashed = !exists && all.first().is_some_and(|backup| backup.created() >= trash_cutoff);
+
+            // credentials are still needed while the configuration can be restored from the trash
+            if !exists && !trashed {
+                deleted_accounts.extend(all.iter().filter_map(|backup| backup.account(&self.storage)));
+            }
+
+            // keep the most recent backup so that the last overwrite or delete can always be undone
+            let keep = usize::from(exists || trashed);
+            backups.extend(all.iter().skip(keep).map(|backup| backup.path().to_path_buf()));
+        }
+
+        let configurations = &self.scan()?.configurations;
+        let metadata = cleanup::entries(&self.storage, &self.state.join("metadata"))?
+            .into_iter()
+            .filter(|entry| {
+                let file_name = entry.file_name();
+                let name = file_name.to_str().and_then(|n| n.strip_prefix("config_"));
+                name.is_some_and(|name| !configurations.contains_key(name))
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        let cutoff = SystemTime::now()
+            .checked_sub(max_log_age)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut logs = Vec::new();
+
+        for day in cleanup::entries(&self.storage, &self.location.join("logs"))? {
+            for log in cleanup::entries(&self.storage, &day.path())? {
+                let stale = self.storage.metadata(&log.path()).is_ok_and(|metadata| {
+                    metadata.is_file() && metadata.modified().is_ok_and(|modified| modified < cutoff)
+                });
+
+                if stale {
+                    logs.push(log.path());
+                }
+            }
+        }
+
+        let mut accounts = Vec::new();
+
+        for (_, properties) in self.describe_all()? {
+            accounts.extend(properties?.get("core", "account").map(str::to_owned));
+        }
+
+        let credentials = cleanup::entries(&self.storage, &self.location.join("legacy_credentials"))?
+            .into_iter()
+            .filter(|entry| {
+                let account = entry.file_name().to_string_lossy().into_owned();
+                deleted_accounts.contains(&account) && !accounts.contains(&account)
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        Ok([
+            Clutter::new(&self.storage, ClutterKind::Backups, backups),
+            Clutter::new(&self.storage, ClutterKind::OrphanedMetadata, metadata),
+            Clutter::new(&self.storage, ClutterKind::StaleLogs, logs),
+            Clutter::new(&self.storage, ClutterKind::UnusedCredentials, credentials),
+        ]
+        .into_iter()
+        .flatten()
+        .collect())
+    }
+
+    /// Remove clutter found by [`ConfigurationStore::find_clutter`]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, err))]
+    pub fn remove_clutter(&mut self, clutter: &Clutter) -> Result<()> {
+        let _lock = self.lock()?;
+        clutter.remove(&self.storage)
+    }
+
+    /// Find a configuration by name
+    pub fn find_by_name(&self, name: &str) -> Result<Option<&Configuration>> {
+        Ok(self.scan()?.configurations.get(name))
+    }
+
+    /// Find a configuration by its exact name, or by an unambiguous prefix of its name
+    ///
+    /// Fails with [`Error::Ambiguous`] if the prefix matches several configurations, or
+    /// [`Error::UnknownConfiguration`] if nothing matches
+    pub fn resolve(&self, name: &str) -> Result<&Configuration> {
+        self.resolve_with(name, NameMatch::Prefix)
+    }
+
+    /// Find a configuration by name, matching as loosely as the given [`NameMatch`]
+    ///
+    /// An exact name always wins, then a prefix, then (if enabled) the closest fuzzy match. Fails with
+    /// [`Error::Ambiguous`] listing the candidates in natural order if there's no single best match
+    pub fn resolve_with(&self, name: &str, matching: NameMatch) -> Result<&Configuration> {
+        let configurations: Vec<&Configuration> = self.configurations()?.collect();
+
+        if let Some(configuration) = configurations.iter().find(|c| c.name() == name) {
+            return Ok(configuration);
+        }
+
+        let prefixed: Vec<&Configuration> = configurations
+            .iter()
+            .copied()
+            .filter(|c| c.name().starts_with(name))
+            .collect();
+
+        let candidates = if !prefixed.is_empty() || matching == NameMatch::Prefix {
+            prefixed
+        } else {
+            let distances: Vec<(usize, &Configuration)> = configurations
+                .iter()
+                .map(|c| (edit_distance(name, c.name()), *c))
+                .filter(|(distance, _)| *distance <= max_typos(name))
+                .collect();
+
+            let closest = distances.iter().map(|(distance, _)| *distance).min();
+
+            distances
+                .into_iter()
+                .filter(|(distance, _)| Some(*distance) == closest)
+                .map(|(_, c)| c)
+                .collect()
+        };
+
+        match candidates.as_slice() {
+            [] => Err(Error::UnknownConfiguration(name.to_owned())),
+            [configuration] => Ok(configuration),
+            _ => Err(Error::Ambiguous(
+                candidates.iter().map(|c| c.name().to_owned()).collect(),
+            )),
+        }
+    }
+
+    /// Find every configuration whose `core/project` is the given project, in natural order
+    ///
+    /// Configurations which can't be parsed are skipped, see [`ConfigurationStore::query`]
+    pub fn find_by_project(&self, project: &str) -> Result<Vec<&Configuration>> {
+        Ok(self
+            .query(Filter::default().project(project))?
+            .into_iter()
+            .map(|(configuration, _)| configuration)
+            .collect())
+    }
+
+    /// Find every configuration whose `core/account` is the given account, in natural order
+    ///
+    /// Configurations which can't be parsed are skipped, see [`ConfigurationStore::query`]
+    pub fn find_by_account(&self, account: &str) -> Result<Vec<&Configuration>> {
+        Ok(self
+            .query(Filter::default().account(account))?
+            .into_iter()
+            .map(|(configuration, _)| configuration)
+            .collect())
+    }
+
+    /// Find every configuration which matches the filter, along with its properties, in natural order
+    ///
+    /// Configurations which can't be parsed are skipped, but permission being denied to read one fails with
+    /// [`Error::PermissionDenied`] rather than making it look like it doesn't match
+    pub fn query(&self, filter: &Filter) -> Result<Vec<(&Configuration, &Properties)>> {
+        let mut matches = Vec::new();
+
+        for configuration in self.configurations()?.filter(|c| filter.matches_name(c.name())) {
+            let properties = match configuration.properties() {
+                Ok(properties) => properties,
+                Err(e @ Error::PermissionDenied { .. }) => return Err(e),
+                Err(_) => continue,
+            };
+
+            if filter.matches(configuration.name(), properties) {
+                matches.push((configuration, properties));
+            }
+        }
+
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{ConflictAction, Severity};
+    use std::{
+        fs::{self, File},
+        io,
+    };
+
+    fn temp_store() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("configurations").join("config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+        tmp
+    }
+
+    /// Open the store at the given location, keeping its state inside it so that tests never touch the real state
+    /// directory
+    pub(crate) fn open_store(location: PathBuf) -> Result<ConfigurationStore> {
+        let state = location.join("state");
+        ConfigurationStore::builder()
+            .location(location)
+            .state_location(state)
+            .open()
+    }
+
+    #[test]
+    pub fn test_resolve() {
+        let tmp = temp_store();
+        for name in ["prod-eu", "prod-us", "staging"] {
+            fs::write(tmp.path().join("configurations").join(format!("config_{}", name)), "").unwrap();
+        }
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(store.resolve("foo").unwrap().name(), "foo");
+        assert_eq!(store.resolve("stag").unwrap().name(), "staging");
+        assert_eq!(store.resolve("prod-e").unwrap().name(), "prod-eu");
+
+        assert!(matches!(
+            store.resolve("prod"),
+            Err(Error::Ambiguous(names)) if names == ["prod-eu", "prod-us"]
+        ));
+        assert!(matches!(store.resolve("stagign"), Err(Error::UnknownConfiguration(_))));
+        assert!(matches!(store.resolve("bar"), Err(Error::UnknownConfiguration(_))));
+    }
+
+    #[test]
+    pub fn test_resolve_fuzzy() {
+        let tmp = temp_store();
+        for name in ["prod-eu", "prod-us", "staging"] {
+            fs::write(tmp.path().join("configurations").join(format!("config_{}", name)), "").unwrap();
+        }
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(
+            store.resolve_with("stagign", NameMatch::Fuzzy).unwrap().name(),
+            "staging"
+        );
+        assert_eq!(
+            store.resolve_with("prdo-eu", NameMatch::Fuzzy).unwrap().name(),
+            "prod-eu"
+        );
+        assert_eq!(store.resolve_with("stag", NameMatch::Fuzzy).unwrap().name(), "staging");
+
+        assert!(matches!(
+            store.resolve_with("prod-xx", NameMatch::Fuzzy),
+            Err(Error::Ambiguous(names)) if names == ["prod-eu", "prod-us"]
+        ));
+        assert!(matches!(
+            store.resolve_with("qwerty", NameMatch::Fuzzy),
+            Err(Error::UnknownConfiguration(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_with_location_skips_directories() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::create_dir(configurations.join("config_backup")).unwrap();
+        fs::create_dir(configurations.join("archive")).unwrap();
+
+        let store = ConfigurationStore::with_location(tmp.path().to_path_buf()).unwrap();
+
+        let names: Vec<&str> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["foo"]);
+
+        let mut invalid: Vec<&InvalidEntry> = store.diagnostics().unwrap().iter().collect();
+        invalid.sort_by_key(|e| e.path().to_path_buf());
+        assert_eq!(invalid.len(), 2);
+        assert_eq!(invalid[0].path(), configurations.join("archive"));
+        assert_eq!(invalid[0].reason(), InvalidEntryReason::Directory);
+        assert_eq!(invalid[1].path(), configurations.join("config_backup"));
+        assert_eq!(invalid[1].reason(), InvalidEntryReason::Directory);
+    }
+
+    #[test]
+    pub fn test_diagnostics_report_invalid_names() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo~"), "").unwrap();
+        fs::write(configurations.join("config_My Config"), "").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let names: Vec<&str> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["foo"]);
+
+        let mut diagnostics: Vec<&InvalidEntry> = store.diagnostics().unwrap().iter().collect();
+        diagnostics.sort_by_key(|e| e.path().to_path_buf());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].path(), configurations.join("config_My Config"));
+        assert_eq!(diagnostics[0].reason(), InvalidEntryReason::InvalidName);
+        assert_eq!(diagnostics[1].path(), configurations.join("config_foo~"));
+
+        assert_eq!(store.warnings().unwrap().len(), 2);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_diagnostics_report_names_which_arent_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        let path = configurations.join(std::ffi::OsStr::from_bytes(b"config_\xff"));
+
+        // some filesystems, e.g. on macOS, refuse names which aren't UTF-8
+        if fs::write(&path, "").is_err() {
+            return;
+        }
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(store.diagnostics().unwrap().len(), 1);
+        assert_eq!(store.diagnostics().unwrap()[0].reason(), InvalidEntryReason::NotUtf8);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_with_location_skips_symlinks_to_directories() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        let target = tmp.path().join("elsewhere");
+        fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&target, configurations.join("config_linked")).unwrap();
+        std::os::unix::fs::symlink(configurations.join("config_foo"), configurations.join("config_bar")).unwrap();
+
+        let store = ConfigurationStore::with_location(tmp.path().to_path_buf()).unwrap();
+
+        let names: Vec<&str> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["bar", "foo"]);
+        assert_eq!(store.diagnostics().unwrap().len(), 1);
+        assert_eq!(store.diagnostics().unwrap()[0].reason(), InvalidEntryReason::Directory);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    pub fn test_with_location_skips_special_files() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        let fifo = configurations.join("config_fifo");
+
+        let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+
+        std::os::unix::fs::symlink(tmp.path().join("missing"), configurations.join("config_broken")).unwrap();
+
+        let store = ConfigurationStore::with_location(tmp.path().to_path_buf()).unwrap();
+
+        let names: Vec<&str> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["foo"]);
+
+        let mut reasons: Vec<InvalidEntryReason> = store.diagnostics().unwrap().iter().map(|e| e.reason()).collect();
+        reasons.sort_by_key(|r| *r as u8);
+        assert_eq!(
+            reasons,
+            vec![InvalidEntryReason::SpecialFile, InvalidEntryReason::Unreadable]
+        );
+    }
+
+    #[test]
+    pub fn test_metadata_round_trip() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.metadata("foo").unwrap().is_empty());
+
+        let mut metadata = Metadata::default();
+        metadata.set(Metadata::SDK_VERSION, "400.0.0");
+        store.set_metadata("foo", &metadata).unwrap();
+
+        assert_eq!(store.metadata("foo").unwrap().sdk_version(), Some("400.0.0"));
+    }
+
+    #[test]
+    pub fn test_metadata_follows_rename_and_delete() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations").join("config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let mut metadata = Metadata::default();
+        metadata.set(Metadata::SDK_VERSION, "400.0.0");
+        store.set_metadata("bar", &metadata).unwrap();
+
+        store.rename("bar", "baz", ConflictAction::Abort).unwrap();
+        assert!(store.metadata("bar").unwrap().is_empty());
+        assert_eq!(store.metadata("baz").unwrap(), metadata);
+
+        store.delete("baz").unwrap();
+        assert!(store.metadata("baz").unwrap().is_empty());
+        assert!(!store.state_location().join("metadata/config_baz").exists());
+    }
+
+    #[test]
+    pub fn test_store_only_contains_gcloud_files() {
+        let tmp = temp_store();
+        let state = tempfile::tempdir().unwrap();
+        let mut store = ConfigurationStore::builder()
+            .location(tmp.path())
+            .state_location(state.path())
+            .open()
+            .unwrap();
+
+        store.pin("foo").unwrap();
+        store.copy("foo", "bar", ConflictAction::Abort).unwrap();
+        store.copy("foo", "bar", ConflictAction::Overwrite).unwrap();
+        store.rebuild_name_cache().unwrap();
+
+        let mut entries: Vec<_> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        entries.sort();
+
+        assert_eq!(entries, vec!["active_config", "configurations"]);
+        assert!(store.state_location().join("metadata/config_foo").is_file());
+        assert_eq!(store.backups("bar").unwrap().len(), 1);
+    }
+
+    #[test]
+    pub fn test_open_migrates_legacy_files() {
+        let tmp = temp_store();
+        fs::create_dir_all(tmp.path().join("gctx/metadata")).unwrap();
+        fs::write(tmp.path().join("gctx/metadata/config_foo"), "[gctx]\npinned = true\n").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.metadata("foo").unwrap().is_pinned());
+        assert!(!tmp.path().join("gctx").exists());
+    }
+
+    #[test]
+    pub fn test_read_only_open_leaves_legacy_files() {
+        let tmp = temp_store();
+        fs::create_dir_all(tmp.path().join("gctx/metadata")).unwrap();
+        fs::write(tmp.path().join("gctx/metadata/config_foo"), "[gctx]\npinned = true\n").unwrap();
+
+        ConfigurationStore::builder()
+            .location(tmp.path())
+            .read_only(true)
+            .open()
+            .unwrap();
+
+        assert!(tmp.path().join("gctx/metadata/config_foo").exists());
+    }
+
+    #[test]
+    pub fn test_import_preserves_contents() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        let contents = "[core]\nproject = my-project\n\n[auth]\ndisable_credentials = true\n";
+
+        store.import("bar", contents.as_bytes(), ConflictAction::Abort).unwrap();
+
+        assert!(store.find_by_name("bar").unwrap().is_some());
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("configurations/config_bar")).unwrap(),
+            contents
+        );
+    }
+
+    #[test]
+    pub fn test_import_invalid_contents_fails() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let result = store.import("bar", "not a configuration".as_bytes(), ConflictAction::Abort);
+
+        assert!(matches!(result, Err(Error::LoadingProperties { .. })));
+        assert!(store.find_by_name("bar").unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_read_only_store_rejects_modifications() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap().into_read_only();
+
+        assert!(store.is_read_only());
+        assert!(matches!(store.activate("foo"), Err(Error::ReadOnlyStore(_))));
+        assert!(matches!(
+            store.copy("foo", "bar", ConflictAction::Abort),
+            Err(Error::ReadOnlyStore(_))
+        ));
+        assert!(matches!(
+            store.rename("foo", "bar", ConflictAction::Abort),
+            Err(Error::ReadOnlyStore(_))
+        ));
+        assert!(matches!(
+            store.set_metadata("foo", Metadata::default().set("key", "value")),
+            Err(Error::ReadOnlyStore(_))
+        ));
+        assert!(store.describe("foo").is_ok());
+        assert!(!tmp.path().join("configurations/config_bar").exists());
+    }
+
+    #[test]
+    pub fn test_natural_cmp() {
+        let mut names = vec!["env-10", "env-2", "env-1", "env", "env-02", "env-1b", "alpha"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+
+        assert_eq!(
+            names,
+            vec!["alpha", "env", "env-1", "env-1b", "env-02", "env-2", "env-10"]
+        );
+    }
+
+    #[test]
+    pub fn test_configurations_sorted() {
+        let tmp = temp_store();
+        for name in ["env-10", "env-2"] {
+            fs::write(tmp.path().join("configurations").join(format!("config_{}", name)), "").unwrap();
+        }
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let natural: Vec<&str> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(natural, vec!["env-2", "env-10", "foo"]);
+
+        let mut configurations = store.configurations().unwrap();
+        assert_eq!(configurations.len(), 3);
+        assert_eq!(configurations.next_back().map(|c| c.name()), Some("foo"));
+
+        let lexical: Vec<&str> = store
+            .configurations_sorted(SortOrder::Lexical)
+            .unwrap()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(lexical, vec!["env-10", "env-2", "foo"]);
+    }
+
+    #[test]
+    pub fn test_name_cache_updated_on_mutation() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(
+            NameCache::read_in(&Storage::Disk, store.state_location(), tmp.path()).unwrap(),
+            None
+        );
+
+        store.copy("foo", "bar", ConflictAction::Abort).unwrap();
+        assert_eq!(
+            NameCache::read_in(&Storage::Disk, store.state_location(), tmp.path()).unwrap(),
+            Some(vec!["bar".to_owned(), "foo".to_owned()])
+        );
+
+        store.delete("bar").unwrap();
+        assert_eq!(
+            NameCache::read_in(&Storage::Disk, store.state_location(), tmp.path()).unwrap(),
+            Some(vec!["foo".to_owned()])
+        );
+    }
+
+    #[test]
+    pub fn test_name_cache_stale_after_external_change() {
+        let tmp = temp_store();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        store.rebuild_name_cache().unwrap();
+
+        // simulate gcloud adding a configuration after the cache was written
+        let past = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(NameCache::file(store.state_location()))
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+
+        assert_eq!(
+            NameCache::read_in(&Storage::Disk, store.state_location(), tmp.path()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_lazy_store_scans_on_first_use() {
+        let tmp = temp_store();
+        let store = ConfigurationStore::with_location_lazy(tmp.path().to_path_buf()).unwrap();
+
+        // added after opening, so only visible if the scan is deferred
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(store.configurations().unwrap().len(), 2);
+        assert!(store.find_by_name("bar").unwrap().is_some());
+    }
+
+    #[test]
+    pub fn test_history_and_previous() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        fs::write(tmp.path().join("configurations/config_baz"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.history().unwrap().is_empty());
+        assert!(store.previous().unwrap().is_none());
+
+        store.activate("bar").unwrap();
+        store.activate("baz").unwrap();
+        store.activate("foo").unwrap();
+        store.activate("baz").unwrap();
+
+        let names: Vec<String> = store
+            .history()
+            .unwrap()
+            .iter()
+            .map(|entry| entry.name().to_owned())
+            .collect();
+        assert_eq!(names, vec!["baz", "foo", "baz", "bar"]);
+        assert_eq!(store.previous().unwrap().unwrap().name(), "foo");
+
+        // deleted configurations are skipped
+        store.delete("foo").unwrap();
+        assert_eq!(store.previous().unwrap().unwrap().name(), "bar");
+
+        // only gcloud's own files are kept in the store
+        assert!(!tmp.path().join("history").exists());
+    }
+
+    #[test]
+    pub fn test_last_activated() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        let last_activated =
+            |store: &ConfigurationStore, name| store.find_by_name(name).unwrap().unwrap().last_activated();
+
+        assert_eq!(last_activated(&store, "bar"), None);
+
+        let before = SystemTime::now() - Duration::from_secs(1);
+        store.activate("bar").unwrap();
+        assert!(last_activated(&store, "bar").is_some_and(|time| time >= before));
+
+        store.rename("bar", "baz", ConflictAction::Abort).unwrap();
+        let renamed = last_activated(&store, "baz");
+        assert!(renamed.is_some());
+
+        // reopening reads it back from the activation history
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        assert_eq!(last_activated(&store, "foo"), None);
+        assert!(last_activated(&store, "baz").is_some());
+    }
+
+    #[test]
+    pub fn test_active_name_only() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        // no configurations directory, so opening the store would fail
+        fs::write(tmp.path().join("active_config"), "foo\n").unwrap();
+        assert_eq!(
+            ConfigurationStore::active_name_only(tmp.path()).unwrap(),
+            Some("foo".to_owned())
+        );
+
+        fs::write(tmp.path().join("active_config"), "NONE").unwrap();
+        assert_eq!(ConfigurationStore::active_name_only(tmp.path()).unwrap(), None);
+
+        fs::remove_file(tmp.path().join("active_config")).unwrap();
+        assert!(matches!(
+            ConfigurationStore::active_name_only(tmp.path()),
+            Err(Error::File { .. })
+        ));
+    }
+
+    #[test]
+    pub fn test_lazy_store_returns_scan_errors() {
+        let tmp = temp_store();
+        let mut store = ConfigurationStore::builder()
+            .location(tmp.path())
+            .state_location(tmp.path().join("state"))
+            .lazy(true)
+            .open()
+            .unwrap();
+
+        // an unreadable directory must not look like an empty store, or an abort conflict could overwrite files
+        fs::remove_dir_all(tmp.path().join("configurations")).unwrap();
+
+        assert!(matches!(store.configurations(), Err(Error::File { .. })));
+        assert!(matches!(store.find_by_name("foo"), Err(Error::File { .. })));
+        assert!(store
+            .create("foo", &Properties::default(), ConflictAction::Abort)
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_read_only_lazy_store_never_migrates() {
+        let tmp = temp_store();
+        fs::create_dir_all(tmp.path().join("gctx/metadata")).unwrap();
+        fs::write(tmp.path().join("gctx/metadata/config_foo"), "[gctx]\npinned=true\n").unwrap();
+
+        let store = ConfigurationStore::builder()
+            .location(tmp.path())
+            .state_location(tmp.path().join("state"))
+            .lazy(true)
+            .read_only(true)
+            .open()
+            .unwrap();
+
+        assert!(store.is_read_only());
+        assert!(tmp.path().join("gctx/metadata/config_foo").exists());
+        assert!(!tmp.path().join("state").exists());
+    }
+
+    #[test]
+    pub fn test_active_ignores_surrounding_whitespace() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("active_config"), "foo\r\n").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let foo = store.find_by_name("foo").unwrap().unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert!(store.is_active(foo));
+        assert_eq!(store.active_configuration().unwrap().name(), "foo");
+    }
+
+    #[test]
+    pub fn test_errors_have_stable_codes() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let existing = store.copy("foo", "foo", ConflictAction::Abort).unwrap_err();
+        let unknown = store.activate("bar").unwrap_err();
+        let active = store.delete("foo").unwrap_err();
+
+        assert_eq!(existing.code(), "EXISTING_CONFIGURATION");
+        assert_eq!(unknown.code(), "UNKNOWN_CONFIGURATION");
+        assert_eq!(active.code(), "DELETE_ACTIVE_CONFIGURATION");
+    }
+
+    #[test]
+    pub fn test_permission_denied_is_distinguished() {
+        let path = Path::new("config_foo");
+        let denied: io::Result<()> = Err(io::ErrorKind::PermissionDenied.into());
+        let missing: io::Result<()> = Err(io::ErrorKind::NotFound.into());
+
+        let error = denied.file_context(FileOperation::Read, path).unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::PermissionDenied {
+                operation: FileOperation::Read,
+                ..
+            }
+        ));
+        assert_eq!(error.code(), "PERMISSION_DENIED");
+        assert_eq!(error.to_string(), "Permission denied trying to read config_foo");
+
+        assert!(matches!(
+            missing.file_context(FileOperation::Read, path),
+            Err(Error::File { .. })
+        ));
+
+        assert_eq!(
+            InvalidEntryReason::unreadable(&io::ErrorKind::PermissionDenied.into()),
+            InvalidEntryReason::PermissionDenied
+        );
+        assert_eq!(
+            InvalidEntryReason::unreadable(&io::ErrorKind::NotFound.into()),
+            InvalidEntryReason::Unreadable
+        );
+    }
+
+    #[test]
+    pub fn test_io_errors_include_the_path() {
+        let tmp = temp_store();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let path = tmp.path().join("configurations").join("config_foo");
+
+        // removed behind the store's back, e.g. by gcloud
+        fs::remove_file(&path).unwrap();
+
+        let error = store.describe("foo").unwrap_err();
+
+        assert!(matches!(
+            &error,
+            Error::File { operation: FileOperation::Read, path: p, .. } if *p == path
+        ));
+        assert_eq!(error.to_string(), format!("Failed to read {}", path.display()));
+        assert_eq!(error.io_error().unwrap().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    pub fn test_find_and_remove_clutter() {
+        let tmp = temp_store();
+        let location = tmp.path();
+        fs::write(
+            location.join("configurations/config_foo"),
+            "[core]\naccount = used@example.org\n",
+        )
+        .unwrap();
+
+        let mut store = open_store(location.to_path_buf()).unwrap();
+        let state = store.state_location().to_path_buf();
+
+        for backup in ["config_foo/1", "config_foo/2", "config_gone/3", "config_gone/4"] {
+            fs::create_dir_all(state.join("backups").join(backup).parent().unwrap()).unwrap();
+            fs::write(state.join("backups").join(backup), "backup").unwrap();
+        }
+
+        // only the deleted configuration's accounts are candidates for removal
+        fs::write(
+            state.join("backups/config_gone/4"),
+            "[core]\naccount = unused@example.org\n",
+        )
+        .unwrap();
+
+        fs::create_dir_all(state.join("metadata")).unwrap();
+        fs::write(state.join("metadata/config_foo"), "[gctx]\ntags = prod\n").unwrap();
+        fs::write(state.join("metadata/config_gone"), "[gctx]\ntags = prod\n").unwrap();
+
+        fs::create_dir_all(location.join("logs/2020.01.01")).unwrap();
+        let old_log = location.join("logs/2020.01.01/old.log");
+        fs::write(&old_log, "old").unwrap();
+        File::options()
+            .write(true)
+            .open(&old_log)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        fs::write(location.join("logs/2020.01.01/new.log"), "new").unwrap();
+
+        fs::create_dir_all(location.join("legacy_credentials/used@example.org")).unwrap();
+        fs::create_dir_all(location.join("legacy_credentials/unused@example.org")).unwrap();
+        fs::write(location.join("legacy_credentials/unused@example.org/adc.json"), "{}").unwrap();
+        fs::create_dir_all(location.join("legacy_credentials/unknown@example.org")).unwrap();
+
+        let clutter = store.find_clutter(Duration::from_secs(86400)).unwrap();
+        let found: Vec<_> = clutter.iter().map(|c| (c.kind(), c.paths().to_vec())).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (
+                    ClutterKind::Backups,
+                    vec![
+                        state.join("backups/config_foo/1"),
+                        state.join("backups/config_gone/3"),
+                        state.join("backups/config_gone/4")
+                    ]
+                ),
+                (ClutterKind::OrphanedMetadata, vec![state.join("metadata/config_gone")]),
+                (ClutterKind::StaleLogs, vec![old_log.clone()]),
+                (
+                    ClutterKind::UnusedCredentials,
+                    vec![location.join("legacy_credentials/unused@example.org")]
+                ),
+            ]
+        );
+        assert_eq!(clutter[0].size(), 48);
+        assert_eq!(clutter[3].size(), 2);
+
+        for clutter in &clutter {
+            store.remove_clutter(clutter).unwrap();
+        }
+
+        assert!(store.find_clutter(Duration::from_secs(86400)).unwrap().is_empty());
+        assert!(state.join("backups/config_foo/2").exists());
+        assert!(!state.join("backups/config_gone").exists());
+        assert!(location.join("logs/2020.01.01/new.log").exists());
+        assert!(location.join("legacy_credentials/used@example.org").exists());
+        assert!(location.join("legacy_credentials/unknown@example.org").exists());
+    }
+
+    #[test]
+    pub fn test_find_clutter_fails_for_unreadable_configuration() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_foo"), "[core\n").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.find_clutter(Duration::from_secs(86400)).is_err());
+    }
+
+    #[test]
+    pub fn test_check_reports_findings() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(tmp.path().join("active_config"), "gone").unwrap();
+        fs::write(configurations.join("config_bad"), "[core\n").unwrap();
+        fs::write(
+            configurations.join("config_zones"),
+            "[compute]\nzone = europe-west1-d\nregion = us-east1\n",
+        )
+        .unwrap();
+        fs::write(configurations.join("config_not valid"), "").unwrap();
+        fs::create_dir(configurations.join("config_dir")).unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let findings = store.check().unwrap();
+
+        assert!(findings.contains(&Finding::DanglingActive("gone".to_owned())));
+        assert!(findings.contains(&Finding::InvalidName(configurations.join("config_not valid"))));
+        assert!(findings
+            .iter()
+            .any(|finding| matches!(finding, Finding::Unparsable { name, .. } if name == "bad")));
+        assert!(findings.contains(&Finding::Property {
+            name: "zones".to_owned(),
+            warning: Warning::ZoneOutsideRegion {
+                zone: "europe-west1-d".to_owned(),
+                region: "us-east1".to_owned()
+            }
+        }));
+        assert!(findings
+            .iter()
+            .any(|finding| matches!(finding, Finding::SkippedEntry(entry) if entry.path().ends_with("config_dir"))));
+        assert_eq!(findings.len(), 5, "{:#?}", findings);
+        assert_eq!(findings[0].severity(), Severity::Error);
+    }
+
+    #[test]
+    pub fn test_active_configuration_missing() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("active_config"), "bar").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(matches!(
+            store.active_configuration(),
+            Err(Error::ActiveConfigurationNotFound(name)) if name == "bar"
+        ));
+    }
+
+    #[test]
+    pub fn test_deactivate() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(store.deactivate().unwrap(), Some("foo".to_owned()));
+        assert_eq!(store.active(), None);
+        assert!(matches!(
+            store.active_configuration(),
+            Err(Error::NoActiveConfiguration)
+        ));
+        assert_eq!(fs::read_to_string(tmp.path().join("active_config")).unwrap(), "NONE");
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        assert_eq!(store.active(), None);
+        assert!(!store.is_active(store.find_by_name("foo").unwrap().unwrap()));
+    }
+
+    #[test]
+    pub fn test_delete_active_with_fallback() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let unknown = store.delete_with("foo", DeleteBehaviour::ActivateFallback("baz".to_owned()));
+        assert!(matches!(unknown, Err(Error::UnknownConfiguration(name)) if name == "baz"));
+        assert_eq!(store.active(), Some("foo"));
+
+        let itself = store.delete_with("foo", DeleteBehaviour::ActivateFallback("foo".to_owned()));
+        assert!(matches!(itself, Err(Error::DeleteActiveConfiguration)));
+
+        store
+            .delete_with("foo", DeleteBehaviour::ActivateFallback("bar".to_owned()))
+            .unwrap();
+
+        assert_eq!(store.active(), Some("bar"));
+        assert!(store.find_by_name("foo").unwrap().is_none());
+        assert_eq!(fs::read_to_string(tmp.path().join("active_config")).unwrap(), "bar");
+    }
+
+    #[test]
+    pub fn test_delete_active_with_fallback_reactivates_on_failure() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        store.configurations().unwrap();
+
+        // a non-empty directory can't be removed as a file
+        let path = tmp.path().join("configurations/config_foo");
+        fs::remove_file(&path).unwrap();
+        fs::create_dir_all(path.join("nested")).unwrap();
+
+        let result = store.delete_with("foo", DeleteBehaviour::ActivateFallback("bar".to_owned()));
+        assert!(result.is_err());
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(fs::read_to_string(tmp.path().join("active_config")).unwrap(), "foo");
+
+        let results = store
+            .delete_many_with(&["foo"], DeleteBehaviour::ActivateFallback("bar".to_owned()))
+            .unwrap();
+        assert!(results[0].1.is_err());
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(fs::read_to_string(tmp.path().join("active_config")).unwrap(), "foo");
+    }
+
+    #[test]
+    pub fn test_copy_with_overrides() {
+        let tmp = temp_store();
+        fs::write(
+            tmp.path().join("configurations/config_foo"),
+            "[core]\nproject=prod-project\naccount=a@example.org\n\n[custom]\nsetting=kept\n",
+        )
+        .unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let mut overrides = Properties::default();
+        overrides.set("core", "project", "staging-project");
+
+        let copied = store
+            .copy_with_overrides("foo", "bar", &overrides, ConflictAction::Abort)
+            .unwrap();
+        assert_eq!(copied.as_ref().map(Configuration::name), Some("bar"));
+
+        let original = store.describe("foo").unwrap();
+        let copy = store.describe("bar").unwrap();
+        assert_eq!(original.get("core", "project"), Some("prod-project"));
+        assert_eq!(copy.get("core", "project"), Some("staging-project"));
+        assert_eq!(copy.get("core", "account"), Some("a@example.org"));
+        assert_eq!(copy.get("custom", "setting"), Some("kept"));
+    }
+
+    #[test]
+    pub fn test_delete_many() {
+        let tmp = temp_store();
+        for name in ["bar", "baz", "qux"] {
+            fs::write(tmp.path().join("configurations").join(format!("config_{}", name)), "").unwrap();
+        }
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        store.pin("baz").unwrap();
+
+        let unknown = store.delete_many(&["bar", "unknown"]);
+        assert!(matches!(unknown, Err(Error::UnknownConfiguration(name)) if name == "unknown"));
+
+        let active = store.delete_many(&["bar", "foo"]);
+        assert!(matches!(active, Err(Error::DeleteActiveConfiguration)));
+
+        let fallback_deleted =
+            store.delete_many_with(&["foo", "qux"], DeleteBehaviour::ActivateFallback("qux".to_owned()));
+        assert!(matches!(fallback_deleted, Err(Error::DeleteActiveConfiguration)));
+        assert_eq!(store.configurations().unwrap().len(), 4);
+
+        let results = store
+            .delete_many_with(
+                &["foo", "baz", "bar"],
+                DeleteBehaviour::ActivateFallback("qux".to_owned()),
+            )
+            .unwrap();
+
+        let names: Vec<_> = results.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["foo", "baz", "bar"]);
+        assert!(results[0].1.is_ok());
+        assert!(matches!(results[1].1, Err(Error::ConfigurationPinned(_))));
+        assert!(results[2].1.is_ok());
+
+        assert_eq!(store.active(), Some("qux"));
+        let remaining: Vec<_> = store.configurations().unwrap().map(|c| c.name()).collect();
+        assert_eq!(remaining, ["baz", "qux"]);
+    }
+
+    #[test]
+    pub fn test_lazy_store_allows_empty_directory() {
+        let tmp = temp_store();
+        fs::remove_file(tmp.path().join("configurations/config_foo")).unwrap();
+
+        assert!(matches!(
+            open_store(tmp.path().to_path_buf()),
+            Err(Error::NoConfigurationsFound(_))
+        ));
+
+        let store = ConfigurationStore::with_location_lazy(tmp.path().to_path_buf()).unwrap();
+        assert!(store.configurations().unwrap().next().is_none());
+    }
+
+    #[test]
+    pub fn test_initialize_creates_default_configuration() {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().join("gcloud");
+
+        let store = ConfigurationStore::initialize(location.clone()).unwrap();
+
+        assert_eq!(store.active(), Some("default"));
+        assert!(store.find_by_name("default").unwrap().is_some());
+        assert_eq!(fs::read_to_string(location.join("active_config")).unwrap(), "default");
+    }
+
+    #[test]
+    pub fn test_initialize_keeps_existing_store() {
+        let tmp = temp_store();
+
+        let store = ConfigurationStore::initialize(tmp.path().to_path_buf()).unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(store.configurations().unwrap().len(), 2);
+    }
+
+    #[test]
+    pub fn test_configuration_exposes_file_details() {
+        let tmp = temp_store();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let configuration = store.find_by_name("foo").unwrap().unwrap();
+
+        assert_eq!(
+            configuration.path(),
+            tmp.path().join("configurations").join("config_foo")
+        );
+        assert!(configuration.modified().unwrap() <= SystemTime::now());
+    }
+
+    #[test]
+    pub fn test_fingerprint_ignores_formatting() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo"), "[core]\nproject=a\naccount=b\n").unwrap();
+        fs::write(
+            configurations.join("config_bar"),
+            "# annotated\r\n[core]\r\naccount = b\r\nproject = a\r\n",
+        )
+        .unwrap();
+        fs::write(configurations.join("config_baz"), "[core]\nproject=c\naccount=b\n").unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let fingerprint = |name| store.find_by_name(name).unwrap().unwrap().fingerprint().unwrap();
+        assert_eq!(fingerprint("foo"), fingerprint("bar"));
+        assert_ne!(fingerprint("foo"), fingerprint("baz"));
+    }
+
+    #[test]
+    pub fn test_lock_excludes_other_users() {
+        let tmp = temp_store();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        // hold the lock as another process would
+        let held = store.lock().unwrap();
+        let other = File::open(store.state_location().join("lock")).unwrap();
+
+        assert!(matches!(other.try_lock(), Err(fs::TryLockError::WouldBlock)));
+
+        drop(held);
+        other.try_lock().unwrap();
+    }
+
+    #[test]
+    pub fn test_concurrent_creates_never_overwrite() {
+        let tmp = temp_store();
+
+        // both stores are scanned before either creates, so only the check under the lock can catch the conflict
+        let mut stores: Vec<ConfigurationStore> =
+            (0..2).map(|_| open_store(tmp.path().to_path_buf()).unwrap()).collect();
+        let barrier = std::sync::Barrier::new(stores.len());
+
+        let results: Vec<Result<Option<Configuration>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = stores
+                .iter_mut()
+                .enumerate()
+                .map(|(i, store)| {
+                    let barrier = &barrier;
+                    scope.spawn(move || {
+                        let properties: Properties = format!("[core]\nproject=project-{}\n", i).parse().unwrap();
+                        barrier.wait();
+                        store.create("bar", &properties, ConflictAction::Abort)
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .any(|result| matches!(result, Err(Error::ExistingConfiguration(name)) if name == "bar")));
+    }
+
+    #[test]
+    pub fn test_virtual_config_is_never_written() {
+        let tmp = temp_store();
+        let properties = crate::PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .zone("europe-west1-d")
+            .build();
+
+        let virtual_config = ConfigurationStore::virtual_config("scratch", properties).unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.find_by_name(virtual_config.name()).unwrap().is_none());
+        assert_eq!(
+            virtual_config.env_vars(),
+            vec![
+                ("CLOUDSDK_CORE_PROJECT".to_owned(), "my-project".to_owned()),
+                ("CLOUDSDK_COMPUTE_ZONE".to_owned(), "europe-west1-d".to_owned()),
+            ]
+        );
+        assert!(matches!(
+            ConfigurationStore::virtual_config("Invalid", Properties::default()),
+            Err(Error::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_pinned_configuration_rejects_modifications() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.pin("bar").unwrap());
+        assert!(!store.pin("bar").unwrap());
+
+        let pinned = |result: Result<_>| matches!(result, Err(Error::ConfigurationPinned(name)) if name == "bar");
+        assert!(pinned(store.set_property("bar", "core", "project", "foo").map(drop)));
+        assert!(pinned(store.delete("bar")));
+        assert!(pinned(store.rename("bar", "baz", ConflictAction::Abort).map(drop)));
+        assert!(pinned(store.copy("foo", "bar", ConflictAction::Overwrite).map(drop)));
+        assert!(pinned(
+            store
+                .create("bar", &Properties::default(), ConflictAction::Overwrite)
+                .map(drop)
+        ));
+        assert!(store.find_by_name("bar").unwrap().is_some());
+
+        // copies aren't pinned, otherwise they couldn't be modified either
+        store.copy("bar", "baz", ConflictAction::Abort).unwrap();
+        assert!(!store.metadata("baz").unwrap().is_pinned());
+
+        assert!(store.unpin("bar").unwrap());
+        store.delete("bar").unwrap();
+    }
+
+    #[test]
+    pub fn test_unpin_first_allows_modifications() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        store.pin("bar").unwrap();
+
+        let mut store = store.into_unpin_first();
+        store.rename("bar", "baz", ConflictAction::Abort).unwrap();
+
+        assert!(!store.metadata("baz").unwrap().is_pinned());
+    }
+
+    #[test]
+    pub fn test_pin_unknown_configuration_fails() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(matches!(store.pin("bar"), Err(Error::UnknownConfiguration(_))));
+    }
+
+    #[test]
+    pub fn test_activate_returns_previous_configuration() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let activation = store.activate("bar").unwrap();
+        assert_eq!(activation.previous(), Some("foo"));
+        assert_eq!(activation.current(), "bar");
+        assert!(activation.changed());
+
+        let activation = store.activate("bar").unwrap();
+        assert_eq!(activation.previous(), Some("bar"));
+        assert!(!activation.changed());
+    }
+
+    #[test]
+    pub fn test_conflict_skip_leaves_existing_configuration() {
+        let tmp = temp_store();
+        fs::write(
+            tmp.path().join("configurations/config_foo"),
+            "[core]\nproject=original\n",
+        )
+        .unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let properties = crate::PropertiesBuilder::default()
+            .project(&"replacement".parse().unwrap())
+            .build();
+        let created = store.create("foo", &properties, ConflictAction::Skip).unwrap();
+
+        assert_eq!(created, None);
+        assert_eq!(store.describe("foo").unwrap().get("core", "project"), Some("original"));
+        assert!(store.last_backup().is_none());
+    }
+
+    #[test]
+    pub fn test_conflict_rename_uses_generated_name() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let imported = store
+            .import("foo", "[core]\nproject=imported\n".as_bytes(), ConflictAction::Rename)
+            .unwrap()
+            .unwrap();
+
+        assert!(imported.name().starts_with("foo-"));
+        assert!(imported.path().is_file());
+        assert_eq!(
+            store.describe(imported.name()).unwrap().get("core", "project"),
+            Some("imported")
+        );
+        assert!(store.find_by_name("foo").unwrap().is_some());
+    }
+
+    #[test]
+    pub fn test_export_then_import_preserves_contents() {
+        let tmp = temp_store();
+        let contents = "# comment\n[core]\nproject=my-project\n\n[custom]\nsetting = kept\n";
+        fs::write(tmp.path().join("configurations/config_foo"), contents).unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let mut exported = Vec::new();
+        store.export("foo", &mut exported).unwrap();
+        assert_eq!(String::from_utf8(exported.clone()).unwrap(), contents);
+
+        store.import("bar", exported.as_slice(), ConflictAction::Abort).unwrap();
+        assert_eq!(
+            fs::read_to_string(tmp.path().join("configurations/config_bar")).unwrap(),
+            contents
+        );
+
+        assert!(matches!(
+            store.export("unknown", Vec::new()),
+            Err(Error::UnknownConfiguration(name)) if name == "unknown"
+        ));
+    }
+
+    #[test]
+    pub fn test_archive_round_trip() {
+        let tmp = temp_store();
+        let contents = "[core]\nproject=my-project\n\n[custom]\nsetting = kept\n";
+        fs::write(tmp.path().join("configurations/config_bar"), contents).unwrap();
+        fs::write(tmp.path().join("active_config"), "bar").unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let mut archive = Vec::new();
+        store.export_archive(&mut archive).unwrap();
+
+        let other = tempfile::tempdir().unwrap();
+        let mut store = ConfigurationStore::builder()
+            .location(other.path())
+            .state_location(other.path().join("state"))
+            .create_if_missing(true)
+            .open()
+            .unwrap();
+        let imported = store.import_archive(archive.as_slice(), ConflictAction::Abort).unwrap();
+
+        let names: Vec<_> = imported.iter().map(Configuration::name).collect();
+        assert_eq!(names, ["bar", "foo"]);
+        assert_eq!(store.active(), Some("bar"));
+        assert_eq!(
+            fs::read_to_string(other.path().join("configurations/config_bar")).unwrap(),
+            contents
+        );
+
+        // existing configurations are resolved with the policy, just like a single import
+        fs::write(other.path().join("configurations/config_bar"), "").unwrap();
+        let result = store.import_archive(archive.as_slice(), ConflictAction::Abort);
+        assert!(matches!(result, Err(Error::ExistingConfiguration(_))));
+
+        let imported = store.import_archive(archive.as_slice(), ConflictAction::Skip).unwrap();
+        assert!(imported.is_empty());
+
+        // nothing is imported unless every collision can be resolved
+        let partial = temp_store();
+        let mut store = open_store(partial.path().to_path_buf()).unwrap();
+        let result = store.import_archive(archive.as_slice(), ConflictAction::Abort);
+        assert!(matches!(result, Err(Error::ExistingConfiguration(name)) if name == "foo"));
+        assert!(!partial.path().join("configurations/config_bar").exists());
+
+        // nor if two entries would be imported with the same name
+        let resolver = |_: &ConfigurationStore, _: &str| Ok(Resolution::Rename("bar".to_owned()));
+        let result = store.import_archive(archive.as_slice(), resolver);
+        assert!(matches!(result, Err(Error::ExistingConfiguration(name)) if name == "bar"));
+        assert!(!partial.path().join("configurations/config_bar").exists());
+    }
+
+    #[test]
+    pub fn test_conflict_resolved_by_callback() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let resolver = |_: &ConfigurationStore, name: &str| Ok(Resolution::Rename(format!("{}-copy", name)));
+        let copied = store.copy("foo", "bar", resolver).unwrap();
+        assert_eq!(copied.as_ref().map(Configuration::name), Some("bar-copy"));
+
+        // a rename which still conflicts is an error rather than an overwrite
+        let resolver = |_: &ConfigurationStore, _: &str| Ok(Resolution::Rename("foo".to_owned()));
+        assert!(matches!(
+            store.rename("bar-copy", "bar", resolver),
+            Err(Error::ExistingConfiguration(name)) if name == "foo"
+        ));
+    }
+
+    #[test]
+    pub fn test_overwrite_makes_backup_which_can_be_restored() {
+        let tmp = temp_store();
+        fs::write(
+            tmp.path().join("configurations/config_foo"),
+            "[core]\nproject=original\n",
+        )
+        .unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let properties = crate::PropertiesBuilder::default()
+            .project(&"replacement".parse().unwrap())
+            .build();
+        store.create("foo", &properties, ConflictAction::Overwrite).unwrap();
+
+        let backup = store.last_backup().unwrap().clone();
+        assert_eq!(backup.name(), "foo");
+        assert_eq!(fs::read_to_string(backup.path()).unwrap(), "[core]\nproject=original\n");
+        assert_eq!(store.backups("foo").unwrap(), vec![backup]);
+
+        store.restore("foo").unwrap();
+        assert_eq!(store.describe("foo").unwrap().get("core", "project"), Some("original"));
+
+        // restoring backed up the replacement, so it can be undone
+        let backups = store.backups("foo").unwrap();
+        assert_eq!(backups.len(), 1);
+        assert!(fs::read_to_string(backups[0].path()).unwrap().contains("replacement"));
+    }
+
+    #[test]
+    pub fn test_rename_over_existing_makes_backup() {
+        let tmp = temp_store();
+        fs::write(tmp.path().join("configurations/config_bar"), "[core]\nproject=bar\n").unwrap();
+        fs::write(tmp.path().join("active_config"), "bar").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        store.rename("foo", "bar", ConflictAction::Overwrite).unwrap();
+
+        assert_eq!(store.backups("bar").unwrap().len(), 1);
+        assert!(store.backups("foo").unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_rename_to_same_name_changes_nothing() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        store
+            .set_metadata("foo", Metadata::default().set_tags(["prod"]))
+            .unwrap();
+
+        let renamed = store.rename("foo", "foo", ConflictAction::Overwrite).unwrap().unwrap();
+
+        assert_eq!(renamed.name(), "foo");
+        assert_eq!(store.metadata("foo").unwrap().tags(), vec!["prod"]);
+        assert!(store.backups("foo").unwrap().is_empty());
+        assert_eq!(store.active(), Some("foo"));
+    }
+
+    #[test]
+    pub fn test_undo_overwrite() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_bar"), "[core]\nproject = original\n").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(matches!(store.undo_last(), Err(Error::NothingToUndo)));
+
+        let properties = crate::PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .build();
+        store.create("bar", &properties, ConflictAction::Overwrite).unwrap();
+
+        // not destructive, so the overwrite is still the last operation
+        store.create("baz", &properties, ConflictAction::Abort).unwrap();
+
+        let undone = store.undo_last().unwrap();
+        assert_eq!(undone, UndoableOperation::Overwritten(vec!["bar".to_owned()]));
+        assert_eq!(
+            fs::read_to_string(configurations.join("config_bar")).unwrap(),
+            "[core]\nproject = original\n"
+        );
+
+        assert!(matches!(store.undo_last(), Err(Error::NothingToUndo)));
+    }
+
+    #[test]
+    pub fn test_undo_delete_reactivates() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo"), "[core]\nproject = my-project\n").unwrap();
+        fs::write(configurations.join("config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        store
+            .delete_with("foo", DeleteBehaviour::ActivateFallback("bar".to_owned()))
+            .unwrap();
+        assert_eq!(store.active(), Some("bar"));
+
+        let undone = store.undo_last().unwrap();
+        assert_eq!(undone, UndoableOperation::Deleted(vec!["foo".to_owned()]));
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("my-project")
+        );
+    }
+
+    #[test]
+    pub fn test_undo_rename_overwrite() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo"), "[core]\nproject = foo-project\n").unwrap();
+        fs::write(configurations.join("config_bar"), "[core]\nproject = bar-project\n").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        store.rename("foo", "bar", ConflictAction::Overwrite).unwrap();
+        assert_eq!(store.active(), Some("bar"));
+
+        store.undo_last().unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("foo-project")
+        );
+        assert_eq!(
+            store.describe("bar").unwrap().get("core", "project"),
+            Some("bar-project")
+        );
+    }
+
+    #[test]
+    pub fn test_restore_without_backups_fails() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(matches!(store.restore("foo"), Err(Error::NoBackups(_))));
+    }
+
+    #[test]
+    pub fn test_apply_is_idempotent() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_bar"), "[core]\nproject = bar-project\n").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        store
+            .set_metadata("foo", Metadata::default().set_tags(["prod"]))
+            .unwrap();
+
+        let desired = [
+            ConfigSpec::new("foo", "[core]\nproject = foo-project\n".parse().unwrap()),
+            ConfigSpec::new("baz", "[core]\nproject = baz-project\n".parse().unwrap()),
+        ];
+        let mut options = ApplyOptions::default();
+        options.prune(true).dry_run(true);
+
+        let planned = store.apply(&desired, &options).unwrap();
+        let names: Vec<_> = planned.iter().map(AppliedChange::name).collect();
+        assert_eq!(names, vec!["foo", "baz", "bar"]);
+        assert!(matches!(&planned[0], AppliedChange::Updated { changes, .. } if changes.len() == 1));
+        assert_eq!(planned[1], AppliedChange::Created("baz".to_owned()));
+        assert_eq!(planned[2], AppliedChange::Pruned("bar".to_owned()));
+        assert!(store.find_by_name("baz").unwrap().is_none());
+
+        let applied = store.apply(&desired, options.dry_run(false)).unwrap();
+        assert_eq!(applied, planned);
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("foo-project")
+        );
+        assert_eq!(store.metadata("foo").unwrap().tags(), vec!["prod"]);
+        assert!(store.find_by_name("baz").unwrap().is_some());
+        assert!(store.find_by_name("bar").unwrap().is_none());
+
+        let reapplied = store.apply(&desired, &options).unwrap();
+        assert!(reapplied.iter().all(|change| !change.is_change()));
+    }
+
+    #[test]
+    pub fn test_apply_can_be_undone() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo"), "[core]\nproject = foo-project\n").unwrap();
+        fs::write(configurations.join("config_env-10"), "").unwrap();
+        fs::write(configurations.join("config_env-2"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let desired = [ConfigSpec::new("foo", "[core]\nproject = other\n".parse().unwrap())];
+        let applied = store.apply(&desired, ApplyOptions::default().prune(true)).unwrap();
+
+        let names: Vec<_> = applied.iter().map(AppliedChange::name).collect();
+        assert_eq!(names, vec!["foo", "env-2", "env-10"]);
+
+        let undone = store.undo_last().unwrap();
+        assert_eq!(
+            undone,
+            UndoableOperation::Applied(vec!["foo".to_owned(), "env-2".to_owned(), "env-10".to_owned()])
+        );
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("foo-project")
+        );
+        assert!(store.find_by_name("env-2").unwrap().is_some());
+        assert!(store.find_by_name("env-10").unwrap().is_some());
+    }
+
+    #[test]
+    pub fn test_apply_checks_before_changing_anything() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        let spec = |name: &str| ConfigSpec::new(name, Properties::default());
+
+        assert!(matches!(
+            store.apply(&[spec("bar"), spec("bar")], &ApplyOptions::default()),
+            Err(Error::DuplicateConfigSpec(_))
+        ));
+        assert!(matches!(
+            store.apply(&[spec("bar")], ApplyOptions::default().prune(true)),
+            Err(Error::DeleteActiveConfiguration)
+        ));
+        assert!(store.find_by_name("bar").unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_create_from_template() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        let template = Template::new("[core]\nproject = my-app-{{env}}\n".parse().unwrap());
+
+        assert!(matches!(store.template("app"), Err(Error::UnknownTemplate(_))));
+        assert!(matches!(
+            store.save_template("not valid", &template),
+            Err(Error::InvalidName(_))
+        ));
+
+        store.save_template("app", &template).unwrap();
+        assert_eq!(store.templates().unwrap(), vec!["app"]);
+
+        let vars = BTreeMap::from([("env".to_owned(), "prod".to_owned())]);
+        store
+            .create_from_template("prod", "app", &vars, ConflictAction::Abort)
+            .unwrap();
+
+        assert_eq!(
+            store.describe("prod").unwrap().get("core", "project"),
+            Some("my-app-prod")
+        );
+        assert!(matches!(
+            store.create_from_template("dev", "app", &BTreeMap::new(), ConflictAction::Abort),
+            Err(Error::MissingTemplateVariable(_))
+        ));
+        assert!(store.find_by_name("dev").unwrap().is_none());
+
+        store.delete_template("app").unwrap();
+        assert!(store.templates().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_restore_from_trash() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_bar"), "[core]\nproject = bar-project\n").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        assert!(store.list_trashed().unwrap().is_empty());
+
+        store.delete("bar").unwrap();
+
+        let trashed = store.list_trashed().unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].name(), "bar");
+
+        // recently deleted configurations aren't clutter until the retention period has passed
+        assert!(store.find_clutter(Duration::from_secs(86400)).unwrap().is_empty());
+
+        store.restore("bar").unwrap();
+
+        assert!(store.list_trashed().unwrap().is_empty());
+        assert_eq!(
+            store.describe("bar").unwrap().get("core", "project"),
+            Some("bar-project")
+        );
+    }
+
+    #[test]
+    pub fn test_expired_trash_is_clutter() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_bar"), "").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf())
+            .unwrap()
+            .with_trash_retention(Duration::ZERO);
+
+        store.delete("bar").unwrap();
+        let trashed = store.list_trashed().unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        let clutter = store.find_clutter(Duration::from_secs(86400)).unwrap();
+        assert_eq!(clutter[0].kind(), ClutterKind::Backups);
+        assert_eq!(clutter[0].paths(), [trashed[0].path().to_path_buf()]);
+    }
+
+    #[test]
+    pub fn test_describe_all_in_name_order() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(configurations.join("config_foo"), "[core]\nproject = foo-project\n").unwrap();
+        fs::write(configurations.join("config_bar"), "[core]\nproject = bar-project\n").unwrap();
+        fs::write(configurations.join("config_baz"), "not a configuration").unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let described = store.describe_all().unwrap();
+
+        let names: Vec<&str> = described.iter().map(|(c, _)| c.name()).collect();
+        assert_eq!(names, vec!["bar", "baz", "foo"]);
+        assert_eq!(
+            described[0].1.as_ref().unwrap().get("core", "project"),
+            Some("bar-project")
+        );
+        assert!(described[1].1.is_err());
+        assert_eq!(
+            described[2].1.as_ref().unwrap().get("core", "project"),
+            Some("foo-project")
+        );
+
+        let projects = |described: Vec<(&Configuration, Result<Properties>)>| -> Vec<(String, Option<String>)> {
+            described
+                .into_iter()
+                .map(|(c, p)| {
+                    (
+                        c.name().to_owned(),
+                        p.ok().and_then(|p| p.get("core", "project").map(str::to_owned)),
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(projects(store.describe_all_sequential().unwrap()), projects(described));
+    }
+
+    #[test]
+    pub fn test_set_and_unset_property() {
+        let tmp = temp_store();
+        let path = tmp.path().join("configurations/config_foo");
+        fs::write(
+            &path,
+            "[core]\nproject = my-project\n[auth]\ndisable_credentials = true\n",
+        )
+        .unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let properties = store.set_property("foo", "compute", "zone", "europe-west1-d").unwrap();
+        assert_eq!(properties.get("compute", "zone"), Some("europe-west1-d"));
+        assert_eq!(
+            store.describe("foo").unwrap().get("auth", "disable_credentials"),
+            Some("true")
+        );
+
+        let properties = store.unset_property("foo", "auth", "disable_credentials").unwrap();
+        assert_eq!(properties.get("auth", "disable_credentials"), None);
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("my-project")
+        );
+        assert!(!tmp.path().join("configurations/.config_foo.tmp").exists());
+    }
+
+    #[test]
+    pub fn test_set_property_keeps_comments() {
+        let tmp = temp_store();
+        let path = tmp.path().join("configurations/config_foo");
+        fs::write(
+            &path,
+            "# shared with the team\n[core]\n; don't change\nproject = my-project\n",
+        )
+        .unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        store.set_property("foo", "core", "account", "a@b.com").unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("# shared with the team"), "{}", contents);
+        assert!(contents.contains("; don't change"), "{}", contents);
+    }
+
+    #[test]
+    pub fn test_set_property_unknown_configuration_fails() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let result = store.set_property("bar", "core", "project", "my-project");
+        assert!(matches!(result, Err(Error::UnknownConfiguration(_))));
+    }
+
+    #[test]
+    pub fn test_subscribers_are_told_about_changes() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let received = events.clone();
+        store.subscribe(move |event| received.lock().unwrap().push(event.clone()));
+
+        store
+            .create("bar", &Properties::default(), ConflictAction::Abort)
+            .unwrap();
+        store.copy("bar", "baz", ConflictAction::Abort).unwrap();
+        store.activate("baz").unwrap();
+        store.rename("baz", "qux", ConflictAction::Abort).unwrap();
+        store.delete("bar").unwrap();
+
+        // skipped operations don't change anything
+        store
+            .create("foo", &Properties::default(), ConflictAction::Skip)
+            .unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0], StoreEvent::Created("bar".to_owned()));
+        assert_eq!(
+            events[1],
+            StoreEvent::Copied {
+                from: "bar".to_owned(),
+                to: "baz".to_owned()
+            }
+        );
+        assert!(matches!(&events[2], StoreEvent::Activated(a) if a.previous() == Some("foo") && a.current() == "baz"));
+        assert_eq!(
+            events[3],
+            StoreEvent::Renamed {
+                from: "baz".to_owned(),
+                to: "qux".to_owned()
+            }
+        );
+        assert_eq!(events[4], StoreEvent::Deleted("bar".to_owned()));
+    }
+
+    #[test]
+    pub fn test_find_by_project_and_account() {
+        let tmp = temp_store();
+        let configurations = tmp.path().join("configurations");
+        fs::write(
+            configurations.join("config_foo"),
+            "[core]\nproject=shared\naccount=a@example.org\n",
+        )
+        .unwrap();
+        fs::write(
+            configurations.join("config_bar"),
+            "[core]\nproject=shared\naccount=b@example.org\n",
+        )
+        .unwrap();
+        fs::write(
+            configurations.join("config_baz"),
+            "[core]\nproject=other\naccount=a@example.org\n",
+        )
+        .unwrap();
+        fs::write(configurations.join("config_broken"), "not a configuration").unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let names = |found: Vec<&Configuration>| found.into_iter().map(|c| c.name().to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(names(store.find_by_project("shared").unwrap()), vec!["bar", "foo"]);
+        assert_eq!(
+            names(store.find_by_account("a@example.org").unwrap()),
+            vec!["baz", "foo"]
+        );
+        assert!(store.find_by_project("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_properties_are_cached_until_the_store_modifies_them() {
+        let tmp = temp_store();
+        let path = tmp.path().join("configurations/config_foo");
+        fs::write(&path, "[core]\nproject=first\n").unwrap();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let properties = store.find_by_name("foo").unwrap().unwrap().properties().unwrap();
+        assert_eq!(properties.get("core", "project"), Some("first"));
+
+        // changes made outside the store aren't seen
+        fs::write(&path, "[core]\nproject=outside\n").unwrap();
+        let properties = store.find_by_name("foo").unwrap().unwrap().properties().unwrap();
+        assert_eq!(properties.get("core", "project"), Some("first"));
+
+        store.set_property("foo", "core", "project", "second").unwrap();
+        let properties = store.find_by_name("foo").unwrap().unwrap().properties().unwrap();
+        assert_eq!(properties.get("core", "project"), Some("second"));
+    }
+
+    #[test]
+    pub fn test_normalize_rewrites_only_when_changed() {
+        let tmp = temp_store();
+        let path = tmp.path().join("configurations/config_foo");
+        fs::write(&path, "[core]\r\nproject = my-project\r\naccount = a@b.com\r\n").unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let mut options = WriteOptions::default();
+        options.line_ending(crate::LineEnding::Lf);
+
+        assert!(store.normalize("foo", &options).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[core]\naccount=a@b.com\nproject=my-project\n"
+        );
+        assert!(!store.normalize("foo", &options).unwrap());
+    }
+
+    #[test]
+    pub fn test_normalize_is_lossless() {
+        let tmp = temp_store();
+        let path = tmp.path().join("configurations/config_foo");
+        fs::write(
+            &path,
+            "[compute]\nzone = europe-west1-d\n# team settings\n[core]\nproject = my-project\n; me\naccount = a@b.com\n",
+        )
+        .unwrap();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let mut options = WriteOptions::default();
+        options.line_ending(crate::LineEnding::Lf);
+
+        assert!(store.normalize("foo", &options).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[compute]\nzone=europe-west1-d\n# team settings\n[core]\n; me\naccount=a@b.com\nproject=my-project\n"
+        );
+
+        let duplicated = "[core]\nproject = first\nproject = second\n";
+        fs::write(&path, duplicated).unwrap();
+
+        assert!(matches!(
+            store.normalize("foo", &options),
+            Err(Error::DuplicateKey { key, .. }) if key == "project"
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), duplicated);
+    }
+
+    #[test]
+    pub fn test_import_existing_without_overwrite_fails() {
+        let tmp = temp_store();
+        let mut store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let result = store.import("foo", "[core]\n".as_bytes(), ConflictAction::Abort);
+
+        assert!(matches!(result, Err(Error::ExistingConfiguration(_))));
+    }
+
+    #[test]
+    pub fn test_generated_name_is_valid() {
+        let name = GeneratedName::new("debug").unwrap();
+
+        assert!(name.as_str().starts_with("debug-"));
+        assert_eq!(name.as_str().len(), "debug-".len() + GeneratedName::SUFFIX_LENGTH);
+        assert!(Configuration::is_valid_name(name.as_str()));
+    }
+
+    #[test]
+    pub fn test_generated_name_with_invalid_prefix() {
+        assert!(matches!(GeneratedName::new("Debug"), Err(Error::InvalidName(_))));
+    }
+
+    #[test]
+    pub fn test_generate_name_is_unused() {
+        let tmp = temp_store();
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+
+        let name = store.generate_name("foo").unwrap();
+        assert!(store.find_by_name(name.as_str()).unwrap().is_none());
+    }
 
     #[test]
     pub fn test_is_valid_name_with_valid_name() {
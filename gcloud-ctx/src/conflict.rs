@@ -0,0 +1,75 @@
+use crate::{ConfigurationStore, Result};
+
+/// Action to perform when a naming conflict occurs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConflictAction {
+    /// Abort the operation
+    Abort,
+
+    /// Overwrite the existing configuration
+    Overwrite,
+
+    /// Leave the existing configuration as it is and skip the operation
+    Skip,
+
+    /// Use a new name made from the conflicting name and a random suffix, see [`crate::GeneratedName`]
+    Rename,
+}
+
+impl From<bool> for ConflictAction {
+    fn from(value: bool) -> Self {
+        if value {
+            ConflictAction::Overwrite
+        } else {
+            ConflictAction::Abort
+        }
+    }
+}
+
+/// How a naming conflict was resolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// Abort the operation
+    Abort,
+
+    /// Overwrite the existing configuration
+    Overwrite,
+
+    /// Leave the existing configuration as it is and skip the operation
+    Skip,
+
+    /// Use the given name instead, which must not already exist
+    Rename(String),
+}
+
+/// Decides what happens when an operation would overwrite an existing configuration
+///
+/// Implemented by [`ConflictAction`] for fixed policies, and by any
+/// `Fn(&ConfigurationStore, &str) -> Result<Resolution>` so that callers can decide case by case,
+/// e.g. by asking the user
+pub trait ConflictResolver {
+    /// Resolve a conflict with the existing configuration called `name`
+    fn resolve(&self, store: &ConfigurationStore, name: &str) -> Result<Resolution>;
+}
+
+impl ConflictResolver for ConflictAction {
+    fn resolve(&self, store: &ConfigurationStore, name: &str) -> Result<Resolution> {
+        let resolution = match self {
+            ConflictAction::Abort => Resolution::Abort,
+            ConflictAction::Overwrite => Resolution::Overwrite,
+            ConflictAction::Skip => Resolution::Skip,
+            ConflictAction::Rename => Resolution::Rename(store.generate_name(name)?.into()),
+        };
+
+        Ok(resolution)
+    }
+}
+
+impl<F> ConflictResolver for F
+where
+    F: Fn(&ConfigurationStore, &str) -> Result<Resolution>,
+{
+    fn resolve(&self, store: &ConfigurationStore, name: &str) -> Result<Resolution> {
+        self(store, name)
+    }
+}
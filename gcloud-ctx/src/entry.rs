@@ -0,0 +1,73 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Reason an entry in the configurations directory was skipped
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InvalidEntryReason {
+    /// The entry is a directory, or a symlink to a directory
+    Directory,
+
+    /// The entry is a special file such as a fifo, socket or device
+    SpecialFile,
+
+    /// The entry couldn't be inspected, e.g. a broken symlink
+    Unreadable,
+
+    /// Permission to inspect the entry was denied, so it may be a configuration which can't be used
+    PermissionDenied,
+
+    /// The name of the entry isn't a valid configuration name, e.g. an editor backup such as `config_foo~`
+    InvalidName,
+
+    /// The name of the entry isn't valid UTF-8
+    NotUtf8,
+}
+
+#[cfg(feature = "store")]
+impl InvalidEntryReason {
+    /// Reason for an entry which couldn't be inspected because of the given error
+    pub(crate) fn unreadable(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::PermissionDenied => InvalidEntryReason::PermissionDenied,
+            _ => InvalidEntryReason::Unreadable,
+        }
+    }
+}
+
+impl fmt::Display for InvalidEntryReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidEntryReason::Directory => write!(f, "it is a directory"),
+            InvalidEntryReason::SpecialFile => write!(f, "it is not a regular file"),
+            InvalidEntryReason::Unreadable => write!(f, "it could not be read"),
+            InvalidEntryReason::PermissionDenied => write!(f, "permission to read it was denied"),
+            InvalidEntryReason::InvalidName => write!(f, "its name isn't a valid configuration name"),
+            InvalidEntryReason::NotUtf8 => write!(f, "its name isn't valid UTF-8"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in the configurations directory which was skipped because it can't be a configuration, see
+/// [`ConfigurationStore::diagnostics`](crate::ConfigurationStore::diagnostics)
+pub struct InvalidEntry {
+    /// Path to the entry
+    pub(crate) path: PathBuf,
+
+    /// Reason the entry was skipped
+    pub(crate) reason: InvalidEntryReason,
+}
+
+impl InvalidEntry {
+    /// Path to the entry
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reason the entry was skipped
+    pub fn reason(&self) -> InvalidEntryReason {
+        self.reason
+    }
+}
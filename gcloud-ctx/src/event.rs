@@ -0,0 +1,67 @@
+use crate::Activation;
+use std::fmt;
+
+/// Change made to a configuration store, passed to the callbacks registered with
+/// [`ConfigurationStore::subscribe`](crate::ConfigurationStore::subscribe)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StoreEvent {
+    /// A configuration was created or imported, possibly overwriting an existing one
+    Created(String),
+
+    /// A configuration was copied
+    Copied {
+        /// Name of the original configuration
+        from: String,
+
+        /// Name of the copy
+        to: String,
+    },
+
+    /// A configuration was activated
+    Activated(Activation),
+
+    /// The given configuration was active, but now no configuration is
+    Deactivated(String),
+
+    /// A configuration was renamed
+    Renamed {
+        /// Old name of the configuration
+        from: String,
+
+        /// New name of the configuration
+        to: String,
+    },
+
+    /// A configuration was deleted
+    Deleted(String),
+}
+
+/// Callback which is told about changes to a configuration store
+type Callback = Box<dyn Fn(&StoreEvent) + Send + Sync>;
+
+/// Callbacks registered with a configuration store
+#[derive(Default)]
+pub(crate) struct Subscribers(Vec<Callback>);
+
+impl Subscribers {
+    /// Register a callback
+    pub(crate) fn add(&mut self, callback: Callback) {
+        self.0.push(callback);
+    }
+
+    /// Tell every callback about a change, in the order they were registered
+    pub(crate) fn notify(&self, event: StoreEvent) {
+        debug!("store changed: {:?}", event);
+
+        for callback in &self.0 {
+            callback(&event);
+        }
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} subscribers", self.0.len())
+    }
+}
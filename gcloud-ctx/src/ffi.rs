@@ -0,0 +1,780 @@
+//! C ABI for using gcloud-ctx from other languages, e.g. shell plugins or tools written in Go, C or Python
+//!
+//! Requires the `ffi` feature. Build a shared library with
+//! `cargo rustc -p gcloud-ctx --release --features ffi --crate-type cdylib` and declare the functions with the
+//! `include/gcloud_ctx.h` header which ships with the crate, generated from this module by cbindgen.
+//!
+//! Functions which can fail return `0` on success and `-1` on failure, or `NULL` if they return a pointer. After a
+//! failure, [`gctx_last_error`] and [`gctx_last_error_code`] describe what went wrong on the calling thread, where the
+//! code is the same as [`Error::code`](crate::Error::code). Strings returned by the library must be released with
+//! [`gctx_string_free`], stores with [`gctx_store_free`] and properties with [`gctx_properties_free`].
+//!
+//! ```c
+//! GctxStore *store = gctx_store_open(NULL);
+//! if (store == NULL) {
+//!     fprintf(stderr, "%s\n", gctx_last_error());
+//!     return 1;
+//! }
+//!
+//! if (gctx_store_activate(store, "foo") != 0) {
+//!     fprintf(stderr, "%s\n", gctx_last_error());
+//! }
+//!
+//! gctx_store_free(store);
+//! ```
+//!
+//! Properties are read and written through [`gctx_store_get_property`] and friends, or through a `GctxProperties`
+//! handle from [`gctx_store_properties`], so callers never need to parse configuration files themselves. Python
+//! bindings built on these functions with `ctypes` ship with the crate in `python/gcloud_ctx.py`, so scripts don't
+//! need to declare the functions themselves.
+
+use crate::{ConfigurationStore, ConflictAction, Error, Properties};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    ptr,
+};
+
+/// Error code when an argument is `NULL` or isn't valid UTF-8
+const INVALID_ARGUMENT: &str = "INVALID_ARGUMENT";
+
+/// Error code when the library panicked, which is always a bug
+const PANIC: &str = "PANIC";
+
+thread_local! {
+    /// Code and message of the last failure on this thread, if the last call failed
+    static LAST_ERROR: RefCell<Option<(CString, CString)>> = const { RefCell::new(None) };
+}
+
+/// Open the configuration store at the given location, or the default location if `location` is `NULL`
+///
+/// Returns `NULL` on failure. The store must be released with [`gctx_store_free`]
+///
+/// # Safety
+///
+/// `location` must be `NULL` or a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_open(location: *const c_char) -> *mut ConfigurationStore {
+    call(ptr::null_mut(), || {
+        let store = if location.is_null() {
+            ConfigurationStore::with_default_location()?
+        } else {
+            ConfigurationStore::with_location(PathBuf::from(to_str(location, "location")?))?
+        };
+
+        Ok(Box::into_raw(Box::new(store)))
+    })
+}
+
+/// Release a store opened with [`gctx_store_open`]. Does nothing if `store` is `NULL`
+///
+/// # Safety
+///
+/// `store` must be `NULL` or a store returned by [`gctx_store_open`] which hasn't already been released
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_free(store: *mut ConfigurationStore) {
+    if !store.is_null() {
+        drop(Box::from_raw(store));
+    }
+}
+
+/// Get the names of every configuration in natural order, one per line
+///
+/// Returns `NULL` on failure. The names must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`]
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_list(store: *const ConfigurationStore) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let store = store.as_ref().ok_or_else(|| null("store"))?;
+        let names: Vec<&str> = store.configurations()?.map(|c| c.name()).collect();
+
+        Ok(to_c_string(&names.join("\n")))
+    })
+}
+
+/// Get the name of the active configuration
+///
+/// Returns `NULL` if no configuration is active, in which case [`gctx_last_error_code`] is `NO_ACTIVE_CONFIGURATION`.
+/// The name must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`]
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_active(store: *const ConfigurationStore) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let store = store.as_ref().ok_or_else(|| null("store"))?;
+        let active = store.active().ok_or(Error::NoActiveConfiguration)?;
+
+        Ok(to_c_string(active))
+    })
+}
+
+/// Activate a configuration by name
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_activate(store: *mut ConfigurationStore, name: *const c_char) -> c_int {
+    call(-1, || {
+        let store = store.as_mut().ok_or_else(|| null("store"))?;
+        store.activate(to_str(name, "name")?)?;
+
+        Ok(0)
+    })
+}
+
+/// Create a configuration with the given properties, in the same INI format as a configuration file, e.g.
+/// `"[core]\nproject = my-project\n"`. `properties` may be `NULL` to create an empty configuration
+///
+/// Fails if the configuration already exists, unless `overwrite` is non-zero. Returns `0` on success and `-1` on
+/// failure
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`], `name` must be a valid NUL terminated string and
+/// `properties` must be `NULL` or a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_create(
+    store: *mut ConfigurationStore,
+    name: *const c_char,
+    properties: *const c_char,
+    overwrite: c_int,
+) -> c_int {
+    call(-1, || {
+        let store = store.as_mut().ok_or_else(|| null("store"))?;
+        let name = to_str(name, "name")?;
+        let properties: Properties = if properties.is_null() {
+            Properties::default()
+        } else {
+            to_str(properties, "properties")?.parse()?
+        };
+        let conflict = if overwrite == 0 {
+            ConflictAction::Abort
+        } else {
+            ConflictAction::Overwrite
+        };
+
+        store.create(name, &properties, conflict)?;
+
+        Ok(0)
+    })
+}
+
+/// Describe the properties of a configuration in the same INI format as a configuration file
+///
+/// Returns `NULL` on failure. The string must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_describe(store: *const ConfigurationStore, name: *const c_char) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let store = store.as_ref().ok_or_else(|| null("store"))?;
+        let properties = store.describe(to_str(name, "name")?)?;
+
+        Ok(to_c_string(&properties.to_string()))
+    })
+}
+
+/// Delete a configuration by name, which fails if it's the active configuration
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_delete(store: *mut ConfigurationStore, name: *const c_char) -> c_int {
+    call(-1, || {
+        let store = store.as_mut().ok_or_else(|| null("store"))?;
+        store.delete(to_str(name, "name")?)?;
+
+        Ok(0)
+    })
+}
+
+/// Get the properties of a configuration, so they can be read without parsing the configuration file
+///
+/// Returns `NULL` on failure. The properties must be released with [`gctx_properties_free`]
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name` must be a valid NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_properties(
+    store: *const ConfigurationStore,
+    name: *const c_char,
+) -> *mut Properties {
+    call(ptr::null_mut(), || {
+        let store = store.as_ref().ok_or_else(|| null("store"))?;
+        let properties = store.describe(to_str(name, "name")?)?;
+
+        Ok(Box::into_raw(Box::new(properties)))
+    })
+}
+
+/// Get a single property of a configuration
+///
+/// Returns `NULL` if the property isn't set, in which case [`gctx_last_error_code`] is `NULL`, or on failure. The
+/// value must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name`, `section` and `key` must be valid NUL
+/// terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_get_property(
+    store: *const ConfigurationStore,
+    name: *const c_char,
+    section: *const c_char,
+    key: *const c_char,
+) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let store = store.as_ref().ok_or_else(|| null("store"))?;
+        let properties = store.describe(to_str(name, "name")?)?;
+        let value = properties.get(to_str(section, "section")?, to_str(key, "key")?);
+
+        Ok(value.map_or(ptr::null_mut(), to_c_string))
+    })
+}
+
+/// Set a single property of a configuration, rewriting it atomically
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name`, `section`, `key` and `value` must be valid NUL
+/// terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_set_property(
+    store: *mut ConfigurationStore,
+    name: *const c_char,
+    section: *const c_char,
+    key: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    call(-1, || {
+        let store = store.as_mut().ok_or_else(|| null("store"))?;
+        store.set_property(
+            to_str(name, "name")?,
+            to_str(section, "section")?,
+            to_str(key, "key")?,
+            to_str(value, "value")?,
+        )?;
+
+        Ok(0)
+    })
+}
+
+/// Remove a single property from a configuration, rewriting it atomically. Removing a property which isn't set
+/// succeeds
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `store` must be a store returned by [`gctx_store_open`] and `name`, `section` and `key` must be valid NUL
+/// terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_store_unset_property(
+    store: *mut ConfigurationStore,
+    name: *const c_char,
+    section: *const c_char,
+    key: *const c_char,
+) -> c_int {
+    call(-1, || {
+        let store = store.as_mut().ok_or_else(|| null("store"))?;
+        store.unset_property(to_str(name, "name")?, to_str(section, "section")?, to_str(key, "key")?)?;
+
+        Ok(0)
+    })
+}
+
+/// Create an empty set of properties, e.g. to build up the properties of a new configuration
+///
+/// The properties must be released with [`gctx_properties_free`]
+#[no_mangle]
+pub extern "C" fn gctx_properties_new() -> *mut Properties {
+    Box::into_raw(Box::default())
+}
+
+/// Release properties returned by the library. Does nothing if `properties` is `NULL`
+///
+/// # Safety
+///
+/// `properties` must be `NULL` or properties returned by the library which haven't already been released
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_free(properties: *mut Properties) {
+    if !properties.is_null() {
+        drop(Box::from_raw(properties));
+    }
+}
+
+/// Get a property
+///
+/// Returns `NULL` if the property isn't set, in which case [`gctx_last_error_code`] is `NULL`, or on failure. The
+/// value must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library and `section` and `key` must be valid NUL terminated
+/// strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_get(
+    properties: *const Properties,
+    section: *const c_char,
+    key: *const c_char,
+) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let properties = properties.as_ref().ok_or_else(|| null("properties"))?;
+        let value = properties.get(to_str(section, "section")?, to_str(key, "key")?);
+
+        Ok(value.map_or(ptr::null_mut(), to_c_string))
+    })
+}
+
+/// Set a property, adding the section if needed
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library and `section`, `key` and `value` must be valid NUL
+/// terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_set(
+    properties: *mut Properties,
+    section: *const c_char,
+    key: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    call(-1, || {
+        let properties = properties.as_mut().ok_or_else(|| null("properties"))?;
+        properties.set(
+            to_str(section, "section")?,
+            to_str(key, "key")?,
+            to_str(value, "value")?,
+        );
+
+        Ok(0)
+    })
+}
+
+/// Remove a property. Removing a property which isn't set succeeds
+///
+/// Returns `0` on success and `-1` on failure
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library and `section` and `key` must be valid NUL terminated
+/// strings
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_unset(
+    properties: *mut Properties,
+    section: *const c_char,
+    key: *const c_char,
+) -> c_int {
+    call(-1, || {
+        let properties = properties.as_mut().ok_or_else(|| null("properties"))?;
+        properties.unset(to_str(section, "section")?, to_str(key, "key")?);
+
+        Ok(0)
+    })
+}
+
+/// Get the number of properties which are set, e.g. to read each one with [`gctx_properties_entry`]
+///
+/// Returns `-1` on failure
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_count(properties: *const Properties) -> c_int {
+    call(-1, || {
+        let properties = properties.as_ref().ok_or_else(|| null("properties"))?;
+
+        Ok(c_int::try_from(properties.iter().count()).unwrap_or(c_int::MAX))
+    })
+}
+
+/// Get the section, key and value of the property at the given index, in the order they appear in the configuration
+///
+/// Returns `0` on success and `-1` on failure, e.g. if the index is out of range. On success each of `section`, `key`
+/// and `value` which isn't `NULL` is set to a string which must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library and `section`, `key` and `value` must each be `NULL` or
+/// valid for writing a pointer
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_entry(
+    properties: *const Properties,
+    index: usize,
+    section: *mut *mut c_char,
+    key: *mut *mut c_char,
+    value: *mut *mut c_char,
+) -> c_int {
+    call(-1, || {
+        let properties = properties.as_ref().ok_or_else(|| null("properties"))?;
+        let entry = properties.iter().nth(index).ok_or_else(|| Failure {
+            code: INVALID_ARGUMENT,
+            message: format!("there's no property at index {}", index),
+        })?;
+
+        for (out, s) in [(section, entry.0), (key, entry.1), (value, entry.2)] {
+            if !out.is_null() {
+                *out = to_c_string(s);
+            }
+        }
+
+        Ok(0)
+    })
+}
+
+/// Format properties in the same INI format as a configuration file
+///
+/// Returns `NULL` on failure. The string must be released with [`gctx_string_free`]
+///
+/// # Safety
+///
+/// `properties` must be properties returned by the library
+#[no_mangle]
+pub unsafe extern "C" fn gctx_properties_to_string(properties: *const Properties) -> *mut c_char {
+    call(ptr::null_mut(), || {
+        let properties = properties.as_ref().ok_or_else(|| null("properties"))?;
+
+        Ok(to_c_string(&properties.to_string()))
+    })
+}
+
+/// Release a string returned by the library. Does nothing if `s` is `NULL`
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by the library which hasn't already been released
+#[no_mangle]
+pub unsafe extern "C" fn gctx_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Message describing the last failure on the calling thread, or `NULL` if the last call succeeded
+///
+/// The message is owned by the library and is only valid until the next call on the same thread, so it must not be
+/// released
+#[no_mangle]
+pub extern "C" fn gctx_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |(_, message)| message.as_ptr())
+    })
+}
+
+/// Code of the last failure on the calling thread, e.g. `UNKNOWN_CONFIGURATION`, or `NULL` if the last call succeeded
+///
+/// The code is owned by the library and is only valid until the next call on the same thread, so it must not be
+/// released
+#[no_mangle]
+pub extern "C" fn gctx_last_error_code() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(code, _)| code.as_ptr()))
+}
+
+/// Run an operation, returning `failed` and recording why if it fails, so that neither errors nor panics cross the C
+/// boundary
+fn call<T>(failed: T, operation: impl FnOnce() -> Result<T, Failure>) -> T {
+    let (outcome, error) = match panic::catch_unwind(AssertUnwindSafe(operation)) {
+        Ok(Ok(value)) => (value, None),
+        Ok(Err(error)) => (failed, Some(error)),
+        Err(_) => (
+            failed,
+            Some(Failure {
+                code: PANIC,
+                message: "gcloud-ctx panicked, please report a bug".to_owned(),
+            }),
+        ),
+    };
+
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = error.map(|e| (to_c_string_lossy(e.code), to_c_string_lossy(&e.message)));
+    });
+
+    outcome
+}
+
+/// Why a call failed
+struct Failure {
+    /// Machine-readable code, e.g. `UNKNOWN_CONFIGURATION`
+    code: &'static str,
+
+    /// Human-readable message
+    message: String,
+}
+
+impl From<Error> for Failure {
+    fn from(error: Error) -> Self {
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Borrow a string argument
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a valid NUL terminated string which outlives the returned value
+unsafe fn to_str<'a>(s: *const c_char, argument: &str) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(null(argument));
+    }
+
+    CStr::from_ptr(s).to_str().map_err(|_| Failure {
+        code: INVALID_ARGUMENT,
+        message: format!("'{}' isn't valid UTF-8", argument),
+    })
+}
+
+/// Error for a `NULL` argument
+fn null(argument: &str) -> Failure {
+    Failure {
+        code: INVALID_ARGUMENT,
+        message: format!("'{}' must not be NULL", argument),
+    }
+}
+
+/// Copy a string to be owned by the caller, which must release it with [`gctx_string_free`]
+fn to_c_string(s: &str) -> *mut c_char {
+    to_c_string_lossy(s).into_raw()
+}
+
+/// Copy a string into a C string, dropping any NUL bytes because C strings can't contain them
+fn to_c_string_lossy(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::tests::open_store;
+    use std::fs;
+
+    /// Copy a string returned by the library and release it
+    unsafe fn take(s: *mut c_char) -> String {
+        assert!(!s.is_null(), "{:?}", last_error());
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        gctx_string_free(s);
+        owned
+    }
+
+    fn last_error() -> Option<(String, String)> {
+        let code = gctx_last_error_code();
+        let message = gctx_last_error();
+
+        (!code.is_null()).then(|| unsafe {
+            (
+                CStr::from_ptr(code).to_string_lossy().into_owned(),
+                CStr::from_ptr(message).to_string_lossy().into_owned(),
+            )
+        })
+    }
+
+    #[test]
+    pub fn test_store_operations() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("configurations/config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+        let location = CString::new(tmp.path().to_str().unwrap()).unwrap();
+
+        unsafe {
+            let store = gctx_store_open(location.as_ptr());
+            assert!(!store.is_null(), "{:?}", last_error());
+
+            // keep the state of the store inside it so the test never touches the real state directory
+            *store = open_store(tmp.path().to_path_buf()).unwrap();
+
+            let name = CString::new("bar").unwrap();
+            let properties = CString::new("[core]\nproject = my-project\n").unwrap();
+            assert_eq!(gctx_store_create(store, name.as_ptr(), properties.as_ptr(), 0), 0);
+            assert_eq!(gctx_store_activate(store, name.as_ptr()), 0);
+
+            assert_eq!(take(gctx_store_list(store)), "bar\nfoo");
+            assert_eq!(take(gctx_store_active(store)), "bar");
+            assert_eq!(
+                take(gctx_store_describe(store, name.as_ptr())),
+                "[core]\nproject=my-project\n"
+            );
+            assert_eq!(last_error(), None);
+
+            let contents = fs::read_to_string(tmp.path().join("configurations/config_bar")).unwrap();
+            assert!(contents.contains("my-project"));
+
+            let foo = CString::new("foo").unwrap();
+            assert_eq!(gctx_store_delete(store, foo.as_ptr()), 0);
+            assert_eq!(take(gctx_store_list(store)), "bar");
+
+            assert_eq!(gctx_store_delete(store, name.as_ptr()), -1);
+            assert_eq!(last_error().unwrap().0, "DELETE_ACTIVE_CONFIGURATION");
+
+            gctx_store_free(store);
+        }
+    }
+
+    #[test]
+    pub fn test_failures_set_last_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("configurations/config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+        let location = CString::new(tmp.path().to_str().unwrap()).unwrap();
+
+        unsafe {
+            let store = gctx_store_open(location.as_ptr());
+            *store = open_store(tmp.path().to_path_buf()).unwrap();
+
+            let name = CString::new("foo").unwrap();
+            assert_eq!(gctx_store_create(store, name.as_ptr(), ptr::null(), 0), -1);
+            assert_eq!(last_error().unwrap().0, "EXISTING_CONFIGURATION");
+            assert_eq!(gctx_store_create(store, name.as_ptr(), ptr::null(), 1), 0);
+            assert_eq!(last_error(), None);
+
+            let unknown = CString::new("unknown").unwrap();
+            assert_eq!(gctx_store_activate(store, unknown.as_ptr()), -1);
+            assert_eq!(last_error().unwrap().0, "UNKNOWN_CONFIGURATION");
+
+            assert_eq!(gctx_store_activate(store, ptr::null()), -1);
+            assert_eq!(last_error().unwrap().0, INVALID_ARGUMENT);
+
+            gctx_store_free(store);
+        }
+    }
+
+    #[test]
+    pub fn test_property_operations() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(
+            tmp.path().join("configurations/config_foo"),
+            "[core]\n; legacy = 1\nproject = my-project\n",
+        )
+        .unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+        let location = CString::new(tmp.path().to_str().unwrap()).unwrap();
+
+        let [name, core, project, account, legacy, value] =
+            ["foo", "core", "project", "account", "legacy", "me@example.com"].map(|s| CString::new(s).unwrap());
+
+        unsafe {
+            let store = gctx_store_open(location.as_ptr());
+            *store = open_store(tmp.path().to_path_buf()).unwrap();
+
+            assert_eq!(
+                take(gctx_store_get_property(
+                    store,
+                    name.as_ptr(),
+                    core.as_ptr(),
+                    project.as_ptr()
+                )),
+                "my-project"
+            );
+
+            // commented out properties aren't set
+            let missing = gctx_store_get_property(store, name.as_ptr(), core.as_ptr(), legacy.as_ptr());
+            assert!(missing.is_null());
+            assert_eq!(last_error(), None);
+
+            assert_eq!(
+                gctx_store_set_property(store, name.as_ptr(), core.as_ptr(), account.as_ptr(), value.as_ptr()),
+                0
+            );
+            assert_eq!(
+                gctx_store_unset_property(store, name.as_ptr(), core.as_ptr(), project.as_ptr()),
+                0
+            );
+
+            let properties = gctx_store_properties(store, name.as_ptr());
+            assert!(!properties.is_null(), "{:?}", last_error());
+            assert_eq!(gctx_properties_count(properties), 1);
+
+            let (mut section, mut key, mut entry) = (ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            assert_eq!(
+                gctx_properties_entry(properties, 0, &mut section, &mut key, &mut entry),
+                0
+            );
+            assert_eq!(
+                (take(section), take(key), take(entry)),
+                ("core".into(), "account".into(), value.to_str().unwrap().into())
+            );
+
+            assert_eq!(
+                gctx_properties_entry(properties, 1, &mut section, ptr::null_mut(), ptr::null_mut()),
+                -1
+            );
+            assert_eq!(last_error().unwrap().0, INVALID_ARGUMENT);
+
+            gctx_properties_free(properties);
+            gctx_store_free(store);
+        }
+    }
+
+    #[test]
+    pub fn test_properties_handle() {
+        let [core, project, my_project] = ["core", "project", "my-project"].map(|s| CString::new(s).unwrap());
+
+        unsafe {
+            let properties = gctx_properties_new();
+            assert_eq!(gctx_properties_count(properties), 0);
+
+            assert_eq!(
+                gctx_properties_set(properties, core.as_ptr(), project.as_ptr(), my_project.as_ptr()),
+                0
+            );
+            assert_eq!(
+                take(gctx_properties_get(properties, core.as_ptr(), project.as_ptr())),
+                "my-project"
+            );
+            assert_eq!(
+                take(gctx_properties_to_string(properties)),
+                "[core]\nproject=my-project\n"
+            );
+
+            assert_eq!(gctx_properties_unset(properties, core.as_ptr(), project.as_ptr()), 0);
+            assert!(gctx_properties_get(properties, core.as_ptr(), project.as_ptr()).is_null());
+            assert_eq!(last_error(), None);
+
+            assert_eq!(
+                gctx_properties_set(properties, ptr::null(), project.as_ptr(), my_project.as_ptr()),
+                -1
+            );
+            assert_eq!(last_error().unwrap().0, INVALID_ARGUMENT);
+
+            gctx_properties_free(properties);
+        }
+    }
+
+    #[test]
+    pub fn test_header_matches_every_function() {
+        let shipped = include_str!("../include/gcloud_ctx.h");
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/gcloud_ctx.h"));
+
+        assert!(
+            shipped == generated,
+            "include/gcloud_ctx.h is out of date, regenerate it with cbindgen as described in cbindgen.toml"
+        );
+    }
+}
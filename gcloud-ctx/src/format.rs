@@ -0,0 +1,91 @@
+use crate::Properties;
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
+
+impl Serialize for Properties {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let sections: Vec<_> = self.document().sections().collect();
+        let mut map = serializer.serialize_map(Some(sections.len()))?;
+
+        for section in sections {
+            map.serialize_entry(section.name(), &SectionProperties(section.properties().collect()))?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(PropertiesVisitor)
+    }
+}
+
+/// Properties of a single section, kept in order
+struct SectionProperties<K, V>(Vec<(K, V)>);
+
+impl<K: Serialize, V: Serialize> Serialize for SectionProperties<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for SectionProperties<String, String> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(SectionVisitor)
+    }
+}
+
+/// Builds properties section by section, keeping the order of the input
+struct PropertiesVisitor;
+
+impl<'de> Visitor<'de> for PropertiesVisitor {
+    type Value = Properties;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object of sections")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Self::Value, A::Error> {
+        let mut properties = Properties::default();
+
+        while let Some((section, values)) = access.next_entry::<String, SectionProperties<String, String>>()? {
+            for (key, value) in values.0 {
+                properties.set(&section, &key, &value);
+            }
+        }
+
+        Ok(properties)
+    }
+}
+
+/// Reads the properties of a section, which must all be strings
+struct SectionVisitor;
+
+impl<'de> Visitor<'de> for SectionVisitor {
+    type Value = SectionProperties<String, String>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object of string properties")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Self::Value, A::Error> {
+        let mut properties = Vec::new();
+
+        while let Some(entry) = access.next_entry()? {
+            properties.push(entry);
+        }
+
+        Ok(SectionProperties(properties))
+    }
+}
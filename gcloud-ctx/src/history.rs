@@ -0,0 +1,157 @@
+use crate::{storage::Storage, FileContext, FileOperation, Result};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Maximum number of activations kept in the journal, after which the oldest are dropped
+const MAX_ENTRIES: usize = 100;
+
+/// Configuration which was activated, recorded in the activation history of a store, see
+/// [`ConfigurationStore::history`](crate::ConfigurationStore::history)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Name of the configuration, which may have since been renamed or deleted
+    name: String,
+
+    /// When the configuration was activated
+    activated: SystemTime,
+}
+
+impl HistoryEntry {
+    /// Name of the configuration, which may have since been renamed or deleted
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// When the configuration was activated
+    pub fn activated(&self) -> SystemTime {
+        self.activated
+    }
+}
+
+/// Journal of activations, kept in the state directory of a store with one line per activation in the order they
+/// happened, e.g. `1700000000 my-config`
+#[derive(Debug)]
+pub(crate) struct History;
+
+impl History {
+    /// Path to the journal within the state directory of a store
+    pub(crate) fn file(state: &Path) -> PathBuf {
+        state.join("history")
+    }
+
+    /// Read the journal from the state directory of a store, oldest first
+    ///
+    /// Lines which can't be understood, e.g. after a partial write by an older version, are skipped
+    pub(crate) fn read(storage: &Storage, state: &Path) -> Result<Vec<HistoryEntry>> {
+        let path = Self::file(state);
+
+        let contents = match storage.read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).file_context(FileOperation::Read, &path),
+        };
+
+        let entries = contents
+            .lines()
+            .filter_map(|line| {
+                let (seconds, name) = line.split_once(' ')?;
+                let activated = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.parse().ok()?);
+
+                Some(HistoryEntry {
+                    name: name.to_owned(),
+                    activated,
+                })
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Record an activation in the journal in the state directory of a store, dropping the oldest entries if it's full
+    pub(crate) fn record(storage: &Storage, state: &Path, name: &str, activated: SystemTime) -> Result<()> {
+        let mut entries = Self::read(storage, state)?;
+
+        entries.push(HistoryEntry {
+            name: name.to_owned(),
+            activated,
+        });
+
+        let skip = entries.len().saturating_sub(MAX_ENTRIES);
+
+        let contents: String = entries[skip..]
+            .iter()
+            .map(|entry| {
+                let seconds = entry
+                    .activated
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+
+                format!("{} {}\n", seconds, entry.name)
+            })
+            .collect();
+
+        let path = Self::file(state);
+
+        if let Some(parent) = path.parent() {
+            storage
+                .create_dir_all(parent)
+                .file_context(FileOperation::Create, parent)?;
+        }
+
+        storage.write(&path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    pub fn test_record_and_read() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = tmp.path().join("state");
+        let time = |seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+
+        assert_eq!(History::read(&Storage::Disk, &state).unwrap(), vec![]);
+
+        History::record(&Storage::Disk, &state, "foo", time(1)).unwrap();
+        History::record(&Storage::Disk, &state, "bar", time(2)).unwrap();
+
+        let entries = History::read(&Storage::Disk, &state).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name(), "foo");
+        assert_eq!(entries[0].activated(), time(1));
+        assert_eq!(entries[1].name(), "bar");
+    }
+
+    #[test]
+    pub fn test_record_drops_oldest_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        for i in 0..MAX_ENTRIES + 5 {
+            History::record(&Storage::Disk, tmp.path(), &format!("config-{}", i), SystemTime::now()).unwrap();
+        }
+
+        let entries = History::read(&Storage::Disk, tmp.path()).unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].name(), "config-5");
+    }
+
+    #[test]
+    pub fn test_read_skips_malformed_lines() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(History::file(tmp.path()), "1 foo\ngarbage\nx bar\n2 baz\n").unwrap();
+
+        let names: Vec<String> = History::read(&Storage::Disk, tmp.path())
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+
+        assert_eq!(names, vec!["foo", "baz"]);
+    }
+}
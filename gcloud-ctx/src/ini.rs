@@ -0,0 +1,494 @@
+use crate::{DuplicateKeys, Error, KeyOrder, ReadOptions, Result, Warning, WriteOptions};
+use std::{cmp::Ordering, io::Write};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Document model for the INI files which gcloud reads and writes
+///
+/// Comment lines are kept with the section header or property which follows them, so they survive being read,
+/// modified and written back, even if the properties are written in a different order
+pub(crate) struct Document {
+    /// Sections in the order they first appear
+    sections: Vec<Section>,
+
+    /// Comment lines after the last property
+    trailing_comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A named section within an INI document
+pub(crate) struct Section {
+    /// Name of the section
+    name: String,
+
+    /// Comment lines before the section header
+    comments: Vec<String>,
+
+    /// Properties in the order they first appear
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A key/value pair within a section
+struct Property {
+    /// Key of the property
+    key: String,
+
+    /// Value of the property
+    value: String,
+
+    /// Comment lines before the property
+    comments: Vec<String>,
+}
+
+impl Document {
+    /// Parse a document, applying the duplicate key semantics from the given options
+    ///
+    /// Any non-fatal problems found whilst parsing are added to `warnings`
+    pub fn parse(input: &str, options: &ReadOptions, warnings: &mut Vec<Warning>) -> Result<Self> {
+        let mut document = Document::default();
+        let mut current: Option<usize> = None;
+        let mut comments = Vec::new();
+
+        let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('#') || line.starts_with(';') {
+                comments.push(line.to_owned());
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header.strip_suffix(']').ok_or_else(|| Error::LoadingProperties {
+                    line: line_number,
+                    reason: "section header is missing a closing ']'".to_owned(),
+                })?;
+
+                let index = document.section_index(name.trim());
+                document.sections[index].comments.append(&mut comments);
+                current = Some(index);
+                continue;
+            }
+
+            let section = current.ok_or_else(|| Error::LoadingProperties {
+                line: line_number,
+                reason: "property is not inside a section".to_owned(),
+            })?;
+
+            let (key, value) = line.split_once(['=', ':']).ok_or_else(|| Error::LoadingProperties {
+                line: line_number,
+                reason: "expected a property in the form 'key = value'".to_owned(),
+            })?;
+
+            let section = &mut document.sections[section];
+            let key = key.trim();
+            let value = value.trim().to_owned();
+
+            match section.properties.iter_mut().find(|p| p.key == key) {
+                Some(_) if options.duplicate_keys == DuplicateKeys::Error => {
+                    return Err(Error::DuplicateKey {
+                        section: section.name.clone(),
+                        key: key.to_owned(),
+                    });
+                }
+                Some(existing) => {
+                    existing.value = value;
+                    existing.comments.append(&mut comments);
+                    warnings.push(Warning::DuplicateKey {
+                        section: section.name.clone(),
+                        key: key.to_owned(),
+                    });
+                }
+                None => section.properties.push(Property {
+                    key: key.to_owned(),
+                    value,
+                    comments: std::mem::take(&mut comments),
+                }),
+            }
+        }
+
+        document.trailing_comments = comments;
+
+        Ok(document)
+    }
+
+    /// Iterate over the sections in the order they first appear
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    /// Get a section by name
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    /// Set the value of a property, adding the section and property if they don't exist
+    ///
+    /// Comments on an existing property are kept
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        let index = self.section_index(section);
+        let section = &mut self.sections[index];
+
+        match section.properties.iter_mut().find(|p| p.key == key) {
+            Some(existing) => existing.value = value.to_owned(),
+            None => section.properties.push(Property {
+                key: key.to_owned(),
+                value: value.to_owned(),
+                comments: Vec::new(),
+            }),
+        }
+    }
+
+    /// Remove a property, and its section if that leaves the section empty, returning the previous value
+    ///
+    /// Comments on the property, or on the section if it's removed, are removed too
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<String> {
+        let index = self.sections.iter().position(|s| s.name == section)?;
+        let properties = &mut self.sections[index].properties;
+        let position = properties.iter().position(|p| p.key == key)?;
+        let property = properties.remove(position);
+
+        if properties.is_empty() {
+            self.sections.remove(index);
+        }
+
+        Some(property.value)
+    }
+
+    /// Remove every comment, e.g. to compare documents by their properties alone
+    #[cfg(feature = "store")]
+    pub fn strip_comments(&mut self) {
+        self.trailing_comments.clear();
+
+        for section in &mut self.sections {
+            section.comments.clear();
+
+            for property in &mut section.properties {
+                property.comments.clear();
+            }
+        }
+    }
+
+    /// Put the document into a normal form, with the properties in each section sorted by key and any empty sections
+    /// removed
+    ///
+    /// Sections keep their order, and comments move with the section or property they belong to. Comments on an
+    /// empty section move to the next section, or to the end of the document if there isn't one
+    #[cfg(feature = "store")]
+    pub fn normalize(&mut self) {
+        let mut orphaned = Vec::new();
+
+        for mut section in std::mem::take(&mut self.sections) {
+            if section.properties.is_empty() {
+                orphaned.append(&mut section.comments);
+                continue;
+            }
+
+            orphaned.append(&mut section.comments);
+            section.comments = std::mem::take(&mut orphaned);
+            self.sections.push(section);
+        }
+
+        orphaned.append(&mut self.trailing_comments);
+        self.trailing_comments = orphaned;
+
+        for section in &mut self.sections {
+            section.properties.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+    }
+
+    /// Sections and their properties in the given order, without changing the document
+    fn ordered(&self, order: KeyOrder) -> Vec<(&Section, Vec<&Property>)> {
+        let mut sections: Vec<_> = self
+            .sections
+            .iter()
+            .map(|section| (section, section.properties.iter().collect::<Vec<_>>()))
+            .collect();
+
+        if order == KeyOrder::Canonical {
+            sections.sort_by(|(a, _), (b, _)| canonical_section_cmp(&a.name, &b.name));
+
+            for (_, properties) in &mut sections {
+                properties.sort_by(|a, b| a.key.cmp(&b.key));
+            }
+        }
+
+        sections
+    }
+
+    /// Write the document using the given options
+    pub fn write<W: Write>(&self, mut writer: W, options: &WriteOptions) -> Result<()> {
+        let ending = options.line_ending.as_str();
+        let mut output = String::new();
+
+        let push_comments = |output: &mut String, comments: &[String]| {
+            for comment in comments {
+                output.push_str(comment);
+                output.push_str(ending);
+            }
+        };
+
+        for (section, properties) in self.ordered(options.key_order) {
+            push_comments(&mut output, &section.comments);
+            output.push('[');
+            output.push_str(&section.name);
+            output.push(']');
+            output.push_str(ending);
+
+            for property in properties {
+                push_comments(&mut output, &property.comments);
+                output.push_str(&property.key);
+                output.push('=');
+                output.push_str(&property.value);
+                output.push_str(ending);
+            }
+        }
+
+        push_comments(&mut output, &self.trailing_comments);
+
+        if !options.final_newline && output.ends_with(ending) {
+            output.truncate(output.len() - ending.len());
+        }
+
+        writer.write_all(output.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Get the index of the named section, adding it if it doesn't exist yet
+    fn section_index(&mut self, name: &str) -> usize {
+        match self.sections.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                self.sections.push(Section {
+                    name: name.to_owned(),
+                    comments: Vec::new(),
+                    properties: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        }
+    }
+}
+
+/// Canonical order of sections, i.e. `core` first and then the rest by name
+fn canonical_section_cmp(a: &str, b: &str) -> Ordering {
+    (a != "core", a).cmp(&(b != "core", b))
+}
+
+impl Section {
+    /// Name of the section
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Iterate over the properties in this section
+    pub fn properties(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties.iter().map(|p| (p.key.as_str(), p.value.as_str()))
+    }
+
+    /// Get the value of a property in this section
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties.iter().find(|p| p.key == key).map(|p| p.value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, duplicate_keys: DuplicateKeys) -> Result<(Document, Vec<Warning>)> {
+        let mut warnings = Vec::new();
+        let document = Document::parse(
+            input,
+            ReadOptions::default().duplicate_keys(duplicate_keys),
+            &mut warnings,
+        )?;
+        Ok((document, warnings))
+    }
+
+    #[test]
+    pub fn test_parse_gcloud_format() {
+        let input = "[core]\nproject = my-project\naccount = a.user@example.org\n\n[compute]\nzone = europe-west1-d\n";
+        let (document, warnings) = parse(input, DuplicateKeys::LastWins).unwrap();
+
+        let core = document.section("core").unwrap();
+        assert_eq!(core.get("project"), Some("my-project"));
+        assert_eq!(core.get("account"), Some("a.user@example.org"));
+        assert_eq!(document.section("compute").unwrap().get("zone"), Some("europe-west1-d"));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    pub fn test_parse_mangled_file() {
+        // BOM, CRLF line endings, comments, odd whitespace and a colon delimiter
+        let input =
+            "\u{feff}# written by hand\r\n[ core ]\r\n  project=my-project  \r\n; comment\r\naccount : a@b.com\r\n";
+        let (document, _) = parse(input, DuplicateKeys::LastWins).unwrap();
+
+        let core = document.section("core").unwrap();
+        assert_eq!(core.get("project"), Some("my-project"));
+        assert_eq!(core.get("account"), Some("a@b.com"));
+    }
+
+    #[test]
+    pub fn test_parse_duplicate_key_last_wins() {
+        let input = "[core]\nproject = first\nproject = second\n";
+        let (document, warnings) = parse(input, DuplicateKeys::LastWins).unwrap();
+
+        assert_eq!(document.section("core").unwrap().get("project"), Some("second"));
+        assert_eq!(
+            warnings,
+            vec![Warning::DuplicateKey {
+                section: "core".to_owned(),
+                key: "project".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_parse_duplicate_key_across_repeated_sections() {
+        let input = "[core]\nproject = first\n[compute]\nzone = europe-west1-d\n[core]\nproject = second\n";
+        let (document, warnings) = parse(input, DuplicateKeys::LastWins).unwrap();
+
+        assert_eq!(document.section("core").unwrap().get("project"), Some("second"));
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    pub fn test_parse_duplicate_key_error() {
+        let input = "[core]\nproject = first\nproject = second\n";
+        let result = parse(input, DuplicateKeys::Error);
+
+        assert!(matches!(result, Err(Error::DuplicateKey { section, key }) if section == "core" && key == "project"));
+    }
+
+    #[test]
+    pub fn test_set_and_write() {
+        let (mut document, _) = parse("[core]\nproject = first\n", DuplicateKeys::LastWins).unwrap();
+        document.set("core", "project", "second");
+        document.set("compute", "zone", "europe-west1-d");
+
+        let mut output = Vec::new();
+        document
+            .write(&mut output, WriteOptions::default().line_ending(crate::LineEnding::Lf))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[core]\nproject=second\n[compute]\nzone=europe-west1-d\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "store")]
+    pub fn test_normalize_sorts_keys_and_removes_empty_sections() {
+        let input = "[empty]\n[compute]\nzone = europe-west1-d\n[core]\nproject = my-project\naccount = a@b.com\n";
+        let (mut document, _) = parse(input, DuplicateKeys::LastWins).unwrap();
+        document.normalize();
+
+        let mut output = Vec::new();
+        document
+            .write(
+                &mut output,
+                WriteOptions::default()
+                    .line_ending(crate::LineEnding::Lf)
+                    .key_order(KeyOrder::Preserve),
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[compute]\nzone=europe-west1-d\n[core]\naccount=a@b.com\nproject=my-project\n"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "store")]
+    pub fn test_normalize_keeps_comments_on_empty_sections() {
+        let input = "# unused for now\n[empty]\n[core]\nproject = my-project\n# also unused\n[other]\n# the end\n";
+        let (mut document, _) = parse(input, DuplicateKeys::LastWins).unwrap();
+        document.normalize();
+
+        let mut output = Vec::new();
+        document
+            .write(
+                &mut output,
+                WriteOptions::default()
+                    .line_ending(crate::LineEnding::Lf)
+                    .key_order(KeyOrder::Preserve),
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# unused for now\n[core]\nproject=my-project\n# also unused\n# the end\n"
+        );
+    }
+
+    #[test]
+    pub fn test_comments_survive_modification() {
+        let input = "# my settings\n[core]\n; the usual project\nproject = my-project\naccount = a@b.com\n\n\
+                     # compute\n[compute]\n# temporary\nzone = europe-west1-d\n# the end\n";
+        let (mut document, _) = parse(input, DuplicateKeys::LastWins).unwrap();
+        document.set("core", "project", "other-project");
+        document.remove("compute", "zone");
+        document.set("auth", "disable_credentials", "true");
+
+        let mut output = Vec::new();
+        document
+            .write(&mut output, WriteOptions::default().line_ending(crate::LineEnding::Lf))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# my settings\n[core]\naccount=a@b.com\n; the usual project\nproject=other-project\n\
+             [auth]\ndisable_credentials=true\n# the end\n"
+        );
+    }
+
+    #[test]
+    pub fn test_write_key_order() {
+        let input = "[compute]\nzone = europe-west1-d\n[auth]\nz = 1\na = 2\n[core]\nproject = my-project\n";
+        let (document, _) = parse(input, DuplicateKeys::LastWins).unwrap();
+        let write = |key_order| {
+            let mut output = Vec::new();
+            document
+                .write(
+                    &mut output,
+                    WriteOptions::default()
+                        .line_ending(crate::LineEnding::Lf)
+                        .key_order(key_order),
+                )
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(
+            write(KeyOrder::Canonical),
+            "[core]\nproject=my-project\n[auth]\na=2\nz=1\n[compute]\nzone=europe-west1-d\n"
+        );
+        assert_eq!(
+            write(KeyOrder::Preserve),
+            "[compute]\nzone=europe-west1-d\n[auth]\nz=1\na=2\n[core]\nproject=my-project\n"
+        );
+    }
+
+    #[test]
+    pub fn test_parse_property_outside_section_fails() {
+        let result = parse("project = my-project\n", DuplicateKeys::LastWins);
+        assert!(matches!(result, Err(Error::LoadingProperties { line: 1, .. })));
+    }
+
+    #[test]
+    pub fn test_parse_unterminated_section_fails() {
+        let result = parse("[core]\nproject = my-project\n[compute\n", DuplicateKeys::LastWins);
+        assert!(matches!(result, Err(Error::LoadingProperties { line: 3, .. })));
+    }
+}
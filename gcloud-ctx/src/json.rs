@@ -0,0 +1,53 @@
+use crate::{Properties, Result};
+use std::io::{Read, Write};
+
+impl Properties {
+    /// Deserialise properties from JSON, as an object of sections which are objects of string properties
+    ///
+    /// ```json
+    /// { "core": { "project": "my-project" }, "compute": { "zone": "europe-west1-d" } }
+    /// ```
+    pub fn from_json_reader<R: Read>(reader: R) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Serialise properties to JSON, with sections and properties in the same order as the configuration file
+    pub fn to_json_writer<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_json_round_trip_keeps_order() {
+        let input = "[core]\nproject = my-project\naccount = a.user@example.org\n[compute]\nzone = europe-west1-d\n";
+        let properties = Properties::from_reader(input.as_bytes()).unwrap();
+
+        let mut json = Vec::new();
+        properties.to_json_writer(&mut json).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["core"]["project"], "my-project");
+        assert_eq!(value["compute"]["zone"], "europe-west1-d");
+
+        let parsed = Properties::from_json_reader(json.as_slice()).unwrap();
+        assert_eq!(
+            parsed.iter().collect::<Vec<_>>(),
+            vec![
+                ("core", "project", "my-project"),
+                ("core", "account", "a.user@example.org"),
+                ("compute", "zone", "europe-west1-d"),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_from_json_rejects_non_string_values() {
+        let result = Properties::from_json_reader(r#"{ "core": { "disable_prompts": true } }"#.as_bytes());
+        assert!(matches!(result, Err(crate::Error::Json(_))));
+    }
+}
@@ -17,6 +17,7 @@
 //! # std::fs::create_dir(&configs).unwrap();
 //! # File::create(configs.join("config_foo")).unwrap();
 //! # std::env::set_var("CLOUDSDK_CONFIG", tmp.path());
+//! # std::env::set_var("GCTX_STATE_DIR", tmp.path().join("state"));
 //! use gcloud_ctx::{ConfigurationStore, ConflictAction};
 //!
 //! let mut store = ConfigurationStore::with_default_location()?;
@@ -24,8 +25,8 @@
 //! // create a new configuration, optionally with a force overwrite
 //! use gcloud_ctx::PropertiesBuilder;
 //! let properties = PropertiesBuilder::default()
-//!     .project("my-project")
-//!     .account("a.user@example.org")
+//!     .project(&"my-project".parse()?)
+//!     .account(&"a.user@example.org".parse()?)
 //!     .zone("europe-west1-d")
 //!     .region("europe-west1")
 //!     .build();
@@ -33,15 +34,15 @@
 //! store.create("foo", &properties, ConflictAction::Overwrite)?;
 //!
 //! // list configurations
-//! for config in store.configurations() {
+//! for config in store.configurations()? {
 //!     println!("{}", config.name());
 //! }
 //!
 //! // activate a configuration by name
 //! store.activate("foo")?;
 //!
-//! // get the active configuration
-//! println!("{}", store.active());
+//! // get the active configuration, if there is one
+//! println!("{:?}", store.active());
 //!
 //! // copy an existing configuration, with force overwrite
 //! store.copy("foo", "bar", ConflictAction::Overwrite)?;
@@ -53,30 +54,196 @@
 //! store.delete("baz")?;
 //!
 //! // get properties of a configuration
-//! let properties = store.describe("foo")?;
+//! let mut properties = store.describe("foo")?;
 //! properties.to_writer(std::io::stdout())?;
+//!
+//! // read and write any property, not just the ones supported by the builder
+//! println!("{:?}", properties.get("core", "project"));
+//! properties.set("auth", "impersonate_service_account", "sa@my-project.iam.gserviceaccount.com");
 //! # Ok::<(), gcloud_ctx::Error>(())
 //! ```
+//!
+//! ## Without the filesystem
+//!
+//! Everything which reads or writes files, i.e. [`ConfigurationStore`] and friends, is behind the default `store`
+//! feature. Without it only the property model is built, i.e. [`Properties`], the validated values and the
+//! serialisation formats, so that it can be used where there's no filesystem, e.g. in a browser on
+//! `wasm32-unknown-unknown`:
+//!
+//! ```toml
+//! gcloud-ctx = { version = "0.4", default-features = false, features = ["json"] }
+//! ```
+//!
+//! The other default features only swap in lighter fallbacks when they're disabled. Without `regex`, names and values
+//! are validated by hand-written matchers which accept exactly the same values, and without `dirs` the default
+//! locations are found from environment variables such as `HOME` and `XDG_CONFIG_HOME`:
+//!
+//! ```toml
+//! gcloud-ctx = { version = "0.4", default-features = false, features = ["store"] }
+//! ```
+//!
+//! ## Logging
+//!
+//! With the `log` feature, the store logs every file it reads or writes at trace level, and each scan of the
+//! configurations directory and change to the store at debug level, through the [`log`](https://docs.rs/log) facade.
+//!
+//! With the `tracing` feature the same messages are also emitted as [`tracing`](https://docs.rs/tracing) events,
+//! and each public [`ConfigurationStore`] operation is a debug level span named after the method, with the
+//! configuration names it was given as fields. Events for the files an operation reads and writes happen inside its
+//! span, and failures are recorded as an error event on the span, so subscribers can time operations and see what
+//! each one touched
+//!
+//! ## Stability
+//!
+//! Everything exported from the crate follows semver, and the [`prelude`] contains the items most applications need.
+//! The only exceptions are `NameCache` and `StateDirectory`, lower-level building blocks for the `gctx` CLI which may
+//! change in any release. They're only exported when the `unstable` feature is enabled
+
+// the logging macros must be defined before the modules which use them
+#[cfg(feature = "store")]
+#[macro_use]
+mod logging;
 
+#[cfg(feature = "store")]
+mod apply;
+#[cfg(feature = "store")]
+mod archive;
+#[cfg(feature = "store")]
+mod atomic;
+#[cfg(feature = "store")]
+mod backup;
+#[cfg(feature = "store")]
+mod builder;
+#[cfg(feature = "store")]
+mod check;
+// parts of the unstable building blocks are only used by the gctx CLI
+#[cfg(feature = "store")]
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod cache;
+#[cfg(feature = "store")]
+mod cleanup;
+#[cfg(feature = "store")]
 mod configuration;
+#[cfg(feature = "store")]
+mod conflict;
+mod entry;
+#[cfg(feature = "store")]
+mod event;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "serde")]
+mod format;
+#[cfg(feature = "store")]
+mod history;
+mod ini;
+#[cfg(feature = "json")]
+mod json;
+pub mod locations;
+mod metadata;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+#[cfg(feature = "store")]
+mod paths;
+mod patterns;
+pub mod prelude;
 mod properties;
+mod query;
+#[cfg(feature = "store")]
+mod resolve;
+mod risk;
+mod sections;
+#[cfg(feature = "store")]
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+mod state;
+#[cfg(feature = "store")]
+mod storage;
+#[cfg(feature = "store")]
+mod store_set;
+mod template;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "store")]
+mod undo;
+mod values;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "yaml")]
+mod yaml;
 
+#[cfg(feature = "store")]
+pub use apply::*;
+#[cfg(feature = "store")]
+pub use backup::*;
+#[cfg(feature = "store")]
+pub use builder::*;
+#[cfg(all(feature = "store", not(feature = "unstable")))]
+use cache::*;
+#[cfg(feature = "unstable")]
+pub use cache::*;
+#[cfg(feature = "store")]
+pub use check::*;
+#[cfg(feature = "store")]
+pub use cleanup::*;
+#[cfg(feature = "store")]
 pub use configuration::*;
+#[cfg(feature = "store")]
+pub use conflict::*;
+pub use entry::*;
+#[cfg(feature = "store")]
+pub use event::*;
+#[cfg(feature = "store")]
+pub use history::*;
+pub use metadata::*;
+#[cfg(feature = "tokio")]
+pub use nonblocking::*;
 pub use properties::*;
+pub use query::*;
+#[cfg(feature = "store")]
+pub use resolve::*;
+pub use risk::*;
+pub use sections::*;
+#[cfg(all(feature = "store", not(feature = "unstable")))]
+use state::*;
+#[cfg(feature = "unstable")]
+pub use state::*;
+#[cfg(feature = "store")]
+pub use store_set::*;
+pub use template::*;
+#[cfg(feature = "store")]
+pub use undo::*;
+pub use values::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
 
-use std::path::PathBuf;
+use std::{fmt, path::PathBuf};
 use thiserror::Error;
 
 /// gcloud-ctx result
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// gcloud-ctx error
+///
+/// New variants may be added in minor releases, so match on [`Error::code`] or include a wildcard arm
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
+    /// A name matched more than one configuration, see [`ConfigurationStore::resolve`]
+    #[cfg(feature = "store")]
+    #[error("The name matches several configurations: {}. Use more of the name", .0.join(", "))]
+    Ambiguous(Vec<String>),
+
+    /// The active configuration doesn't exist in the store
+    #[error("The active configuration '{0}' doesn't exist. Use 'gctx activate' to activate another configuration")]
+    ActiveConfigurationNotFound(String),
+
     /// The configuration directory was not found within the configuration store directory
     #[error("Unable to locate user configuration directory")]
     ConfigurationDirectoryNotFound,
 
+    /// Attempted to modify a pinned configuration
+    #[error("Configuration '{0}' is pinned")]
+    ConfigurationPinned(String),
+
     /// Unable to find the gcloud configuration root directory
     #[error("Unable to find the gcloud configuration directory at {0}\n\nIs gcloud installed?")]
     ConfigurationStoreNotFound(PathBuf),
@@ -85,31 +252,414 @@ pub enum Error {
     #[error("Unable to delete the configuration because it is currently active")]
     DeleteActiveConfiguration,
 
+    /// The same configuration was declared more than once, see [`ConfigurationStore::apply`]
+    #[cfg(feature = "store")]
+    #[error("Configuration '{0}' is declared more than once")]
+    DuplicateConfigSpec(String),
+
+    /// A property was defined more than once and duplicates were configured to be an error
+    #[error("Property '{section}/{key}' is defined more than once")]
+    DuplicateKey {
+        /// Section containing the duplicate property
+        section: String,
+
+        /// Key of the duplicate property
+        key: String,
+    },
+
     /// Error loading properties from a configuration
-    #[error("Unable to load properties")]
-    LoadingProperties(#[from] serde_ini::de::Error),
+    #[error("Unable to load properties: {reason} (line {line})")]
+    LoadingProperties {
+        /// Line number where the error occurred
+        line: usize,
+
+        /// Description of the problem
+        reason: String,
+    },
 
     /// The operation would overwrite an existing configuration
     #[error("A configuration named '{0}' already exists. Use --force to overwrite it")]
     ExistingConfiguration(String),
 
+    /// A root with the same name was already added to the set of stores, see [`StoreSet::add`]
+    #[cfg(feature = "store")]
+    #[error("A configuration store root named '{0}' already exists")]
+    ExistingRoot(String),
+
+    /// A file operation failed
+    #[error("Failed to {operation} {}", path.display())]
+    File {
+        /// Operation which failed
+        operation: FileOperation,
+
+        /// Path to the file or directory
+        path: PathBuf,
+
+        /// Underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The account isn't an email address, see [`Account`]
+    #[error(
+        "'{0}' is not a valid account. Accounts are email addresses, e.g. a.user@example.org or a service account"
+    )]
+    InvalidAccount(String),
+
+    /// The address of a configuration isn't `root:name`, see [`StoreAddress`]
+    #[cfg(feature = "store")]
+    #[error("'{0}' is not a valid address. Addresses are a root and a configuration name, e.g. work:my-config")]
+    InvalidAddress(String),
+
+    /// An archive of configurations couldn't be read or written, see [`ConfigurationStore::import_archive`]
+    #[error("Invalid configuration archive: {0}")]
+    InvalidArchive(String),
+
+    /// The validation mode isn't one of `strict`, `relaxed` or `off`, see [`LocationValidation`]
+    #[error("'{0}' is not a valid location validation mode, expected 'strict', 'relaxed' or 'off'")]
+    InvalidLocationValidation(String),
+
     /// The configuration name is invalid
     #[error("'{0}' is invalid. Configuration names must only contain ASCII letters and numbers")]
     InvalidName(String),
 
+    /// The project ID is invalid, see [`ProjectId`]
+    #[error("'{0}' is not a valid project ID. Project IDs are 6 to 30 lowercase letters, digits or hyphens, start with a letter and don't end with a hyphen")]
+    InvalidProjectId(String),
+
+    /// The region doesn't look like a region, see [`LocationValidation`]
+    #[error("'{0}' doesn't look like a region, e.g. europe-west1. Relax the location validation if it's a new region")]
+    InvalidRegion(String),
+
+    /// The zone doesn't look like a zone, see [`LocationValidation`]
+    #[error("'{0}' doesn't look like a zone, e.g. europe-west1-d. Relax the location validation if it's a new zone")]
+    InvalidZone(String),
+
+    /// Error reading or writing properties as JSON
+    #[cfg(feature = "json")]
+    #[error("Unable to read or write properties as JSON")]
+    Json(#[from] serde_json::Error),
+
     /// General I/O error
     #[error("I/O error")]
     Io(#[from] std::io::Error),
 
-    /// Not configurations were found in the configuration store
+    /// A template placeholder has no variable, see [`Template::render`]
+    #[error("Template placeholder '{{{{{0}}}}}' has no variable. Pass a value for '{0}'")]
+    MissingTemplateVariable(String),
+
+    /// No configuration is active, see [`ConfigurationStore::deactivate`]
+    #[error("No configuration is active. Use 'gctx activate' to activate a configuration")]
+    NoActiveConfiguration,
+
+    /// No configurations were found in the configuration store, see [`ConfigurationStoreBuilder::bootstrap_default`]
     #[error("Unable to find any gcloud configurations in {0}")]
     NoConfigurationsFound(PathBuf),
 
-    /// Error saving properties to a configuration
-    #[error("Unable to save properties")]
-    SavingProperties(#[from] serde_ini::ser::Error),
+    /// There's no destructive operation to undo, see [`ConfigurationStore::undo_last`]
+    #[cfg(feature = "store")]
+    #[error("There's nothing to undo")]
+    NothingToUndo,
+
+    /// No backups exist for the configuration
+    #[error("There are no backups of configuration '{0}'")]
+    NoBackups(String),
+
+    /// Permission to read or modify a file was denied, so it can't be treated as missing
+    #[cfg(feature = "store")]
+    #[error("Permission denied trying to {operation} {}", path.display())]
+    PermissionDenied {
+        /// Operation which failed
+        operation: FileOperation,
+
+        /// Path to the file or directory
+        path: PathBuf,
+
+        /// Underlying I/O error
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Attempted to modify a configuration store which was opened read-only
+    #[error("The configuration store at {0} is read-only")]
+    ReadOnlyStore(PathBuf),
+
+    /// Unable to find the OS-specific directory for the gcloud-ctx state directory
+    #[error("Unable to locate the state directory. Set GCTX_STATE_DIR to choose one")]
+    StateDirectoryNotFound,
+
+    /// The environment variable which sets the location of the configuration store points to a path which doesn't exist
+    #[error("{variable} is set to '{value}' which doesn't exist. Create the directory, or unset {variable} to use the default location")]
+    StoreEnvVarMissing {
+        /// Name of the environment variable, i.e. `CLOUDSDK_CONFIG`
+        variable: String,
+
+        /// Value of the environment variable
+        value: String,
+    },
+
+    /// The environment variable which sets the location of the configuration store points to something other than a
+    /// directory, e.g. a file
+    #[error("{variable} is set to '{value}' which isn't a directory. It must be the gcloud configuration directory, e.g. ~/.config/gcloud")]
+    StoreEnvVarNotADirectory {
+        /// Name of the environment variable, i.e. `CLOUDSDK_CONFIG`
+        variable: String,
+
+        /// Value of the environment variable
+        value: String,
+    },
 
     /// A configuration with the given name wasn't found
     #[error("Unable to find configuration '{0}'")]
     UnknownConfiguration(String),
+
+    /// A root with the given name wasn't found in the set of stores, see [`StoreSet`]
+    #[cfg(feature = "store")]
+    #[error("Unable to find configuration store root '{0}'")]
+    UnknownRoot(String),
+
+    /// A template with the given name wasn't found, see [`ConfigurationStore::template`]
+    #[cfg(feature = "store")]
+    #[error("Unable to find template '{0}'")]
+    UnknownTemplate(String),
+
+    /// Unable to watch the configuration store for changes
+    #[cfg(feature = "watch")]
+    #[error("Unable to watch the configuration store for changes")]
+    Watch(#[from] notify::Error),
+
+    /// Error reading or writing properties as YAML
+    #[cfg(feature = "yaml")]
+    #[error("Unable to read or write properties as YAML")]
+    Yaml(#[from] serde_yaml_ng::Error),
+}
+
+impl Error {
+    /// Stable, machine-readable identifier for the kind of error, e.g. `EXISTING_CONFIGURATION`
+    ///
+    /// Codes never change once released, unlike the messages, so wrappers and scripts can branch on them
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::ActiveConfigurationNotFound(_) => "ACTIVE_CONFIGURATION_NOT_FOUND",
+            #[cfg(feature = "store")]
+            Error::Ambiguous(_) => "AMBIGUOUS",
+            Error::ConfigurationDirectoryNotFound => "CONFIGURATION_DIRECTORY_NOT_FOUND",
+            Error::ConfigurationPinned(_) => "CONFIGURATION_PINNED",
+            Error::ConfigurationStoreNotFound(_) => "CONFIGURATION_STORE_NOT_FOUND",
+            Error::DeleteActiveConfiguration => "DELETE_ACTIVE_CONFIGURATION",
+            #[cfg(feature = "store")]
+            Error::DuplicateConfigSpec(_) => "DUPLICATE_CONFIG_SPEC",
+            Error::DuplicateKey { .. } => "DUPLICATE_KEY",
+            Error::LoadingProperties { .. } => "LOADING_PROPERTIES",
+            Error::ExistingConfiguration(_) => "EXISTING_CONFIGURATION",
+            #[cfg(feature = "store")]
+            Error::ExistingRoot(_) => "EXISTING_ROOT",
+            Error::File { .. } => "FILE",
+            Error::InvalidAccount(_) => "INVALID_ACCOUNT",
+            #[cfg(feature = "store")]
+            Error::InvalidAddress(_) => "INVALID_ADDRESS",
+            Error::InvalidArchive(_) => "INVALID_ARCHIVE",
+            Error::InvalidLocationValidation(_) => "INVALID_LOCATION_VALIDATION",
+            Error::InvalidName(_) => "INVALID_NAME",
+            Error::InvalidProjectId(_) => "INVALID_PROJECT_ID",
+            Error::InvalidRegion(_) => "INVALID_REGION",
+            Error::InvalidZone(_) => "INVALID_ZONE",
+            #[cfg(feature = "json")]
+            Error::Json(_) => "JSON",
+            Error::Io(_) => "IO",
+            Error::MissingTemplateVariable(_) => "MISSING_TEMPLATE_VARIABLE",
+            Error::NoActiveConfiguration => "NO_ACTIVE_CONFIGURATION",
+            Error::NoConfigurationsFound(_) => "NO_CONFIGURATIONS_FOUND",
+            Error::NoBackups(_) => "NO_BACKUPS",
+            #[cfg(feature = "store")]
+            Error::NothingToUndo => "NOTHING_TO_UNDO",
+            #[cfg(feature = "store")]
+            Error::PermissionDenied { .. } => "PERMISSION_DENIED",
+            Error::ReadOnlyStore(_) => "READ_ONLY_STORE",
+            Error::StateDirectoryNotFound => "STATE_DIRECTORY_NOT_FOUND",
+            Error::StoreEnvVarMissing { .. } => "STORE_ENV_VAR_MISSING",
+            Error::StoreEnvVarNotADirectory { .. } => "STORE_ENV_VAR_NOT_A_DIRECTORY",
+            Error::UnknownConfiguration(_) => "UNKNOWN_CONFIGURATION",
+            #[cfg(feature = "store")]
+            Error::UnknownRoot(_) => "UNKNOWN_ROOT",
+            #[cfg(feature = "store")]
+            Error::UnknownTemplate(_) => "UNKNOWN_TEMPLATE",
+            #[cfg(feature = "watch")]
+            Error::Watch(_) => "WATCH",
+            #[cfg(feature = "yaml")]
+            Error::Yaml(_) => "YAML",
+        }
+    }
+
+    /// Underlying I/O error, if this is an I/O failure, e.g. to check for [`std::io::ErrorKind::PermissionDenied`]
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        match self {
+            Error::File { source, .. } => Some(source),
+            #[cfg(feature = "store")]
+            Error::PermissionDenied { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// File operation which failed, see [`Error::File`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileOperation {
+    /// Reading a file or listing a directory
+    Read,
+
+    /// Writing a file
+    Write,
+
+    /// Creating a file or directory
+    Create,
+
+    /// Renaming a file
+    Rename,
+
+    /// Copying a file
+    Copy,
+
+    /// Deleting a file
+    Delete,
+
+    /// Locking a file
+    Lock,
+}
+
+impl fmt::Display for FileOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operation = match self {
+            FileOperation::Read => "read",
+            FileOperation::Write => "write",
+            FileOperation::Create => "create",
+            FileOperation::Rename => "rename",
+            FileOperation::Copy => "copy",
+            FileOperation::Delete => "delete",
+            FileOperation::Lock => "lock",
+        };
+
+        f.write_str(operation)
+    }
+}
+
+/// Attach the operation and path to an I/O error, see [`Error::File`]
+#[cfg(feature = "store")]
+pub(crate) trait FileContext<T> {
+    /// Convert an I/O error into [`Error::File`] for the given operation and path, or [`Error::PermissionDenied`] if
+    /// permission was denied
+    fn file_context(self, operation: FileOperation, path: &std::path::Path) -> Result<T>;
+}
+
+#[cfg(feature = "store")]
+impl<T> FileContext<T> for std::io::Result<T> {
+    fn file_context(self, operation: FileOperation, path: &std::path::Path) -> Result<T> {
+        match self {
+            Ok(value) => {
+                trace!("{} {}", operation, path.display());
+                Ok(value)
+            }
+            Err(source) => {
+                debug!("failed to {} {}: {}", operation, path.display(), source);
+
+                if source.kind() == std::io::ErrorKind::PermissionDenied {
+                    return Err(Error::PermissionDenied {
+                        operation,
+                        path: path.to_path_buf(),
+                        source,
+                    });
+                }
+
+                Err(Error::File {
+                    operation,
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        }
+    }
+}
+
+/// gcloud-ctx warning about a non-fatal problem encountered during an operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A property was defined more than once and the last value was used
+    DuplicateKey {
+        /// Section containing the duplicate property
+        section: String,
+
+        /// Key of the duplicate property
+        key: String,
+    },
+
+    /// An entry in the configurations directory was skipped because it can't be a configuration
+    SkippedEntry(InvalidEntry),
+
+    /// An environment variable overrides a property of the configuration
+    EnvConflict(EnvConflict),
+
+    /// The zone doesn't look like a zone, but was allowed because location validation is relaxed
+    UnrecognisedZone(String),
+
+    /// The region doesn't look like a region, but was allowed because location validation is relaxed
+    UnrecognisedRegion(String),
+
+    /// A property in a section gcloud-ctx models isn't one gcloud understands, e.g. it's misspelt
+    UnknownProperty {
+        /// Section containing the property
+        section: String,
+
+        /// Key of the property
+        key: String,
+    },
+
+    /// A section has no properties
+    EmptySection(String),
+
+    /// The `core/project` property isn't a valid project ID, see [`ProjectId`]
+    MalformedProject(String),
+
+    /// The `core/account` property isn't a valid account, see [`Account`]
+    MalformedAccount(String),
+
+    /// The zone isn't in the region, so gcloud commands would use different locations depending on their scope
+    ZoneOutsideRegion {
+        /// Configured zone
+        zone: String,
+
+        /// Configured region
+        region: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::DuplicateKey { section, key } => {
+                write!(
+                    f,
+                    "Property '{}/{}' is defined more than once, using the last value",
+                    section, key
+                )
+            }
+            Warning::SkippedEntry(entry) => {
+                write!(f, "Skipped '{}' because {}", entry.path().display(), entry.reason())
+            }
+            Warning::EnvConflict(conflict) => write!(f, "{}", conflict),
+            Warning::UnrecognisedZone(zone) => write!(f, "'{}' doesn't look like a zone, e.g. europe-west1-d", zone),
+            Warning::UnrecognisedRegion(region) => {
+                write!(f, "'{}' doesn't look like a region, e.g. europe-west1", region)
+            }
+            Warning::UnknownProperty { section, key } => {
+                write!(f, "Property '{}/{}' isn't a known gcloud property", section, key)
+            }
+            Warning::EmptySection(section) => write!(f, "Section '{}' has no properties", section),
+            Warning::MalformedProject(project) => write!(f, "Project '{}' isn't a valid project ID", project),
+            Warning::MalformedAccount(account) => write!(f, "Account '{}' isn't an email address", account),
+            Warning::ZoneOutsideRegion { zone, region } => {
+                write!(f, "Zone '{}' isn't in region '{}'", zone, region)
+            }
+        }
+    }
 }
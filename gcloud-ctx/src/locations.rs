@@ -0,0 +1,213 @@
+//! Catalogue of the GCP regions and zones which existed when this version of gcloud-ctx was released
+//!
+//! The catalogue is maintained by hand from the output of `gcloud compute zones list --format="value(name)"` and
+//! embedded in the library, so it's always available offline, e.g. for suggestions and completions. Google adds locations from time to time, so an unknown location
+//! isn't necessarily invalid, see [`LocationValidation`](crate::LocationValidation)
+//!
+//! ```rust
+//! use gcloud_ctx::locations;
+//!
+//! assert!(locations::is_known_zone("europe-west1-d"));
+//! assert_eq!(locations::zones_in("us-central1"), ["us-central1-a", "us-central1-b", "us-central1-c", "us-central1-f"]);
+//! ```
+
+/// Every known region and its zones, in name order
+const CATALOGUE: &[(&str, &[&str])] = &[
+    (
+        "africa-south1",
+        &["africa-south1-a", "africa-south1-b", "africa-south1-c"],
+    ),
+    ("asia-east1", &["asia-east1-a", "asia-east1-b", "asia-east1-c"]),
+    ("asia-east2", &["asia-east2-a", "asia-east2-b", "asia-east2-c"]),
+    (
+        "asia-northeast1",
+        &["asia-northeast1-a", "asia-northeast1-b", "asia-northeast1-c"],
+    ),
+    (
+        "asia-northeast2",
+        &["asia-northeast2-a", "asia-northeast2-b", "asia-northeast2-c"],
+    ),
+    (
+        "asia-northeast3",
+        &["asia-northeast3-a", "asia-northeast3-b", "asia-northeast3-c"],
+    ),
+    ("asia-south1", &["asia-south1-a", "asia-south1-b", "asia-south1-c"]),
+    ("asia-south2", &["asia-south2-a", "asia-south2-b", "asia-south2-c"]),
+    (
+        "asia-southeast1",
+        &["asia-southeast1-a", "asia-southeast1-b", "asia-southeast1-c"],
+    ),
+    (
+        "asia-southeast2",
+        &["asia-southeast2-a", "asia-southeast2-b", "asia-southeast2-c"],
+    ),
+    (
+        "australia-southeast1",
+        &[
+            "australia-southeast1-a",
+            "australia-southeast1-b",
+            "australia-southeast1-c",
+        ],
+    ),
+    (
+        "australia-southeast2",
+        &[
+            "australia-southeast2-a",
+            "australia-southeast2-b",
+            "australia-southeast2-c",
+        ],
+    ),
+    (
+        "europe-central2",
+        &["europe-central2-a", "europe-central2-b", "europe-central2-c"],
+    ),
+    (
+        "europe-north1",
+        &["europe-north1-a", "europe-north1-b", "europe-north1-c"],
+    ),
+    (
+        "europe-north2",
+        &["europe-north2-a", "europe-north2-b", "europe-north2-c"],
+    ),
+    (
+        "europe-southwest1",
+        &["europe-southwest1-a", "europe-southwest1-b", "europe-southwest1-c"],
+    ),
+    ("europe-west1", &["europe-west1-b", "europe-west1-c", "europe-west1-d"]),
+    ("europe-west2", &["europe-west2-a", "europe-west2-b", "europe-west2-c"]),
+    ("europe-west3", &["europe-west3-a", "europe-west3-b", "europe-west3-c"]),
+    ("europe-west4", &["europe-west4-a", "europe-west4-b", "europe-west4-c"]),
+    ("europe-west6", &["europe-west6-a", "europe-west6-b", "europe-west6-c"]),
+    ("europe-west8", &["europe-west8-a", "europe-west8-b", "europe-west8-c"]),
+    ("europe-west9", &["europe-west9-a", "europe-west9-b", "europe-west9-c"]),
+    (
+        "europe-west10",
+        &["europe-west10-a", "europe-west10-b", "europe-west10-c"],
+    ),
+    (
+        "europe-west12",
+        &["europe-west12-a", "europe-west12-b", "europe-west12-c"],
+    ),
+    ("me-central1", &["me-central1-a", "me-central1-b", "me-central1-c"]),
+    ("me-central2", &["me-central2-a", "me-central2-b", "me-central2-c"]),
+    ("me-west1", &["me-west1-a", "me-west1-b", "me-west1-c"]),
+    (
+        "northamerica-northeast1",
+        &[
+            "northamerica-northeast1-a",
+            "northamerica-northeast1-b",
+            "northamerica-northeast1-c",
+        ],
+    ),
+    (
+        "northamerica-northeast2",
+        &[
+            "northamerica-northeast2-a",
+            "northamerica-northeast2-b",
+            "northamerica-northeast2-c",
+        ],
+    ),
+    (
+        "northamerica-south1",
+        &[
+            "northamerica-south1-a",
+            "northamerica-south1-b",
+            "northamerica-south1-c",
+        ],
+    ),
+    (
+        "southamerica-east1",
+        &["southamerica-east1-a", "southamerica-east1-b", "southamerica-east1-c"],
+    ),
+    (
+        "southamerica-west1",
+        &["southamerica-west1-a", "southamerica-west1-b", "southamerica-west1-c"],
+    ),
+    (
+        "us-central1",
+        &["us-central1-a", "us-central1-b", "us-central1-c", "us-central1-f"],
+    ),
+    ("us-east1", &["us-east1-b", "us-east1-c", "us-east1-d"]),
+    ("us-east4", &["us-east4-a", "us-east4-b", "us-east4-c"]),
+    ("us-east5", &["us-east5-a", "us-east5-b", "us-east5-c"]),
+    ("us-south1", &["us-south1-a", "us-south1-b", "us-south1-c"]),
+    ("us-west1", &["us-west1-a", "us-west1-b", "us-west1-c"]),
+    ("us-west2", &["us-west2-a", "us-west2-b", "us-west2-c"]),
+    ("us-west3", &["us-west3-a", "us-west3-b", "us-west3-c"]),
+    ("us-west4", &["us-west4-a", "us-west4-b", "us-west4-c"]),
+];
+
+/// Known regions, in name order
+pub fn regions() -> impl Iterator<Item = &'static str> {
+    CATALOGUE.iter().map(|(region, _)| *region)
+}
+
+/// Known zones, in name order
+pub fn zones() -> impl Iterator<Item = &'static str> {
+    CATALOGUE.iter().flat_map(|(_, zones)| zones.iter().copied())
+}
+
+/// Known zones in the given region, which is empty if the region isn't known
+pub fn zones_in(region: &str) -> &'static [&'static str] {
+    CATALOGUE
+        .iter()
+        .find(|(name, _)| *name == region)
+        .map_or(&[], |(_, zones)| zones)
+}
+
+/// Is the given region known?
+pub fn is_known_region(region: &str) -> bool {
+    regions().any(|r| r == region)
+}
+
+/// Is the given zone known?
+pub fn is_known_zone(zone: &str) -> bool {
+    zones().any(|z| z == zone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LocationValidation;
+
+    #[test]
+    #[cfg(feature = "store")]
+    pub fn test_catalogue_is_in_name_order() {
+        let regions: Vec<_> = regions().collect();
+        let mut sorted = regions.clone();
+        sorted.sort_by(|a, b| crate::natural_cmp(a, b));
+
+        assert_eq!(regions, sorted);
+    }
+
+    #[test]
+    pub fn test_catalogue_passes_strict_validation() {
+        for (region, zones) in CATALOGUE {
+            assert!(LocationValidation::Strict.check_region(region).is_ok(), "{}", region);
+            assert!(!zones.is_empty(), "{} has no zones", region);
+
+            for zone in *zones {
+                assert!(
+                    zone.starts_with(&format!("{}-", region)),
+                    "{} isn't in {}",
+                    zone,
+                    region
+                );
+                assert!(LocationValidation::Strict.check_zone(zone).is_ok(), "{}", zone);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_queries() {
+        assert!(is_known_region("europe-west1"));
+        assert!(!is_known_region("europe-west1-d"));
+        assert!(is_known_zone("europe-west1-d"));
+        assert!(!is_known_zone("europe-west1-a"));
+        assert!(zones_in("mars-north1").is_empty());
+        assert_eq!(
+            zones().count(),
+            CATALOGUE.iter().map(|(_, zones)| zones.len()).sum::<usize>()
+        );
+    }
+}
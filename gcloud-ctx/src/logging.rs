@@ -0,0 +1,219 @@
+//! Diagnostic logging through the [`log`](https://docs.rs/log) facade, enabled by the `log` feature, and as
+//! [`tracing`](https://docs.rs/tracing) events, enabled by the `tracing` feature
+//!
+//! Every file the store reads or writes is logged at trace level, and scans of the configurations directory and
+//! changes to the store are logged at debug level. With `tracing` they happen inside the debug level span of the
+//! [`ConfigurationStore`](crate::ConfigurationStore) operation which caused them. Without either feature the macros
+//! compile to nothing
+
+/// Log at debug level when the `log` or `tracing` feature is enabled
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::debug!($($arg)+);
+
+        #[cfg(feature = "tracing")]
+        ::tracing::debug!($($arg)+);
+
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+/// Log at trace level when the `log` or `tracing` feature is enabled
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::trace!($($arg)+);
+
+        #[cfg(feature = "tracing")]
+        ::tracing::trace!($($arg)+);
+
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+#[cfg(all(test, any(feature = "log", feature = "tracing")))]
+mod tests {
+    use crate::configuration::tests::open_store;
+    #[cfg(feature = "log")]
+    use log::{LevelFilter, Log, Metadata, Record};
+    #[cfg(feature = "log")]
+    use std::sync::Once;
+    use std::sync::{Arc, Mutex};
+
+    /// Every message logged by any test, since the logger is global
+    #[cfg(feature = "log")]
+    static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[cfg(feature = "log")]
+    struct Capture;
+
+    #[cfg(feature = "log")]
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            MESSAGES
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", record.level(), record.args()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[cfg(feature = "log")]
+    fn capture() {
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            log::set_logger(&Capture).unwrap();
+            log::set_max_level(LevelFilter::Trace);
+        });
+    }
+
+    /// Store with a single configuration called foo, which is active
+    fn store_with_foo() -> (tempfile::TempDir, std::path::PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().to_path_buf();
+        std::fs::create_dir(location.join("configurations")).unwrap();
+        std::fs::write(location.join("configurations").join("config_foo"), "").unwrap();
+        std::fs::write(location.join("active_config"), "foo").unwrap();
+        (tmp, location)
+    }
+
+    #[test]
+    #[cfg(feature = "log")]
+    pub fn test_activate_logs_files_touched() {
+        capture();
+
+        let (_tmp, location) = store_with_foo();
+        let configurations = location.join("configurations");
+
+        let mut store = open_store(location.clone()).unwrap();
+        store.activate("foo").unwrap();
+
+        let messages = MESSAGES.lock().unwrap();
+        let scanned = format!("DEBUG scanning {}", configurations.display());
+        let written = format!("TRACE write {}", location.join("active_config").display());
+
+        assert!(messages.contains(&scanned), "{:#?}", messages);
+        assert!(messages.contains(&written), "{:#?}", messages);
+        assert!(
+            messages
+                .iter()
+                .any(|message| message.starts_with("DEBUG store changed: Activated")),
+            "{:#?}",
+            messages
+        );
+    }
+
+    /// Records every span and event as a line, with events prefixed by the names of the spans they happened in
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        spans: Arc<Mutex<Vec<String>>>,
+        stack: Arc<Mutex<Vec<u64>>>,
+    }
+
+    /// Formats the fields of a span or event, with the message first
+    #[cfg(feature = "tracing")]
+    struct Fields(String);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::field::Visit for Fields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0.insert_str(0, &format!("{:?}", value));
+            } else {
+                self.0.push_str(&format!(" {}={:?}", field.name(), value));
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut fields = Fields(String::new());
+            span.record(&mut fields);
+
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(span.metadata().name().to_owned());
+            self.lines
+                .lock()
+                .unwrap()
+                .push(format!("span {}{}", span.metadata().name(), fields.0));
+
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+
+            let spans = self.spans.lock().unwrap();
+            let scope: Vec<&str> = self
+                .stack
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|id| spans[*id as usize - 1].as_str())
+                .collect();
+
+            self.lines.lock().unwrap().push(format!(
+                "{}: {} {}",
+                scope.join(":"),
+                event.metadata().level(),
+                fields.0
+            ));
+        }
+
+        fn enter(&self, span: &tracing::span::Id) {
+            self.stack.lock().unwrap().push(span.into_u64());
+        }
+
+        fn exit(&self, _: &tracing::span::Id) {
+            self.stack.lock().unwrap().pop();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    pub fn test_operations_have_spans_containing_file_events() {
+        let (_tmp, location) = store_with_foo();
+        let recorder = Recorder::default();
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut store = open_store(location.clone()).unwrap();
+            store.activate("foo").unwrap();
+            assert!(store.describe("bar").is_err());
+        });
+
+        let lines = recorder.lines.lock().unwrap();
+        let written = format!("activate: TRACE write {}", location.join("active_config").display());
+
+        assert!(lines.contains(&"span activate name=\"foo\"".to_owned()), "{:#?}", lines);
+        assert!(lines.contains(&written), "{:#?}", lines);
+        assert!(lines.contains(&"span describe name=\"bar\"".to_owned()), "{:#?}", lines);
+        assert!(
+            lines
+                .iter()
+                .any(|line| line.starts_with("describe: ERROR") && line.contains("bar")),
+            "{:#?}",
+            lines
+        );
+    }
+}
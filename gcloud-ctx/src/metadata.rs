@@ -0,0 +1,129 @@
+use crate::{ini::Document, Error, ReadOptions, WriteOptions};
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+};
+
+/// Section of the metadata file which contains the metadata values
+const SECTION: &str = "gctx";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Additional information recorded by gcloud-ctx about a configuration
+///
+/// Metadata is stored separately to the configuration file so that gcloud never sees it
+pub struct Metadata {
+    /// Metadata values by key
+    values: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Key used to record the gcloud SDK version which the configuration was created with
+    pub const SDK_VERSION: &'static str = "sdk_version";
+
+    /// Key used to record a command to run after the configuration is activated
+    pub const ON_ACTIVATE_COMMAND: &'static str = "on_activate_command";
+
+    /// Key used to record a comma-separated list of tags, e.g. `prod`
+    pub const TAGS: &'static str = "tags";
+
+    /// Key used to record that the configuration is pinned and mustn't be modified
+    pub const PINNED: &'static str = "pinned";
+
+    /// Deserialise metadata from the given reader
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let document = Document::parse(&input, &ReadOptions::default(), &mut Vec::new())?;
+
+        let values = document
+            .section(SECTION)
+            .map(|section| {
+                section
+                    .properties()
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self { values })
+    }
+
+    /// Serialise the metadata to the given writer
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut document = Document::default();
+
+        for (key, value) in &self.values {
+            document.set(SECTION, key, value);
+        }
+
+        document.write(writer, &WriteOptions::default())
+    }
+
+    /// Get a metadata value
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Set a metadata value
+    pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
+        self.values.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// Remove a metadata value, returning the previous value if there was one
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.values.remove(key)
+    }
+
+    /// Is the metadata empty?
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The gcloud SDK version which the configuration was created with, if recorded
+    pub fn sdk_version(&self) -> Option<&str> {
+        self.get(Self::SDK_VERSION)
+    }
+
+    /// The command to run after the configuration is activated, if set
+    pub fn on_activate_command(&self) -> Option<&str> {
+        self.get(Self::ON_ACTIVATE_COMMAND)
+    }
+
+    /// Is the configuration pinned, i.e. protected from modification?
+    pub fn is_pinned(&self) -> bool {
+        self.get(Self::PINNED) == Some("true")
+    }
+
+    /// Pin or unpin the configuration, removing the value entirely when unpinned
+    pub fn set_pinned(&mut self, pinned: bool) -> &mut Self {
+        if pinned {
+            self.set(Self::PINNED, "true");
+        } else {
+            self.remove(Self::PINNED);
+        }
+
+        self
+    }
+
+    /// Tags applied to the configuration
+    pub fn tags(&self) -> Vec<&str> {
+        self.get(Self::TAGS)
+            .map(|tags| tags.split(',').map(str::trim).filter(|t| !t.is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Replace the tags applied to the configuration, removing the value entirely if there are none
+    pub fn set_tags<'a>(&mut self, tags: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        let tags: Vec<&str> = tags.into_iter().map(str::trim).filter(|t| !t.is_empty()).collect();
+
+        if tags.is_empty() {
+            self.remove(Self::TAGS);
+        } else {
+            self.set(Self::TAGS, &tags.join(","));
+        }
+
+        self
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{Activation, Configuration, ConfigurationStore, ConflictAction, DeleteBehaviour, Properties, Result};
+use std::{
+    panic,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// A [`ConfigurationStore`] for async applications, e.g. editor language servers or web dashboards
+///
+/// Every operation reads or writes files, so each one runs on tokio's blocking thread pool with
+/// [`tokio::task::spawn_blocking`] and the returned future completes when it's done, which keeps the runtime's worker
+/// threads responsive. The futures must be awaited within a tokio runtime. Clones share the same store, so their
+/// operations never overlap, but operations which are started without waiting for earlier ones to finish may run in
+/// any order
+///
+/// ```
+/// # let tmp = tempfile::tempdir().unwrap();
+/// # std::fs::create_dir(tmp.path().join("configurations")).unwrap();
+/// # std::fs::File::create(tmp.path().join("configurations").join("config_foo")).unwrap();
+/// # std::fs::File::create(tmp.path().join("active_config")).unwrap();
+/// # std::env::set_var("GCTX_STATE_DIR", tmp.path().join("state"));
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// use gcloud_ctx::AsyncConfigurationStore;
+///
+/// # let location = tmp.path().to_path_buf();
+/// # runtime.block_on(async move {
+/// let store = AsyncConfigurationStore::with_location(location).await?;
+/// store.activate("foo").await?;
+///
+/// assert_eq!(store.active().await, Some("foo".to_owned()));
+/// # Ok::<(), gcloud_ctx::Error>(())
+/// # })?;
+/// # Ok::<(), gcloud_ctx::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncConfigurationStore {
+    /// Store shared by every clone
+    store: Arc<Mutex<ConfigurationStore>>,
+}
+
+impl AsyncConfigurationStore {
+    /// Opens the configuration store in the default location, see [`ConfigurationStore::with_default_location`]
+    pub async fn with_default_location() -> Result<Self> {
+        spawn(ConfigurationStore::with_default_location).await.map(Self::from)
+    }
+
+    /// Opens the configuration store at the given location, see [`ConfigurationStore::with_location`]
+    pub async fn with_location(location: PathBuf) -> Result<Self> {
+        spawn(move || ConfigurationStore::with_location(location))
+            .await
+            .map(Self::from)
+    }
+
+    /// Run any operation on the underlying store in the background, e.g. one which doesn't have an async variant
+    pub async fn with_store<T, F>(&self, operation: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut ConfigurationStore) -> T + Send + 'static,
+    {
+        let store = Arc::clone(&self.store);
+        spawn(move || operation(&mut lock(&store))).await
+    }
+
+    /// Get the name of the currently active configuration, see [`ConfigurationStore::active`]
+    pub async fn active(&self) -> Option<String> {
+        self.with_store(|store| store.active().map(str::to_owned)).await
+    }
+
+    /// Get the currently available configurations in natural order, see [`ConfigurationStore::configurations`]
+    pub async fn configurations(&self) -> Result<Vec<Configuration>> {
+        self.with_store(|store| Ok(store.configurations()?.cloned().collect()))
+            .await
+    }
+
+    /// Activate a configuration by name, see [`ConfigurationStore::activate`]
+    pub async fn activate(&self, name: &str) -> Result<Activation> {
+        let name = name.to_owned();
+        self.with_store(move |store| store.activate(&name)).await
+    }
+
+    /// Create a new configuration, see [`ConfigurationStore::create`]
+    pub async fn create(
+        &self,
+        name: &str,
+        properties: Properties,
+        conflict: ConflictAction,
+    ) -> Result<Option<Configuration>> {
+        let name = name.to_owned();
+        self.with_store(move |store| store.create(&name, &properties, conflict))
+            .await
+    }
+
+    /// Copy an existing configuration, see [`ConfigurationStore::copy`]
+    pub async fn copy(
+        &self,
+        src_name: &str,
+        dest_name: &str,
+        conflict: ConflictAction,
+    ) -> Result<Option<Configuration>> {
+        let (src_name, dest_name) = (src_name.to_owned(), dest_name.to_owned());
+        self.with_store(move |store| store.copy(&src_name, &dest_name, conflict))
+            .await
+    }
+
+    /// Rename a configuration, see [`ConfigurationStore::rename`]
+    pub async fn rename(
+        &self,
+        old_name: &str,
+        new_name: &str,
+        conflict: ConflictAction,
+    ) -> Result<Option<Configuration>> {
+        let (old_name, new_name) = (old_name.to_owned(), new_name.to_owned());
+        self.with_store(move |store| store.rename(&old_name, &new_name, conflict))
+            .await
+    }
+
+    /// Delete a configuration, see [`ConfigurationStore::delete_with`]
+    pub async fn delete(&self, name: &str, behaviour: DeleteBehaviour) -> Result<()> {
+        let name = name.to_owned();
+        self.with_store(move |store| store.delete_with(&name, behaviour)).await
+    }
+
+    /// Describe the properties in the given configuration, see [`ConfigurationStore::describe`]
+    pub async fn describe(&self, name: &str) -> Result<Properties> {
+        let name = name.to_owned();
+        self.with_store(move |store| store.describe(&name)).await
+    }
+}
+
+impl From<ConfigurationStore> for AsyncConfigurationStore {
+    fn from(store: ConfigurationStore) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(store)),
+        }
+    }
+}
+
+/// Lock the store, carrying on if an earlier operation panicked because the store is never left half-modified
+fn lock(store: &Mutex<ConfigurationStore>) -> MutexGuard<'_, ConfigurationStore> {
+    store.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Run an operation on tokio's blocking thread pool
+async fn spawn<T, F>(operation: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(operation).await {
+        Ok(value) => value,
+        // surface the panic to the caller, just as if the operation had run on their thread
+        Err(e) if e.is_panic() => panic::resume_unwind(e.into_panic()),
+        Err(e) => panic!(
+            "gcloud-ctx operation was cancelled because the runtime shut down: {}",
+            e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::tests::open_store;
+    use std::fs;
+
+    #[tokio::test]
+    pub async fn test_operations_run_in_background() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+        fs::write(tmp.path().join("configurations/config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+
+        let store = AsyncConfigurationStore::from(open_store(tmp.path().to_path_buf()).unwrap());
+
+        let properties = crate::PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .build();
+        store.create("bar", properties, ConflictAction::Abort).await.unwrap();
+        store.activate("bar").await.unwrap();
+
+        // clones share the same store
+        let clone = store.clone();
+        assert_eq!(clone.active().await, Some("bar".to_owned()));
+
+        let names: Vec<_> = store
+            .configurations()
+            .await
+            .unwrap()
+            .iter()
+            .map(|c| c.name().to_owned())
+            .collect();
+        assert_eq!(names, ["bar", "foo"]);
+
+        let properties = store.describe("bar").await.unwrap();
+        assert_eq!(properties.core().project(), Some("my-project"));
+
+        store.delete("foo", DeleteBehaviour::Refuse).await.unwrap();
+        assert!(store.describe("foo").await.is_err());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "operation panicked")]
+    pub async fn test_panics_are_passed_to_the_caller() {
+        spawn(|| panic!("operation panicked")).await
+    }
+
+    #[tokio::test]
+    pub async fn test_operations_run_on_the_blocking_pool() {
+        let runtime_thread = std::thread::current().id();
+        let operation_thread = spawn(|| std::thread::current().id()).await;
+
+        assert_ne!(operation_thread, runtime_thread);
+
+        // a panicking operation doesn't stop later ones
+        let panicked = tokio::spawn(spawn(|| panic!("operation panicked"))).await;
+        assert!(panicked.is_err());
+        assert_eq!(spawn(|| 42).await, 42);
+    }
+}
@@ -0,0 +1,155 @@
+//! Well-known directories of the current user, used to find the default configuration store and state directory
+//!
+//! These come from the `dirs` crate when the `dirs` feature is enabled, otherwise they're found from the same
+//! environment variables the `dirs` crate reads first, e.g. `HOME` and `XDG_CONFIG_HOME`
+
+use std::path::PathBuf;
+
+#[cfg(feature = "dirs")]
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    dirs::home_dir()
+}
+
+#[cfg(feature = "dirs")]
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir()
+}
+
+#[cfg(feature = "dirs")]
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    dirs::state_dir()
+}
+
+#[cfg(feature = "dirs")]
+pub(crate) fn data_local_dir() -> Option<PathBuf> {
+    dirs::data_local_dir()
+}
+
+#[cfg(not(feature = "dirs"))]
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    fallback::home_dir(&env)
+}
+
+#[cfg(not(feature = "dirs"))]
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    fallback::config_dir(&env)
+}
+
+#[cfg(not(feature = "dirs"))]
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    fallback::state_dir(&env)
+}
+
+#[cfg(not(feature = "dirs"))]
+pub(crate) fn data_local_dir() -> Option<PathBuf> {
+    fallback::data_local_dir(&env)
+}
+
+#[cfg(not(feature = "dirs"))]
+fn env(name: &str) -> Option<std::ffi::OsString> {
+    std::env::var_os(name)
+}
+
+#[cfg(any(test, not(feature = "dirs")))]
+mod fallback {
+    use std::{ffi::OsString, path::PathBuf};
+
+    /// Reads an environment variable
+    pub(super) type Env<'a> = dyn Fn(&str) -> Option<OsString> + 'a;
+
+    /// Path from an environment variable, which is ignored unless it's absolute
+    fn var(env: &Env, name: &str) -> Option<PathBuf> {
+        env(name).map(PathBuf::from).filter(|path| path.is_absolute())
+    }
+
+    /// Path from an XDG environment variable, or the given directory under the home directory
+    #[cfg_attr(any(windows, target_os = "macos"), allow(dead_code))]
+    fn xdg(env: &Env, name: &str, default: &str) -> Option<PathBuf> {
+        var(env, name).or_else(|| home_dir(env).map(|home| home.join(default)))
+    }
+
+    pub(super) fn home_dir(env: &Env) -> Option<PathBuf> {
+        if cfg!(windows) {
+            var(env, "USERPROFILE")
+        } else {
+            var(env, "HOME")
+        }
+    }
+
+    pub(super) fn config_dir(env: &Env) -> Option<PathBuf> {
+        if cfg!(windows) {
+            var(env, "APPDATA")
+        } else if cfg!(target_os = "macos") {
+            home_dir(env).map(|home| home.join("Library/Application Support"))
+        } else {
+            xdg(env, "XDG_CONFIG_HOME", ".config")
+        }
+    }
+
+    pub(super) fn state_dir(env: &Env) -> Option<PathBuf> {
+        if cfg!(any(windows, target_os = "macos")) {
+            None
+        } else {
+            xdg(env, "XDG_STATE_HOME", ".local/state")
+        }
+    }
+
+    pub(super) fn data_local_dir(env: &Env) -> Option<PathBuf> {
+        if cfg!(windows) {
+            var(env, "LOCALAPPDATA")
+        } else if cfg!(target_os = "macos") {
+            home_dir(env).map(|home| home.join("Library/Application Support"))
+        } else {
+            xdg(env, "XDG_DATA_HOME", ".local/share")
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::fallback;
+    use std::{ffi::OsString, path::PathBuf};
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.into())
+    }
+
+    #[test]
+    pub fn test_fallback_uses_home() {
+        let env = env(&[("HOME", "/home/user")]);
+
+        assert_eq!(fallback::home_dir(&env), Some(PathBuf::from("/home/user")));
+        assert_eq!(fallback::config_dir(&env), Some(PathBuf::from("/home/user/.config")));
+        assert_eq!(
+            fallback::state_dir(&env),
+            Some(PathBuf::from("/home/user/.local/state"))
+        );
+        assert_eq!(
+            fallback::data_local_dir(&env),
+            Some(PathBuf::from("/home/user/.local/share"))
+        );
+    }
+
+    #[test]
+    pub fn test_fallback_prefers_absolute_xdg_variables() {
+        let env = env(&[
+            ("HOME", "/home/user"),
+            ("XDG_CONFIG_HOME", "/config"),
+            ("XDG_STATE_HOME", "relative/state"),
+        ]);
+
+        assert_eq!(fallback::config_dir(&env), Some(PathBuf::from("/config")));
+        assert_eq!(
+            fallback::state_dir(&env),
+            Some(PathBuf::from("/home/user/.local/state"))
+        );
+    }
+
+    #[test]
+    pub fn test_fallback_without_home() {
+        let env = env(&[("HOME", "relative")]);
+
+        assert_eq!(fallback::home_dir(&env), None);
+        assert_eq!(fallback::config_dir(&env), None);
+    }
+}
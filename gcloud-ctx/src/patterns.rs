@@ -0,0 +1,204 @@
+//! Patterns which configuration names and validated values must match
+//!
+//! The patterns are regular expressions when the `regex` feature is enabled, otherwise hand-written matchers which
+//! accept exactly the same strings are used so that the dependency can be left out
+
+#[cfg(feature = "regex")]
+pub(crate) use self::regexes::*;
+
+#[cfg(not(feature = "regex"))]
+pub(crate) use self::fallback::*;
+
+#[cfg(feature = "regex")]
+mod regexes {
+    use lazy_static::lazy_static;
+    use regex::Regex;
+
+    lazy_static! {
+        static ref NAME_REGEX: Regex = Regex::new("^[a-z][-a-z0-9]*$").unwrap();
+
+        // domain-scoped projects, e.g. example.com:my-project, predate the current rules but are still valid
+        static ref PROJECT_ID_REGEX: Regex = Regex::new("^([a-z0-9.-]+:)?[a-z][-a-z0-9]{4,28}[a-z0-9]$").unwrap();
+
+        // deliberately loose, gcloud itself decides whether the account exists
+        static ref ACCOUNT_REGEX: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s.]+$").unwrap();
+
+        // e.g. europe-west1 or northamerica-northeast2, and zones add a letter, e.g. europe-west1-d
+        static ref REGION_REGEX: Regex = Regex::new("^[a-z]+(-[a-z]+)+[0-9]+$").unwrap();
+        static ref ZONE_REGEX: Regex = Regex::new("^[a-z]+(-[a-z]+)+[0-9]+-[a-z]$").unwrap();
+    }
+
+    // configuration names are only checked by the store
+    #[cfg_attr(not(feature = "store"), allow(dead_code))]
+    pub(crate) fn name(value: &str) -> bool {
+        NAME_REGEX.is_match(value)
+    }
+
+    pub(crate) fn project_id(value: &str) -> bool {
+        PROJECT_ID_REGEX.is_match(value)
+    }
+
+    pub(crate) fn account(value: &str) -> bool {
+        ACCOUNT_REGEX.is_match(value)
+    }
+
+    pub(crate) fn region(value: &str) -> bool {
+        REGION_REGEX.is_match(value)
+    }
+
+    pub(crate) fn zone(value: &str) -> bool {
+        ZONE_REGEX.is_match(value)
+    }
+}
+
+#[cfg(any(test, not(feature = "regex")))]
+mod fallback {
+    fn lowercase_or_digit(c: char) -> bool {
+        c.is_ascii_lowercase() || c.is_ascii_digit()
+    }
+
+    /// `^[a-z][-a-z0-9]*$`
+    #[cfg_attr(not(feature = "store"), allow(dead_code))]
+    pub(crate) fn name(value: &str) -> bool {
+        let mut chars = value.chars();
+        chars.next().is_some_and(|c| c.is_ascii_lowercase()) && chars.all(|c| c == '-' || lowercase_or_digit(c))
+    }
+
+    /// `^([a-z0-9.-]+:)?[a-z][-a-z0-9]{4,28}[a-z0-9]$`
+    pub(crate) fn project_id(value: &str) -> bool {
+        let id = match value.split_once(':') {
+            Some((domain, id)) => {
+                if domain.is_empty() || !domain.chars().all(|c| c == '.' || c == '-' || lowercase_or_digit(c)) {
+                    return false;
+                }
+
+                id
+            }
+            None => value,
+        };
+
+        let bytes = id.as_bytes();
+
+        (6..=30).contains(&bytes.len())
+            && bytes[0].is_ascii_lowercase()
+            && lowercase_or_digit(bytes[bytes.len() - 1] as char)
+            && id.chars().all(|c| c == '-' || lowercase_or_digit(c))
+    }
+
+    /// `^[^@\s]+@[^@\s]+\.[^@\s.]+$`
+    pub(crate) fn account(value: &str) -> bool {
+        let Some((user, domain)) = value.split_once('@') else {
+            return false;
+        };
+
+        let Some((host, tld)) = domain.rsplit_once('.') else {
+            return false;
+        };
+
+        !user.is_empty()
+            && !host.is_empty()
+            && !tld.is_empty()
+            && !domain.contains('@')
+            && !value.chars().any(char::is_whitespace)
+    }
+
+    /// `^[a-z]+(-[a-z]+)+[0-9]+$`
+    pub(crate) fn region(value: &str) -> bool {
+        let letters = value.trim_end_matches(|c: char| c.is_ascii_digit());
+
+        letters.len() < value.len()
+            && letters.split('-').count() >= 2
+            && letters
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase()))
+    }
+
+    /// `^[a-z]+(-[a-z]+)+[0-9]+-[a-z]$`
+    pub(crate) fn zone(value: &str) -> bool {
+        match value.rsplit_once('-') {
+            Some((prefix, letter)) => {
+                region(prefix) && letter.len() == 1 && letter.chars().all(|c| c.is_ascii_lowercase())
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "regex"))]
+mod tests {
+    use super::{fallback, regexes};
+
+    const SAMPLES: &[&str] = &[
+        "",
+        "a",
+        "-",
+        "foo",
+        "foo-bar",
+        "foo-",
+        "Foo",
+        "foo_bar",
+        "1foo",
+        "foo1",
+        "my-project",
+        "abcdef",
+        "short",
+        "a1-b2-c3",
+        "example.com:my-project",
+        ":my-project",
+        "a:b:my-project",
+        "Example.com:my-project",
+        "a23456789012345678901234567890",
+        "a234567890123456789012345678901",
+        "my-project-",
+        "a.user@example.org",
+        "sa@my-project.iam.gserviceaccount.com",
+        "a.user",
+        "a.user@",
+        "@example.org",
+        "a.user@example",
+        "a.user@example.",
+        "a.user@.org",
+        "a user@example.org",
+        "a@b@c.org",
+        "a@b..org",
+        "a@b.c.d",
+        "a@b.\u{2003}org",
+        "europe-west1",
+        "northamerica-northeast2",
+        "europe-west1-d",
+        "northamerica-northeast1-a",
+        "europe-west",
+        "europe1",
+        "-west1",
+        "europe--west1",
+        "europe-west1-",
+        "europe-west1-dd",
+        "europe-west1-1",
+        "europe-west1d",
+        "mars-north1",
+        "us-central1",
+        "us-central1-f",
+        "eu1-west1",
+    ];
+
+    #[test]
+    pub fn test_fallbacks_match_regexes() {
+        for sample in SAMPLES {
+            assert_eq!(fallback::name(sample), regexes::name(sample), "name {:?}", sample);
+            assert_eq!(
+                fallback::project_id(sample),
+                regexes::project_id(sample),
+                "project ID {:?}",
+                sample
+            );
+            assert_eq!(
+                fallback::account(sample),
+                regexes::account(sample),
+                "account {:?}",
+                sample
+            );
+            assert_eq!(fallback::region(sample), regexes::region(sample), "region {:?}", sample);
+            assert_eq!(fallback::zone(sample), regexes::zone(sample), "zone {:?}", sample);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! The stable API for applications which use `gcloud-ctx`
+//!
+//! Everything exported here follows semver, so it's safe to glob import:
+//!
+//! ```rust
+//! # let tmp = tempfile::tempdir().unwrap();
+//! # std::fs::File::create(tmp.path().join("active_config")).unwrap();
+//! # std::fs::create_dir(tmp.path().join("configurations")).unwrap();
+//! # std::fs::File::create(tmp.path().join("configurations/config_foo")).unwrap();
+//! # std::env::set_var("GCTX_STATE_DIR", tmp.path().join("state"));
+//! use gcloud_ctx::prelude::*;
+//!
+//! let mut store = ConfigurationStore::builder().location(tmp.path()).open()?;
+//! let properties = PropertiesBuilder::default().project(&"my-project".parse()?).build();
+//!
+//! store.create("bar", &properties, ConflictAction::Abort)?;
+//! assert_eq!(store.describe("bar")?.get("core", "project"), Some("my-project"));
+//! # Ok::<(), Error>(())
+//! ```
+//!
+//! Less common items, e.g. [`ReadOptions`](crate::ReadOptions), are exported from the crate root and follow semver
+//! too. Only the lower-level building blocks behind the `unstable` feature may change in a minor release
+
+pub use crate::{
+    Account, Error, FileOperation, Filter, Metadata, ProjectId, Properties, PropertiesBuilder, Region, Result, Warning,
+    Zone,
+};
+
+#[cfg(feature = "store")]
+pub use crate::{
+    Activation, Backup, Clutter, ClutterKind, Configuration, ConfigurationStore, ConfigurationStoreBuilder,
+    ConflictAction, ConflictResolver, DeleteBehaviour, Resolution, StoreEvent,
+};
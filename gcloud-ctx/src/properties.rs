@@ -1,63 +1,647 @@
-use crate::Error;
-use serde::{Deserialize, Serialize};
-use serde_ini::{Serializer, Writer};
-use std::io::{Read, Write};
+use crate::{
+    ini::Document, Account, ArtifactsSection, ComputeSection, ContainerSection, CoreSection, DataprocSection, Error,
+    FunctionsSection, LocationValidation, ProjectId, RunSection, Warning, Zone, KNOWN_PROPERTIES, TYPED_SECTIONS,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+    sync::OnceLock,
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 /// Configuration properties
+///
+/// All sections and keys are kept, not just the ones supported by [`PropertiesBuilder`], so that properties
+/// written by gcloud itself survive being read and written back. So are `#` and `;` comment lines, which stay with
+/// the section or property they come before
 pub struct Properties {
-    /// Core properties
-    #[serde(skip_serializing_if = "Option::is_none")]
-    core: Option<CoreProperties>,
+    /// All properties, including those which aren't directly supported
+    document: Document,
 
-    /// Compute properties
-    #[serde(skip_serializing_if = "Option::is_none")]
-    compute: Option<ComputeProperties>,
+    /// Sections without a typed view, built on first use and reset whenever the properties change
+    extra_sections: OnceLock<BTreeMap<String, BTreeMap<String, String>>>,
 }
 
 impl Properties {
-    /// Deserialise properties from the given reader
+    /// Deserialise properties from the given reader using the default [`ReadOptions`]
     pub fn from_reader<R: Read>(reader: R) -> Result<Self, Error> {
-        let properties = serde_ini::de::from_read(reader)?;
+        let (properties, _) = Self::from_reader_with_options(reader, &ReadOptions::default())?;
         Ok(properties)
     }
 
-    /// Serialise the properties to the given writer
+    /// Deserialise properties from the given reader using the given options
+    ///
+    /// Returns the properties along with any warnings encountered whilst reading them
+    pub fn from_reader_with_options<R: Read>(
+        mut reader: R,
+        options: &ReadOptions,
+    ) -> Result<(Self, Vec<Warning>), Error> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let mut warnings = Vec::new();
+        let document = Document::parse(&input, options, &mut warnings)?;
+
+        let properties = Self {
+            document,
+            extra_sections: OnceLock::new(),
+        };
+
+        Ok((properties, warnings))
+    }
+
+    /// Get the value of a property, e.g. `get("auth", "impersonate_service_account")`
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.document.section(section).and_then(|s| s.get(key))
+    }
+
+    /// Set the value of a property, adding it if it doesn't already exist
+    pub fn set(&mut self, section: &str, key: &str, value: &str) -> &mut Self {
+        self.document_mut().set(section, key, value);
+        self
+    }
+
+    /// Typed view of the `core` properties, e.g. the project and account
+    pub fn core(&self) -> CoreSection<'_> {
+        CoreSection::new(self)
+    }
+
+    /// Typed view of the `compute` properties, e.g. the default zone and region
+    pub fn compute(&self) -> ComputeSection<'_> {
+        ComputeSection::new(self)
+    }
+
+    /// Typed view of the `container` properties, e.g. the default GKE cluster
+    pub fn container(&self) -> ContainerSection<'_> {
+        ContainerSection::new(self)
+    }
+
+    /// Typed view of the `run` properties, e.g. the default Cloud Run region
+    pub fn run(&self) -> RunSection<'_> {
+        RunSection::new(self)
+    }
+
+    /// Typed view of the `functions` properties, e.g. the default Cloud Functions region
+    pub fn functions(&self) -> FunctionsSection<'_> {
+        FunctionsSection::new(self)
+    }
+
+    /// Typed view of the `artifacts` properties, e.g. the default Artifact Registry location
+    pub fn artifacts(&self) -> ArtifactsSection<'_> {
+        ArtifactsSection::new(self)
+    }
+
+    /// Typed view of the `dataproc` properties, e.g. the default Dataproc region
+    pub fn dataproc(&self) -> DataprocSection<'_> {
+        DataprocSection::new(self)
+    }
+
+    /// Check the properties for likely mistakes, e.g. before writing them or to implement a lint command
+    ///
+    /// Reports properties gcloud doesn't understand in the sections gcloud-ctx models, empty sections, a malformed
+    /// project or account, zones and regions which don't look like locations and a zone outside the region. Nothing
+    /// here stops gcloud using the properties, so these are warnings rather than errors
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+
+        for section in self.document.sections() {
+            if section.properties().next().is_none() {
+                warnings.push(Warning::EmptySection(section.name().to_owned()));
+            }
+
+            let known = match KNOWN_PROPERTIES.iter().find(|(name, _)| *name == section.name()) {
+                Some((_, known)) => known,
+                None => continue,
+            };
+
+            warnings.extend(
+                section
+                    .properties()
+                    .filter(|(key, _)| !known.contains(key))
+                    .map(|(key, _)| Warning::UnknownProperty {
+                        section: section.name().to_owned(),
+                        key: key.to_owned(),
+                    }),
+            );
+        }
+
+        let core = self.core();
+
+        if let Some(project) = core.project().filter(|p| p.parse::<ProjectId>().is_err()) {
+            warnings.push(Warning::MalformedProject(project.to_owned()));
+        }
+
+        if let Some(account) = core.account().filter(|a| a.parse::<Account>().is_err()) {
+            warnings.push(Warning::MalformedAccount(account.to_owned()));
+        }
+
+        // relaxed validation only ever warns
+        let compute = self.compute();
+        let zone = compute.zone().map(|zone| LocationValidation::Relaxed.check_zone(zone));
+        let region = compute
+            .region()
+            .map(|region| LocationValidation::Relaxed.check_region(region));
+        warnings.extend([zone, region].into_iter().flatten().flatten().flatten());
+
+        warnings.extend(self.check_zone_in_region());
+
+        warnings
+    }
+
+    /// Check that the zone is in the region, if both are set
+    ///
+    /// Returns a warning if they disagree, e.g. `europe-west1-d` and `us-east1`. Zones or regions which don't look
+    /// like locations can't be compared, so they aren't reported here
+    pub fn check_zone_in_region(&self) -> Option<Warning> {
+        let compute = self.compute();
+        let zone: Zone = compute.zone()?.parse().ok()?;
+        let region = compute.region()?;
+
+        (zone.region().as_str() != region).then(|| Warning::ZoneOutsideRegion {
+            zone: zone.to_string(),
+            region: region.to_owned(),
+        })
+    }
+
+    /// Sections which don't have a typed view, e.g. `auth`, as a map of section name to properties
+    ///
+    /// This lets callers inspect properties which gcloud-ctx doesn't model yet. Properties in sections with a typed
+    /// view, e.g. `core/disable_usage_reporting`, are available from [`Properties::get`] and [`Properties::iter`]
+    pub fn extra_sections(&self) -> &BTreeMap<String, BTreeMap<String, String>> {
+        self.extra_sections.get_or_init(|| {
+            let mut sections = BTreeMap::new();
+
+            for section in self.document.sections() {
+                if TYPED_SECTIONS.contains(&section.name()) {
+                    continue;
+                }
+
+                let properties: &mut BTreeMap<_, _> = sections.entry(section.name().to_owned()).or_default();
+                properties.extend(section.properties().map(|(k, v)| (k.to_owned(), v.to_owned())));
+            }
+
+            sections
+        })
+    }
+
+    /// Iterate over all properties as `(section, key, value)` in the order they appear
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.document
+            .sections()
+            .flat_map(|section| section.properties().map(move |(k, v)| (section.name(), k, v)))
+    }
+
+    /// Find the changes which would turn these properties into the other ones
+    ///
+    /// Changed and removed properties come first in the order they appear here, followed by added properties in
+    /// the order they appear in the other properties
+    pub fn diff(&self, other: &Properties) -> Vec<PropertyChange> {
+        let change = |section: &str, key: &str, old: Option<&str>, new: Option<&str>| PropertyChange {
+            section: section.to_owned(),
+            key: key.to_owned(),
+            old_value: old.map(str::to_owned),
+            new_value: new.map(str::to_owned),
+        };
+
+        let changed = self.iter().filter_map(|(section, key, old)| {
+            let new = other.get(section, key);
+            (new != Some(old)).then(|| change(section, key, Some(old), new))
+        });
+
+        let added = other
+            .iter()
+            .filter(|(section, key, _)| self.get(section, key).is_none())
+            .map(|(section, key, new)| change(section, key, None, Some(new)));
+
+        changed.chain(added).collect()
+    }
+
+    /// Merge another set of properties into these ones, using the strategy to decide which value wins when a
+    /// property is set in both
+    pub fn merge(&mut self, other: &Properties, strategy: MergeStrategy) -> &mut Self {
+        for (section, key, value) in other.iter() {
+            if strategy == MergeStrategy::OtherWins || self.get(section, key).is_none() {
+                self.document_mut().set(section, key, value);
+            }
+        }
+
+        self
+    }
+
+    /// Remove a property, returning the previous value if there was one
+    pub fn unset(&mut self, section: &str, key: &str) -> Option<String> {
+        self.document_mut().remove(section, key)
+    }
+
+    /// Find any `CLOUDSDK_*` environment variables which override these properties with a different value
+    ///
+    /// gcloud gives environment variables precedence over the active configuration, which is a common source of
+    /// confusion when the environment silently disagrees with the configuration
+    #[cfg(feature = "store")]
+    pub fn env_conflicts(&self) -> Vec<EnvConflict> {
+        self.env_conflicts_with(|name| std::env::var(name).ok())
+    }
+
+    /// Find environment conflicts using the given function to look up environment variables, e.g. where there's no
+    /// process environment such as in a browser
+    pub fn env_conflicts_with(&self, lookup: impl Fn(&str) -> Option<String>) -> Vec<EnvConflict> {
+        self.values()
+            .into_iter()
+            .filter_map(|(section, key, value)| env_conflict(section, key, value, &lookup))
+            .collect()
+    }
+
+    /// The properties as gcloud will actually use them, with any `CLOUDSDK_*` environment variables applied on top
+    #[cfg(feature = "store")]
+    pub fn effective(&self) -> EffectiveProperties {
+        self.effective_with(|name| std::env::var(name).ok())
+    }
+
+    /// Apply environment variables on top of the properties using the given function to look them up, see
+    /// [`Properties::effective`]
+    ///
+    /// Every property which is set can be overridden, as well as the supported properties which aren't set
+    pub fn effective_with(&self, lookup: impl Fn(&str) -> Option<String>) -> EffectiveProperties {
+        let mut overrides = self.env_conflicts_with(&lookup);
+
+        let others: Vec<EnvConflict> = self
+            .iter()
+            .filter(|(section, key, _)| !self.values().iter().any(|(s, k, _)| s == section && k == key))
+            .filter_map(|(section, key, value)| env_conflict(section, key, Some(value), &lookup))
+            .collect();
+        overrides.extend(others);
+
+        let mut properties = self.clone();
+
+        for conflict in &overrides {
+            properties.set(&conflict.section, &conflict.key, &conflict.env_value);
+        }
+
+        EffectiveProperties { properties, overrides }
+    }
+
+    /// Every property as the `CLOUDSDK_<SECTION>_<KEY>` environment variable which gcloud reads it from, e.g. to
+    /// apply the properties to a command without writing them to a configuration
+    pub fn env_vars(&self) -> Vec<(String, String)> {
+        self.iter()
+            .map(|(section, key, value)| (env_var_name(section, key), value.to_owned()))
+            .collect()
+    }
+
+    /// All supported properties as `(section, key, value)`
+    fn values(&self) -> Vec<(&'static str, &'static str, Option<&str>)> {
+        vec![
+            ("core", "project", self.get("core", "project")),
+            ("core", "account", self.get("core", "account")),
+            ("compute", "zone", self.get("compute", "zone")),
+            ("compute", "region", self.get("compute", "region")),
+        ]
+    }
+
+    /// Serialise the properties to the given writer using the default [`WriteOptions`]
     pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
-        let mut ser = Serializer::new(Writer::new(writer, serde_ini::LineEnding::Linefeed));
-        self.serialize(&mut ser)?;
+        self.to_writer_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Serialise the properties to the given writer using the given options
+    pub fn to_writer_with_options<W: Write>(&self, writer: W, options: &WriteOptions) -> Result<(), Error> {
+        self.document.write(writer, options)
+    }
+
+    /// Underlying document, for other serialisation formats
+    #[cfg(feature = "serde")]
+    pub(crate) fn document(&self) -> &Document {
+        &self.document
+    }
 
-        Ok(())
+    /// Document containing the properties, for modification
+    fn document_mut(&mut self) -> &mut Document {
+        self.extra_sections = OnceLock::new();
+        &mut self.document
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-/// Supported properties in the core section
-struct CoreProperties {
-    /// `core/project` setting
-    #[serde(skip_serializing_if = "Option::is_none")]
-    project: Option<String>,
+/// Name of the environment variable which overrides a property, e.g. `CLOUDSDK_CORE_PROJECT`
+fn env_var_name(section: &str, key: &str) -> String {
+    format!("CLOUDSDK_{}_{}", section, key).to_uppercase()
+}
 
-    /// `core/account` setting
-    #[serde(skip_serializing_if = "Option::is_none")]
-    account: Option<String>,
+/// The environment variable which overrides a property with a different value, if there is one
+fn env_conflict(
+    section: &str,
+    key: &str,
+    value: Option<&str>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Option<EnvConflict> {
+    let variable = env_var_name(section, key);
+    let env_value = lookup(&variable)?;
+
+    if value == Some(env_value.as_str()) {
+        return None;
+    }
+
+    Some(EnvConflict {
+        variable,
+        section: section.to_owned(),
+        key: key.to_owned(),
+        env_value,
+        value: value.map(str::to_owned),
+    })
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-/// Supported properties in the compute section
-struct ComputeProperties {
-    /// `compute/zone` setting - default compute zone
-    #[serde(skip_serializing_if = "Option::is_none")]
-    zone: Option<String>,
+impl FromStr for Properties {
+    type Err = Error;
 
-    /// `compute/region` setting - default compute region
-    #[serde(skip_serializing_if = "Option::is_none")]
-    region: Option<String>,
+    /// Parse properties from the contents of a configuration file using the default [`ReadOptions`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_reader(s.as_bytes())
+    }
+}
+
+impl fmt::Display for Properties {
+    /// Format the properties as a configuration file, always using `\n` line endings
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = Vec::new();
+
+        self.to_writer_with_options(&mut output, WriteOptions::default().line_ending(LineEnding::Lf))
+            .map_err(|_| fmt::Error)?;
+
+        f.write_str(&String::from_utf8_lossy(&output))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An environment variable which overrides a property with a different value
+pub struct EnvConflict {
+    /// Name of the environment variable, e.g. `CLOUDSDK_CORE_PROJECT`
+    variable: String,
+
+    /// Section of the overridden property
+    section: String,
+
+    /// Key of the overridden property
+    key: String,
+
+    /// Value of the environment variable
+    env_value: String,
+
+    /// Value of the property in the configuration, if set
+    value: Option<String>,
+}
+
+impl EnvConflict {
+    /// Name of the environment variable, e.g. `CLOUDSDK_CORE_PROJECT`
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Section of the overridden property
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Key of the overridden property
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Value of the environment variable
+    pub fn env_value(&self) -> &str {
+        &self.env_value
+    }
+
+    /// Value of the property in the configuration, if set
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+}
+
+impl fmt::Display for EnvConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}={} overrides {}/{}",
+            self.variable, self.env_value, self.section, self.key
+        )?;
+
+        match &self.value {
+            Some(value) => write!(f, " (configured as '{}')", value),
+            None => write!(f, " (not configured)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Properties as gcloud will actually use them, after `CLOUDSDK_*` environment variables are applied, see
+/// [`Properties::effective`]
+pub struct EffectiveProperties {
+    /// Properties with the environment variables applied
+    properties: Properties,
+
+    /// Environment variables which changed a property
+    overrides: Vec<EnvConflict>,
+}
+
+impl EffectiveProperties {
+    /// Properties with the environment variables applied
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Every environment variable which changed a property, with the value it replaced
+    pub fn overrides(&self) -> &[EnvConflict] {
+        &self.overrides
+    }
+
+    /// The environment variable which changed the given property, if any
+    pub fn overridden(&self, section: &str, key: &str) -> Option<&EnvConflict> {
+        self.overrides
+            .iter()
+            .find(|conflict| conflict.section == section && conflict.key == key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A difference in a single property between two sets of properties
+pub struct PropertyChange {
+    /// Section of the property
+    section: String,
+
+    /// Key of the property
+    key: String,
+
+    /// Value before the change, or `None` if the property was added
+    old_value: Option<String>,
+
+    /// Value after the change, or `None` if the property was removed
+    new_value: Option<String>,
+}
+
+impl PropertyChange {
+    /// Section of the property
+    pub fn section(&self) -> &str {
+        &self.section
+    }
+
+    /// Key of the property
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Value before the change, or `None` if the property was added
+    pub fn old_value(&self) -> Option<&str> {
+        self.old_value.as_deref()
+    }
+
+    /// Value after the change, or `None` if the property was removed
+    pub fn new_value(&self) -> Option<&str> {
+        self.new_value.as_deref()
+    }
+}
+
+impl fmt::Display for PropertyChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.old_value, &self.new_value) {
+            (Some(old), Some(new)) => write!(f, "{}/{}: '{}' -> '{}'", self.section, self.key, old, new),
+            (None, Some(new)) => write!(f, "{}/{}: added '{}'", self.section, self.key, new),
+            (Some(old), None) => write!(f, "{}/{}: removed '{}'", self.section, self.key, old),
+            (None, None) => write!(f, "{}/{}: unchanged", self.section, self.key),
+        }
+    }
+}
+
+/// Which value wins when merging properties which are set in both sets
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Values from the properties being merged in replace existing values
+    OtherWins,
+
+    /// Existing values are kept, and only missing properties are added
+    SelfWins,
+}
+
+/// Behaviour when a property is defined more than once
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Use the last value, matching gcloud, and emit a [`Warning::DuplicateKey`]
+    #[default]
+    LastWins,
+
+    /// Fail with [`Error::DuplicateKey`]
+    Error,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+/// Options which control how properties are deserialised
+pub struct ReadOptions {
+    /// Behaviour when a property is defined more than once
+    pub(crate) duplicate_keys: DuplicateKeys,
+}
+
+impl ReadOptions {
+    /// Set the behaviour for duplicate properties, defaults to [`DuplicateKeys::LastWins`]
+    pub fn duplicate_keys(&mut self, duplicate_keys: DuplicateKeys) -> &mut Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+}
+
+/// Line ending used when serialising properties
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Unix-style line feed (`\n`)
+    Lf,
+
+    /// Windows-style carriage return and line feed (`\r\n`)
+    CrLf,
+}
+
+impl LineEnding {
+    /// The characters which make up the line ending
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+impl Default for LineEnding {
+    /// Defaults to the platform convention, which matches the files written by gcloud
+    fn default() -> Self {
+        if cfg!(windows) {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+}
+
+/// Order of the sections and properties when serialising properties
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// `core` first, then the other sections by name, with the properties in each section sorted by key
+    ///
+    /// The same properties are always written the same way however they were built, so files kept in version
+    /// control only change when the properties do
+    #[default]
+    Canonical,
+
+    /// The order the sections and properties were first read or set
+    Preserve,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Options which control how properties are serialised
+pub struct WriteOptions {
+    /// Line ending to use
+    pub(crate) line_ending: LineEnding,
+
+    /// Whether the output should end with a line ending
+    pub(crate) final_newline: bool,
+
+    /// Order of the sections and properties
+    pub(crate) key_order: KeyOrder,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            line_ending: LineEnding::default(),
+            final_newline: true,
+            key_order: KeyOrder::default(),
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Set the line ending, defaults to the platform convention
+    pub fn line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Set whether the output should end with a line ending, defaults to `true`
+    pub fn final_newline(&mut self, final_newline: bool) -> &mut Self {
+        self.final_newline = final_newline;
+        self
+    }
+
+    /// Set the order of the sections and properties, defaults to [`KeyOrder::Canonical`]
+    pub fn key_order(&mut self, key_order: KeyOrder) -> &mut Self {
+        self.key_order = key_order;
+        self
+    }
 }
 
 #[derive(Debug, Default)]
 /// Properties builder
 pub struct PropertiesBuilder {
+    /// Existing properties which the builder starts from
+    base: Properties,
+
     /// core/project setting
     project: Option<String>,
 
@@ -69,41 +653,98 @@ pub struct PropertiesBuilder {
 
     /// compute/region setting
     region: Option<String>,
+
+    /// container/cluster setting
+    cluster: Option<String>,
+
+    /// run/region setting
+    run_region: Option<String>,
+
+    /// run/platform setting
+    run_platform: Option<String>,
+
+    /// functions/region setting
+    functions_region: Option<String>,
+
+    /// artifacts/location setting
+    artifacts_location: Option<String>,
+
+    /// dataproc/region setting
+    dataproc_region: Option<String>,
+
+    /// How strictly the zone and region are checked by [`PropertiesBuilder::validate`]
+    location_validation: LocationValidation,
 }
 
 impl PropertiesBuilder {
+    /// Create a builder seeded from existing properties, e.g. to edit a configuration
+    ///
+    /// Any properties which aren't set on the builder carry over untouched, including ones the builder doesn't support
+    pub fn from_properties(properties: &Properties) -> Self {
+        Self {
+            base: properties.clone(),
+            ..Self::default()
+        }
+    }
+
     /// Build the properties
     pub fn build(&self) -> Properties {
-        let core = if self.project.is_some() || self.account.is_some() {
-            Some(CoreProperties {
-                project: self.project.clone(),
-                account: self.account.clone(),
-            })
-        } else {
-            None
-        };
+        let mut properties = self.base.clone();
 
-        let compute = if self.zone.is_some() || self.region.is_some() {
-            Some(ComputeProperties {
-                zone: self.zone.clone(),
-                region: self.region.clone(),
-            })
-        } else {
-            None
-        };
+        let values = [
+            ("core", "project", &self.project),
+            ("core", "account", &self.account),
+            ("compute", "zone", &self.zone),
+            ("compute", "region", &self.region),
+            ("container", "cluster", &self.cluster),
+            ("run", "region", &self.run_region),
+            ("run", "platform", &self.run_platform),
+            ("functions", "region", &self.functions_region),
+            ("artifacts", "location", &self.artifacts_location),
+            ("dataproc", "region", &self.dataproc_region),
+        ];
+
+        for (section, key, value) in values {
+            if let Some(value) = value {
+                properties.set(section, key, value);
+            }
+        }
+
+        properties
+    }
+
+    /// Set how strictly the zone and region are checked by [`PropertiesBuilder::validate`], defaults to
+    /// [`LocationValidation::Relaxed`]
+    pub fn location_validation(&mut self, location_validation: LocationValidation) -> &mut Self {
+        self.location_validation = location_validation;
+        self
+    }
+
+    /// Check the zone and region which have been set, returning warnings about any which don't look right when
+    /// validation is relaxed
+    pub fn validate(&self) -> Result<Vec<Warning>, Error> {
+        let mut warnings = Vec::new();
+
+        if let Some(zone) = &self.zone {
+            warnings.extend(self.location_validation.check_zone(zone)?);
+        }
 
-        Properties { core, compute }
+        if let Some(region) = &self.region {
+            warnings.extend(self.location_validation.check_region(region)?);
+        }
+
+        Ok(warnings)
     }
 
     /// Set the project property
-    pub fn project(&mut self, project: &str) -> &mut Self {
-        self.project = Some(project.to_owned());
+    pub fn project(&mut self, project: &ProjectId) -> &mut Self {
+        self.project = Some(project.as_str().to_owned());
         self
     }
 
     /// Set the account property
-    pub fn account(&mut self, account: &str) -> &mut Self {
-        self.account = Some(account.to_owned());
+    pub fn account(&mut self, account: &Account) -> &mut Self {
+        self.account = Some(account.as_str().to_owned());
         self
     }
 
@@ -118,4 +759,435 @@ impl PropertiesBuilder {
         self.region = Some(region.to_owned());
         self
     }
+
+    /// Set the zone property, and the region property to the region which contains it
+    pub fn zone_and_region(&mut self, zone: &Zone) -> &mut Self {
+        self.zone(zone.as_str()).region(zone.region().as_str())
+    }
+
+    /// Set the default GKE cluster property, i.e. `container/cluster`
+    pub fn cluster(&mut self, cluster: &str) -> &mut Self {
+        self.cluster = Some(cluster.to_owned());
+        self
+    }
+
+    /// Set the default Cloud Run region property, i.e. `run/region`
+    pub fn run_region(&mut self, run_region: &str) -> &mut Self {
+        self.run_region = Some(run_region.to_owned());
+        self
+    }
+
+    /// Set the Cloud Run platform property, i.e. `run/platform`, e.g. `managed`
+    pub fn run_platform(&mut self, run_platform: &str) -> &mut Self {
+        self.run_platform = Some(run_platform.to_owned());
+        self
+    }
+
+    /// Set the default Cloud Functions region property, i.e. `functions/region`
+    pub fn functions_region(&mut self, functions_region: &str) -> &mut Self {
+        self.functions_region = Some(functions_region.to_owned());
+        self
+    }
+
+    /// Set the default Artifact Registry location property, i.e. `artifacts/location`
+    pub fn artifacts_location(&mut self, artifacts_location: &str) -> &mut Self {
+        self.artifacts_location = Some(artifacts_location.to_owned());
+        self
+    }
+
+    /// Set the default Dataproc region property, i.e. `dataproc/region`
+    pub fn dataproc_region(&mut self, dataproc_region: &str) -> &mut Self {
+        self.dataproc_region = Some(dataproc_region.to_owned());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties() -> Properties {
+        PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .zone("europe-west1-d")
+            .build()
+    }
+
+    fn to_string(properties: &Properties, options: &WriteOptions) -> String {
+        let mut buffer = Vec::new();
+        properties.to_writer_with_options(&mut buffer, options).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    pub fn test_from_str_and_display_round_trip() {
+        let properties: Properties = "[core]\r\nproject = my-project\r\n[compute]\r\nzone=europe-west1-d\r\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(properties.get("core", "project"), Some("my-project"));
+        assert_eq!(
+            properties.to_string(),
+            "[core]\nproject=my-project\n[compute]\nzone=europe-west1-d\n"
+        );
+    }
+
+    #[test]
+    pub fn test_from_str_invalid_fails() {
+        assert!("not a configuration".parse::<Properties>().is_err());
+    }
+
+    #[test]
+    pub fn test_from_reader_with_duplicate_keys() {
+        let input = "[core]\nproject = first\naccount = a.user@example.org\nproject = second\n";
+        let (properties, warnings) =
+            Properties::from_reader_with_options(input.as_bytes(), &ReadOptions::default()).unwrap();
+
+        let output = to_string(
+            &properties,
+            WriteOptions::default()
+                .line_ending(LineEnding::Lf)
+                .key_order(KeyOrder::Preserve),
+        );
+        assert_eq!(output, "[core]\nproject=second\naccount=a.user@example.org\n");
+        assert_eq!(
+            warnings,
+            vec![Warning::DuplicateKey {
+                section: "core".to_owned(),
+                key: "project".to_owned()
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_from_reader_with_duplicate_keys_as_error() {
+        let input = "[compute]\nzone = europe-west1-d\nzone = europe-west1-b\n";
+        let result = Properties::from_reader_with_options(
+            input.as_bytes(),
+            ReadOptions::default().duplicate_keys(DuplicateKeys::Error),
+        );
+
+        assert!(matches!(result, Err(Error::DuplicateKey { .. })));
+    }
+
+    #[test]
+    pub fn test_get_and_set_arbitrary_properties() {
+        let input =
+            "[core]\nproject = my-project\n[auth]\nimpersonate_service_account = sa@example.iam.gserviceaccount.com\n";
+        let mut properties = Properties::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            properties.get("auth", "impersonate_service_account"),
+            Some("sa@example.iam.gserviceaccount.com")
+        );
+        assert_eq!(properties.get("auth", "missing"), None);
+        assert_eq!(properties.iter().count(), 2);
+
+        properties
+            .set("core", "project", "other-project")
+            .set("container", "cluster", "my-cluster");
+
+        let output = to_string(&properties, WriteOptions::default().line_ending(LineEnding::Lf));
+        assert_eq!(
+            output,
+            "[core]\nproject=other-project\n[auth]\nimpersonate_service_account=sa@example.iam.gserviceaccount.com\n[container]\ncluster=my-cluster\n"
+        );
+    }
+
+    #[test]
+    pub fn test_typed_sections() {
+        let properties = PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .account(&"a.user@example.org".parse().unwrap())
+            .zone("europe-west1-d")
+            .region("europe-west1")
+            .cluster("my-cluster")
+            .run_region("europe-west2")
+            .run_platform("managed")
+            .functions_region("europe-west3")
+            .artifacts_location("europe")
+            .dataproc_region("europe-west4")
+            .build();
+
+        assert_eq!(properties.core().project(), Some("my-project"));
+        assert_eq!(properties.core().account(), Some("a.user@example.org"));
+        assert_eq!(properties.compute().zone(), Some("europe-west1-d"));
+        assert_eq!(properties.compute().region(), Some("europe-west1"));
+        assert_eq!(properties.container().cluster(), Some("my-cluster"));
+        assert_eq!(properties.run().region(), Some("europe-west2"));
+        assert_eq!(properties.run().platform(), Some("managed"));
+        assert_eq!(properties.functions().region(), Some("europe-west3"));
+        assert_eq!(properties.artifacts().location(), Some("europe"));
+        assert_eq!(properties.dataproc().region(), Some("europe-west4"));
+        assert_eq!(properties.get("run", "region"), Some("europe-west2"));
+
+        let empty = Properties::default();
+        assert_eq!(empty.run().region(), None);
+    }
+
+    #[test]
+    pub fn test_validate() {
+        let input = "[core]\nproject=My_Project\naccount=a.user@example.org\nprojcet=typo\n[compute]\nzone=europe-west1-d\nregion=us-east1\n[auth]\nanything=goes\n[empty]\n";
+        let parsed = Properties::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!(
+            parsed.validate(),
+            vec![
+                Warning::UnknownProperty {
+                    section: "core".to_owned(),
+                    key: "projcet".to_owned()
+                },
+                Warning::EmptySection("empty".to_owned()),
+                Warning::MalformedProject("My_Project".to_owned()),
+                Warning::ZoneOutsideRegion {
+                    zone: "europe-west1-d".to_owned(),
+                    region: "us-east1".to_owned()
+                },
+            ]
+        );
+
+        assert!(properties().validate().is_empty());
+    }
+
+    #[test]
+    pub fn test_zone_in_region() {
+        let properties = PropertiesBuilder::default()
+            .zone_and_region(&"europe-west1-d".parse().unwrap())
+            .build();
+        assert_eq!(properties.compute().region(), Some("europe-west1"));
+        assert_eq!(properties.check_zone_in_region(), None);
+
+        let properties = PropertiesBuilder::default()
+            .zone("europe-west1-d")
+            .region("us-east1")
+            .build();
+        assert_eq!(
+            properties.check_zone_in_region(),
+            Some(Warning::ZoneOutsideRegion {
+                zone: "europe-west1-d".to_owned(),
+                region: "us-east1".to_owned()
+            })
+        );
+
+        let properties = PropertiesBuilder::default().zone("europe-west1-d").build();
+        assert_eq!(properties.check_zone_in_region(), None);
+    }
+
+    #[test]
+    pub fn test_builder_validates_locations() {
+        let mut builder = PropertiesBuilder::default();
+        builder.zone("europe-west1-d").region("europe-west");
+
+        assert_eq!(
+            builder.validate().unwrap(),
+            vec![Warning::UnrecognisedRegion("europe-west".to_owned())]
+        );
+
+        builder.location_validation(LocationValidation::Strict);
+        assert!(matches!(builder.validate(), Err(Error::InvalidRegion(_))));
+
+        builder.location_validation(LocationValidation::Off);
+        assert!(builder.validate().unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn test_extra_sections() {
+        let input = "[core]\nproject=my-project\n[auth]\ndisable_credentials=true\n[container]\ncluster=my-cluster\n[billing]\nquota_project=my-quota\n";
+        let mut properties = Properties::from_reader(input.as_bytes()).unwrap();
+
+        let extra = properties.extra_sections();
+        assert_eq!(extra.keys().collect::<Vec<_>>(), vec!["auth", "billing"]);
+        assert_eq!(extra["auth"]["disable_credentials"], "true");
+
+        properties.set(
+            "auth",
+            "impersonate_service_account",
+            "sa@my-project.iam.gserviceaccount.com",
+        );
+        properties.unset("billing", "quota_project");
+
+        let extra = properties.extra_sections();
+        assert_eq!(extra["auth"].len(), 2);
+        assert!(extra.get("billing").is_none_or(BTreeMap::is_empty));
+    }
+
+    #[test]
+    pub fn test_round_trip_preserves_unknown_sections() {
+        let input = "[core]\nproject=my-project\ndisable_usage_reporting=True\n[auth]\ndisable_credentials=true\n[container]\ncluster=my-cluster\n";
+        let properties = Properties::from_reader(input.as_bytes()).unwrap();
+
+        let output = to_string(
+            &properties,
+            WriteOptions::default()
+                .line_ending(LineEnding::Lf)
+                .key_order(KeyOrder::Preserve),
+        );
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    pub fn test_builder_from_properties_carries_over_unset_fields() {
+        let input = "[core]\nproject=my-project\naccount=a.user@example.org\n[auth]\ndisable_credentials=true\n";
+        let existing = Properties::from_reader(input.as_bytes()).unwrap();
+
+        let properties = PropertiesBuilder::from_properties(&existing)
+            .project(&"other-project".parse().unwrap())
+            .zone("europe-west1-d")
+            .build();
+
+        let output = to_string(
+            &properties,
+            WriteOptions::default()
+                .line_ending(LineEnding::Lf)
+                .key_order(KeyOrder::Preserve),
+        );
+        assert_eq!(
+            output,
+            "[core]\nproject=other-project\naccount=a.user@example.org\n[auth]\ndisable_credentials=true\n[compute]\nzone=europe-west1-d\n"
+        );
+    }
+
+    #[test]
+    pub fn test_diff() {
+        let old = PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .account(&"a.user@example.org".parse().unwrap())
+            .zone("europe-west1-d")
+            .build();
+
+        let new = PropertiesBuilder::default()
+            .project(&"other-project".parse().unwrap())
+            .zone("europe-west1-d")
+            .region("europe-west1")
+            .build();
+
+        let changes: Vec<String> = old.diff(&new).iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                "core/project: 'my-project' -> 'other-project'",
+                "core/account: removed 'a.user@example.org'",
+                "compute/region: added 'europe-west1'",
+            ]
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    pub fn test_merge() {
+        let personal = PropertiesBuilder::default()
+            .project(&"my-project".parse().unwrap())
+            .account(&"a.user@example.org".parse().unwrap())
+            .build();
+
+        let mut baseline = Properties::default();
+        baseline
+            .set("core", "project", "team-project")
+            .set("compute", "zone", "europe-west1-d");
+
+        let mut other_wins = personal.clone();
+        other_wins.merge(&baseline, MergeStrategy::OtherWins);
+        assert_eq!(other_wins.get("core", "project"), Some("team-project"));
+        assert_eq!(other_wins.get("core", "account"), Some("a.user@example.org"));
+        assert_eq!(other_wins.get("compute", "zone"), Some("europe-west1-d"));
+
+        let mut self_wins = personal;
+        self_wins.merge(&baseline, MergeStrategy::SelfWins);
+        assert_eq!(self_wins.get("core", "project"), Some("my-project"));
+        assert_eq!(self_wins.get("compute", "zone"), Some("europe-west1-d"));
+    }
+
+    #[test]
+    pub fn test_env_conflicts() {
+        let lookup = |name: &str| match name {
+            "CLOUDSDK_CORE_PROJECT" => Some("other-project".to_owned()),
+            "CLOUDSDK_COMPUTE_ZONE" => Some("europe-west1-d".to_owned()),
+            "CLOUDSDK_COMPUTE_REGION" => Some("us-east1".to_owned()),
+            _ => None,
+        };
+
+        let conflicts = properties().env_conflicts_with(lookup);
+
+        // the zone matches so it isn't a conflict
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].variable(), "CLOUDSDK_CORE_PROJECT");
+        assert_eq!(conflicts[0].value(), Some("my-project"));
+        assert_eq!(
+            conflicts[0].to_string(),
+            "CLOUDSDK_CORE_PROJECT=other-project overrides core/project (configured as 'my-project')"
+        );
+        assert_eq!(conflicts[1].variable(), "CLOUDSDK_COMPUTE_REGION");
+        assert_eq!(conflicts[1].value(), None);
+    }
+
+    #[test]
+    pub fn test_effective_with() {
+        let mut properties = properties();
+        properties.set("core", "disable_prompts", "False");
+
+        let lookup = |name: &str| match name {
+            "CLOUDSDK_CORE_PROJECT" => Some("other-project".to_owned()),
+            "CLOUDSDK_COMPUTE_ZONE" => Some("europe-west1-d".to_owned()),
+            "CLOUDSDK_COMPUTE_REGION" => Some("europe-west1".to_owned()),
+            "CLOUDSDK_CORE_DISABLE_PROMPTS" => Some("True".to_owned()),
+            _ => None,
+        };
+
+        let effective = properties.effective_with(lookup);
+        let effective_properties = effective.properties();
+
+        assert_eq!(effective_properties.get("core", "project"), Some("other-project"));
+        assert_eq!(effective_properties.get("compute", "zone"), Some("europe-west1-d"));
+        assert_eq!(effective_properties.get("compute", "region"), Some("europe-west1"));
+        assert_eq!(effective_properties.get("core", "disable_prompts"), Some("True"));
+
+        assert_eq!(effective.overrides().len(), 3);
+        assert_eq!(
+            effective.overridden("core", "project").and_then(EnvConflict::value),
+            Some("my-project")
+        );
+        assert_eq!(
+            effective.overridden("compute", "region").and_then(EnvConflict::value),
+            None
+        );
+        assert!(effective.overridden("compute", "zone").is_none());
+        assert!(effective.overridden("core", "disable_prompts").is_some());
+    }
+
+    #[test]
+    pub fn test_to_writer_with_linefeed() {
+        let output = to_string(&properties(), WriteOptions::default().line_ending(LineEnding::Lf));
+        assert_eq!(output, "[core]\nproject=my-project\n[compute]\nzone=europe-west1-d\n");
+    }
+
+    #[test]
+    pub fn test_to_writer_with_crlf() {
+        let output = to_string(&properties(), WriteOptions::default().line_ending(LineEnding::CrLf));
+        assert_eq!(
+            output,
+            "[core]\r\nproject=my-project\r\n[compute]\r\nzone=europe-west1-d\r\n"
+        );
+    }
+
+    #[test]
+    pub fn test_to_writer_without_final_newline() {
+        let output = to_string(
+            &properties(),
+            WriteOptions::default()
+                .line_ending(LineEnding::CrLf)
+                .final_newline(false),
+        );
+        assert_eq!(
+            output,
+            "[core]\r\nproject=my-project\r\n[compute]\r\nzone=europe-west1-d"
+        );
+    }
+
+    #[test]
+    pub fn test_to_writer_defaults_to_platform_line_ending() {
+        let output = to_string(&properties(), &WriteOptions::default());
+        let expected = if cfg!(windows) { "\r\n" } else { "\n" };
+        assert!(output.ends_with(expected));
+    }
 }
@@ -0,0 +1,169 @@
+use crate::Properties;
+use std::fmt;
+
+/// Predicate over the properties of a configuration
+type Predicate = Box<dyn Fn(&Properties) -> bool + Send + Sync>;
+
+/// Filter for [`ConfigurationStore::query`](crate::ConfigurationStore::query)
+///
+/// A configuration matches when it matches every criterion which has been set, so the default filter matches every
+/// configuration
+#[derive(Default)]
+pub struct Filter {
+    /// Glob which the name must match
+    name: Option<String>,
+
+    /// Required `core/project`
+    project: Option<String>,
+
+    /// Required `core/account`
+    account: Option<String>,
+
+    /// Required `compute/zone`
+    zone: Option<String>,
+
+    /// Arbitrary check of the properties
+    predicate: Option<Predicate>,
+}
+
+impl Filter {
+    /// Only match configurations whose name matches a glob, where `*` matches any run of characters and `?` matches
+    /// exactly one
+    pub fn name(&mut self, glob: &str) -> &mut Self {
+        self.name = Some(glob.to_owned());
+        self
+    }
+
+    /// Only match configurations whose `core/project` is the given project
+    pub fn project(&mut self, project: &str) -> &mut Self {
+        self.project = Some(project.to_owned());
+        self
+    }
+
+    /// Only match configurations whose `core/account` is the given account
+    pub fn account(&mut self, account: &str) -> &mut Self {
+        self.account = Some(account.to_owned());
+        self
+    }
+
+    /// Only match configurations whose `compute/zone` is the given zone
+    pub fn zone(&mut self, zone: &str) -> &mut Self {
+        self.zone = Some(zone.to_owned());
+        self
+    }
+
+    /// Only match configurations whose properties pass an arbitrary check
+    pub fn predicate(&mut self, predicate: impl Fn(&Properties) -> bool + Send + Sync + 'static) -> &mut Self {
+        self.predicate = Some(Box::new(predicate));
+        self
+    }
+
+    /// Check whether a configuration with the given name and properties matches the filter
+    pub fn matches(&self, name: &str, properties: &Properties) -> bool {
+        let core = properties.core();
+
+        self.matches_name(name)
+            && self.project.as_deref().is_none_or(|p| core.project() == Some(p))
+            && self.account.as_deref().is_none_or(|a| core.account() == Some(a))
+            && self
+                .zone
+                .as_deref()
+                .is_none_or(|z| properties.compute().zone() == Some(z))
+            && self.predicate.as_ref().is_none_or(|predicate| predicate(properties))
+    }
+
+    /// Check whether a name matches the name glob, if there is one, without needing the properties
+    pub(crate) fn matches_name(&self, name: &str) -> bool {
+        self.name.as_deref().is_none_or(|glob| glob_matches(glob, name))
+    }
+}
+
+impl fmt::Debug for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("name", &self.name)
+            .field("project", &self.project)
+            .field("account", &self.account)
+            .field("zone", &self.zone)
+            .field("predicate", &self.predicate.as_ref().map(|_| "Fn(&Properties) -> bool"))
+            .finish()
+    }
+}
+
+/// Check whether a value matches a glob, where `*` matches any run of characters and `?` matches exactly one
+fn glob_matches(glob: &str, value: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    // position to resume from after the last *, as (glob, value)
+    let mut backtrack = None;
+    let (mut g, mut v) = (0, 0);
+
+    while v < value.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, v));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                g += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last * swallow one more character
+                Some((star, consumed)) => {
+                    backtrack = Some((star, consumed + 1));
+                    g = star + 1;
+                    v = consumed + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_glob_matches() {
+        assert!(glob_matches("foo", "foo"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("dev-*", "dev-europe"));
+        assert!(glob_matches("*-prod", "payments-prod"));
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(glob_matches("a*c", "abcbc"));
+        assert!(glob_matches("b?r", "bar"));
+
+        assert!(!glob_matches("foo", "foobar"));
+        assert!(!glob_matches("dev-*", "prod-europe"));
+        assert!(!glob_matches("b?r", "br"));
+        assert!(!glob_matches("a*c", "abcb"));
+    }
+
+    #[test]
+    pub fn test_matches_every_criterion() {
+        let properties: Properties =
+            "[core]\nproject=my-project\naccount=a@example.org\n[compute]\nzone=europe-west1-d\n"
+                .parse()
+                .unwrap();
+
+        assert!(Filter::default().matches("foo", &properties));
+        assert!(Filter::default()
+            .name("f*")
+            .project("my-project")
+            .account("a@example.org")
+            .zone("europe-west1-d")
+            .predicate(|p| p.compute().region().is_none())
+            .matches("foo", &properties));
+
+        assert!(!Filter::default().name("b*").matches("foo", &properties));
+        assert!(!Filter::default().project("other-project").matches("foo", &properties));
+        assert!(!Filter::default().account("b@example.org").matches("foo", &properties));
+        assert!(!Filter::default().zone("europe-west1-b").matches("foo", &properties));
+        assert!(!Filter::default().predicate(|_| false).matches("foo", &properties));
+    }
+}
@@ -0,0 +1,64 @@
+/// How loosely [`ConfigurationStore::resolve_with`](crate::ConfigurationStore::resolve_with) matches names
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NameMatch {
+    /// Exact names, then unambiguous prefixes, e.g. `prod` for `prod-eu`
+    #[default]
+    Prefix,
+
+    /// As [`NameMatch::Prefix`], then names with a few typos, e.g. `prdo-eu` for `prod-eu`
+    ///
+    /// One mistake is allowed for every three characters, and the closest match wins
+    Fuzzy,
+}
+
+/// Most typos allowed for a name to fuzzily match another
+pub(crate) fn max_typos(name: &str) -> usize {
+    // one for every three characters, so short names don't match everything
+    (name.chars().count() / 3).max(1)
+}
+
+/// Number of insertions, deletions, substitutions and adjacent transpositions needed to turn `a` into `b`
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i characters of a and the first j characters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_edit_distance() {
+        assert_eq!(edit_distance("prod-eu", "prod-eu"), 0);
+        assert_eq!(edit_distance("prdo-eu", "prod-eu"), 1);
+        assert_eq!(edit_distance("prod", "prod-eu"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
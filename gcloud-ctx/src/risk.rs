@@ -0,0 +1,81 @@
+use crate::Metadata;
+
+/// How risky it is to work in a configuration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Risk {
+    /// An everyday configuration
+    Normal,
+
+    /// A configuration where mistakes are costly, e.g. production
+    Dangerous,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Decides whether a configuration is a dangerous context so that every command treats it the same way
+pub struct RiskPolicy {
+    /// Tags which mark a configuration as dangerous
+    dangerous_tags: Vec<String>,
+}
+
+impl RiskPolicy {
+    /// Tags which mark a configuration as dangerous by default
+    pub const DEFAULT_DANGEROUS_TAGS: [&'static str; 2] = ["prod", "protected"];
+
+    /// Set the tags which mark a configuration as dangerous, defaults to [`RiskPolicy::DEFAULT_DANGEROUS_TAGS`]
+    pub fn dangerous_tags(&mut self, tags: &[&str]) -> &mut Self {
+        self.dangerous_tags = tags.iter().map(|t| (*t).to_owned()).collect();
+        self
+    }
+
+    /// Assess the risk of a configuration from its metadata
+    pub fn assess(&self, metadata: &Metadata) -> Risk {
+        let dangerous = metadata
+            .tags()
+            .iter()
+            .any(|tag| self.dangerous_tags.iter().any(|d| d.eq_ignore_ascii_case(tag)));
+
+        if dangerous {
+            Risk::Dangerous
+        } else {
+            Risk::Normal
+        }
+    }
+}
+
+impl Default for RiskPolicy {
+    fn default() -> Self {
+        Self {
+            dangerous_tags: Self::DEFAULT_DANGEROUS_TAGS.iter().map(|t| (*t).to_owned()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(tags: &[&str]) -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.set_tags(tags.iter().copied());
+        metadata
+    }
+
+    #[test]
+    pub fn test_assess_default_tags() {
+        let policy = RiskPolicy::default();
+
+        assert_eq!(policy.assess(&Metadata::default()), Risk::Normal);
+        assert_eq!(policy.assess(&tagged(&["team-a"])), Risk::Normal);
+        assert_eq!(policy.assess(&tagged(&["team-a", "PROD"])), Risk::Dangerous);
+        assert_eq!(policy.assess(&tagged(&["protected"])), Risk::Dangerous);
+    }
+
+    #[test]
+    pub fn test_assess_custom_tags() {
+        let mut policy = RiskPolicy::default();
+        policy.dangerous_tags(&["live"]);
+
+        assert_eq!(policy.assess(&tagged(&["prod"])), Risk::Normal);
+        assert_eq!(policy.assess(&tagged(&["live"])), Risk::Dangerous);
+    }
+}
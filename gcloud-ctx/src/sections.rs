@@ -0,0 +1,209 @@
+use crate::Properties;
+
+/// Sections which have a typed view, see [`Properties::extra_sections`] for the others
+pub(crate) const TYPED_SECTIONS: [&str; 7] = [
+    "core",
+    "compute",
+    "container",
+    "run",
+    "functions",
+    "artifacts",
+    "dataproc",
+];
+
+/// Properties gcloud understands in each of the typed sections, see [`Properties::validate`]
+pub(crate) const KNOWN_PROPERTIES: [(&str, &[&str]); 7] = [
+    (
+        "core",
+        &[
+            "account",
+            "api_key",
+            "custom_ca_certs_file",
+            "disable_color",
+            "disable_file_logging",
+            "disable_prompts",
+            "disable_usage_reporting",
+            "log_http",
+            "max_log_days",
+            "pass_credentials_to_gsutil",
+            "project",
+            "request_reason",
+            "show_structured_logs",
+            "trace_token",
+            "universe_domain",
+            "user_output_enabled",
+            "verbosity",
+        ],
+    ),
+    (
+        "compute",
+        &[
+            "image_family_scope",
+            "region",
+            "use_new_list_usable_subnets_api",
+            "zone",
+        ],
+    ),
+    (
+        "container",
+        &[
+            "build_timeout",
+            "cluster",
+            "new_scopes_behavior",
+            "use_application_default_credentials",
+            "use_client_certificate",
+        ],
+    ),
+    ("run", &["cluster", "cluster_location", "platform", "region"]),
+    ("functions", &["gen2", "region", "v2"]),
+    ("artifacts", &["location", "repository"]),
+    ("dataproc", &["region"]),
+];
+
+/// Properties in the `core` section, which apply to every gcloud command, see [`Properties::core`]
+#[derive(Debug, Clone, Copy)]
+pub struct CoreSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> CoreSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Project which commands act on, i.e. `core/project`
+    pub fn project(&self) -> Option<&'a str> {
+        self.properties.get("core", "project")
+    }
+
+    /// Account which commands authenticate as, i.e. `core/account`
+    pub fn account(&self) -> Option<&'a str> {
+        self.properties.get("core", "account")
+    }
+}
+
+/// Properties in the `compute` section, used by `gcloud compute`, see [`Properties::compute`]
+#[derive(Debug, Clone, Copy)]
+pub struct ComputeSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> ComputeSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default zone, i.e. `compute/zone`
+    pub fn zone(&self) -> Option<&'a str> {
+        self.properties.get("compute", "zone")
+    }
+
+    /// Default region, i.e. `compute/region`
+    pub fn region(&self) -> Option<&'a str> {
+        self.properties.get("compute", "region")
+    }
+}
+
+/// Properties in the `container` section, used by `gcloud container`, see [`Properties::container`]
+#[derive(Debug, Clone, Copy)]
+pub struct ContainerSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> ContainerSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default GKE cluster, i.e. `container/cluster`
+    pub fn cluster(&self) -> Option<&'a str> {
+        self.properties.get("container", "cluster")
+    }
+}
+
+/// Properties in the `run` section, used by `gcloud run`, see [`Properties::run`]
+#[derive(Debug, Clone, Copy)]
+pub struct RunSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> RunSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default region for Cloud Run services, i.e. `run/region`
+    pub fn region(&self) -> Option<&'a str> {
+        self.properties.get("run", "region")
+    }
+
+    /// Platform which services are deployed to, e.g. `managed` or `gke`, i.e. `run/platform`
+    pub fn platform(&self) -> Option<&'a str> {
+        self.properties.get("run", "platform")
+    }
+}
+
+/// Properties in the `functions` section, used by `gcloud functions`, see [`Properties::functions`]
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionsSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> FunctionsSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default region for Cloud Functions, i.e. `functions/region`
+    pub fn region(&self) -> Option<&'a str> {
+        self.properties.get("functions", "region")
+    }
+}
+
+/// Properties in the `artifacts` section, used by `gcloud artifacts`, see [`Properties::artifacts`]
+#[derive(Debug, Clone, Copy)]
+pub struct ArtifactsSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> ArtifactsSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default location for Artifact Registry repositories, i.e. `artifacts/location`
+    pub fn location(&self) -> Option<&'a str> {
+        self.properties.get("artifacts", "location")
+    }
+}
+
+/// Properties in the `dataproc` section, used by `gcloud dataproc`, see [`Properties::dataproc`]
+#[derive(Debug, Clone, Copy)]
+pub struct DataprocSection<'a> {
+    /// Properties which contain the section
+    properties: &'a Properties,
+}
+
+impl<'a> DataprocSection<'a> {
+    /// Create a view of the section in the given properties
+    pub(crate) fn new(properties: &'a Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Default region for Dataproc clusters and jobs, i.e. `dataproc/region`
+    pub fn region(&self) -> Option<&'a str> {
+        self.properties.get("dataproc", "region")
+    }
+}
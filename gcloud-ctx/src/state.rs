@@ -0,0 +1,200 @@
+use crate::{
+    paths,
+    storage::{EntryKind, Storage},
+    Error, FileContext, FileOperation, Result,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable which overrides the root of the gcloud-ctx state directory
+const STATE_ENV_VAR: &str = "GCTX_STATE_DIR";
+
+/// Name of the directory inside the configuration store where earlier versions kept their files
+const LEGACY_DIRECTORY: &str = "gctx";
+
+/// Location of the files gcloud-ctx keeps about a configuration store, e.g. metadata, backups and caches
+///
+/// These live outside the configuration store so that gcloud-ctx never writes anything to the gcloud directory except
+/// the configurations themselves. The root follows the OS conventions, e.g.:
+///
+/// - Linux: `$XDG_STATE_HOME/gctx`, i.e. `~/.local/state/gctx`
+/// - Mac: `~/Library/Application Support/gctx`
+/// - Windows: `%LOCALAPPDATA%\gctx`
+///
+/// The root can be overridden with the `GCTX_STATE_DIR` environment variable. Each store has its own directory under
+/// the root, keyed by the path of the store, so that switching `CLOUDSDK_CONFIG` keeps the files separate
+#[derive(Debug)]
+pub struct StateDirectory;
+
+impl StateDirectory {
+    /// Root of the state directory, shared by all configuration stores
+    pub fn root() -> Result<PathBuf> {
+        if let Some(value) = std::env::var_os(STATE_ENV_VAR) {
+            return Ok(value.into());
+        }
+
+        let base = paths::state_dir()
+            .or_else(paths::data_local_dir)
+            .ok_or(Error::StateDirectoryNotFound)?;
+
+        Ok(base.join("gctx"))
+    }
+
+    /// State directory for the configuration store at the given location
+    pub fn for_store(location: &Path) -> Result<PathBuf> {
+        Ok(Self::root()?.join("stores").join(Self::key(location)))
+    }
+
+    /// Key which identifies the configuration store at the given location within the state directory
+    ///
+    /// The key is a hash of the canonical path, so it's the same however the location is written
+    pub fn key(location: &Path) -> String {
+        let location = fs::canonicalize(location).unwrap_or_else(|_| location.to_path_buf());
+
+        // FNV-1a, because the key must stay the same across Rust versions, unlike the std hashers
+        let hash = location
+            .to_string_lossy()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+
+        format!("{:016x}", hash)
+    }
+
+    /// Write a file in the state directory, creating any missing directories
+    ///
+    /// The file is replaced atomically, so readers never see it partially written even if gctx is interrupted
+    pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).file_context(FileOperation::Create, parent)?;
+        }
+
+        crate::atomic::write(path, contents)
+    }
+
+    /// Move any files written inside the configuration store by earlier versions into the state directory
+    ///
+    /// Files which already exist in the state directory are kept, and the old directory is removed once it's empty
+    pub(crate) fn migrate(storage: &Storage, location: &Path, state: &Path) -> Result<()> {
+        let legacy = location.join(LEGACY_DIRECTORY);
+
+        if !storage.is_dir(&legacy) {
+            return Ok(());
+        }
+
+        // the lock is recreated in the state directory when it's next needed
+        let lock = legacy.join("lock");
+        ignore_missing(storage.remove_file(&lock)).file_context(FileOperation::Delete, &lock)?;
+
+        move_contents(storage, &legacy, state)?;
+
+        // leave anything which couldn't be moved for the user to sort out
+        let _ = storage.remove_dir(&legacy);
+
+        Ok(())
+    }
+}
+
+/// Move the contents of one directory into another, merging sub-directories and keeping existing files
+fn move_contents(storage: &Storage, from: &Path, to: &Path) -> Result<()> {
+    storage.create_dir_all(to).file_context(FileOperation::Create, to)?;
+
+    for entry in storage.read_dir(from).file_context(FileOperation::Read, from)? {
+        let entry = entry.file_context(FileOperation::Read, from)?;
+        let destination = to.join(entry.file_name());
+
+        if entry.kind().file_context(FileOperation::Read, &entry.path())? == EntryKind::Directory {
+            move_contents(storage, &entry.path(), &destination)?;
+            let _ = storage.remove_dir(&entry.path());
+        } else if !storage.exists(&destination) {
+            // another process may have migrated the same file already
+            ignore_missing(storage.rename(&entry.path(), &destination))
+                .file_context(FileOperation::Rename, &entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Treat a file which doesn't exist as success
+fn ignore_missing(result: std::io::Result<()>) -> std::io::Result<()> {
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_key_is_stable() {
+        let tmp = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            StateDirectory::key(tmp.path()),
+            StateDirectory::key(&tmp.path().join("."))
+        );
+        assert_ne!(
+            StateDirectory::key(tmp.path()),
+            StateDirectory::key(&tmp.path().join("other"))
+        );
+        assert_eq!(StateDirectory::key(tmp.path()).len(), 16);
+    }
+
+    #[test]
+    pub fn test_write_creates_missing_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("data").join("regions.json");
+
+        StateDirectory::write(&path, "{}").unwrap();
+        StateDirectory::write(&path, "[]").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    pub fn test_migrate_moves_legacy_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().join("gcloud");
+        let state = tmp.path().join("state");
+
+        fs::create_dir_all(location.join("gctx/metadata")).unwrap();
+        fs::write(location.join("gctx/metadata/config_foo"), "[gctx]\npinned=true\n").unwrap();
+        fs::write(location.join("gctx/lock"), "").unwrap();
+        fs::create_dir_all(state.join("metadata")).unwrap();
+        fs::write(state.join("metadata/config_bar"), "kept").unwrap();
+
+        StateDirectory::migrate(&Storage::Disk, &location, &state).unwrap();
+
+        assert!(!location.join("gctx").exists());
+        assert!(!state.join("lock").exists());
+        assert_eq!(
+            fs::read_to_string(state.join("metadata/config_foo")).unwrap(),
+            "[gctx]\npinned=true\n"
+        );
+        assert_eq!(fs::read_to_string(state.join("metadata/config_bar")).unwrap(), "kept");
+    }
+
+    #[test]
+    pub fn test_migrate_keeps_existing_state() {
+        let tmp = tempfile::tempdir().unwrap();
+        let location = tmp.path().join("gcloud");
+        let state = tmp.path().join("state");
+
+        fs::create_dir_all(location.join("gctx/cache")).unwrap();
+        fs::write(location.join("gctx/cache/names"), "old\n").unwrap();
+        fs::create_dir_all(state.join("cache")).unwrap();
+        fs::write(state.join("cache/names"), "new\n").unwrap();
+
+        StateDirectory::migrate(&Storage::Disk, &location, &state).unwrap();
+
+        assert_eq!(fs::read_to_string(state.join("cache/names")).unwrap(), "new\n");
+        assert!(location.join("gctx/cache/names").exists());
+    }
+}
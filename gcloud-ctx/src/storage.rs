@@ -0,0 +1,567 @@
+use crate::{atomic, FileContext, FileOperation, Result};
+#[cfg(feature = "test-util")]
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Where a configuration store and its state are kept
+///
+/// Every file the store reads or writes goes through here, with the same signatures and errors as [`std::fs`], so
+/// that a store can be kept entirely in memory for unit tests, see [`crate::test_util`]
+#[derive(Debug, Clone, Default)]
+pub(crate) enum Storage {
+    /// Files on disk, which is where every store except a test store lives
+    #[default]
+    Disk,
+
+    /// Files in memory, which are lost when the last store using them is dropped
+    #[cfg(feature = "test-util")]
+    Memory(Arc<Mutex<MemoryFiles>>),
+}
+
+/// Kind of entry in a directory, with symlinks followed to what they point to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryKind {
+    File,
+    Directory,
+
+    /// Anything else, e.g. a socket or device
+    Special,
+}
+
+/// Entry in a directory, see [`Storage::read_dir`]
+#[derive(Debug)]
+pub(crate) struct StorageEntry {
+    /// Path to the entry
+    path: PathBuf,
+
+    /// Kind of entry, or why it couldn't be found, e.g. a dangling symlink
+    kind: io::Result<EntryKind>,
+}
+
+impl StorageEntry {
+    pub(crate) fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    pub(crate) fn file_name(&self) -> OsString {
+        self.path.file_name().unwrap_or_default().to_owned()
+    }
+
+    pub(crate) fn kind(&self) -> io::Result<EntryKind> {
+        match &self.kind {
+            Ok(kind) => Ok(*kind),
+            Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+        }
+    }
+}
+
+/// Details of a file or directory, see [`Storage::metadata`]
+#[derive(Debug)]
+pub(crate) enum StorageMetadata {
+    Disk(fs::Metadata),
+    #[cfg(feature = "test-util")]
+    Memory(Node),
+}
+
+impl StorageMetadata {
+    pub(crate) fn is_file(&self) -> bool {
+        match self {
+            Self::Disk(metadata) => metadata.is_file(),
+            #[cfg(feature = "test-util")]
+            Self::Memory(node) => matches!(node, Node::File { .. }),
+        }
+    }
+
+    pub(crate) fn is_dir(&self) -> bool {
+        match self {
+            Self::Disk(metadata) => metadata.is_dir(),
+            #[cfg(feature = "test-util")]
+            Self::Memory(node) => matches!(node, Node::Directory { .. }),
+        }
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        match self {
+            Self::Disk(metadata) => metadata.permissions().readonly(),
+            #[cfg(feature = "test-util")]
+            Self::Memory(_) => false,
+        }
+    }
+
+    pub(crate) fn modified(&self) -> io::Result<SystemTime> {
+        match self {
+            Self::Disk(metadata) => metadata.modified(),
+            #[cfg(feature = "test-util")]
+            Self::Memory(Node::File { modified, .. } | Node::Directory { modified }) => Ok(*modified),
+        }
+    }
+
+    pub(crate) fn created(&self) -> io::Result<SystemTime> {
+        match self {
+            Self::Disk(metadata) => metadata.created(),
+            #[cfg(feature = "test-util")]
+            Self::Memory(Node::File { created, .. }) => Ok(*created),
+            #[cfg(feature = "test-util")]
+            Self::Memory(Node::Directory { modified }) => Ok(*modified),
+        }
+    }
+}
+
+/// Advisory lock on a store, which is released when it's dropped, see [`Storage::lock`]
+#[derive(Debug)]
+pub(crate) struct StoreLock {
+    /// Locked file, if the store is on disk
+    _file: Option<File>,
+}
+
+impl Storage {
+    /// Storage which is only held in memory, with an empty root directory
+    #[cfg(feature = "test-util")]
+    pub(crate) fn memory() -> Self {
+        let mut files = MemoryFiles::default();
+        files.nodes.insert(PathBuf::from("/"), Node::directory());
+        Self::Memory(Arc::new(Mutex::new(files)))
+    }
+
+    /// Files held in memory, locked for the duration of one operation
+    #[cfg(feature = "test-util")]
+    fn files(files: &Mutex<MemoryFiles>) -> MutexGuard<'_, MemoryFiles> {
+        files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Disk => fs::read(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => match Self::files(files).node(path)? {
+                Node::File { contents, .. } => Ok(contents.clone()),
+                Node::Directory { .. } => Err(is_a_directory(path)),
+            },
+        }
+    }
+
+    pub(crate) fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Replace the contents of a file so that readers never see it partially written, see [`atomic::write`]
+    pub(crate) fn write(&self, path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        match self {
+            Self::Disk => atomic::write(path, contents),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let result = Self::files(files).write(path, contents.as_ref().to_vec());
+                result.file_context(FileOperation::Write, path)
+            }
+        }
+    }
+
+    pub(crate) fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::create_dir_all(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => Self::files(files).create_dir_all(path),
+        }
+    }
+
+    pub(crate) fn remove_file(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::remove_file(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let mut files = Self::files(files);
+
+                match files.node(path)? {
+                    Node::File { .. } => files.remove(path, false),
+                    Node::Directory { .. } => Err(is_a_directory(path)),
+                }
+            }
+        }
+    }
+
+    /// Remove a directory, which must be empty
+    pub(crate) fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::remove_dir(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let mut files = Self::files(files);
+
+                match files.node(path)? {
+                    Node::Directory { .. } if files.children(path).next().is_some() => {
+                        Err(io::Error::other(format!("{} isn't empty", path.display())))
+                    }
+                    Node::Directory { .. } => files.remove(path, false),
+                    Node::File { .. } => Err(not_a_directory(path)),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::remove_dir_all(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let mut files = Self::files(files);
+
+                match files.node(path)? {
+                    Node::Directory { .. } => files.remove(path, true),
+                    Node::File { .. } => Err(not_a_directory(path)),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::rename(from, to),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let mut files = Self::files(files);
+
+                match files.node(from)?.clone() {
+                    node @ Node::File { .. } => {
+                        files.insert(to, node)?;
+                        files.remove(from, false)
+                    }
+                    Node::Directory { .. } => Err(is_a_directory(from)),
+                }
+            }
+        }
+    }
+
+    pub(crate) fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        match self {
+            Self::Disk => fs::copy(from, to).map(|_| ()),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let mut files = Self::files(files);
+
+                match files.node(from)? {
+                    Node::File { contents, .. } => {
+                        let contents = contents.clone();
+                        files.write(to, contents)
+                    }
+                    Node::Directory { .. } => Err(is_a_directory(from)),
+                }
+            }
+        }
+    }
+
+    /// List the entries of a directory, along with any entries which couldn't be read
+    pub(crate) fn read_dir(&self, path: &Path) -> io::Result<Vec<io::Result<StorageEntry>>> {
+        match self {
+            Self::Disk => Ok(fs::read_dir(path)?
+                .map(|entry| {
+                    entry.map(|entry| {
+                        let kind = match entry.file_type() {
+                            Ok(file_type) if file_type.is_symlink() => {
+                                fs::metadata(entry.path()).map(|m| m.file_type())
+                            }
+                            other => other,
+                        };
+
+                        StorageEntry {
+                            path: entry.path(),
+                            kind: kind.map(|file_type| {
+                                if file_type.is_file() {
+                                    EntryKind::File
+                                } else if file_type.is_dir() {
+                                    EntryKind::Directory
+                                } else {
+                                    EntryKind::Special
+                                }
+                            }),
+                        }
+                    })
+                })
+                .collect()),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let files = Self::files(files);
+
+                match files.node(path)? {
+                    Node::Directory { .. } => Ok(files
+                        .children(path)
+                        .map(|(path, node)| {
+                            Ok(StorageEntry {
+                                path: path.clone(),
+                                kind: Ok(node.kind()),
+                            })
+                        })
+                        .collect()),
+                    Node::File { .. } => Err(not_a_directory(path)),
+                }
+            }
+        }
+    }
+
+    /// Details of a file or directory, following symlinks
+    pub(crate) fn metadata(&self, path: &Path) -> io::Result<StorageMetadata> {
+        match self {
+            Self::Disk => fs::metadata(path).map(StorageMetadata::Disk),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => Ok(StorageMetadata::Memory(Self::files(files).node(path)?.clone())),
+        }
+    }
+
+    pub(crate) fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    pub(crate) fn is_file(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|metadata| metadata.is_file())
+    }
+
+    pub(crate) fn is_dir(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok_and(|metadata| metadata.is_dir())
+    }
+
+    /// Total size of a file, or of all the files in a directory, ignoring anything which can't be read
+    pub(crate) fn size(&self, path: &Path) -> u64 {
+        match self {
+            Self::Disk => disk_size(path),
+            #[cfg(feature = "test-util")]
+            Self::Memory(files) => {
+                let files = Self::files(files);
+
+                files
+                    .nodes
+                    .range(path.to_path_buf()..)
+                    .take_while(|(other, _)| other.starts_with(path))
+                    .map(|(_, node)| match node {
+                        Node::File { contents, .. } => contents.len() as u64,
+                        Node::Directory { .. } => 0,
+                    })
+                    .sum()
+            }
+        }
+    }
+
+    /// Take an advisory lock using the file at the given path, creating it and its directory if they don't exist
+    ///
+    /// Stores in memory are only ever used by one process, which can't modify them concurrently, so they aren't locked
+    pub(crate) fn lock(&self, path: &Path) -> Result<StoreLock> {
+        match self {
+            Self::Disk => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).file_context(FileOperation::Create, parent)?;
+                }
+
+                let file = File::options()
+                    .create(true)
+                    .truncate(false)
+                    .write(true)
+                    .open(path)
+                    .file_context(FileOperation::Create, path)?;
+                file.lock().file_context(FileOperation::Lock, path)?;
+
+                Ok(StoreLock { _file: Some(file) })
+            }
+            #[cfg(feature = "test-util")]
+            Self::Memory(_) => Ok(StoreLock { _file: None }),
+        }
+    }
+}
+
+/// Total size of a file on disk, or of all the files in a directory, ignoring anything which can't be read
+fn disk_size(path: &Path) -> u64 {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| disk_size(&entry.path())).sum())
+        .unwrap_or_default()
+}
+
+/// Files and directories held in memory, keyed by their full path
+#[cfg(feature = "test-util")]
+#[derive(Debug, Default)]
+pub(crate) struct MemoryFiles {
+    nodes: BTreeMap<PathBuf, Node>,
+}
+
+/// File or directory held in memory
+#[cfg(feature = "test-util")]
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    File {
+        contents: Vec<u8>,
+        created: SystemTime,
+        modified: SystemTime,
+    },
+    Directory {
+        modified: SystemTime,
+    },
+}
+
+#[cfg(feature = "test-util")]
+impl Node {
+    fn directory() -> Self {
+        Node::Directory {
+            modified: SystemTime::now(),
+        }
+    }
+
+    fn kind(&self) -> EntryKind {
+        match self {
+            Node::File { .. } => EntryKind::File,
+            Node::Directory { .. } => EntryKind::Directory,
+        }
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl MemoryFiles {
+    fn node(&self, path: &Path) -> io::Result<&Node> {
+        self.nodes
+            .get(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} doesn't exist", path.display())))
+    }
+
+    /// Entries directly inside a directory
+    fn children<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = (&'a PathBuf, &'a Node)> + 'a {
+        self.nodes
+            .range(path.to_path_buf()..)
+            .skip(1)
+            .take_while(move |(other, _)| other.starts_with(path))
+            .filter(move |(other, _)| other.parent() == Some(path))
+    }
+
+    /// Add a file or directory, whose parent directory must exist, as it would on disk
+    fn insert(&mut self, path: &Path, node: Node) -> io::Result<()> {
+        let parent = path.parent().unwrap_or(path);
+
+        match self.node(parent)? {
+            Node::Directory { .. } => {}
+            Node::File { .. } => return Err(not_a_directory(parent)),
+        }
+
+        if let Some(Node::Directory { .. }) = self.nodes.get(path) {
+            return Err(is_a_directory(path));
+        }
+
+        self.nodes.insert(path.to_path_buf(), node);
+        self.touch(parent);
+
+        Ok(())
+    }
+
+    /// Replace the contents of a file, keeping when it was created if it already exists
+    fn write(&mut self, path: &Path, contents: Vec<u8>) -> io::Result<()> {
+        let now = SystemTime::now();
+        let created = match self.nodes.get(path) {
+            Some(Node::File { created, .. }) => *created,
+            _ => now,
+        };
+
+        self.insert(
+            path,
+            Node::File {
+                contents,
+                created,
+                modified: now,
+            },
+        )
+    }
+
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        match self.nodes.get(path) {
+            Some(Node::Directory { .. }) => Ok(()),
+            Some(Node::File { .. }) => Err(not_a_directory(path)),
+            None => {
+                if let Some(parent) = path.parent() {
+                    self.create_dir_all(parent)?;
+                }
+
+                self.insert(path, Node::directory())
+            }
+        }
+    }
+
+    /// Remove a file or directory, along with everything inside it if `recursive`
+    fn remove(&mut self, path: &Path, recursive: bool) -> io::Result<()> {
+        if recursive {
+            self.nodes.retain(|other, _| !other.starts_with(path));
+        } else {
+            self.nodes.remove(path);
+        }
+
+        if let Some(parent) = path.parent() {
+            self.touch(parent);
+        }
+
+        Ok(())
+    }
+
+    /// Record that a directory was modified, e.g. because a file was added to it
+    fn touch(&mut self, path: &Path) {
+        if let Some(Node::Directory { modified }) = self.nodes.get_mut(path) {
+            *modified = SystemTime::now();
+        }
+    }
+}
+
+#[cfg(feature = "test-util")]
+fn is_a_directory(path: &Path) -> io::Error {
+    io::Error::other(format!("{} is a directory", path.display()))
+}
+
+#[cfg(feature = "test-util")]
+fn not_a_directory(path: &Path) -> io::Error {
+    io::Error::other(format!("{} isn't a directory", path.display()))
+}
+
+#[cfg(all(test, feature = "test-util"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_memory_files_behave_like_the_disk() {
+        let storage = Storage::memory();
+        let dir = Path::new("/gcloud/configurations");
+        let file = dir.join("config_foo");
+
+        assert_eq!(storage.read(&file).unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert!(storage.write(&file, "contents").is_err(), "the directory doesn't exist");
+
+        storage.create_dir_all(dir).unwrap();
+        storage.write(&file, "contents").unwrap();
+        storage.copy(&file, &dir.join("config_bar")).unwrap();
+        storage.rename(&file, &dir.join("config_baz")).unwrap();
+
+        let names: Vec<_> = storage
+            .read_dir(dir)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["config_bar", "config_baz"]);
+
+        assert!(storage.is_dir(dir));
+        assert!(storage.is_file(&dir.join("config_bar")));
+        assert_eq!(storage.read_to_string(&dir.join("config_baz")).unwrap(), "contents");
+        assert_eq!(storage.size(Path::new("/gcloud")), 16);
+
+        assert!(storage.remove_dir(dir).is_err(), "the directory isn't empty");
+        storage.remove_dir_all(dir).unwrap();
+        assert!(!storage.exists(&dir.join("config_bar")));
+        assert!(storage.is_dir(Path::new("/gcloud")));
+    }
+}
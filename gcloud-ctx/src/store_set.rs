@@ -0,0 +1,350 @@
+use crate::{Activation, Configuration, ConfigurationStore, ConflictResolver, Error, Properties, Result};
+use std::{fmt, path::PathBuf, str::FromStr};
+
+/// Address of a configuration within a [`StoreSet`], written as `root:name`, e.g. `work:my-config`
+///
+/// Neither root names nor configuration names can contain a `:`, so every address is unambiguous
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StoreAddress {
+    /// Name of the root containing the configuration
+    root: String,
+
+    /// Name of the configuration within the root
+    name: String,
+}
+
+impl StoreAddress {
+    /// Address of the named configuration in the given root
+    pub fn new(root: &str, name: &str) -> Result<Self> {
+        if !Configuration::is_valid_name(root) || !Configuration::is_valid_name(name) {
+            return Err(Error::InvalidAddress(format!("{}:{}", root, name)));
+        }
+
+        Ok(Self {
+            root: root.to_owned(),
+            name: name.to_owned(),
+        })
+    }
+
+    /// Name of the root containing the configuration
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Name of the configuration within the root
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for StoreAddress {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (root, name) = value
+            .split_once(':')
+            .ok_or_else(|| Error::InvalidAddress(value.to_owned()))?;
+
+        Self::new(root, name).map_err(|_| Error::InvalidAddress(value.to_owned()))
+    }
+}
+
+impl fmt::Display for StoreAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.root, self.name)
+    }
+}
+
+/// Several configuration stores opened side by side, e.g. the personal and work gcloud installations of a
+/// consultant, each under a short root name
+///
+/// Configurations are addressed as `root:name` with a [`StoreAddress`], and can be copied between roots
+///
+/// ```rust
+/// # let personal = tempfile::tempdir().unwrap();
+/// # let work = tempfile::tempdir().unwrap();
+/// # for tmp in [&personal, &work] {
+/// #     std::fs::create_dir(tmp.path().join("configurations")).unwrap();
+/// #     std::fs::write(tmp.path().join("configurations/config_default"), "").unwrap();
+/// #     std::fs::write(tmp.path().join("active_config"), "default").unwrap();
+/// # }
+/// # std::env::set_var("GCTX_STATE_DIR", personal.path().join("state"));
+/// use gcloud_ctx::{ConflictAction, StoreSet};
+///
+/// let mut stores = StoreSet::new();
+/// stores.open("personal", personal.path())?;
+/// stores.open("work", work.path())?;
+///
+/// stores.copy("personal:default", "work:from-personal", ConflictAction::Abort)?;
+/// stores.activate("work:from-personal")?;
+/// # Ok::<(), gcloud_ctx::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct StoreSet {
+    /// Stores by root name, in the order they were added
+    stores: Vec<(String, ConfigurationStore)>,
+}
+
+impl StoreSet {
+    /// Create an empty set of stores
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a store which is already open under the given root name
+    ///
+    /// Root names follow the same rules as configuration names, see [`Configuration::is_valid_name`]
+    pub fn add(&mut self, root: &str, store: ConfigurationStore) -> Result<()> {
+        if !Configuration::is_valid_name(root) {
+            return Err(Error::InvalidName(root.to_owned()));
+        }
+
+        if self.stores.iter().any(|(existing, _)| existing == root) {
+            return Err(Error::ExistingRoot(root.to_owned()));
+        }
+
+        self.stores.push((root.to_owned(), store));
+        Ok(())
+    }
+
+    /// Open the store at the given location, e.g. the value of `CLOUDSDK_CONFIG` for another gcloud installation,
+    /// and add it under the given root name
+    pub fn open(&mut self, root: &str, location: impl Into<PathBuf>) -> Result<()> {
+        let store = ConfigurationStore::with_location(location.into())?;
+        self.add(root, store)
+    }
+
+    /// Remove a root, returning its store
+    pub fn remove(&mut self, root: &str) -> Result<ConfigurationStore> {
+        let index = self
+            .stores
+            .iter()
+            .position(|(existing, _)| existing == root)
+            .ok_or_else(|| Error::UnknownRoot(root.to_owned()))?;
+
+        Ok(self.stores.remove(index).1)
+    }
+
+    /// Names of the roots, in the order they were added
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.stores.iter().map(|(root, _)| root.as_str())
+    }
+
+    /// Store with the given root name
+    pub fn store(&self, root: &str) -> Result<&ConfigurationStore> {
+        self.stores
+            .iter()
+            .find(|(existing, _)| existing == root)
+            .map(|(_, store)| store)
+            .ok_or_else(|| Error::UnknownRoot(root.to_owned()))
+    }
+
+    /// Store with the given root name, for making changes
+    pub fn store_mut(&mut self, root: &str) -> Result<&mut ConfigurationStore> {
+        self.stores
+            .iter_mut()
+            .find(|(existing, _)| existing == root)
+            .map(|(_, store)| store)
+            .ok_or_else(|| Error::UnknownRoot(root.to_owned()))
+    }
+
+    /// Every configuration in every root, with roots in the order they were added and configurations in natural order
+    pub fn configurations(&self) -> Result<Vec<(StoreAddress, &Configuration)>> {
+        let mut configurations = Vec::new();
+
+        for (root, store) in &self.stores {
+            for configuration in store.configurations()? {
+                let address = StoreAddress {
+                    root: root.clone(),
+                    name: configuration.name().to_owned(),
+                };
+
+                configurations.push((address, configuration));
+            }
+        }
+
+        Ok(configurations)
+    }
+
+    /// Find a configuration by its `root:name` address
+    pub fn find(&self, address: &str) -> Result<&Configuration> {
+        let address: StoreAddress = address.parse()?;
+
+        self.store(&address.root)?
+            .find_by_name(&address.name)?
+            .ok_or_else(|| Error::UnknownConfiguration(address.to_string()))
+    }
+
+    /// Get the properties of a configuration by its `root:name` address
+    pub fn describe(&self, address: &str) -> Result<Properties> {
+        let address: StoreAddress = address.parse()?;
+        self.store(&address.root)?.describe(&address.name)
+    }
+
+    /// Activate a configuration by its `root:name` address, within its own root
+    ///
+    /// Each root has its own active configuration, so activating a configuration in one root leaves the others as
+    /// they are
+    pub fn activate(&mut self, address: &str) -> Result<Activation> {
+        let address: StoreAddress = address.parse()?;
+        self.store_mut(&address.root)?.activate(&address.name)
+    }
+
+    /// Copy a configuration from one address to another, which may be in a different root
+    ///
+    /// The contents are copied as-is, so properties which aren't directly supported are preserved. Returns the
+    /// address of the copy, whose name differs from `to` if the conflict was resolved by renaming, or `None` if the
+    /// copy was skipped
+    pub fn copy<C: ConflictResolver>(&mut self, from: &str, to: &str, conflict: C) -> Result<Option<StoreAddress>> {
+        let from: StoreAddress = from.parse()?;
+        let to: StoreAddress = to.parse()?;
+
+        let copy = if from.root == to.root {
+            self.store_mut(&from.root)?.copy(&from.name, &to.name, conflict)?
+        } else {
+            let mut contents = Vec::new();
+            self.store(&from.root)?.export(&from.name, &mut contents)?;
+            self.store_mut(&to.root)?
+                .import(&to.name, contents.as_slice(), conflict)?
+        };
+
+        Ok(copy.map(|configuration| StoreAddress {
+            root: to.root,
+            name: configuration.name().to_owned(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{configuration::tests::open_store, ConflictAction};
+    use std::fs;
+
+    fn temp_store(configurations: &[(&str, &str)]) -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::create_dir(tmp.path().join("configurations")).unwrap();
+
+        for (name, contents) in configurations {
+            let path = tmp.path().join("configurations").join(format!("config_{}", name));
+            fs::write(path, contents).unwrap();
+        }
+
+        fs::write(tmp.path().join("active_config"), configurations[0].0).unwrap();
+        tmp
+    }
+
+    fn store_set(personal: &tempfile::TempDir, work: &tempfile::TempDir) -> StoreSet {
+        let mut stores = StoreSet::new();
+        stores
+            .add("personal", open_store(personal.path().to_path_buf()).unwrap())
+            .unwrap();
+        stores
+            .add("work", open_store(work.path().to_path_buf()).unwrap())
+            .unwrap();
+        stores
+    }
+
+    #[test]
+    pub fn test_parse_address() {
+        let address: StoreAddress = "work:my-config".parse().unwrap();
+        assert_eq!(address.root(), "work");
+        assert_eq!(address.name(), "my-config");
+        assert_eq!(address.to_string(), "work:my-config");
+
+        for invalid in ["", "work", ":my-config", "work:", "work:my:config", "Work:my-config"] {
+            assert!(
+                matches!(invalid.parse::<StoreAddress>(), Err(Error::InvalidAddress(_))),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_add_rejects_duplicate_and_invalid_roots() {
+        let personal = temp_store(&[("foo", "")]);
+        let work = temp_store(&[("bar", "")]);
+        let mut stores = store_set(&personal, &work);
+
+        let duplicate = open_store(work.path().to_path_buf()).unwrap();
+        assert!(matches!(stores.add("work", duplicate), Err(Error::ExistingRoot(_))));
+
+        let invalid = open_store(work.path().to_path_buf()).unwrap();
+        assert!(matches!(stores.add("a:b", invalid), Err(Error::InvalidName(_))));
+
+        assert_eq!(stores.roots().collect::<Vec<_>>(), vec!["personal", "work"]);
+    }
+
+    #[test]
+    pub fn test_configurations_are_addressed_by_root() {
+        let personal = temp_store(&[("foo", "[core]\nproject = personal-project\n")]);
+        let work = temp_store(&[("foo", "[core]\nproject = work-project\n"), ("bar", "")]);
+        let stores = store_set(&personal, &work);
+
+        let addresses: Vec<String> = stores
+            .configurations()
+            .unwrap()
+            .into_iter()
+            .map(|(address, _)| address.to_string())
+            .collect();
+        assert_eq!(addresses, vec!["personal:foo", "work:bar", "work:foo"]);
+
+        let properties = stores.describe("work:foo").unwrap();
+        assert_eq!(properties.get("core", "project"), Some("work-project"));
+
+        assert!(matches!(stores.find("home:foo"), Err(Error::UnknownRoot(_))));
+        assert!(matches!(
+            stores.find("personal:bar"),
+            Err(Error::UnknownConfiguration(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_activate_only_changes_its_root() {
+        let personal = temp_store(&[("foo", ""), ("bar", "")]);
+        let work = temp_store(&[("foo", ""), ("bar", "")]);
+        let mut stores = store_set(&personal, &work);
+
+        stores.activate("work:bar").unwrap();
+
+        assert_eq!(stores.store("work").unwrap().active(), Some("bar"));
+        assert_eq!(stores.store("personal").unwrap().active(), Some("foo"));
+    }
+
+    #[test]
+    pub fn test_copy_between_roots_preserves_contents() {
+        let contents = "# team settings\n[core]\nproject = my-project\n\n[custom]\nkey = value\n";
+        let personal = temp_store(&[("foo", contents)]);
+        let work = temp_store(&[("bar", "")]);
+        let mut stores = store_set(&personal, &work);
+
+        let copy = stores
+            .copy("personal:foo", "work:from-personal", ConflictAction::Abort)
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.to_string(), "work:from-personal");
+
+        let copied = fs::read_to_string(work.path().join("configurations/config_from-personal")).unwrap();
+        assert_eq!(copied, contents);
+
+        let result = stores.copy("personal:foo", "work:from-personal", ConflictAction::Abort);
+        assert!(matches!(result, Err(Error::ExistingConfiguration(_))));
+    }
+
+    #[test]
+    pub fn test_copy_within_root() {
+        let personal = temp_store(&[("foo", "[core]\nproject = my-project\n")]);
+        let work = temp_store(&[("bar", "")]);
+        let mut stores = store_set(&personal, &work);
+
+        let copy = stores
+            .copy("personal:foo", "personal:baz", ConflictAction::Abort)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(copy.to_string(), "personal:baz");
+        assert!(stores.find("personal:baz").is_ok());
+        assert!(matches!(stores.find("work:baz"), Err(Error::UnknownConfiguration(_))));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{Error, Properties};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Properties whose values contain `{{placeholders}}`, used to stamp out consistent configurations, e.g. one per
+/// environment of a project
+///
+/// ```rust
+/// use gcloud_ctx::Template;
+/// use std::collections::BTreeMap;
+///
+/// let template: Template = "[core]\nproject = my-app-{{env}}\n".parse::<gcloud_ctx::Properties>()?.into();
+/// assert_eq!(template.placeholders(), ["env"].into());
+///
+/// let vars = BTreeMap::from([("env".to_owned(), "prod".to_owned())]);
+/// let properties = template.render(&vars)?;
+/// assert_eq!(properties.get("core", "project"), Some("my-app-prod"));
+/// # Ok::<(), gcloud_ctx::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Template {
+    /// Properties with placeholders in their values
+    properties: Properties,
+}
+
+impl Template {
+    /// Template from properties with placeholders in their values
+    pub fn new(properties: Properties) -> Self {
+        Self { properties }
+    }
+
+    /// Properties of the template, with the placeholders left in
+    pub fn properties(&self) -> &Properties {
+        &self.properties
+    }
+
+    /// Names of the placeholders used by the template
+    pub fn placeholders(&self) -> BTreeSet<&str> {
+        self.properties
+            .iter()
+            .flat_map(|(_, _, value)| Placeholders(value))
+            .filter_map(|segment| match segment {
+                Segment::Placeholder(name) => Some(name),
+                Segment::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// Replace every placeholder with its variable, keeping the order of the sections and properties
+    ///
+    /// Fails with [`Error::MissingTemplateVariable`] if a placeholder has no variable. Variables which aren't used by
+    /// any placeholder are ignored
+    pub fn render(&self, vars: &BTreeMap<String, String>) -> Result<Properties, Error> {
+        let mut properties = self.properties.clone();
+
+        let rendered = self
+            .properties
+            .iter()
+            .filter(|(_, _, value)| value.contains("{{"))
+            .map(|(section, key, value)| Ok((section, key, render(value, vars)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for (section, key, value) in rendered {
+            properties.set(section, key, &value);
+        }
+
+        Ok(properties)
+    }
+}
+
+impl From<Properties> for Template {
+    fn from(properties: Properties) -> Self {
+        Self::new(properties)
+    }
+}
+
+/// Replace the placeholders in a single value
+fn render(value: &str, vars: &BTreeMap<String, String>) -> Result<String, Error> {
+    Placeholders(value)
+        .map(|segment| match segment {
+            Segment::Text(text) => Ok(text),
+            Segment::Placeholder(name) => vars
+                .get(name)
+                .map(String::as_str)
+                .ok_or_else(|| Error::MissingTemplateVariable(name.to_owned())),
+        })
+        .collect()
+}
+
+/// Part of a value, either literal text or the name of a placeholder
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits a value into literal text and placeholders
+///
+/// Placeholders are `{{name}}`, optionally with spaces around the name, where the name is ASCII letters, digits,
+/// underscores or hyphens. Anything else, e.g. an unclosed `{{`, is literal text
+struct Placeholders<'a>(&'a str);
+
+impl<'a> Iterator for Placeholders<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let mut search = 0;
+
+        while let Some(start) = self.0[search..].find("{{").map(|i| i + search) {
+            let placeholder = self.0[start + 2..]
+                .find("}}")
+                .map(|end| (self.0[start + 2..start + 2 + end].trim(), start + 4 + end))
+                .filter(|(name, _)| is_placeholder_name(name));
+
+            match placeholder {
+                Some(_) if start > 0 => {
+                    let (text, rest) = self.0.split_at(start);
+                    self.0 = rest;
+                    return Some(Segment::Text(text));
+                }
+                Some((name, end)) => {
+                    self.0 = &self.0[end..];
+                    return Some(Segment::Placeholder(name));
+                }
+                None => search = start + 1,
+            }
+        }
+
+        let text = self.0;
+        self.0 = "";
+        Some(Segment::Text(text))
+    }
+}
+
+/// Is this a valid placeholder name?
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyOrder, LineEnding, WriteOptions};
+
+    fn vars(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+        vars.iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    pub fn test_placeholders_split_values() {
+        let segments: Vec<_> = Placeholders("{{a}}-{{ b }}-{{}}-{{c").collect();
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment::Placeholder("a"),
+                Segment::Text("-"),
+                Segment::Placeholder("b"),
+                Segment::Text("-{{}}-{{c"),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_render_keeps_order() {
+        let properties: Properties = "[core]\nproject = {{project}}-{{env}}\naccount = me@example.org\n\n\
+                                      [compute]\nregion = {{region}}\n"
+            .parse()
+            .unwrap();
+        let template = Template::new(properties);
+
+        assert_eq!(template.placeholders(), ["env", "project", "region"].into());
+
+        let rendered = template
+            .render(&vars(&[
+                ("project", "my-app"),
+                ("env", "dev"),
+                ("region", "europe-west1"),
+                ("unused", "ignored"),
+            ]))
+            .unwrap();
+
+        let mut output = Vec::new();
+        rendered
+            .to_writer_with_options(
+                &mut output,
+                WriteOptions::default()
+                    .line_ending(LineEnding::Lf)
+                    .key_order(KeyOrder::Preserve),
+            )
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[core]\nproject=my-app-dev\naccount=me@example.org\n[compute]\nregion=europe-west1\n"
+        );
+    }
+
+    #[test]
+    pub fn test_render_missing_variable_fails() {
+        let template = Template::new("[core]\nproject = {{project}}\n".parse().unwrap());
+
+        assert!(matches!(
+            template.render(&vars(&[])),
+            Err(Error::MissingTemplateVariable(name)) if name == "project"
+        ));
+    }
+}
@@ -0,0 +1,154 @@
+//! In-memory configuration stores for unit testing code which uses gcloud-ctx
+//!
+//! Requires the `test-util` feature. Each store, along with the files gcloud-ctx keeps about it, is held entirely in
+//! memory and is gone when the store is dropped. Tests never touch the disk, so they don't need temporary directories,
+//! can't see the real configuration store or state directory and don't need to set `CLOUDSDK_CONFIG`
+//!
+//! ```rust
+//! use gcloud_ctx::{test_util::TestStoreBuilder, PropertiesBuilder};
+//!
+//! let properties = PropertiesBuilder::default().project(&"my-project".parse()?).build();
+//!
+//! let mut store = TestStoreBuilder::default()
+//!     .with_configuration("foo", &properties)
+//!     .with_active_configuration("bar", &properties)
+//!     .build()?;
+//!
+//! store.activate("foo")?;
+//! assert_eq!(store.active(), Some("foo"));
+//! # Ok::<(), gcloud_ctx::Error>(())
+//! ```
+
+use crate::{storage::Storage, ConfigurationStore, FileContext, FileOperation, Properties, Result};
+use std::path::Path;
+
+/// Location of every test store, which only exists in the memory of that store
+const LOCATION: &str = "/gcloud";
+
+/// Location of the state directory of every test store
+const STATE_LOCATION: &str = "/gctx";
+
+#[derive(Debug, Default)]
+/// Builds an in-memory [`ConfigurationStore`] containing the given configurations
+pub struct TestStoreBuilder {
+    /// Configurations to create, with their serialised properties
+    configurations: Vec<(String, Vec<u8>)>,
+
+    /// Name of the active configuration
+    active: Option<String>,
+}
+
+impl TestStoreBuilder {
+    /// Add a configuration with the given properties
+    pub fn with_configuration(&mut self, name: &str, properties: &Properties) -> &mut Self {
+        let mut contents = Vec::new();
+        properties
+            .to_writer(&mut contents)
+            .expect("writing properties to memory can't fail");
+
+        self.configurations.push((name.to_owned(), contents));
+        self
+    }
+
+    /// Add a configuration with the given properties and make it the active one
+    pub fn with_active_configuration(&mut self, name: &str, properties: &Properties) -> &mut Self {
+        self.active = Some(name.to_owned());
+        self.with_configuration(name, properties)
+    }
+
+    /// Build the store
+    ///
+    /// If no configurations were added then the store contains an empty `default` configuration, just like
+    /// a freshly installed gcloud, and if no configuration was made active then the first one is active. Every store
+    /// has its own files, so changes made through one store are never seen by another
+    pub fn build(&self) -> Result<ConfigurationStore> {
+        let storage = Storage::memory();
+        let location = Path::new(LOCATION);
+        let configurations_path = location.join("configurations");
+        storage
+            .create_dir_all(&configurations_path)
+            .file_context(FileOperation::Create, &configurations_path)?;
+
+        let default = [("default".to_owned(), Vec::new())];
+        let configurations = if self.configurations.is_empty() {
+            &default[..]
+        } else {
+            &self.configurations[..]
+        };
+
+        for (name, contents) in configurations {
+            storage.write(&configurations_path.join(format!("config_{}", name)), contents)?;
+        }
+
+        let active = self.active.as_ref().unwrap_or(&configurations[0].0);
+        storage.write(&location.join("active_config"), active)?;
+
+        ConfigurationStore::open(
+            storage,
+            location.to_path_buf(),
+            false,
+            false,
+            Some(STATE_LOCATION.into()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_empty_builder_has_default_configuration() {
+        let store = TestStoreBuilder::default().build().unwrap();
+
+        assert_eq!(store.active(), Some("default"));
+        assert_eq!(store.configurations().unwrap().len(), 1);
+    }
+
+    #[test]
+    pub fn test_builder_writes_configurations() {
+        let mut properties = Properties::default();
+        properties.set("core", "project", "my-project");
+
+        let store = TestStoreBuilder::default()
+            .with_configuration("foo", &properties)
+            .with_configuration("bar", &Properties::default())
+            .build()
+            .unwrap();
+
+        assert_eq!(store.active(), Some("foo"));
+        assert_eq!(
+            store.describe("foo").unwrap().get("core", "project"),
+            Some("my-project")
+        );
+    }
+
+    #[test]
+    pub fn test_store_never_touches_the_disk() {
+        let mut store = TestStoreBuilder::default()
+            .with_configuration("foo", &Properties::default())
+            .build()
+            .unwrap();
+
+        store.copy("foo", "bar", crate::ConflictAction::Abort).unwrap();
+        store.activate("bar").unwrap();
+        store.delete("foo").unwrap();
+
+        assert_eq!(store.history().unwrap()[0].name(), "bar");
+        assert_eq!(store.list_trashed().unwrap()[0].name(), "foo");
+        assert!(!store.location().exists());
+        assert!(!store.state_location().exists());
+    }
+
+    #[test]
+    pub fn test_stores_are_independent() {
+        let builder = TestStoreBuilder::default();
+        let mut first = builder.build().unwrap();
+        let second = builder.build().unwrap();
+
+        first.rename("default", "foo", crate::ConflictAction::Abort).unwrap();
+
+        assert!(first.find_by_name("default").unwrap().is_none());
+        assert!(second.find_by_name("default").unwrap().is_some());
+    }
+}
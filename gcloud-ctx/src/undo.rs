@@ -0,0 +1,182 @@
+use crate::{storage::Storage, Backup, FileContext, FileOperation, Result};
+use std::path::{Path, PathBuf};
+
+/// Destructive operation which can be undone, see [`ConfigurationStore::undo_last`](crate::ConfigurationStore::undo_last)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UndoableOperation {
+    /// Configurations were overwritten, e.g. by creating, copying, importing or renaming with
+    /// [`ConflictAction::Overwrite`](crate::ConflictAction::Overwrite)
+    Overwritten(Vec<String>),
+
+    /// Configurations were deleted
+    Deleted(Vec<String>),
+
+    /// Configurations were updated or pruned by
+    /// [`ConfigurationStore::apply`](crate::ConfigurationStore::apply)
+    Applied(Vec<String>),
+}
+
+/// Journal of the last destructive operation, kept in the state directory of a store so that it can be undone
+///
+/// The journal refers to the backups of everything the operation overwrote or deleted, e.g.
+///
+/// ```text
+/// overwritten bar
+/// active foo
+/// renamed foo bar
+/// restore bar 1700000000123
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Journal {
+    /// The operation
+    pub(crate) operation: UndoableOperation,
+
+    /// Active configuration before the operation, if the operation changed it
+    pub(crate) active: Option<String>,
+
+    /// Original and new name of a configuration which the operation renamed, which is renamed back first
+    pub(crate) renamed: Option<(String, String)>,
+
+    /// Configurations to restore and the backups to restore them from
+    pub(crate) restore: Vec<(String, PathBuf)>,
+}
+
+impl Journal {
+    /// Journal for an operation which made the given backups before overwriting or deleting configurations
+    pub(crate) fn new(operation: UndoableOperation, backups: impl IntoIterator<Item = Backup>) -> Self {
+        Self {
+            operation,
+            active: None,
+            renamed: None,
+            restore: backups
+                .into_iter()
+                .map(|backup| (backup.name().to_owned(), backup.path().to_path_buf()))
+                .collect(),
+        }
+    }
+
+    /// Path to the journal within the state directory of a store
+    pub(crate) fn file(state: &Path) -> PathBuf {
+        state.join("undo")
+    }
+
+    /// Read the journal from the state directory of a store, if there is one
+    pub(crate) fn read(storage: &Storage, state: &Path) -> Result<Option<Self>> {
+        let path = Self::file(state);
+
+        let contents = match storage.read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).file_context(FileOperation::Read, &path),
+        };
+
+        let mut lines = contents.lines();
+        let names = |rest: &str| rest.split(' ').map(str::to_owned).collect();
+
+        let operation = match lines.next().and_then(|line| line.split_once(' ')) {
+            Some(("overwritten", rest)) => UndoableOperation::Overwritten(names(rest)),
+            Some(("deleted", rest)) => UndoableOperation::Deleted(names(rest)),
+            Some(("applied", rest)) => UndoableOperation::Applied(names(rest)),
+            _ => return Ok(None), // written by a newer version, so don't guess what it means
+        };
+
+        let mut journal = Self {
+            operation,
+            active: None,
+            renamed: None,
+            restore: Vec::new(),
+        };
+
+        for line in lines {
+            match line.split(' ').collect::<Vec<_>>().as_slice() {
+                ["active", name] => journal.active = Some((*name).to_owned()),
+                ["renamed", from, to] => journal.renamed = Some(((*from).to_owned(), (*to).to_owned())),
+                ["restore", name, backup] => journal
+                    .restore
+                    .push(((*name).to_owned(), Backup::directory(state, name).join(backup))),
+                _ => return Ok(None),
+            }
+        }
+
+        Ok(Some(journal))
+    }
+
+    /// Write the journal to the state directory of a store, replacing any earlier one
+    pub(crate) fn write(&self, storage: &Storage, state: &Path) -> Result<()> {
+        let mut contents = match &self.operation {
+            UndoableOperation::Overwritten(names) => format!("overwritten {}\n", names.join(" ")),
+            UndoableOperation::Deleted(names) => format!("deleted {}\n", names.join(" ")),
+            UndoableOperation::Applied(names) => format!("applied {}\n", names.join(" ")),
+        };
+
+        if let Some(active) = &self.active {
+            contents.push_str(&format!("active {}\n", active));
+        }
+
+        if let Some((from, to)) = &self.renamed {
+            contents.push_str(&format!("renamed {} {}\n", from, to));
+        }
+
+        for (name, backup) in &self.restore {
+            let file_name = backup.file_name().unwrap_or_default().to_string_lossy();
+            contents.push_str(&format!("restore {} {}\n", name, file_name));
+        }
+
+        let path = Self::file(state);
+
+        if let Some(parent) = path.parent() {
+            storage
+                .create_dir_all(parent)
+                .file_context(FileOperation::Create, parent)?;
+        }
+
+        storage.write(&path, contents)
+    }
+
+    /// Remove the journal from the state directory of a store, once it's been undone
+    pub(crate) fn clear(storage: &Storage, state: &Path) -> Result<()> {
+        let path = Self::file(state);
+
+        match storage.remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e).file_context(FileOperation::Delete, &path),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    pub fn test_journal_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = Journal {
+            operation: UndoableOperation::Deleted(vec!["foo".to_owned(), "bar".to_owned()]),
+            active: Some("foo".to_owned()),
+            renamed: Some(("baz".to_owned(), "qux".to_owned())),
+            restore: vec![
+                ("foo".to_owned(), Backup::directory(tmp.path(), "foo").join("1")),
+                ("bar".to_owned(), Backup::directory(tmp.path(), "bar").join("2")),
+            ],
+        };
+
+        assert_eq!(Journal::read(&Storage::Disk, tmp.path()).unwrap(), None);
+
+        journal.write(&Storage::Disk, tmp.path()).unwrap();
+        assert_eq!(Journal::read(&Storage::Disk, tmp.path()).unwrap(), Some(journal));
+
+        Journal::clear(&Storage::Disk, tmp.path()).unwrap();
+        assert_eq!(Journal::read(&Storage::Disk, tmp.path()).unwrap(), None);
+    }
+
+    #[test]
+    pub fn test_unknown_journal_is_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(Journal::file(tmp.path()), "moved foo\n").unwrap();
+
+        assert_eq!(Journal::read(&Storage::Disk, tmp.path()).unwrap(), None);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{patterns, Error, Result, Warning};
+use std::{fmt, str::FromStr};
+
+/// How strictly zones and regions are checked, see [`PropertiesBuilder::validate`](crate::PropertiesBuilder::validate)
+///
+/// Zones and regions are checked against the pattern of the existing ones, e.g. `europe-west1-d`, which may not
+/// anticipate new locations. Relax the validation if Google ships a location which doesn't match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LocationValidation {
+    /// Locations which don't match are an error
+    Strict,
+
+    /// Locations which don't match are a warning
+    #[default]
+    Relaxed,
+
+    /// Locations aren't checked
+    Off,
+}
+
+impl LocationValidation {
+    /// Check a zone, returning a warning if it doesn't match and validation is relaxed
+    pub fn check_zone(self, zone: &str) -> Result<Option<Warning>> {
+        self.check(
+            patterns::zone(zone),
+            || Error::InvalidZone(zone.to_owned()),
+            || Warning::UnrecognisedZone(zone.to_owned()),
+        )
+    }
+
+    /// Check a region, returning a warning if it doesn't match and validation is relaxed
+    pub fn check_region(self, region: &str) -> Result<Option<Warning>> {
+        self.check(
+            patterns::region(region),
+            || Error::InvalidRegion(region.to_owned()),
+            || Warning::UnrecognisedRegion(region.to_owned()),
+        )
+    }
+
+    /// Turn the result of a check into an error or warning depending on the validation mode
+    fn check(
+        self,
+        valid: bool,
+        error: impl FnOnce() -> Error,
+        warning: impl FnOnce() -> Warning,
+    ) -> Result<Option<Warning>> {
+        match self {
+            _ if valid => Ok(None),
+            LocationValidation::Strict => Err(error()),
+            LocationValidation::Relaxed => Ok(Some(warning())),
+            LocationValidation::Off => Ok(None),
+        }
+    }
+}
+
+impl FromStr for LocationValidation {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "strict" => Ok(LocationValidation::Strict),
+            "relaxed" => Ok(LocationValidation::Relaxed),
+            "off" => Ok(LocationValidation::Off),
+            other => Err(Error::InvalidLocationValidation(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// An account which gcloud authenticates as, i.e. a user's email address or a service account such as
+/// `sa@my-project.iam.gserviceaccount.com`
+pub struct Account(String);
+
+impl Account {
+    /// The account
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the account is a service account rather than a user
+    pub fn is_service_account(&self) -> bool {
+        self.0.ends_with(".gserviceaccount.com")
+    }
+}
+
+impl FromStr for Account {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if !patterns::account(value) {
+            return Err(Error::InvalidAccount(value.to_owned()));
+        }
+
+        Ok(Account(value.to_owned()))
+    }
+}
+
+impl fmt::Display for Account {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Account> for String {
+    fn from(value: Account) -> Self {
+        value.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A GCP region, e.g. `europe-west1`
+pub struct Region(String);
+
+impl Region {
+    /// The region
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Region {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        LocationValidation::Strict.check_region(value)?;
+        Ok(Region(value.to_owned()))
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A GCP zone, e.g. `europe-west1-d`
+pub struct Zone(String);
+
+impl Zone {
+    /// The zone
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Region which contains the zone, e.g. `europe-west1` for `europe-west1-d`
+    pub fn region(&self) -> Region {
+        let (region, _) = self.0.rsplit_once('-').expect("zones always have a suffix");
+        Region(region.to_owned())
+    }
+}
+
+impl FromStr for Zone {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        LocationValidation::Strict.check_zone(value)?;
+        Ok(Zone(value.to_owned()))
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A GCP project ID, e.g. `my-project`
+///
+/// Project IDs are 6 to 30 lowercase ASCII letters, digits or hyphens. They must start with a letter and can't end
+/// with a hyphen
+pub struct ProjectId(String);
+
+impl ProjectId {
+    /// The project ID
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ProjectId {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        if !patterns::project_id(value) {
+            return Err(Error::InvalidProjectId(value.to_owned()));
+        }
+
+        Ok(ProjectId(value.to_owned()))
+    }
+}
+
+impl fmt::Display for ProjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<ProjectId> for String {
+    fn from(value: ProjectId) -> Self {
+        value.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_location_validation() {
+        for mode in [
+            LocationValidation::Strict,
+            LocationValidation::Relaxed,
+            LocationValidation::Off,
+        ] {
+            assert_eq!(mode.check_zone("europe-west1-d").unwrap(), None);
+            assert_eq!(mode.check_zone("northamerica-northeast2-a").unwrap(), None);
+            assert_eq!(mode.check_region("us-central1").unwrap(), None);
+        }
+
+        assert!(matches!(
+            LocationValidation::Strict.check_zone("europe-west1"),
+            Err(Error::InvalidZone(_))
+        ));
+        assert!(matches!(
+            LocationValidation::Strict.check_region("europe-west1-d"),
+            Err(Error::InvalidRegion(_))
+        ));
+        assert_eq!(
+            LocationValidation::Relaxed.check_zone("mars-north1").unwrap(),
+            Some(Warning::UnrecognisedZone("mars-north1".to_owned()))
+        );
+        assert_eq!(LocationValidation::Off.check_region("anything").unwrap(), None);
+        assert_eq!("off".parse::<LocationValidation>().unwrap(), LocationValidation::Off);
+    }
+
+    #[test]
+    pub fn test_zone_region() {
+        let zone: Zone = "northamerica-northeast1-a".parse().unwrap();
+        assert_eq!(zone.region(), "northamerica-northeast1".parse().unwrap());
+
+        assert!(matches!("europe-west1".parse::<Zone>(), Err(Error::InvalidZone(_))));
+        assert!(matches!(
+            "europe-west1-d".parse::<Region>(),
+            Err(Error::InvalidRegion(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_valid_accounts() {
+        let user: Account = "a.user@example.org".parse().unwrap();
+        assert_eq!(user.as_str(), "a.user@example.org");
+        assert!(!user.is_service_account());
+
+        let service_account: Account = "sa@my-project.iam.gserviceaccount.com".parse().unwrap();
+        assert!(service_account.is_service_account());
+    }
+
+    #[test]
+    pub fn test_invalid_accounts() {
+        for account in [
+            "",
+            "a.user",
+            "a.user@",
+            "@example.org",
+            "a.user@example",
+            "a user@example.org",
+            "a@b@c.org",
+        ] {
+            let result = account.parse::<Account>();
+            assert!(
+                matches!(result, Err(Error::InvalidAccount(_))),
+                "{} should be invalid",
+                account
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_valid_project_ids() {
+        for id in [
+            "my-project",
+            "abcdef",
+            "a1-b2-c3",
+            "example.com:my-project",
+            "a23456789012345678901234567890",
+        ] {
+            assert_eq!(id.parse::<ProjectId>().unwrap().as_str(), id);
+        }
+    }
+
+    #[test]
+    pub fn test_invalid_project_ids() {
+        for id in [
+            "",
+            "short",
+            "a234567890123456789012345678901",
+            "1-project",
+            "-project",
+            "my-project-",
+            "My-Project",
+            "my_project",
+            "my project",
+        ] {
+            let result = id.parse::<ProjectId>();
+            assert!(
+                matches!(result, Err(Error::InvalidProjectId(_))),
+                "{} should be invalid",
+                id
+            );
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{storage::Storage, ConfigurationStore, Result, Scan};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+/// Change to a configuration store, see [`ConfigurationStore::watch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// A configuration was added, e.g. by `gcloud config configurations create`
+    Added(String),
+
+    /// A configuration was removed
+    Removed(String),
+
+    /// A different configuration was activated
+    Activated(String),
+
+    /// No configuration is active any more
+    Deactivated,
+}
+
+/// Watches a configuration store for changes made by any process, e.g. gcloud itself
+///
+/// Events are found by comparing the store before and after each change on disk, so a burst of file system activity,
+/// e.g. an atomic write, only produces the events which actually changed the store. Iterating blocks until the next
+/// event, see [`StoreWatcher::next_timeout`] to wait for a limited time instead
+pub struct StoreWatcher {
+    /// File system watcher, which stops watching when it's dropped
+    _watcher: RecommendedWatcher,
+
+    /// Notifications from the file system watcher
+    changes: Receiver<notify::Result<notify::Event>>,
+
+    /// Location of the configuration store on disk
+    location: PathBuf,
+
+    /// Path to the configurations sub-folder
+    configurations_path: PathBuf,
+
+    /// Names of the configurations when the store was last compared
+    names: BTreeSet<String>,
+
+    /// Name of the active configuration when the store was last compared
+    active: Option<String>,
+
+    /// Events which have been found but not returned yet
+    pending: VecDeque<WatchEvent>,
+}
+
+impl StoreWatcher {
+    /// Start watching the given store
+    pub(crate) fn new(store: &ConfigurationStore) -> Result<Self> {
+        let location = store.location().to_path_buf();
+        let configurations_path = location.join("configurations");
+
+        let (sender, changes) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&location, RecursiveMode::NonRecursive)?;
+        watcher.watch(&configurations_path, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+            location,
+            configurations_path,
+            names: store.configurations()?.map(|c| c.name().to_owned()).collect(),
+            active: store.active().map(str::to_owned),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Wait up to `timeout` for the next change to the store, returning `None` if there wasn't one
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<WatchEvent> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            // errors from the watcher, e.g. a full event queue, still mean the store may have changed
+            let _ = self.changes.recv_timeout(remaining).ok()?;
+            self.compare();
+        }
+    }
+
+    /// Compare the store with how it was last time, queueing an event for each difference
+    fn compare(&mut self) {
+        // any other queued notifications are covered by the same comparison
+        while self.changes.try_recv().is_ok() {}
+
+        // a directory which can't be read is most likely being modified, so wait for the next notification
+        if let Ok(scan) = Scan::read(&Storage::Disk, &self.configurations_path) {
+            let names = scan.names();
+
+            self.pending
+                .extend(names.difference(&self.names).cloned().map(WatchEvent::Added));
+            self.pending
+                .extend(self.names.difference(&names).cloned().map(WatchEvent::Removed));
+
+            self.names = names;
+        }
+
+        // tools which don't write atomically leave the file empty for a moment
+        let active = match ConfigurationStore::read_active(&Storage::Disk, &self.location) {
+            Ok(active) if !active.is_empty() => ConfigurationStore::parse_active(active),
+            _ => return,
+        };
+
+        if active != self.active {
+            self.pending.push_back(match &active {
+                Some(name) => WatchEvent::Activated(name.clone()),
+                None => WatchEvent::Deactivated,
+            });
+            self.active = active;
+        }
+    }
+}
+
+impl Iterator for StoreWatcher {
+    type Item = WatchEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let _ = self.changes.recv().ok()?;
+            self.compare();
+        }
+    }
+}
+
+impl fmt::Debug for StoreWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreWatcher")
+            .field("location", &self.location)
+            .field("names", &self.names)
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::tests::open_store;
+    use std::fs;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    pub fn test_watch_reports_added_removed_and_activated() {
+        let tmp = tempfile::tempdir().unwrap();
+        let configurations = tmp.path().join("configurations");
+        fs::create_dir(&configurations).unwrap();
+        fs::write(configurations.join("config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let mut watcher = store.watch().unwrap();
+
+        fs::write(configurations.join("config_bar"), "").unwrap();
+        assert_eq!(watcher.next_timeout(TIMEOUT), Some(WatchEvent::Added("bar".to_owned())));
+
+        fs::write(tmp.path().join("active_config"), "bar").unwrap();
+        assert_eq!(
+            watcher.next_timeout(TIMEOUT),
+            Some(WatchEvent::Activated("bar".to_owned()))
+        );
+
+        fs::remove_file(configurations.join("config_foo")).unwrap();
+        assert_eq!(
+            watcher.next_timeout(TIMEOUT),
+            Some(WatchEvent::Removed("foo".to_owned()))
+        );
+
+        fs::write(tmp.path().join("active_config"), "NONE").unwrap();
+        assert_eq!(watcher.next_timeout(TIMEOUT), Some(WatchEvent::Deactivated));
+    }
+
+    #[test]
+    pub fn test_watch_ignores_unrelated_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let configurations = tmp.path().join("configurations");
+        fs::create_dir(&configurations).unwrap();
+        fs::write(configurations.join("config_foo"), "").unwrap();
+        fs::write(tmp.path().join("active_config"), "foo").unwrap();
+
+        let store = open_store(tmp.path().to_path_buf()).unwrap();
+        let mut watcher = store.watch().unwrap();
+
+        fs::write(tmp.path().join("credentials.db"), "").unwrap();
+        fs::write(configurations.join("config_foo"), "[core]\nproject=changed\n").unwrap();
+
+        assert_eq!(watcher.next_timeout(Duration::from_millis(500)), None);
+    }
+}
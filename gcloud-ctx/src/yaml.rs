@@ -0,0 +1,50 @@
+use crate::{Properties, Result};
+use std::io::{Read, Write};
+
+impl Properties {
+    /// Deserialise properties from YAML, as a mapping of sections which are mappings of string properties
+    ///
+    /// ```yaml
+    /// core:
+    ///   project: my-project
+    /// compute:
+    ///   zone: europe-west1-d
+    /// ```
+    pub fn from_yaml<R: Read>(reader: R) -> Result<Self> {
+        Ok(serde_yaml_ng::from_reader(reader)?)
+    }
+
+    /// Serialise properties to YAML, with sections and properties in the same order as the configuration file
+    pub fn to_yaml<W: Write>(&self, writer: W) -> Result<()> {
+        serde_yaml_ng::to_writer(writer, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_yaml_round_trip_keeps_order() {
+        let input = "[core]\nproject = my-project\naccount = a.user@example.org\n[compute]\nzone = europe-west1-d\n";
+        let properties = Properties::from_reader(input.as_bytes()).unwrap();
+
+        let mut yaml = Vec::new();
+        properties.to_yaml(&mut yaml).unwrap();
+
+        assert_eq!(
+            String::from_utf8(yaml.clone()).unwrap(),
+            "core:\n  project: my-project\n  account: a.user@example.org\ncompute:\n  zone: europe-west1-d\n"
+        );
+
+        let parsed = Properties::from_yaml(yaml.as_slice()).unwrap();
+        assert_eq!(parsed.iter().collect::<Vec<_>>(), properties.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    pub fn test_from_yaml_rejects_nested_values() {
+        let result = Properties::from_yaml("core:\n  project:\n    name: nested\n".as_bytes());
+        assert!(matches!(result, Err(crate::Error::Yaml(_))));
+    }
+}
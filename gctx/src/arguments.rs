@@ -1,22 +1,116 @@
+use crate::{
+    commands::{OnConflict, Sort},
+    examples::ExampleFormat,
+    output::OutputFormat,
+    shell::Shell,
+};
 use clap::{crate_version, Parser};
+use std::path::PathBuf;
 
 /// gcloud configuration manager
 #[derive(Parser, Debug)]
-#[clap(version = crate_version!())]
+#[clap(version = crate_version!(), disable_help_subcommand = true)]
 pub struct Opts {
     /// Switch to this context (shorthand for activate, ignores subsequent arguments)
     pub context: Option<String>,
 
+    /// Report how long each stage of the command took
+    #[clap(long, global = true)]
+    pub timings: bool,
+
+    /// Fail with a non-zero exit code if any warnings are emitted
+    #[clap(long, global = true)]
+    pub strict: bool,
+
+    /// Open the configuration store read-only so that no command can modify it
+    #[clap(long, global = true)]
+    pub read_only: bool,
+
+    /// Create the configuration store with an active 'default' configuration if it doesn't exist yet, e.g. before
+    /// gcloud is installed
+    #[clap(long, global = true, conflicts_with = "read-only")]
+    pub create_if_missing: bool,
+
+    /// Unpin a pinned configuration instead of failing when a command modifies it
+    #[clap(long, global = true)]
+    pub unpin_first: bool,
+
+    /// Run the on-activate command of a configuration after activating it
+    #[clap(long, global = true)]
+    pub run_hooks: bool,
+
     #[clap(subcommand)]
     pub subcmd: Option<SubCommand>,
 }
 
+#[derive(Parser, Debug)]
+pub enum ArchiveAction {
+    /// Write every configuration, and which one is active, to a tar archive
+    Export {
+        /// Path to write the archive to
+        path: PathBuf,
+    },
+
+    /// Import every configuration in an archive made by 'gctx archive export'
+    Import {
+        /// Path to the archive to import
+        path: PathBuf,
+
+        /// How to resolve a name collision, defaults to aborting
+        #[clap(long, arg_enum, default_value = "abort")]
+        on_conflict: OnConflict,
+    },
+}
+
+#[derive(Parser, Debug)]
+pub enum CacheAction {
+    /// Print the configuration names, from the cache when it's up to date
+    Names,
+
+    /// Rebuild the cache by scanning the configuration store
+    Rebuild,
+}
+
 #[derive(Parser, Debug)]
 pub enum SubCommand {
     /// Activate a configuration by name
     Activate {
         /// Name of the configuration to activate
         name: Option<String>,
+
+        /// Read the name of the configuration from stdin
+        #[clap(long, conflicts_with = "name")]
+        stdin: bool,
+
+        /// Fetch credentials for the GKE cluster set in container/cluster after activating the configuration
+        #[clap(long)]
+        gke: bool,
+    },
+
+    /// Back up or restore the whole configuration store, e.g. to move it to a new machine
+    Archive {
+        #[clap(subcommand)]
+        action: ArchiveAction,
+    },
+
+    /// Manage the cache of configuration names used by completions and prompts
+    Cache {
+        #[clap(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Clean up old backups, metadata for deleted configurations, old gcloud logs and credentials only deleted configurations used
+    ///
+    /// Each kind of clutter is only removed after confirmation, unless --yes is given. Credentials are only ever
+    /// removed after confirming each account
+    Cleanup {
+        /// Only report what could be cleaned up and how much space it would reclaim
+        #[clap(long)]
+        advise: bool,
+
+        /// Remove everything that was found except credentials without asking for confirmation
+        #[clap(short, long, conflicts_with = "advise")]
+        yes: bool,
     },
 
     /// Copy a configuration
@@ -34,22 +128,42 @@ pub enum SubCommand {
         /// Force a copy to overwrite an existing configuration
         #[clap(short, long)]
         force: bool,
+
+        /// How to resolve a name collision, defaults to aborting
+        #[clap(long, arg_enum, conflicts_with = "force")]
+        on_conflict: Option<OnConflict>,
+
+        /// Use a different project in the copy, keeping every other property
+        #[clap(long)]
+        project: Option<String>,
     },
 
     /// Create a new configuration
     Create {
         /// Create a configuration interactively
-        #[clap(short, long, conflicts_with_all(&["name", "project", "account", "zone", "region", "activate", "force"]))]
+        #[clap(short, long, conflicts_with_all(&["name", "auto-name", "project", "pick-project", "account", "zone", "region", "activate", "force", "on-conflict", "record-sdk-version"]))]
         interactive: bool,
 
         // Name of the new configuration
-        #[clap(required_unless_present("interactive"), requires_all(&["project", "account", "zone"]))]
+        #[clap(required_unless_present_any(&["interactive", "auto-name"]), requires_all(&["project-source", "account", "zone"]))]
         name: Option<String>,
 
+        /// Generate a unique name for the new configuration and print it to stdout
+        #[clap(long, conflicts_with_all(&["name", "force", "on-conflict"]), requires_all(&["project-source", "account", "zone"]))]
+        auto_name: bool,
+
+        /// Prefix for the generated name
+        #[clap(long, requires("auto-name"), default_value = "tmp")]
+        prefix: String,
+
         /// Setting for core/project
-        #[clap(short, long)]
+        #[clap(short, long, group = "project-source")]
         project: Option<String>,
 
+        /// Pick core/project from the projects your account can access (requires the online feature)
+        #[clap(long, group = "project-source")]
+        pick_project: bool,
+
         /// Setting for core/account
         #[clap(short, long)]
         account: Option<String>,
@@ -69,25 +183,191 @@ pub enum SubCommand {
         /// Force a create to overwrite an existing configuration
         #[clap(short, long)]
         force: bool,
+
+        /// How to resolve a name collision, defaults to aborting
+        #[clap(long, arg_enum, conflicts_with = "force")]
+        on_conflict: Option<OnConflict>,
+
+        /// Record the installed gcloud SDK version, to warn on activation if the SDK changes significantly
+        #[clap(long)]
+        record_sdk_version: bool,
     },
 
     /// Show the current configuration
     Current,
 
-    /// Delete a configuration
+    /// Deactivate the current configuration, so that no configuration is active
+    Deactivate,
+
+    /// Delete one or more configurations
+    ///
+    /// When deleting several configurations, nothing is deleted if any of them are unknown, or if the active
+    /// configuration is one of them without a --fallback
     Delete {
-        /// Name of the configuration to delete
-        name: String,
+        /// Names of the configurations to delete
+        #[clap(required_unless_present = "stdin")]
+        names: Vec<String>,
+
+        /// Delete each configuration named on stdin, one per line
+        #[clap(long, conflicts_with = "names", requires = "yes")]
+        stdin: bool,
+
+        /// Confirm deleting configurations read from stdin
+        #[clap(short, long)]
+        yes: bool,
+
+        /// Activate this configuration first if the one being deleted is active
+        #[clap(long, value_name = "NAME")]
+        fallback: Option<String>,
     },
 
     /// Describe all the properties in a configuration
     Describe {
         /// Name of the configuration, defaults to current
         name: Option<String>,
+
+        /// Describe each configuration named on stdin, one per line
+        #[clap(long, conflicts_with = "name")]
+        stdin: bool,
+
+        /// Describe all configurations
+        #[clap(long, conflicts_with_all = &["name", "stdin"])]
+        all: bool,
+
+        /// Output format
+        #[clap(short, long, arg_enum, default_value = "text", conflicts_with = "stdin")]
+        output: OutputFormat,
+    },
+
+    /// Print copy-pasteable examples of real-world flows, e.g. onboarding a new project
+    Examples {
+        /// Example topic, e.g. onboarding, prod-staging or ci. Prints every example if omitted
+        topic: Option<String>,
+
+        /// Output format
+        #[clap(short, long, arg_enum, default_value = "text")]
+        output: ExampleFormat,
+    },
+
+    /// Export a configuration file as-is, e.g. to import it on another machine
+    Export {
+        /// Name of the configuration to export
+        name: String,
+
+        /// Path to write the configuration file to, defaults to stdout
+        path: Option<PathBuf>,
+    },
+
+    /// Print help for a command or an example topic
+    Help {
+        /// Command or example topic
+        topic: Option<String>,
+    },
+
+    /// Show or set the command which runs after a configuration is activated with --run-hooks
+    Hook {
+        /// Name of the configuration
+        name: String,
+
+        /// Command to run, e.g. gcloud container clusters get-credentials my-cluster
+        command: Option<String>,
+
+        /// Remove the command
+        #[clap(long, conflicts_with = "command")]
+        clear: bool,
+    },
+
+    /// Import a configuration from a file
+    Import {
+        /// Path to the configuration file to import, or - to read it from stdin
+        path: PathBuf,
+
+        /// Name of the imported configuration, defaults to the file name without any config_ prefix
+        #[clap(short, long)]
+        name: Option<String>,
+
+        /// How to resolve a name collision, defaults to asking when run interactively and aborting otherwise
+        #[clap(long, arg_enum)]
+        on_conflict: Option<OnConflict>,
+    },
+
+    /// Inspect another configuration store without modifying it, e.g. when debugging a colleague's environment
+    Inspect {
+        /// Location of the configuration store, e.g. /home/other/.config/gcloud
+        #[clap(long)]
+        store: PathBuf,
     },
 
     /// List all available configurations
-    List,
+    List {
+        /// Print only the names, one per line, e.g. to pipe into another command with --stdin
+        #[clap(long, conflicts_with = "output")]
+        names_only: bool,
+
+        /// Output format
+        #[clap(short, long, arg_enum, default_value = "text")]
+        output: OutputFormat,
+
+        /// Order of the configurations
+        #[clap(long, arg_enum, default_value = "natural")]
+        sort: Sort,
+
+        /// Compare two configuration stores instead, showing which configurations exist in each and which differ
+        #[clap(long, value_name = "LEFT:RIGHT", conflicts_with_all = &["names-only", "output"])]
+        compare: Option<String>,
+
+        /// Only list configurations whose name matches this glob, e.g. 'dev-*'
+        #[clap(long, value_name = "GLOB", conflicts_with = "compare")]
+        name: Option<String>,
+
+        /// Only list configurations whose core/project is this project
+        #[clap(long, conflicts_with = "compare")]
+        project: Option<String>,
+
+        /// Only list configurations whose core/account is this account
+        #[clap(long, conflicts_with = "compare")]
+        account: Option<String>,
+
+        /// Only list configurations whose compute/zone is this zone
+        #[clap(long, conflicts_with = "compare")]
+        zone: Option<String>,
+    },
+
+    /// Rewrite configurations in a normalised form, e.g. to keep them deterministic under version control
+    Normalize {
+        /// Name of the configuration to normalise
+        #[clap(required_unless_present = "all")]
+        name: Option<String>,
+
+        /// Normalise all configurations
+        #[clap(long, conflicts_with = "name")]
+        all: bool,
+    },
+
+    /// Guided setup for new team members, from finding the configuration store to shell integration
+    Onboard {
+        /// Directory of team configuration templates to import
+        #[clap(long)]
+        templates: Option<PathBuf>,
+    },
+
+    /// Pin a configuration so that it can't be modified, e.g. to protect a golden configuration
+    Pin {
+        /// Name of the configuration to pin
+        name: String,
+    },
+
+    /// Print the active configuration for use in a shell prompt, marking dangerous configurations
+    Prompt {
+        /// Highlight dangerous configurations in bold red
+        #[clap(long)]
+        color: bool,
+    },
+
+    /// Refresh the data gctx embeds a snapshot of, e.g. the regions used by suggest-region, using gcloud
+    ///
+    /// gctx works fully offline using the embedded snapshots. Refreshed data is kept in the store until the next refresh
+    RefreshData,
 
     /// Rename a configuration
     Rename {
@@ -100,5 +380,96 @@ pub enum SubCommand {
         /// Force a rename to overwrite an existing configuration
         #[clap(short, long)]
         force: bool,
+
+        /// How to resolve a name collision, defaults to aborting
+        #[clap(long, arg_enum, conflicts_with = "force")]
+        on_conflict: Option<OnConflict>,
+    },
+
+    /// Restore the most recent backup of a configuration, made when it was overwritten or deleted
+    Restore {
+        /// Name of the configuration
+        name: String,
+    },
+
+    /// Run a command using a configuration without activating it
+    Run {
+        /// Name of the configuration to use
+        #[clap(required_unless_present = "ephemeral")]
+        name: Option<String>,
+
+        /// Use a temporary configuration made from the given properties, which is never saved to the store
+        #[clap(long, conflicts_with = "name", requires = "project")]
+        ephemeral: bool,
+
+        /// Setting for core/project, with --ephemeral
+        #[clap(short, long, requires = "ephemeral")]
+        project: Option<String>,
+
+        /// Setting for core/account, with --ephemeral
+        #[clap(short, long, requires = "ephemeral")]
+        account: Option<String>,
+
+        /// Setting for compute/zone, with --ephemeral
+        #[clap(short, long, requires = "ephemeral")]
+        zone: Option<String>,
+
+        /// Setting for compute/region, with --ephemeral
+        #[clap(short, long, requires = "ephemeral")]
+        region: Option<String>,
+
+        /// Confirm running the command with a dangerous configuration, e.g. one tagged prod
+        #[clap(long, conflicts_with = "ephemeral")]
+        yes_prod: bool,
+
+        /// Command to run and its arguments, after --
+        #[clap(last = true, required = true)]
+        command: Vec<String>,
+    },
+
+    /// Suggest a region and zone for new configurations, from the organisation defaults or your timezone and locale
+    ///
+    /// Organisation defaults are set with the GCTX_DEFAULT_REGION and GCTX_DEFAULT_ZONE environment variables
+    SuggestRegion,
+
+    /// Show, add or remove the tags of a configuration, e.g. prod to mark it as dangerous
+    Tag {
+        /// Name of the configuration
+        name: String,
+
+        /// Tags to add
+        tags: Vec<String>,
+
+        /// Remove the given tags instead of adding them
+        #[clap(long)]
+        remove: bool,
+    },
+
+    /// Unpin a configuration so that it can be modified again
+    Unpin {
+        /// Name of the configuration to unpin
+        name: String,
+    },
+
+    /// Use a configuration in all terminals or only the current terminal
+    ///
+    /// Terminal-local activation prints shell statements which must be evaluated, e.g. with a wrapper
+    /// function which only evaluates the output of `use`, as printed by `gctx onboard`:
+    /// gctx() { if [ "$1" = "use" ]; then eval "$(command gctx "$@")"; else command gctx "$@"; fi }
+    Use {
+        /// Name of the configuration to use
+        name: String,
+
+        /// Activate for all terminals (default unless GCTX_USE_MODE=local)
+        #[clap(short, long, conflicts_with = "local")]
+        global: bool,
+
+        /// Activate for the current terminal only
+        #[clap(short, long)]
+        local: bool,
+
+        /// Shell syntax for the printed statements, detected automatically by default
+        #[clap(long, arg_enum)]
+        shell: Option<Shell>,
     },
 }
@@ -0,0 +1,39 @@
+use anyhow::{bail, Result};
+use colored::*;
+use std::io::BufRead;
+
+/// Read configuration names from a line-based stream, e.g. the output of `gctx list --names-only`
+///
+/// Each line holds one name. Blank lines and lines starting with `#` are ignored.
+pub fn read_names<R: BufRead>(reader: R) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let name = line.trim();
+
+        if !name.is_empty() && !name.starts_with('#') {
+            names.push(name.to_owned());
+        }
+    }
+
+    Ok(names)
+}
+
+/// Run an operation for each name, continuing past failures and reporting them together at the end
+pub fn for_each(names: &[String], mut operation: impl FnMut(&str) -> Result<()>) -> Result<()> {
+    let mut failed = 0;
+
+    for name in names {
+        if let Err(e) = operation(name) {
+            eprintln!("{} '{}': {:#}", "Error:".red(), name, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        bail!("{} of {} configuration(s) failed", failed, names.len());
+    }
+
+    Ok(())
+}
@@ -1,7 +1,35 @@
+use crate::{
+    batch, data, hooks,
+    output::{
+        self,
+        schema::{self, ConfigurationEntry, DescribeAllOutput, DescribeOutput, ListOutput},
+        OutputFormat,
+    },
+    regions, sdk,
+    settings::{ActivationScope, Settings},
+    shell::{Shell, ACTIVE_CONFIG_VAR},
+    timings, warnings,
+};
 use anyhow::{bail, Context, Result};
+use clap::ArgEnum;
 use colored::*;
-use dialoguer::{Confirm, Input};
-use gcloud_ctx::{ConfigurationStore, ConflictAction, PropertiesBuilder};
+use dialoguer::{console::Term, theme::ColorfulTheme, Confirm, Input, Select};
+use gcloud_ctx::{
+    Account, Clutter, ClutterKind, Configuration, ConfigurationStore, ConflictAction, ConflictResolver,
+    DeleteBehaviour, Filter, KeyOrder, Metadata, NameCache, ProjectId, Properties, PropertiesBuilder, ReadOptions,
+    Resolution, Risk, RiskPolicy, SortOrder, Warning, WriteOptions,
+};
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// gcloud logs which haven't been modified for this many days are cleaned up by `gctx cleanup`
+const LOG_MAX_AGE_DAYS: u64 = 30;
 
 /// Used to control whether to activate a configuration after creation
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -23,12 +51,177 @@ impl From<bool> for PostCreation {
     }
 }
 
+/// Whether the configuration store is opened read-only
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Open the configuration store read-only for the rest of this invocation
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+/// Whether the configuration store is created if it doesn't exist yet
+static CREATE_IF_MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Create the configuration store if it doesn't exist yet for the rest of this invocation
+pub fn set_create_if_missing(create_if_missing: bool) {
+    CREATE_IF_MISSING.store(create_if_missing, Ordering::Relaxed);
+}
+
+/// Whether pinned configurations are unpinned instead of failing when they're modified
+static UNPIN_FIRST: AtomicBool = AtomicBool::new(false);
+
+/// Unpin pinned configurations before modifying them for the rest of this invocation
+pub fn set_unpin_first(unpin_first: bool) {
+    UNPIN_FIRST.store(unpin_first, Ordering::Relaxed);
+}
+
+/// Open the configuration store at the default location, recording how long each stage takes
+pub fn open_store() -> Result<ConfigurationStore> {
+    let location = timings::measure("store discovery", ConfigurationStore::default_location)?;
+    let mut store = timings::measure("directory scan", || {
+        ConfigurationStore::builder()
+            .location(location)
+            .read_only(READ_ONLY.load(Ordering::Relaxed))
+            .create_if_missing(CREATE_IF_MISSING.load(Ordering::Relaxed))
+            .open()
+    })?;
+
+    if UNPIN_FIRST.load(Ordering::Relaxed) {
+        store = store.into_unpin_first();
+    }
+
+    store.warnings()?.into_iter().for_each(warnings::emit);
+
+    Ok(store)
+}
+
+/// Open the configuration store read-only without scanning the configurations, for commands such as shell prompts
+/// which only read the active one and must never modify anything
+///
+/// Warnings about the configurations directory aren't emitted because it's only scanned if it's needed
+pub fn open_store_lazy() -> Result<ConfigurationStore> {
+    let location = timings::measure("store discovery", ConfigurationStore::default_location)?;
+
+    let store = ConfigurationStore::builder()
+        .location(location)
+        .lazy(true)
+        .read_only(true)
+        .open()?;
+
+    Ok(store)
+}
+
+/// Policy for resolving a name collision when writing a configuration
+#[derive(ArgEnum, Copy, Clone, Debug, PartialEq)]
+pub enum OnConflict {
+    /// Ask which resolution to use
+    Ask,
+
+    /// Abort the operation
+    Abort,
+
+    /// Keep the existing configuration and skip the operation
+    Keep,
+
+    /// Overwrite the existing configuration
+    Overwrite,
+
+    /// Write the configuration with a new, generated name
+    Rename,
+}
+
+impl OnConflict {
+    /// Policy for commands which also take --force, which overwrites instead of the default of aborting
+    pub fn or_force(on_conflict: Option<OnConflict>, force: bool) -> Self {
+        match on_conflict {
+            Some(on_conflict) => on_conflict,
+            None if force => OnConflict::Overwrite,
+            None => OnConflict::Abort,
+        }
+    }
+}
+
+impl Default for OnConflict {
+    /// Ask when run interactively, otherwise abort
+    fn default() -> Self {
+        if io::stdin().is_terminal() && io::stderr().is_terminal() {
+            OnConflict::Ask
+        } else {
+            OnConflict::Abort
+        }
+    }
+}
+
+/// Order in which configurations are listed
+#[derive(ArgEnum, Copy, Clone, Debug, PartialEq)]
+pub enum Sort {
+    /// Compare numbers by value, e.g. env-2 before env-10
+    Natural,
+
+    /// Compare character by character, e.g. env-10 before env-2
+    Lexical,
+}
+
+impl From<Sort> for SortOrder {
+    fn from(value: Sort) -> Self {
+        match value {
+            Sort::Natural => SortOrder::Natural,
+            Sort::Lexical => SortOrder::Lexical,
+        }
+    }
+}
+
 /// List the available configurations with an indicator of the active one
-pub fn list() -> Result<()> {
-    let store = ConfigurationStore::with_default_location()?;
+pub fn list(
+    names_only: bool,
+    format: OutputFormat,
+    order: SortOrder,
+    name: Option<&str>,
+    project: Option<&str>,
+    account: Option<&str>,
+    zone: Option<&str>,
+) -> Result<()> {
+    let store = open_store()?;
+    let mut configurations = store.configurations_sorted(order)?;
 
-    for config in store.configurations() {
-        if store.is_active(config) {
+    if name.is_some() || project.is_some() || account.is_some() || zone.is_some() {
+        let mut filter = Filter::default();
+
+        if let Some(name) = name {
+            filter.name(name);
+        }
+        if let Some(project) = project {
+            filter.project(project);
+        }
+        if let Some(account) = account {
+            filter.account(account);
+        }
+        if let Some(zone) = zone {
+            filter.zone(zone);
+        }
+
+        let found = store.query(&filter)?;
+        configurations.retain(|c| found.iter().any(|(f, _)| f == c));
+    }
+
+    if format == OutputFormat::Json {
+        return output::print_json(&ListOutput {
+            schema_version: schema::SCHEMA_VERSION,
+            active: store.active().map(str::to_owned),
+            configurations: configurations
+                .into_iter()
+                .map(|c| ConfigurationEntry {
+                    name: c.name().to_owned(),
+                    active: store.is_active(c),
+                })
+                .collect(),
+        });
+    }
+
+    for config in configurations {
+        if names_only {
+            println!("{}", config.name());
+        } else if store.is_active(config) {
             println!("{} {}", "*".blue(), config.name().blue());
         } else {
             println!("  {}", config.name());
@@ -38,30 +231,334 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
+/// List the configurations in two stores, given as `LEFT:RIGHT` paths, showing where each exists and whether
+/// the contents differ
+pub fn list_compare(stores: &str, order: SortOrder) -> Result<()> {
+    let (left_location, right_location) = split_store_pair(stores).with_context(|| {
+        format!(
+            "Expected two configuration stores in the form LEFT:RIGHT, got '{}'",
+            stores
+        )
+    })?;
+
+    let left = ConfigurationStore::with_location(PathBuf::from(left_location))?.into_read_only();
+    let right = ConfigurationStore::with_location(PathBuf::from(right_location))?.into_read_only();
+
+    left.warnings()?.into_iter().for_each(warnings::emit);
+    right.warnings()?.into_iter().for_each(warnings::emit);
+
+    let mut configurations = left.configurations_sorted(order)?;
+
+    for configuration in right.configurations()? {
+        if left.find_by_name(configuration.name())?.is_none() {
+            configurations.push(configuration);
+        }
+    }
+
+    configurations.sort_by(|a, b| match order {
+        SortOrder::Natural => a.cmp(b),
+        SortOrder::Lexical => a.name().cmp(b.name()),
+    });
+
+    for name in configurations.into_iter().map(Configuration::name) {
+        match (left.find_by_name(name)?, right.find_by_name(name)?) {
+            (Some(l), Some(r)) if l.fingerprint()? == r.fingerprint()? => println!("  same       {}", name),
+            (Some(_), Some(_)) => println!("  {}  {}", "different".yellow(), name.yellow()),
+            (Some(_), None) => println!("< {} only  {}", "left".blue(), name),
+            (None, _) => println!("> {} only {}", "right".blue(), name),
+        }
+    }
+
+    Ok(())
+}
+
+/// Split a pair of store locations in the form `LEFT:RIGHT`, allowing for Windows drive letters such as `C:\`
+fn split_store_pair(stores: &str) -> Option<(&str, &str)> {
+    // only the left side's drive letter can come before the separator
+    let is_drive_letter = |index: usize| {
+        index == 1
+            && stores.starts_with(|c: char| c.is_ascii_alphabetic())
+            && stores[index + 1..].starts_with(['\\', '/'])
+    };
+
+    let index = stores
+        .match_indices(':')
+        .map(|(i, _)| i)
+        .find(|&i| !is_drive_letter(i))?;
+    let (left, right) = (&stores[..index], &stores[index + 1..]);
+
+    (!left.is_empty() && !right.is_empty()).then_some((left, right))
+}
+
 /// Activate the given configuration by name
-pub fn activate(name: &str) -> Result<()> {
-    let mut store = ConfigurationStore::with_default_location()?;
-    store.activate(name)?;
+pub fn activate(name: &str, run_hooks: bool, gke: bool) -> Result<()> {
+    let mut store = open_store()?;
+
+    // check the cluster can be found before switching so that a failure leaves the active configuration alone
+    let cluster = gke.then(|| gke_cluster(&store, name)).transpose()?;
+
+    let activation = store.activate(name)?;
 
-    println!("Successfully activated '{}'", name.blue());
+    match activation.previous() {
+        _ if !activation.changed() => println!("Configuration '{}' is already active", name.blue()),
+        Some(previous) => println!("Switched from '{}' to '{}'", previous.yellow(), name.blue()),
+        None => println!("Successfully activated '{}'", name.blue()),
+    }
+
+    check_sdk_version(&store, name)?;
+    check_env_conflicts(&store, name);
+
+    if run_hooks {
+        hooks::run_on_activate(&store, name)?;
+    }
+
+    if let Some(cluster) = cluster {
+        get_gke_credentials(&cluster, name)?;
+    }
 
     Ok(())
 }
 
+/// GKE cluster named by a configuration
+struct GkeCluster {
+    /// Name of the cluster
+    name: String,
+
+    /// `--zone` or `--region` flag which locates the cluster
+    location: String,
+}
+
+/// Find the GKE cluster named by the `container/cluster` property of a configuration, in its zone or, if it doesn't
+/// have one, its region
+fn gke_cluster(store: &ConfigurationStore, name: &str) -> Result<GkeCluster> {
+    let properties = store.describe(name)?;
+
+    let cluster = properties.container().cluster().with_context(|| {
+        format!(
+            "Configuration '{}' has no container/cluster property, which --gke needs to find the cluster",
+            name
+        )
+    })?;
+
+    let location = match (properties.compute().zone(), properties.compute().region()) {
+        (Some(zone), _) => format!("--zone={}", zone),
+        (None, Some(region)) => format!("--region={}", region),
+        (None, None) => bail!(
+            "Configuration '{}' has no compute/zone or compute/region property, which --gke needs to find the cluster",
+            name
+        ),
+    };
+
+    Ok(GkeCluster {
+        name: cluster.to_owned(),
+        location,
+    })
+}
+
+/// Fetch credentials for a GKE cluster using the given configuration
+///
+/// The output of gcloud is streamed as it runs
+fn get_gke_credentials(cluster: &GkeCluster, name: &str) -> Result<()> {
+    eprintln!("Fetching credentials for GKE cluster '{}'", cluster.name.blue());
+
+    let command = [
+        "gcloud",
+        "container",
+        "clusters",
+        "get-credentials",
+        &cluster.name,
+        &cluster.location,
+    ]
+    .map(str::to_owned);
+
+    run_command(
+        &with_configuration_flag(&command, name),
+        [(ACTIVE_CONFIG_VAR.to_owned(), name.to_owned())],
+    )
+}
+
+/// Activate the configuration named on stdin
+pub fn activate_stdin(run_hooks: bool, gke: bool) -> Result<()> {
+    let names = batch::read_names(io::stdin().lock())?;
+
+    match names.as_slice() {
+        [name] => activate(name, run_hooks, gke),
+        _ => bail!(
+            "Expected exactly one configuration name on stdin but found {}",
+            names.len()
+        ),
+    }
+}
+
+/// Print the configuration names, using the name cache when it's up to date to avoid scanning the store
+pub fn cached_names() -> Result<()> {
+    let location = timings::measure("store discovery", ConfigurationStore::default_location)?;
+
+    let names = match NameCache::read(&location)? {
+        Some(names) => names,
+        None => {
+            let store = open_store()?;
+
+            // best effort, e.g. the store may be read-only
+            let _ = store.rebuild_name_cache();
+
+            store.configurations()?.map(|c| c.name().to_owned()).collect()
+        }
+    };
+
+    for name in names {
+        println!("{}", name);
+    }
+
+    Ok(())
+}
+
+/// Rebuild the cache of configuration names
+pub fn rebuild_cache() -> Result<()> {
+    let store = open_store()?;
+    store.rebuild_name_cache()?;
+
+    println!("Rebuilt the configuration name cache");
+
+    Ok(())
+}
+
+/// Report clutter in the store and its state directory, removing it unless only advising
+pub fn cleanup(advise: bool, yes: bool) -> Result<()> {
+    let mut store = open_store()?;
+    let clutter = store.find_clutter(Duration::from_secs(LOG_MAX_AGE_DAYS * 24 * 60 * 60))?;
+
+    if clutter.is_empty() {
+        println!("Nothing to clean up");
+        return Ok(());
+    }
+
+    let total = clutter.iter().map(|c| c.size()).sum();
+    println!("{} can be reclaimed:", format_size(total).blue());
+
+    for c in &clutter {
+        let count = c.paths().len();
+        let noun = if count == 1 { "entry" } else { "entries" };
+        println!("  {}: {} {}, {}", c.kind(), count, noun, format_size(c.size()));
+    }
+
+    if advise {
+        return Ok(());
+    }
+
+    if !yes && !io::stdin().is_terminal() {
+        bail!("Use --yes to clean up without confirmation, or --advise to only report what could be cleaned up");
+    }
+
+    for c in &clutter {
+        if c.kind() == ClutterKind::UnusedCredentials {
+            remove_credentials(&mut store, c)?;
+            continue;
+        }
+
+        let remove = yes
+            || Confirm::new()
+                .with_prompt(format!("Remove {}?", c.kind()))
+                .default(false)
+                .interact_on(&Term::stderr())?;
+
+        if remove {
+            store.remove_clutter(c)?;
+            println!("Removed {}", c.kind().to_string().yellow());
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove unused credentials, confirming each account separately even with --yes because they can't be recovered
+fn remove_credentials(store: &mut ConfigurationStore, credentials: &Clutter) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        println!(
+            "Skipped {}, which are only removed after confirming each account",
+            credentials.kind()
+        );
+        return Ok(());
+    }
+
+    for account in credentials.split() {
+        let name = account.paths()[0].file_name().unwrap_or_default().to_string_lossy();
+
+        let remove = Confirm::new()
+            .with_prompt(format!("Remove credentials for {}?", name))
+            .default(false)
+            .interact_on(&Term::stderr())?;
+
+        if remove {
+            store.remove_clutter(&account)?;
+            println!("Removed credentials for {}", name.yellow());
+        }
+    }
+
+    Ok(())
+}
+
+/// Format a number of bytes for people, e.g. 1.5 KiB
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
 /// Copy an existing configuration
-pub fn copy(src_name: &str, dest_name: &str, conflict: ConflictAction, activate: PostCreation) -> Result<()> {
-    let mut store = ConfigurationStore::with_default_location()?;
-    store.copy(src_name, dest_name, conflict)?;
+pub fn copy(
+    src_name: &str,
+    dest_name: &str,
+    project: Option<&str>,
+    on_conflict: OnConflict,
+    activate: PostCreation,
+) -> Result<()> {
+    let mut store = open_store()?;
+    let incoming = match on_conflict {
+        OnConflict::Ask => Some(store.describe(src_name)?),
+        _ => None,
+    };
+    let resolver =
+        |store: &ConfigurationStore, name: &str| resolve_conflict(store, name, incoming.as_ref(), on_conflict);
+
+    let copied = match project {
+        Some(project) => {
+            let overrides = PropertiesBuilder::default().project(&project.parse()?).build();
+            store.copy_with_overrides(src_name, dest_name, &overrides, resolver)?
+        }
+        None => store.copy(src_name, dest_name, resolver)?,
+    };
+
+    let copied = match copied {
+        Some(configuration) => configuration,
+        None => {
+            println!("Kept existing configuration '{}'", dest_name.yellow());
+            return Ok(());
+        }
+    };
 
     println!(
         "Successfully copied configuration '{}' to '{}'",
         src_name.yellow(),
-        dest_name.blue()
+        copied.name().blue()
     );
+    print_backup_hint(&store);
 
     if activate == PostCreation::Activate {
-        store.activate(dest_name)?;
-        println!("Configuration '{}' is now active", dest_name.blue());
+        store.activate(copied.name())?;
+        println!("Configuration '{}' is now active", copied.name().blue());
     }
 
     Ok(())
@@ -69,13 +566,13 @@ pub fn copy(src_name: &str, dest_name: &str, conflict: ConflictAction, activate:
 
 /// Create a new configuration interactively
 pub fn create_interactive() -> Result<()> {
-    let store = ConfigurationStore::with_default_location()?;
+    let store = open_store()?;
 
     let name = Input::<String>::new()
         .with_prompt("Name".blue().to_string())
         .interact()?;
 
-    if store.find_by_name(&name).is_some() {
+    if store.find_by_name(&name)?.is_some() {
         let prompt = "A configuration with the same name already exists. Overwrite?"
             .yellow()
             .to_string();
@@ -86,22 +583,37 @@ pub fn create_interactive() -> Result<()> {
         }
     }
 
-    let project = Input::<String>::new()
+    // asks again if the project ID or account is invalid
+    let project = Input::<ProjectId>::new()
         .with_prompt("Project".blue().to_string())
         .interact()?;
 
-    let account = Input::<String>::new()
+    let account = Input::<Account>::new()
         .with_prompt("Account".blue().to_string())
         .interact()?;
 
-    let zone = Input::<String>::new()
-        .with_prompt("Zone".blue().to_string())
-        .interact()?;
+    let suggestion = regions::suggest(&Settings::from_env()?);
 
-    let region = Input::<String>::new()
+    let mut zone = Input::<String>::new();
+    zone.with_prompt("Zone".blue().to_string());
+
+    if let Some(suggested) = suggestion.zone {
+        zone.default(suggested);
+    }
+
+    let zone = zone.interact()?;
+
+    let mut region = Input::<String>::new();
+    region
         .with_prompt("Region (optional)".blue().to_string())
-        .allow_empty(true)
-        .interact()?;
+        .allow_empty(true);
+
+    // only suggest the region of the chosen zone, otherwise they'd conflict
+    if zone.starts_with(&format!("{}-", suggestion.region)) {
+        region.default(suggestion.region);
+    }
+
+    let region = region.interact()?;
     let region = if region.is_empty() { None } else { Some(region) };
 
     let activate = Confirm::new()
@@ -109,43 +621,40 @@ pub fn create_interactive() -> Result<()> {
         .default(false)
         .interact()?;
 
-    create(
-        &name,
-        &project,
-        &account,
-        &zone,
-        region.as_deref(),
-        ConflictAction::Overwrite,
-        activate.into(),
-    )?;
+    let properties = build_properties(project.as_str(), account.as_str(), &zone, region.as_deref())?;
+
+    create(&name, &properties, OnConflict::Overwrite, activate.into(), false)?;
 
     Ok(())
 }
 
-/// Create a new configuration
+/// Create a new configuration, optionally recording the installed gcloud SDK version in its metadata
 pub fn create(
     name: &str,
-    project: &str,
-    account: &str,
-    zone: &str,
-    region: Option<&str>,
-    conflict: ConflictAction,
+    properties: &Properties,
+    on_conflict: OnConflict,
     activate: PostCreation,
+    record_sdk_version: bool,
 ) -> Result<()> {
-    let mut store = ConfigurationStore::with_default_location()?;
-    let mut builder = PropertiesBuilder::default();
+    let mut store = open_store()?;
+    let resolver =
+        |store: &ConfigurationStore, name: &str| resolve_conflict(store, name, Some(properties), on_conflict);
 
-    builder.project(project).account(account).zone(zone);
+    let created = match store.create(name, properties, resolver)? {
+        Some(configuration) => configuration,
+        None => {
+            println!("Kept existing configuration '{}'", name.yellow());
+            return Ok(());
+        }
+    };
+    let name = created.name();
 
-    if let Some(region) = region {
-        builder.region(region);
+    if record_sdk_version {
+        record_installed_sdk_version(&mut store, name)?;
     }
 
-    let properties = builder.build();
-
-    store.create(name, &properties, conflict)?;
-
     println!("Successfully created configuration '{}'", name.blue());
+    print_backup_hint(&store);
 
     if activate == PostCreation::Activate {
         store.activate(name)?;
@@ -155,51 +664,888 @@ pub fn create(
     Ok(())
 }
 
+/// Create a new configuration with a generated name
+///
+/// Only the generated name is printed to stdout so that scripts can capture it
+pub fn create_auto_named(
+    prefix: &str,
+    properties: &Properties,
+    activate: PostCreation,
+    record_sdk_version: bool,
+) -> Result<()> {
+    let mut store = open_store()?;
+
+    let name = store.generate_name(prefix)?;
+    store.create(name.as_str(), properties, ConflictAction::Abort)?;
+
+    if record_sdk_version {
+        record_installed_sdk_version(&mut store, name.as_str())?;
+    }
+
+    println!("{}", name);
+    eprintln!("Successfully created configuration '{}'", name.as_str().blue());
+
+    if activate == PostCreation::Activate {
+        store.activate(name.as_str())?;
+        eprintln!("Configuration '{}' is now active", name.as_str().blue());
+    }
+
+    Ok(())
+}
+
+/// Build the properties for a new configuration
+pub fn build_properties(project: &str, account: &str, zone: &str, region: Option<&str>) -> Result<Properties> {
+    let mut builder = PropertiesBuilder::default();
+
+    builder.project(&project.parse()?).account(&account.parse()?).zone(zone);
+
+    if let Some(region) = region {
+        builder.region(region);
+    }
+
+    validate_locations(&mut builder)?;
+
+    Ok(builder.build())
+}
+
 /// Show the current activated configuration
 pub fn current() -> Result<()> {
-    let store = ConfigurationStore::with_default_location()?;
-    println!("{}", store.active().blue());
+    let store = open_store()?;
+
+    if let Some(active) = store.active() {
+        println!("{}", active.blue());
+    }
+
+    if let Ok(name) = std::env::var(ACTIVE_CONFIG_VAR) {
+        if store.active() != Some(name.as_str()) {
+            warnings::emit(format!(
+                "{}={} overrides the active configuration in this terminal",
+                ACTIVE_CONFIG_VAR, name
+            ));
+        }
+    }
+
+    if let Err(e) = store.active_configuration() {
+        warnings::emit(e);
+    }
+
+    if let Some(active) = store.active() {
+        check_env_conflicts(&store, active);
+    }
+
+    Ok(())
+}
+
+/// Deactivate the current configuration
+pub fn deactivate() -> Result<()> {
+    let mut store = open_store()?;
+
+    match store.deactivate()? {
+        Some(previous) => println!("Successfully deactivated '{}'", previous.blue()),
+        None => println!("No configuration is active"),
+    }
+
     Ok(())
 }
 
-/// Delete a configuration
-pub fn delete(name: &str) -> Result<()> {
-    let mut store = ConfigurationStore::with_default_location()?;
-    store.delete(name)?;
+/// Delete a configuration, activating the fallback first if it's the active configuration
+pub fn delete(name: &str, fallback: Option<String>) -> Result<()> {
+    let mut store = open_store()?;
+    store.delete_with(name, delete_behaviour(fallback))?;
 
     println!("Successfully deleted configuration '{}'", name.yellow());
     Ok(())
 }
 
+/// Delete several configurations, printing the result of each
+///
+/// Nothing is deleted if any of the names are unknown, or if the active configuration is one of them without a
+/// fallback. Otherwise failures, e.g. pinned configurations, are reported together at the end
+pub fn delete_many(names: &[String], fallback: Option<String>) -> Result<()> {
+    let mut store = open_store()?;
+    let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    // results are in the same order as the names
+    let mut results = store.delete_many_with(&refs, delete_behaviour(fallback))?.into_iter();
+
+    batch::for_each(names, |name| {
+        let (_, result) = results.next().expect("a result for every name");
+        result?;
+        println!("Successfully deleted configuration '{}'", name.yellow());
+        Ok(())
+    })
+}
+
+/// Delete each configuration named on stdin
+pub fn delete_stdin(fallback: Option<String>) -> Result<()> {
+    let names = batch::read_names(io::stdin().lock())?;
+    delete_many(&names, fallback)
+}
+
+/// What to do when deleting the active configuration
+fn delete_behaviour(fallback: Option<String>) -> DeleteBehaviour {
+    fallback.map_or(DeleteBehaviour::Refuse, DeleteBehaviour::ActivateFallback)
+}
+
 /// Describe all the properties in the given configuration
-pub fn describe(name: Option<&str>) -> Result<()> {
-    let store = ConfigurationStore::with_default_location()?;
-    let name = name.unwrap_or_else(|| store.active());
-    let properties = store.describe(name)?;
+pub fn describe(name: Option<&str>, format: OutputFormat) -> Result<()> {
+    let store = open_store()?;
+    let name = match name {
+        Some(name) => name,
+        None => store.active_configuration()?.name(),
+    };
+
+    match format {
+        OutputFormat::Text => print_properties(&store, name),
+        OutputFormat::Json => {
+            let (properties, parse_warnings) = store.describe_with_options(name, &ReadOptions::default())?;
+            parse_warnings.into_iter().for_each(warnings::emit);
+
+            output::print_json(&DescribeOutput {
+                schema_version: schema::SCHEMA_VERSION,
+                name: name.to_owned(),
+                properties: schema::property_map(&properties),
+            })
+        }
+    }
+}
+
+/// Describe all configurations, reading them in parallel
+///
+/// Configurations which can't be read are reported as warnings and left out
+pub fn describe_all(format: OutputFormat) -> Result<()> {
+    let store = open_store()?;
+    let described = store.describe_all()?;
+    let mut configurations = Vec::new();
+
+    for (configuration, result) in described {
+        match result {
+            Ok(properties) => configurations.push((configuration.name(), properties)),
+            Err(e) => warnings::emit(format!("Unable to describe '{}': {}", configuration.name(), e)),
+        }
+    }
+
+    match format {
+        OutputFormat::Text => {
+            for (name, properties) in configurations {
+                println!("# {}", name);
+                print_as_written(&properties)?;
+            }
+
+            Ok(())
+        }
+        OutputFormat::Json => output::print_json(&DescribeAllOutput {
+            schema_version: schema::SCHEMA_VERSION,
+            configurations: configurations
+                .into_iter()
+                .map(|(name, properties)| (name.to_owned(), schema::property_map(&properties)))
+                .collect(),
+        }),
+    }
+}
+
+/// Describe each configuration named on stdin, each preceded by a comment line with its name
+pub fn describe_stdin() -> Result<()> {
+    let store = open_store()?;
+    let names = batch::read_names(io::stdin().lock())?;
 
+    batch::for_each(&names, |name| {
+        let (properties, parse_warnings) = store.describe_with_options(name, &ReadOptions::default())?;
+        parse_warnings.into_iter().for_each(warnings::emit);
+
+        println!("# {}", name);
+        print_as_written(&properties)
+    })
+}
+
+/// Print the properties of a configuration, emitting any warnings from parsing it
+fn print_properties(store: &ConfigurationStore, name: &str) -> Result<()> {
+    let (properties, parse_warnings) = store.describe_with_options(name, &ReadOptions::default())?;
+
+    parse_warnings.into_iter().for_each(warnings::emit);
+
+    print_as_written(&properties)
+}
+
+/// Print properties in the order they're written in the configuration file, rather than the canonical order used
+/// when writing them
+fn print_as_written(properties: &Properties) -> Result<()> {
     properties
-        .to_writer(std::io::stdout())
-        .context("Serialising properties for display")?;
+        .to_writer_with_options(std::io::stdout(), WriteOptions::default().key_order(KeyOrder::Preserve))
+        .context("Serialising properties for display")
+}
+
+/// Show, set or clear the on-activate command of a configuration
+pub fn hook(name: &str, command: Option<&str>, clear: bool) -> Result<()> {
+    let mut store = open_store()?;
+
+    if store.find_by_name(name)?.is_none() {
+        bail!(gcloud_ctx::Error::UnknownConfiguration(name.to_owned()));
+    }
+
+    let mut metadata = store.metadata(name)?;
+
+    match (command, clear) {
+        (Some(command), _) => {
+            metadata.set(Metadata::ON_ACTIVATE_COMMAND, command);
+            store.set_metadata(name, &metadata)?;
+            println!("Set the on-activate command for '{}'", name.blue());
+        }
+        (None, true) => {
+            metadata.remove(Metadata::ON_ACTIVATE_COMMAND);
+            store.set_metadata(name, &metadata)?;
+            println!("Cleared the on-activate command for '{}'", name.blue());
+        }
+        (None, false) => match metadata.on_activate_command() {
+            Some(command) => println!("{}", command),
+            None => eprintln!("Configuration '{}' has no on-activate command", name.blue()),
+        },
+    }
+
+    Ok(())
+}
+
+/// Inspect a configuration store at the given location without modifying it
+pub fn inspect(location: &Path) -> Result<()> {
+    let store = match ConfigurationStore::builder().location(location).read_only(true).open() {
+        Ok(store) => store,
+        Err(gcloud_ctx::Error::PermissionDenied { .. }) => bail!(
+            "Permission denied reading the configuration store at {}. {}",
+            location.display(),
+            sudo_hint(location)
+        ),
+        Err(e) => return Err(e.into()),
+    };
+
+    store.warnings()?.into_iter().for_each(warnings::emit);
+
+    println!("Configuration store at {} (read-only)", location.display());
+
+    for config in store.configurations()? {
+        if store.is_active(config) {
+            println!("{} {}", "*".blue(), config.name().blue());
+        } else {
+            println!("  {}", config.name());
+        }
+    }
+
+    eprintln!(
+        "Run other commands against this store without modifying it with: CLOUDSDK_CONFIG={} gctx --read-only <command>",
+        location.display()
+    );
+
+    Ok(())
+}
+
+/// Suggest how to read a configuration store which belongs to another user
+#[cfg(unix)]
+fn sudo_hint(location: &Path) -> String {
+    use std::os::unix::fs::MetadataExt;
+
+    match fs::metadata(location) {
+        Ok(metadata) => format!(
+            "Try again as its owner, e.g. sudo -u '#{}' gctx inspect --store {}",
+            metadata.uid(),
+            location.display()
+        ),
+        Err(_) => "Try again with sudo".to_owned(),
+    }
+}
+
+/// Suggest how to read a configuration store which belongs to another user
+#[cfg(not(unix))]
+fn sudo_hint(_location: &Path) -> String {
+    "Try again from an elevated prompt".to_owned()
+}
+
+/// Export every configuration to a tar archive
+pub fn export_archive(path: &Path) -> Result<()> {
+    let store = open_store()?;
+
+    let mut contents = Vec::new();
+    store.export_archive(&mut contents)?;
+    fs::write(path, contents).with_context(|| format!("Writing {}", path.display()))?;
+
+    println!(
+        "Exported {} configuration(s) to {}",
+        store.configurations()?.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+/// Import every configuration in a tar archive, resolving any name collisions with the given policy
+pub fn import_archive(path: &Path, on_conflict: OnConflict) -> Result<()> {
+    let conflict = match on_conflict {
+        OnConflict::Abort => ConflictAction::Abort,
+        OnConflict::Keep => ConflictAction::Skip,
+        OnConflict::Overwrite => ConflictAction::Overwrite,
+        OnConflict::Rename => ConflictAction::Rename,
+        OnConflict::Ask => bail!("--on-conflict ask isn't supported when importing an archive"),
+    };
+
+    let mut store = open_store()?;
+    let contents = fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+    let previous = store.active().map(str::to_owned);
+
+    for configuration in store.import_archive(contents.as_slice(), conflict)? {
+        println!("Successfully imported configuration '{}'", configuration.name().blue());
+    }
+
+    if let Some(active) = store.active().filter(|active| previous.as_deref() != Some(*active)) {
+        println!("Configuration '{}' is now active", active.blue());
+    }
+
+    Ok(())
+}
+
+/// Export a configuration file to the given path, or to stdout
+pub fn export(name: &str, path: Option<&Path>) -> Result<()> {
+    let store = open_store()?;
+
+    match path {
+        Some(path) => {
+            let mut contents = Vec::new();
+            store.export(name, &mut contents)?;
+            fs::write(path, contents).with_context(|| format!("Writing {}", path.display()))?;
+            eprintln!("Exported configuration '{}' to {}", name.blue(), path.display());
+        }
+        None => store.export(name, io::stdout().lock())?,
+    }
+
+    Ok(())
+}
+
+/// Import a configuration from a file, or from stdin if the path is `-`, resolving any name collision with the
+/// given policy
+pub fn import(path: &Path, name: Option<&str>, on_conflict: OnConflict) -> Result<()> {
+    let mut store = open_store()?;
+    let from_stdin = path == Path::new("-");
+
+    let contents = if from_stdin {
+        io::read_to_string(io::stdin().lock()).context("Reading stdin")?
+    } else {
+        fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?
+    };
+
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None if from_stdin => bail!("Use --name to name a configuration imported from stdin"),
+        None => path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.trim_start_matches("config_").to_owned())
+            .context("Unable to determine a configuration name from the file, use --name to set one")?,
+    };
+
+    let incoming: Option<Properties> = contents.parse().ok();
+    let resolver =
+        |store: &ConfigurationStore, name: &str| resolve_conflict(store, name, incoming.as_ref(), on_conflict);
+
+    let imported = match store.import(&name, contents.as_bytes(), resolver) {
+        Err(gcloud_ctx::Error::ExistingConfiguration(existing)) if existing == name => bail!(
+            "A configuration named '{}' already exists. Use --on-conflict to choose how to resolve it",
+            name
+        ),
+        result => result?,
+    };
+
+    let imported = match imported {
+        Some(configuration) => configuration,
+        None => {
+            println!("Kept existing configuration '{}'", name.yellow());
+            return Ok(());
+        }
+    };
+
+    println!("Successfully imported configuration '{}'", imported.name().blue());
+    print_backup_hint(&store);
+
+    Ok(())
+}
+
+/// Decide how to resolve a name collision between an existing configuration and an incoming one, which can only be
+/// compared when its properties are known
+fn resolve_conflict(
+    store: &ConfigurationStore,
+    name: &str,
+    incoming: Option<&Properties>,
+    on_conflict: OnConflict,
+) -> gcloud_ctx::Result<Resolution> {
+    let resolution = match on_conflict {
+        OnConflict::Abort => ConflictAction::Abort.resolve(store, name)?,
+        OnConflict::Keep => ConflictAction::Skip.resolve(store, name)?,
+        OnConflict::Overwrite => ConflictAction::Overwrite.resolve(store, name)?,
+        OnConflict::Rename => ConflictAction::Rename.resolve(store, name)?,
+        OnConflict::Ask => loop {
+            let items = [
+                "Keep the existing configuration",
+                "Overwrite the existing configuration",
+                "Use a different name",
+                "Show differences",
+            ];
+            let items = match incoming {
+                Some(_) => &items[..],
+                None => &items[..3],
+            };
+
+            let selection = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("A configuration named '{}' already exists", name))
+                .items(items)
+                .default(0)
+                .interact_on_opt(&Term::stderr())?;
+
+            match selection {
+                Some(0) => break Resolution::Skip,
+                Some(1) => break Resolution::Overwrite,
+                Some(2) => {
+                    let new_name = Input::<String>::new()
+                        .with_prompt("New name".blue().to_string())
+                        .default(store.generate_name(name)?.into())
+                        .interact_on(&Term::stderr())?;
+
+                    break Resolution::Rename(new_name);
+                }
+                Some(_) => {
+                    if let Some(incoming) = incoming {
+                        print_differences(store, name, incoming)?;
+                    }
+                }
+                None => break Resolution::Abort,
+            }
+        },
+    };
+
+    Ok(resolution)
+}
+
+/// Print the differences between an existing configuration and an incoming one
+fn print_differences(store: &ConfigurationStore, name: &str, incoming: &Properties) -> gcloud_ctx::Result<()> {
+    let existing = store.describe(name)?;
+    let changes = existing.diff(incoming);
+
+    if changes.is_empty() {
+        eprintln!("The configurations have the same properties");
+    }
+
+    for change in changes {
+        eprintln!("  {}", change);
+    }
+
+    Ok(())
+}
+
+/// Normalise the named configuration, or all configurations, reporting which ones changed
+pub fn normalize(name: Option<&str>, all: bool) -> Result<()> {
+    let store = open_store()?;
+
+    let names: Vec<String> = if all {
+        store.configurations()?.map(|c| c.name().to_owned()).collect()
+    } else {
+        vec![name.context("A configuration name or --all is required")?.to_owned()]
+    };
+
+    for name in names {
+        if store.normalize(&name, &WriteOptions::default())? {
+            println!("Normalized configuration '{}'", name.blue());
+        } else {
+            println!("Configuration '{}' is already normalized", name.blue());
+        }
+    }
+
+    Ok(())
+}
+
+/// Pin a configuration so that it can't be modified
+pub fn pin(name: &str) -> Result<()> {
+    let mut store = open_store()?;
+
+    if store.pin(name)? {
+        println!("Pinned configuration '{}'", name.blue());
+    } else {
+        println!("Configuration '{}' is already pinned", name.blue());
+    }
+
+    Ok(())
+}
+
+/// Print the active configuration for a shell prompt, with a marker if it's dangerous
+///
+/// A terminal-local activation takes precedence, just as it does for gcloud
+pub fn prompt(color: bool) -> Result<()> {
+    let store = open_store_lazy()?;
+    let name = match std::env::var(ACTIVE_CONFIG_VAR) {
+        Ok(name) => name,
+        Err(_) => match store.active() {
+            Some(active) => active.to_owned(),
+            None => return Ok(()),
+        },
+    };
+
+    // the variable could be set to anything, so never use it to build a path unless it's a real name
+    if !Configuration::is_valid_name(&name) {
+        return Ok(());
+    }
+
+    match RiskPolicy::default().assess(&store.metadata(&name)?) {
+        Risk::Dangerous if color => {
+            // prompts capture the output so it's never a terminal, but the colour is explicitly requested
+            colored::control::set_override(true);
+            println!("{}", format!("{} !", name).red().bold());
+        }
+        Risk::Dangerous => println!("{} !", name),
+        Risk::Normal => println!("{}", name),
+    }
+
+    Ok(())
+}
+
+/// Tell the user how to get back a configuration which was overwritten, if one was
+fn print_backup_hint(store: &ConfigurationStore) {
+    if let Some(backup) = store.last_backup() {
+        println!(
+            "The previous version of '{}' was backed up, restore it with 'gctx restore {}'",
+            backup.name().yellow(),
+            backup.name()
+        );
+    }
+}
+
+/// Refresh the embedded data snapshots using gcloud
+pub fn refresh_data() -> Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        bail!("Unable to refresh data in read-only mode");
+    }
+
+    let regions = regions::fetch()?;
+    let count = regions.len();
+    let path = data::save(&data::REGIONS, regions)?;
+
+    println!(
+        "Refreshed {} {} in {}",
+        count,
+        data::REGIONS.name.blue(),
+        path.display()
+    );
 
     Ok(())
 }
 
 /// Rename a configuration
-pub fn rename(old_name: &str, new_name: &str, conflict: ConflictAction) -> Result<()> {
-    let mut store = ConfigurationStore::with_default_location()?;
-    store.rename(old_name, new_name, conflict)?;
+pub fn rename(old_name: &str, new_name: &str, on_conflict: OnConflict) -> Result<()> {
+    let mut store = open_store()?;
+    let incoming = match on_conflict {
+        OnConflict::Ask => Some(store.describe(old_name)?),
+        _ => None,
+    };
+    let resolver =
+        |store: &ConfigurationStore, name: &str| resolve_conflict(store, name, incoming.as_ref(), on_conflict);
+
+    let renamed = match store.rename(old_name, new_name, resolver)? {
+        Some(configuration) => configuration,
+        None => {
+            println!("Kept existing configuration '{}'", new_name.yellow());
+            return Ok(());
+        }
+    };
 
     println!(
         "Successfully renamed configuration '{}' to '{}'",
         old_name.yellow(),
-        new_name.blue()
+        renamed.name().blue()
     );
+    print_backup_hint(&store);
+
+    if store.is_active(&renamed) {
+        println!("Configuration '{}' is now active", renamed.name().blue());
+    }
+
+    Ok(())
+}
+
+/// Restore the most recent backup of a configuration
+pub fn restore(name: &str) -> Result<()> {
+    let mut store = open_store()?;
+    let replaced = store.find_by_name(name)?.is_some();
+    store.restore(name)?;
+
+    println!("Restored the previous version of configuration '{}'", name.blue());
+
+    if replaced {
+        println!(
+            "The version it replaced was backed up, run 'gctx restore {}' again to undo",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+/// Run a command using a configuration without activating it
+///
+/// The exit code of the command is passed on if it fails
+pub fn run(name: &str, command: &[String], yes_prod: bool) -> Result<()> {
+    let store = open_store()?;
+
+    if store.find_by_name(name)?.is_none() {
+        bail!(gcloud_ctx::Error::UnknownConfiguration(name.to_owned()));
+    }
+
+    if RiskPolicy::default().assess(&store.metadata(name)?) == Risk::Dangerous && !yes_prod {
+        bail!(
+            "Configuration '{}' is dangerous, e.g. tagged prod. Use --yes-prod to run the command with it",
+            name
+        );
+    }
+
+    // environment variables still override the properties of the configuration, just as they would for gcloud
+    check_env_conflicts(&store, name);
+
+    run_command(
+        &with_configuration_flag(command, name),
+        [(ACTIVE_CONFIG_VAR.to_owned(), name.to_owned())],
+    )
+}
+
+/// Add `--configuration <name>` to a gcloud command line so that gcloud selects the configuration exactly as it
+/// would for its own flag, unless the command isn't gcloud or already chooses a configuration
+fn with_configuration_flag(command: &[String], name: &str) -> Vec<String> {
+    let mut command = command.to_vec();
+
+    let is_gcloud = command
+        .first()
+        .and_then(|program| Path::new(program).file_stem())
+        .is_some_and(|stem| stem == "gcloud");
+    let has_flag = command
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--configuration" || arg.starts_with("--configuration="));
+
+    if is_gcloud && !has_flag {
+        // global flags are accepted anywhere, and this way they can't end up after a -- meant for another command
+        command.insert(1, format!("--configuration={}", name));
+    }
+
+    command
+}
+
+/// Build the properties for an ephemeral configuration, where only the properties given are set
+pub fn build_ephemeral_properties(
+    project: Option<&str>,
+    account: Option<&str>,
+    zone: Option<&str>,
+    region: Option<&str>,
+) -> Result<Properties> {
+    let mut builder = PropertiesBuilder::default();
+
+    if let Some(project) = project {
+        builder.project(&project.parse()?);
+    }
+
+    if let Some(account) = account {
+        builder.account(&account.parse()?);
+    }
+
+    if let Some(zone) = zone {
+        builder.zone(zone);
+    }
+
+    if let Some(region) = region {
+        builder.region(region);
+    }
+
+    validate_locations(&mut builder)?;
+
+    Ok(builder.build())
+}
+
+/// Check the zone and region of new properties, as strictly as GCTX_LOCATION_VALIDATION says
+fn validate_locations(builder: &mut PropertiesBuilder) -> Result<()> {
+    builder.location_validation(Settings::from_env()?.location_validation);
+
+    for warning in builder.validate()? {
+        warnings::emit(warning);
+    }
+
+    Ok(())
+}
+
+/// Run a command using a configuration which is never saved to the store
+///
+/// Properties which aren't given are taken from the active configuration by gcloud as usual
+pub fn run_ephemeral(properties: Properties, command: &[String]) -> Result<()> {
+    let configuration = ConfigurationStore::virtual_config("ephemeral", properties)?;
+    run_command(command, configuration.env_vars())
+}
+
+/// Run a command with the given extra environment variables, exiting with its exit code if it fails
+fn run_command(command: &[String], env: impl IntoIterator<Item = (String, String)>) -> Result<()> {
+    let (program, args) = command.split_first().context("A command to run is required")?;
+
+    let status = Command::new(program)
+        .args(args)
+        .envs(env)
+        .status()
+        .with_context(|| format!("Unable to run '{}'", program))?;
 
-    if let Some(configuration) = store.find_by_name(new_name) {
-        if store.is_active(configuration) {
-            println!("Configuration '{}' is now active", new_name.blue());
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Suggest a region and zone for new configurations
+pub fn suggest_region() -> Result<()> {
+    let suggestion = regions::suggest(&Settings::from_env()?);
+
+    println!("Region: {}", suggestion.region.blue());
+    match suggestion.zone {
+        Some(zone) => println!("Zone:   {}", zone.blue()),
+        None => println!("Zone:   {}", "unknown, the region isn't in the catalogue".yellow()),
+    }
+
+    println!("Suggested based on {}", suggestion.reason);
+
+    Ok(())
+}
+
+/// Show, add or remove the tags of a configuration
+pub fn tag(name: &str, tags: &[String], remove: bool) -> Result<()> {
+    let mut store = open_store()?;
+
+    if store.find_by_name(name)?.is_none() {
+        bail!(gcloud_ctx::Error::UnknownConfiguration(name.to_owned()));
+    }
+
+    let mut metadata = store.metadata(name)?;
+
+    if !tags.is_empty() {
+        let mut updated: Vec<String> = metadata.tags().into_iter().map(str::to_owned).collect();
+
+        if remove {
+            updated.retain(|t| !tags.contains(t));
+        } else {
+            for tag in tags {
+                if !updated.contains(tag) {
+                    updated.push(tag.clone());
+                }
+            }
+        }
+
+        metadata.set_tags(updated.iter().map(String::as_str));
+        store.set_metadata(name, &metadata)?;
+    }
+
+    for tag in metadata.tags() {
+        println!("{}", tag);
+    }
+
+    Ok(())
+}
+
+/// Unpin a configuration so that it can be modified again
+pub fn unpin(name: &str) -> Result<()> {
+    let mut store = open_store()?;
+
+    if store.unpin(name)? {
+        println!("Unpinned configuration '{}'", name.blue());
+    } else {
+        println!("Configuration '{}' isn't pinned", name.blue());
+    }
+
+    Ok(())
+}
+
+/// Use a configuration, either for all terminals or only the current terminal
+///
+/// Shell statements are printed to stdout so that they can be evaluated by a wrapper function, and
+/// all other messages are printed to stderr
+pub fn use_configuration(name: &str, scope: ActivationScope, shell: Shell, run_hooks: bool) -> Result<()> {
+    let mut store = open_store()?;
+
+    match scope {
+        ActivationScope::Global => {
+            store.activate(name)?;
+
+            // a terminal-local activation would otherwise continue to take precedence
+            if std::env::var_os(ACTIVE_CONFIG_VAR).is_some() {
+                println!("{}", shell.unset_env(ACTIVE_CONFIG_VAR));
+            }
+
+            eprintln!("Successfully activated '{}' in all terminals", name.blue());
+            check_sdk_version(&store, name)?;
+            check_env_conflicts(&store, name);
+        }
+        ActivationScope::Local => {
+            if store.find_by_name(name)?.is_none() {
+                bail!(gcloud_ctx::Error::UnknownConfiguration(name.to_owned()));
+            }
+
+            println!("{}", shell.set_env(ACTIVE_CONFIG_VAR, name));
+
+            if io::stdout().is_terminal() {
+                eprintln!(
+                    "{}",
+                    "Terminal-local activation must be evaluated by your shell, e.g. eval \"$(gctx use --local <name>)\""
+                        .yellow()
+                );
+            } else {
+                eprintln!("Successfully activated '{}' in this terminal", name.blue());
+            }
+
+            check_sdk_version(&store, name)?;
+            check_env_conflicts(&store, name);
         }
     }
 
+    if run_hooks {
+        hooks::run_on_activate(&store, name)?;
+    }
+
     Ok(())
 }
+
+/// Record the installed gcloud SDK version in the metadata of a configuration
+fn record_installed_sdk_version(store: &mut ConfigurationStore, name: &str) -> Result<()> {
+    match sdk::installed_version() {
+        Some(version) => {
+            let mut metadata = store.metadata(name)?;
+            metadata.set(Metadata::SDK_VERSION, &version);
+            store.set_metadata(name, &metadata)?;
+        }
+        None => warnings::emit("Unable to detect the installed gcloud SDK version, so it wasn't recorded"),
+    }
+
+    Ok(())
+}
+
+/// Warn if a configuration was created with a much older or newer gcloud SDK than the one installed
+fn check_sdk_version(store: &ConfigurationStore, name: &str) -> Result<()> {
+    let metadata = store.metadata(name)?;
+
+    if let (Some(recorded), Some(installed)) = (metadata.sdk_version(), sdk::installed_version()) {
+        if sdk::has_drifted(recorded, &installed) {
+            warnings::emit(format!(
+                "Configuration '{}' was created with gcloud SDK {} but {} is installed, so it may not behave the same",
+                name, recorded, installed
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Warn about any CLOUDSDK_* environment variables which override properties of a configuration
+///
+/// This is best effort so that commands such as `current` still work if the configuration can't be read
+fn check_env_conflicts(store: &ConfigurationStore, name: &str) {
+    if let Ok(properties) = store.describe(name) {
+        properties
+            .env_conflicts()
+            .into_iter()
+            .map(Warning::EnvConflict)
+            .for_each(warnings::emit);
+    }
+}
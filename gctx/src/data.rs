@@ -0,0 +1,94 @@
+use crate::warnings;
+use anyhow::{Context, Result};
+use gcloud_ctx::StateDirectory;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// Version of the snapshot format understood by this version of gctx. Refreshed snapshots in any other format are
+/// ignored in favour of the embedded snapshot, e.g. after upgrading gctx
+const FORMAT_VERSION: u32 = 1;
+
+/// External data which gctx embeds a snapshot of, so that it works fully offline
+pub struct Asset {
+    /// Name of the asset, which is also the name of its refreshed snapshot file
+    pub name: &'static str,
+
+    /// Snapshot embedded at build time
+    embedded: &'static str,
+}
+
+/// Catalogue of regions and the hints used to suggest them
+pub const REGIONS: Asset = Asset {
+    name: "regions",
+    embedded: include_str!("../data/regions.json"),
+};
+
+/// A versioned snapshot of an asset
+#[derive(Debug, Deserialize, Serialize)]
+struct Snapshot<T> {
+    /// Format version of the snapshot
+    version: u32,
+
+    /// Entries in the asset
+    items: Vec<T>,
+}
+
+/// Just the version of a snapshot, read first so that snapshots in other formats can be skipped
+#[derive(Debug, Deserialize)]
+struct Header {
+    /// Format version of the snapshot
+    version: u32,
+}
+
+/// Load an asset, preferring a snapshot saved by `gctx refresh-data` over the embedded snapshot
+pub fn load<T: DeserializeOwned>(asset: &Asset) -> Vec<T> {
+    if let Some(items) = refreshed(asset) {
+        return items;
+    }
+
+    let snapshot: Snapshot<T> = serde_json::from_str(asset.embedded).expect("embedded snapshots are valid");
+    snapshot.items
+}
+
+/// Save a refreshed snapshot of an asset, returning where it was saved
+pub fn save<T: Serialize>(asset: &Asset, items: Vec<T>) -> Result<PathBuf> {
+    let path = path(asset)?;
+    let snapshot = Snapshot {
+        version: FORMAT_VERSION,
+        items,
+    };
+
+    // written atomically so a failed refresh never leaves a half-written snapshot behind
+    StateDirectory::write(&path, serde_json::to_string_pretty(&snapshot)?)
+        .with_context(|| format!("Unable to save {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Load the refreshed snapshot of an asset, if there is one in a format this version understands
+fn refreshed<T: DeserializeOwned>(asset: &Asset) -> Option<Vec<T>> {
+    let path = path(asset).ok()?;
+    let contents = fs::read_to_string(&path).ok()?;
+
+    let snapshot = serde_json::from_str::<Header>(&contents).and_then(|header| match header.version {
+        FORMAT_VERSION => serde_json::from_str::<Snapshot<T>>(&contents).map(Some),
+        _ => Ok(None),
+    });
+
+    match snapshot {
+        Ok(snapshot) => snapshot.map(|s| s.items),
+        Err(_) => {
+            warnings::emit(format!(
+                "Ignoring unreadable data in {}, run 'gctx refresh-data' to fix it",
+                path.display()
+            ));
+            None
+        }
+    }
+}
+
+/// Path of the refreshed snapshot of an asset, which is shared by all stores
+fn path(asset: &Asset) -> Result<PathBuf> {
+    let root = StateDirectory::root()?;
+    Ok(root.join("data").join(format!("{}.json", asset.name)))
+}
@@ -0,0 +1,185 @@
+use crate::arguments::Opts;
+use anyhow::{bail, Result};
+use clap::{ArgEnum, CommandFactory};
+use colored::*;
+
+/// Format used to print examples
+#[derive(ArgEnum, Copy, Clone, Debug, PartialEq)]
+pub enum ExampleFormat {
+    /// Human-readable text
+    Text,
+
+    /// Markdown, e.g. to paste into a team wiki
+    Markdown,
+}
+
+/// A real-world flow made up of copy-pasteable steps
+pub struct Example {
+    /// Topic name used to look the example up, e.g. `gctx examples ci`
+    pub topic: &'static str,
+
+    /// Short title
+    pub title: &'static str,
+
+    /// When the flow is useful
+    pub description: &'static str,
+
+    /// Steps as pairs of explanation and command
+    pub steps: &'static [(&'static str, &'static str)],
+}
+
+/// Registry of examples, in the order they're listed
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        topic: "onboarding",
+        title: "Onboarding a new project",
+        description: "Set up a configuration for a project you've just been given access to",
+        steps: &[
+            (
+                "Create a configuration, picking the project from those your account can access",
+                "gctx create my-project --pick-project --account a.user@example.org --zone europe-west1-d --activate",
+            ),
+            ("Check the properties are what you expect", "gctx describe my-project"),
+            (
+                "Fetch cluster credentials whenever the configuration is activated",
+                "gctx hook my-project \"gcloud container clusters get-credentials my-cluster\"",
+            ),
+        ],
+    },
+    Example {
+        topic: "prod-staging",
+        title: "Splitting production and staging",
+        description: "Keep separate configurations per environment and make production hard to use by accident",
+        steps: &[
+            (
+                "Create the staging configuration",
+                "gctx create app-staging --project app-staging --account a.user@example.org --zone europe-west1-d",
+            ),
+            ("Copy it for production", "gctx copy app-staging app-prod"),
+            ("Mark production as dangerous", "gctx tag app-prod prod"),
+            (
+                "Show the active configuration in your prompt, highlighting production",
+                "PS1='$(gctx prompt --color) '$PS1",
+            ),
+            (
+                "Work in production in this terminal only",
+                "eval \"$(gctx use app-prod --local)\"",
+            ),
+        ],
+    },
+    Example {
+        topic: "ci",
+        title: "Using gctx in CI",
+        description: "Create throwaway configurations in pipelines without touching other jobs",
+        steps: &[
+            (
+                "Create a uniquely named configuration and capture its name",
+                "NAME=$(gctx create --auto-name --prefix ci --project my-project --account ci@my-project.iam.gserviceaccount.com --zone europe-west1-d)",
+            ),
+            (
+                "Use it for this job only",
+                "eval \"$(gctx use \"$NAME\" --local)\"",
+            ),
+            ("Fail the job on any warning", "gctx describe \"$NAME\" --strict --output json"),
+            ("Clean up afterwards", "gctx delete \"$NAME\""),
+        ],
+    },
+];
+
+/// Find an example by topic
+pub fn find(topic: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|e| e.topic == topic)
+}
+
+/// Print the named example, or all of them
+pub fn print(topic: Option<&str>, format: ExampleFormat) -> Result<()> {
+    let examples: Vec<&Example> = match topic {
+        Some(topic) => match find(topic) {
+            Some(example) => vec![example],
+            None => bail!("Unknown example '{}', expected one of: {}", topic, topics()),
+        },
+        None => EXAMPLES.iter().collect(),
+    };
+
+    for (index, example) in examples.into_iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+
+        match format {
+            ExampleFormat::Text => print_text(example),
+            ExampleFormat::Markdown => print_markdown(example),
+        }
+    }
+
+    Ok(())
+}
+
+/// Show help for a command or an example topic, or general help with the list of topics
+pub fn help(topic: Option<&str>) -> Result<()> {
+    let mut command = Opts::command();
+
+    match topic {
+        None => {
+            command.print_help()?;
+            println!();
+            println!("Run 'gctx help <command>' for help with a command, or 'gctx help <topic>' for an example.");
+            println!("Example topics: {}", topics());
+        }
+        Some(topic) => {
+            if command.find_subcommand(topic).is_some() {
+                // let clap render the help so that it's identical to 'gctx <command> --help'
+                if let Err(e) = command.try_get_matches_from(["gctx", topic, "--help"]) {
+                    e.print()?;
+                }
+            } else if let Some(example) = find(topic) {
+                print_text(example);
+            } else {
+                bail!(
+                    "No help for '{}', expected a command or one of the example topics: {}",
+                    topic,
+                    topics()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Comma separated list of example topics
+fn topics() -> String {
+    EXAMPLES.iter().map(|e| e.topic).collect::<Vec<_>>().join(", ")
+}
+
+/// Print an example as text
+fn print_text(example: &Example) {
+    println!("{} ({})", example.title.blue(), example.topic);
+    println!("{}", example.description);
+
+    for (explanation, command) in example.steps {
+        println!();
+        println!("  # {}", explanation);
+        println!("  {}", command);
+    }
+}
+
+/// Print an example as markdown
+fn print_markdown(example: &Example) {
+    println!("## {}", example.title);
+    println!();
+    println!("{}", example.description);
+    println!();
+    println!("```bash");
+
+    for (index, (explanation, command)) in example.steps.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+
+        println!("# {}", explanation);
+        println!("{}", command);
+    }
+
+    println!("```");
+}
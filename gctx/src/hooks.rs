@@ -0,0 +1,52 @@
+use crate::{shell::ACTIVE_CONFIG_VAR, warnings};
+use anyhow::Result;
+use colored::*;
+use gcloud_ctx::ConfigurationStore;
+use std::{io, process::Command};
+
+/// Run the on-activate command of a configuration, if it has one
+///
+/// Output is streamed to stderr so it never mixes with statements printed for the shell to evaluate. A failing
+/// command is reported as a warning and doesn't revert the activation.
+pub fn run_on_activate(store: &ConfigurationStore, name: &str) -> Result<()> {
+    let metadata = store.metadata(name)?;
+
+    let command = match metadata.on_activate_command() {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+
+    eprintln!("Running on-activate command for '{}': {}", name.blue(), command);
+
+    let status = shell_command(command)
+        .env(ACTIVE_CONFIG_VAR, name)
+        .stdout(io::stderr())
+        .status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => warnings::emit(format!(
+            "On-activate command for '{}' failed ({}), the configuration is still active",
+            name, status
+        )),
+        Err(e) => warnings::emit(format!("Unable to run the on-activate command for '{}': {}", name, e)),
+    }
+
+    Ok(())
+}
+
+/// Build a command which runs the given command line using the platform shell
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Build a command which runs the given command line using the platform shell
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
@@ -1,70 +1,259 @@
 mod arguments;
+mod batch;
 mod commands;
+mod data;
+mod examples;
 mod fzf;
+mod hooks;
+mod onboard;
+mod output;
+mod projects;
+mod regions;
+mod sdk;
+mod settings;
+mod shell;
+mod shortcut;
+mod timings;
+mod warnings;
 
-use anyhow::Result;
-use arguments::{Opts, SubCommand};
+use anyhow::{anyhow, bail, Result};
+use arguments::{ArchiveAction, CacheAction, Opts, SubCommand};
 use clap::Parser;
+use commands::OnConflict;
+use settings::{ActivationScope, Settings};
 
 fn main() -> Result<()> {
     let opts = Opts::parse();
-    run(opts)?;
+    let show_timings = opts.timings;
+    let strict = opts.strict;
+
+    if show_timings {
+        timings::record_store_operations();
+    }
+
+    let result = timings::measure("total", || run(opts)).map_err(with_hint);
+
+    if show_timings {
+        timings::report();
+    }
+
+    result?;
+
+    if strict && warnings::count() > 0 {
+        bail!("{} warning(s) emitted in strict mode", warnings::count());
+    }
+
     Ok(())
 }
 
 /// Run the application using the command line arguments
 pub fn run(opts: Opts) -> Result<()> {
     set_virtual_terminal();
+    let run_hooks = opts.run_hooks;
+    commands::set_read_only(opts.read_only);
+    commands::set_create_if_missing(opts.create_if_missing);
+    commands::set_unpin_first(opts.unpin_first);
+
+    execute(opts.context, opts.subcmd, run_hooks)
+}
 
-    if let Some(name) = opts.context {
+/// Execute the requested command, activating the given context if one was passed instead
+fn execute(context: Option<String>, subcmd: Option<SubCommand>, run_hooks: bool) -> Result<()> {
+    if let Some(name) = context {
         // shortcut for activate
-        commands::activate(&name)?;
+        shortcut::activate(&name, run_hooks)?;
         return Ok(());
-    } else if let Some(subcmd) = opts.subcmd {
+    } else if let Some(subcmd) = subcmd {
         match subcmd {
-            SubCommand::Activate { name } => match name {
-                Some(name) => commands::activate(&name)?,
-                None => commands::activate(&fzf::fuzzy_find_config()?)?,
+            SubCommand::Activate { name, stdin, gke } => match name {
+                Some(name) => commands::activate(&name, run_hooks, gke)?,
+                None if stdin => commands::activate_stdin(run_hooks, gke)?,
+                None => commands::activate(&fzf::fuzzy_find_config()?, run_hooks, gke)?,
             },
+            SubCommand::Archive {
+                action: ArchiveAction::Export { path },
+            } => commands::export_archive(&path)?,
+            SubCommand::Archive {
+                action: ArchiveAction::Import { path, on_conflict },
+            } => commands::import_archive(&path, on_conflict)?,
+            SubCommand::Cache {
+                action: CacheAction::Names,
+            } => commands::cached_names()?,
+            SubCommand::Cache {
+                action: CacheAction::Rebuild,
+            } => commands::rebuild_cache()?,
+            SubCommand::Cleanup { advise, yes } => commands::cleanup(advise, yes)?,
             SubCommand::Copy {
                 src_name,
                 dest_name,
                 activate,
                 force,
+                on_conflict,
+                project,
             } => {
-                commands::copy(&src_name, &dest_name, force.into(), activate.into())?;
+                let on_conflict = OnConflict::or_force(on_conflict, force);
+                commands::copy(&src_name, &dest_name, project.as_deref(), on_conflict, activate.into())?;
             }
             SubCommand::Create { interactive: true, .. } => commands::create_interactive()?,
             SubCommand::Create {
                 interactive: false,
+                auto_name: true,
+                prefix,
+                project,
+                pick_project,
+                account,
+                zone,
+                region,
+                activate,
+                record_sdk_version,
+                ..
+            } => {
+                // safe to unwrap these because they are set as required in clap
+                let project = resolve_project(project, pick_project)?;
+                let properties =
+                    commands::build_properties(&project, &account.unwrap(), &zone.unwrap(), region.as_deref())?;
+
+                commands::create_auto_named(&prefix, &properties, activate.into(), record_sdk_version)?;
+            }
+            SubCommand::Create {
+                interactive: false,
+                auto_name: false,
                 name,
                 project,
+                pick_project,
                 account,
                 zone,
                 region,
                 activate,
                 force,
+                on_conflict,
+                record_sdk_version,
+                ..
             } => {
+                // safe to unwrap these because they are set as required in clap
+                let project = resolve_project(project, pick_project)?;
+                let properties =
+                    commands::build_properties(&project, &account.unwrap(), &zone.unwrap(), region.as_deref())?;
+
                 commands::create(
-                    // safe to unwrap these because they are set as required in clap
                     &name.unwrap(),
-                    &project.unwrap(),
-                    &account.unwrap(),
-                    &zone.unwrap(),
-                    region.as_deref(),
-                    force.into(),
+                    &properties,
+                    OnConflict::or_force(on_conflict, force),
                     activate.into(),
+                    record_sdk_version,
                 )?;
             }
             SubCommand::Current => commands::current()?,
-            SubCommand::Delete { name } => commands::delete(&name)?,
-            SubCommand::Describe { name } => commands::describe(name.as_deref())?,
-            SubCommand::List => commands::list()?,
+            SubCommand::Deactivate => commands::deactivate()?,
+            SubCommand::Delete {
+                names,
+                stdin: false,
+                fallback,
+                ..
+            } => match names.as_slice() {
+                [name] => commands::delete(name, fallback)?,
+                names => commands::delete_many(names, fallback)?,
+            },
+            SubCommand::Delete {
+                stdin: true, fallback, ..
+            } => commands::delete_stdin(fallback)?,
+            SubCommand::Describe {
+                name,
+                stdin: false,
+                all: false,
+                output,
+            } => commands::describe(name.as_deref(), output)?,
+            SubCommand::Describe { all: true, output, .. } => commands::describe_all(output)?,
+            SubCommand::Describe { stdin: true, .. } => commands::describe_stdin()?,
+            SubCommand::Examples { topic, output } => examples::print(topic.as_deref(), output)?,
+            SubCommand::Export { name, path } => commands::export(&name, path.as_deref())?,
+            SubCommand::Help { topic } => examples::help(topic.as_deref())?,
+            SubCommand::Hook { name, command, clear } => commands::hook(&name, command.as_deref(), clear)?,
+            SubCommand::Import {
+                path,
+                name,
+                on_conflict,
+            } => commands::import(&path, name.as_deref(), on_conflict.unwrap_or_default())?,
+            SubCommand::Inspect { store } => commands::inspect(&store)?,
+            SubCommand::List {
+                compare: Some(stores),
+                sort,
+                ..
+            } => commands::list_compare(&stores, sort.into())?,
+            SubCommand::List {
+                names_only,
+                output,
+                sort,
+                compare: None,
+                name,
+                project,
+                account,
+                zone,
+            } => commands::list(
+                names_only,
+                output,
+                sort.into(),
+                name.as_deref(),
+                project.as_deref(),
+                account.as_deref(),
+                zone.as_deref(),
+            )?,
+            SubCommand::Normalize { name, all } => commands::normalize(name.as_deref(), all)?,
+            SubCommand::Onboard { templates } => onboard::run(templates.as_deref())?,
+            SubCommand::Pin { name } => commands::pin(&name)?,
+            SubCommand::Prompt { color } => commands::prompt(color)?,
+            SubCommand::RefreshData => commands::refresh_data()?,
             SubCommand::Rename {
                 old_name,
                 new_name,
                 force,
-            } => commands::rename(&old_name, &new_name, force.into())?,
+                on_conflict,
+            } => commands::rename(&old_name, &new_name, OnConflict::or_force(on_conflict, force))?,
+            SubCommand::Restore { name } => commands::restore(&name)?,
+            SubCommand::Run {
+                ephemeral: true,
+                project,
+                account,
+                zone,
+                region,
+                command,
+                ..
+            } => {
+                let properties = commands::build_ephemeral_properties(
+                    project.as_deref(),
+                    account.as_deref(),
+                    zone.as_deref(),
+                    region.as_deref(),
+                )?;
+
+                commands::run_ephemeral(properties, &command)?;
+            }
+            SubCommand::Run {
+                name,
+                yes_prod,
+                command,
+                ..
+            } => {
+                // safe to unwrap because the name is required without --ephemeral
+                commands::run(&name.unwrap(), &command, yes_prod)?;
+            }
+            SubCommand::SuggestRegion => commands::suggest_region()?,
+            SubCommand::Tag { name, tags, remove } => commands::tag(&name, &tags, remove)?,
+            SubCommand::Unpin { name } => commands::unpin(&name)?,
+            SubCommand::Use {
+                name,
+                global,
+                local,
+                shell,
+            } => {
+                let scope = match (global, local) {
+                    (true, _) => ActivationScope::Global,
+                    (_, true) => ActivationScope::Local,
+                    _ => Settings::from_env()?.use_scope,
+                };
+
+                commands::use_configuration(&name, scope, shell.unwrap_or_default(), run_hooks)?;
+            }
         }
     } else {
         commands::current()?;
@@ -73,6 +262,27 @@ pub fn run(opts: Opts) -> Result<()> {
     Ok(())
 }
 
+/// Add a hint on how to resolve errors which the user can fix with a gctx command or flag
+fn with_hint(error: anyhow::Error) -> anyhow::Error {
+    match error.downcast_ref::<gcloud_ctx::Error>() {
+        Some(gcloud_ctx::Error::ConfigurationPinned(name)) => anyhow!(
+            "{}. Use --unpin-first or unpin it with 'gctx unpin {}' to modify it",
+            error,
+            name
+        ),
+        _ => error,
+    }
+}
+
+/// Use the given project, or let the user pick one when requested
+fn resolve_project(project: Option<String>, pick_project: bool) -> Result<String> {
+    match project {
+        Some(project) => Ok(project),
+        None if pick_project => projects::pick_project(),
+        None => bail!("A project is required"),
+    }
+}
+
 #[cfg(windows)]
 fn set_virtual_terminal() {
     // ensures colours work properly on Windows, otherwise `cargo run`
@@ -0,0 +1,138 @@
+use crate::{
+    commands::{self, OnConflict},
+    shell::Shell,
+};
+use anyhow::{bail, Context, Result};
+use colored::*;
+use dialoguer::{console::Term, Confirm};
+use gcloud_ctx::{ConfigurationStore, Error};
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+};
+
+/// Number of steps in the wizard
+const STEPS: usize = 5;
+
+/// Guide a new user through setting up gcloud configurations
+///
+/// Each step is a thin wrapper over an existing command, so the wizard only decides what to run and in which order
+pub fn run(templates: Option<&Path>) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        bail!("gctx onboard is interactive, run it from a terminal");
+    }
+
+    step(1, "Configuration store");
+    discover_store()?;
+
+    step(2, "Team templates");
+    import_templates(templates)?;
+
+    step(3, "Authentication");
+    println!("If you haven't already, authenticate gcloud with your user account:");
+    println!();
+    println!("  gcloud auth login");
+    println!("  gcloud auth application-default login   # for client libraries and local development");
+
+    step(4, "Your first configuration");
+    if confirm("Create a configuration now?")? {
+        commands::create_interactive()?;
+    }
+
+    step(5, "Shell integration");
+    print_shell_integration(Shell::default());
+
+    println!();
+    println!("{}", "You're all set! Run 'gctx examples' for more ideas".blue());
+
+    Ok(())
+}
+
+/// Print a step heading
+fn step(number: usize, title: &str) {
+    println!();
+    println!("{}", format!("Step {} of {}: {}", number, STEPS, title).blue().bold());
+}
+
+/// Ask a yes/no question, defaulting to yes
+fn confirm(prompt: &str) -> Result<bool> {
+    Ok(Confirm::new()
+        .with_prompt(prompt)
+        .default(true)
+        .interact_on(&Term::stderr())?)
+}
+
+/// Find the configuration store, offering to create it if gcloud hasn't been set up yet
+fn discover_store() -> Result<()> {
+    let location = ConfigurationStore::default_location()?;
+
+    match ConfigurationStore::with_location(location.clone()) {
+        Ok(store) => {
+            println!(
+                "Found {} configuration(s) in {}",
+                store.configurations()?.len(),
+                location.display()
+            );
+        }
+        Err(
+            Error::ConfigurationStoreNotFound(_) | Error::NoConfigurationsFound(_) | Error::StoreEnvVarMissing { .. },
+        ) => {
+            println!("There is no configuration store at {}", location.display());
+
+            if !confirm("Create it with an empty 'default' configuration?")? {
+                bail!("A configuration store is required, run 'gcloud init' to create one");
+            }
+
+            ConfigurationStore::initialize(location.clone())?;
+            println!("Created the configuration store at {}", location.display());
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
+
+/// Import every configuration file in the templates directory, asking how to resolve any conflicts
+fn import_templates(templates: Option<&Path>) -> Result<()> {
+    let directory = match templates {
+        Some(directory) => directory,
+        None => {
+            println!("No templates given, skipping. Use 'gctx onboard --templates <dir>' to import your team's");
+            return Ok(());
+        }
+    };
+
+    let mut files: Vec<PathBuf> = fs::read_dir(directory)
+        .with_context(|| format!("Reading {}", directory.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        println!("No templates found in {}", directory.display());
+    }
+
+    for file in files {
+        if let Err(e) = commands::import(&file, None, OnConflict::Ask) {
+            eprintln!("{} '{}': {:#}", "Skipped".yellow(), file.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Show how to integrate gctx with the detected shell
+fn print_shell_integration(shell: Shell) {
+    println!("Add this function to your shell profile so that 'gctx use --local' works:");
+    println!();
+    println!("  {}", shell.wrapper_function());
+
+    if shell == Shell::Posix {
+        println!();
+        println!("Show the active configuration in your prompt, highlighting dangerous ones:");
+        println!();
+        println!(r#"  PS1='$(gctx prompt --color) '"$PS1""#);
+    }
+}
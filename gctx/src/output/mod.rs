@@ -0,0 +1,21 @@
+pub mod schema;
+
+use anyhow::Result;
+use clap::ArgEnum;
+use serde::Serialize;
+
+/// Format of command output
+#[derive(ArgEnum, Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+
+    /// JSON, as defined by the types in [`schema`]
+    Json,
+}
+
+/// Print a value as pretty JSON on stdout
+pub fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Types which define the JSON output of each command
+//!
+//! External tooling relies on this format, so any breaking change must increment [`SCHEMA_VERSION`]
+
+use gcloud_ctx::Properties;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Version of the JSON output format
+///
+/// - 2: `active` in the output of `gctx list` is null when no configuration is active
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Properties grouped by section then key
+pub type PropertyMap = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Output of `gctx list`
+#[derive(Serialize, Debug)]
+pub struct ListOutput {
+    /// Version of the output format
+    pub schema_version: u32,
+
+    /// Name of the active configuration, or null if no configuration is active
+    pub active: Option<String>,
+
+    /// All configurations, sorted by name
+    pub configurations: Vec<ConfigurationEntry>,
+}
+
+/// A configuration within [`ListOutput`]
+#[derive(Serialize, Debug)]
+pub struct ConfigurationEntry {
+    /// Name of the configuration
+    pub name: String,
+
+    /// Whether this is the active configuration
+    pub active: bool,
+}
+
+/// Output of `gctx describe`
+#[derive(Serialize, Debug)]
+pub struct DescribeOutput {
+    /// Version of the output format
+    pub schema_version: u32,
+
+    /// Name of the configuration
+    pub name: String,
+
+    /// All properties of the configuration
+    pub properties: PropertyMap,
+}
+
+/// Output of `gctx describe --all`
+#[derive(Serialize, Debug)]
+pub struct DescribeAllOutput {
+    /// Version of the output format
+    pub schema_version: u32,
+
+    /// Properties of each configuration, keyed by name
+    pub configurations: BTreeMap<String, PropertyMap>,
+}
+
+/// Group properties by section then key
+pub fn property_map(properties: &Properties) -> PropertyMap {
+    let mut map = PropertyMap::new();
+
+    for (section, key, value) in properties.iter() {
+        map.entry(section.to_owned())
+            .or_default()
+            .insert(key.to_owned(), value.to_owned());
+    }
+
+    map
+}
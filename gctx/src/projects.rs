@@ -0,0 +1,68 @@
+use anyhow::{bail, Result};
+
+/// Let the user pick a project which their gcloud account can access, returning the project ID
+#[cfg(feature = "online")]
+pub fn pick_project() -> Result<String> {
+    use anyhow::Context;
+    use dialoguer::{console::Term, theme::ColorfulTheme, FuzzySelect};
+    use std::process::Command;
+
+    let output = Command::new("gcloud")
+        .args(["projects", "list", "--format=json"])
+        .output()
+        .context("Running gcloud projects list")?;
+
+    if !output.status.success() {
+        bail!(
+            "gcloud projects list failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let projects = parse_projects(&output.stdout)?;
+
+    if projects.is_empty() {
+        bail!("Your account can't access any projects");
+    }
+
+    let items: Vec<String> = projects.iter().map(|(id, name)| format!("{} ({})", id, name)).collect();
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Project")
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?;
+
+    match selection {
+        Some(index) => Ok(projects[index].0.clone()),
+        None => bail!("No project selected"),
+    }
+}
+
+/// Let the user pick a project which their gcloud account can access, returning the project ID
+#[cfg(not(feature = "online"))]
+pub fn pick_project() -> Result<String> {
+    bail!("Picking a project requires gctx to be built with the 'online' feature, use --project instead")
+}
+
+/// Parse the `(projectId, name)` pairs from the output of `gcloud projects list --format=json`
+#[cfg(feature = "online")]
+fn parse_projects(json: &[u8]) -> Result<Vec<(String, String)>> {
+    let value: serde_json::Value = serde_json::from_slice(json)?;
+
+    let projects = value
+        .as_array()
+        .map(|projects| {
+            projects
+                .iter()
+                .filter_map(|project| {
+                    let id = project.get("projectId")?.as_str()?;
+                    let name = project.get("name").and_then(|n| n.as_str()).unwrap_or(id);
+                    Some((id.to_owned(), name.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(projects)
+}
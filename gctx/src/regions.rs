@@ -0,0 +1,350 @@
+use crate::{data, settings::Settings};
+use anyhow::{bail, Context, Result};
+use gcloud_ctx::locations;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, process::Command};
+
+/// A Google Cloud region along with the hints used to suggest it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Region {
+    /// Name of the region, e.g. europe-west2
+    name: String,
+
+    /// Zone to suggest within the region
+    zone: String,
+
+    /// IANA timezones close to the region. Entries ending in `/` match any timezone with that prefix and are only
+    /// used if no timezone matches exactly
+    timezones: Vec<String>,
+
+    /// ISO 3166 country codes close to the region, as found in locales such as `en_GB.UTF-8`
+    countries: Vec<String>,
+}
+
+/// Region suggested when there are no hints at all
+const FALLBACK: &str = "us-central1";
+
+/// Why a region was suggested
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    /// The organisation default set in the environment
+    OrgDefault,
+
+    /// The local timezone
+    Timezone(String),
+
+    /// The country of the locale
+    Locale(String),
+
+    /// There were no hints, so the default gcloud region was used
+    Fallback,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::OrgDefault => write!(f, "your organisation default"),
+            Reason::Timezone(timezone) => write!(f, "your timezone ({})", timezone),
+            Reason::Locale(country) => write!(f, "your locale ({})", country),
+            Reason::Fallback => write!(f, "nothing, no timezone or locale was found"),
+        }
+    }
+}
+
+/// A suggested region and zone for a new configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Suggested region
+    pub region: String,
+
+    /// Suggested zone within the region, unless the region isn't known
+    pub zone: Option<String>,
+
+    /// Why the region was suggested
+    pub reason: Reason,
+}
+
+/// Suggest a region and zone, preferring the organisation defaults and then the nearest region to the user
+pub fn suggest(settings: &Settings) -> Suggestion {
+    let catalogue: Vec<Region> = data::load(&data::REGIONS);
+
+    // a default zone on its own implies its region
+    let default_region = settings
+        .default_region
+        .clone()
+        .or_else(|| settings.default_zone.as_deref().and_then(region_of).map(str::to_owned));
+
+    if let Some(region) = default_region {
+        let zone = settings.default_zone.clone().or_else(|| zone_in(&catalogue, &region));
+
+        return Suggestion {
+            region,
+            zone,
+            reason: Reason::OrgDefault,
+        };
+    }
+
+    let by_timezone =
+        local_timezone().and_then(|tz| by_timezone(&catalogue, &tz).map(|region| (region, Reason::Timezone(tz))));
+    let by_locale =
+        || local_country().and_then(|c| by_country(&catalogue, &c).map(|region| (region, Reason::Locale(c))));
+
+    match by_timezone.or_else(by_locale) {
+        Some((region, reason)) => Suggestion {
+            region: region.name.clone(),
+            zone: Some(region.zone.clone()),
+            reason,
+        },
+        None => Suggestion {
+            region: FALLBACK.to_owned(),
+            zone: zone_in(&catalogue, FALLBACK),
+            reason: Reason::Fallback,
+        },
+    }
+}
+
+/// Zone to suggest within a region, from the catalogue or otherwise the first zone gcloud-ctx knows about
+fn zone_in(catalogue: &[Region], region: &str) -> Option<String> {
+    match find(catalogue, region) {
+        Some(known) => Some(known.zone.clone()),
+        None => locations::zones_in(region).first().map(|zone| (*zone).to_owned()),
+    }
+}
+
+/// Region of a zone, e.g. europe-west2 from europe-west2-b
+fn region_of(zone: &str) -> Option<&str> {
+    zone.rsplit_once('-').map(|(region, _)| region)
+}
+
+/// Fetch the current regions and zones from gcloud, keeping the hints for regions which are already in the catalogue
+///
+/// Regions which no longer exist are dropped, and the suggested zone is replaced if it no longer exists
+pub fn fetch() -> Result<Vec<Region>> {
+    let output = Command::new("gcloud")
+        .args(["compute", "zones", "list", "--format=json"])
+        .output()
+        .context("Running gcloud compute zones list")?;
+
+    if !output.status.success() {
+        bail!(
+            "gcloud compute zones list failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let zones = parse_zones(&output.stdout)?;
+
+    if zones.is_empty() {
+        bail!("gcloud compute zones list didn't return any zones");
+    }
+
+    let catalogue: Vec<Region> = data::load(&data::REGIONS);
+    let mut regions: Vec<Region> = catalogue
+        .into_iter()
+        .filter_map(|mut region| {
+            let available = zones.get(&region.name)?;
+
+            if !available.contains(&region.zone) {
+                region.zone = available[0].clone();
+            }
+
+            Some(region)
+        })
+        .collect();
+
+    for (name, available) in zones {
+        if find(&regions, &name).is_none() {
+            regions.push(Region {
+                name,
+                zone: available[0].clone(),
+                timezones: Vec::new(),
+                countries: Vec::new(),
+            });
+        }
+    }
+
+    Ok(regions)
+}
+
+/// Parse the output of `gcloud compute zones list --format=json` into the sorted zones of each region
+fn parse_zones(json: &[u8]) -> Result<BTreeMap<String, Vec<String>>> {
+    let value: serde_json::Value = serde_json::from_slice(json)?;
+    let mut zones: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for zone in value.as_array().into_iter().flatten() {
+        let name = zone.get("name").and_then(|n| n.as_str());
+
+        // the region is given as a URL, e.g. https://www.googleapis.com/compute/v1/projects/p/regions/europe-west2
+        let region = zone
+            .get("region")
+            .and_then(|r| r.as_str())
+            .and_then(|r| r.rsplit('/').next());
+
+        if let (Some(name), Some(region)) = (name, region) {
+            zones.entry(region.to_owned()).or_default().push(name.to_owned());
+        }
+    }
+
+    for available in zones.values_mut() {
+        available.sort();
+    }
+
+    Ok(zones)
+}
+
+/// Find a region in the catalogue by name
+fn find<'a>(catalogue: &'a [Region], name: &str) -> Option<&'a Region> {
+    catalogue.iter().find(|r| r.name == name)
+}
+
+/// Find the nearest region to a timezone, preferring exact matches over continent-wide ones
+fn by_timezone<'a>(catalogue: &'a [Region], timezone: &str) -> Option<&'a Region> {
+    catalogue
+        .iter()
+        .find(|r| r.timezones.iter().any(|tz| tz == timezone))
+        .or_else(|| {
+            catalogue.iter().find(|r| {
+                r.timezones
+                    .iter()
+                    .any(|tz| tz.ends_with('/') && timezone.starts_with(tz.as_str()))
+            })
+        })
+}
+
+/// Find the nearest region to a country
+fn by_country<'a>(catalogue: &'a [Region], country: &str) -> Option<&'a Region> {
+    catalogue.iter().find(|r| r.countries.iter().any(|c| c == country))
+}
+
+/// Local IANA timezone, from `TZ` or the `/etc/localtime` link
+fn local_timezone() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ") {
+        let tz = tz.trim_start_matches(':');
+        return (!tz.is_empty()).then(|| tz.to_owned());
+    }
+
+    let target = std::fs::read_link("/etc/localtime").ok()?;
+    let target = target.to_str()?;
+
+    target.split_once("zoneinfo/").map(|(_, tz)| tz.to_owned())
+}
+
+/// Country of the locale, from the first locale variable which is set
+fn local_country() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|locale| !locale.is_empty())
+        .and_then(|locale| country_of(&locale))
+}
+
+/// Country of a locale, e.g. `GB` from `en_GB.UTF-8`
+fn country_of(locale: &str) -> Option<String> {
+    let locale = locale.split(['.', '@']).next()?;
+    let (_, country) = locale.split_once('_')?;
+    Some(country.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ActivationScope;
+    use gcloud_ctx::LocationValidation;
+
+    fn region(name: &str, timezones: &[&str], countries: &[&str]) -> Region {
+        Region {
+            name: name.to_owned(),
+            zone: format!("{}-c", name),
+            timezones: timezones.iter().map(|tz| (*tz).to_owned()).collect(),
+            countries: countries.iter().map(|c| (*c).to_owned()).collect(),
+        }
+    }
+
+    fn settings(default_region: Option<&str>, default_zone: Option<&str>) -> Settings {
+        Settings {
+            use_scope: ActivationScope::Global,
+            shortcut: true,
+            default_region: default_region.map(str::to_owned),
+            default_zone: default_zone.map(str::to_owned),
+            location_validation: LocationValidation::default(),
+        }
+    }
+
+    #[test]
+    pub fn test_embedded_catalogue_only_suggests_known_zones() {
+        let snapshot: serde_json::Value = serde_json::from_str(include_str!("../data/regions.json")).unwrap();
+
+        for region in snapshot["items"].as_array().unwrap() {
+            let zone = region["zone"].as_str().unwrap();
+
+            assert!(
+                locations::is_known_zone(zone),
+                "{} isn't in the locations catalogue",
+                zone
+            );
+            assert_eq!(region_of(zone), region["name"].as_str());
+        }
+    }
+
+    #[test]
+    pub fn test_by_timezone_prefers_exact_match() {
+        let catalogue = [
+            region("europe-west1", &["Europe/"], &[]),
+            region("europe-west2", &["Europe/London"], &[]),
+        ];
+
+        assert_eq!(by_timezone(&catalogue, "Europe/London").unwrap().name, "europe-west2");
+    }
+
+    #[test]
+    pub fn test_by_timezone_falls_back_to_prefix() {
+        let catalogue = [
+            region("europe-west2", &["Europe/London"], &[]),
+            region("europe-west1", &["Europe/"], &[]),
+        ];
+
+        assert_eq!(by_timezone(&catalogue, "Europe/Paris").unwrap().name, "europe-west1");
+        assert!(by_timezone(&catalogue, "Asia/Tokyo").is_none());
+    }
+
+    #[test]
+    pub fn test_country_of_locale() {
+        assert_eq!(country_of("en_GB.UTF-8").as_deref(), Some("GB"));
+        assert_eq!(country_of("de_de@euro").as_deref(), Some("DE"));
+        assert_eq!(country_of("pt_BR").as_deref(), Some("BR"));
+        assert_eq!(country_of("C.UTF-8"), None);
+        assert_eq!(country_of("POSIX"), None);
+    }
+
+    #[test]
+    pub fn test_by_country() {
+        let catalogue = [region("europe-west2", &[], &["GB", "IE"])];
+
+        assert_eq!(by_country(&catalogue, "IE").unwrap().name, "europe-west2");
+        assert!(by_country(&catalogue, "FR").is_none());
+    }
+
+    #[test]
+    pub fn test_suggest_org_default_zone_implies_region() {
+        let suggestion = suggest(&settings(None, Some("europe-west2-a")));
+
+        assert_eq!(suggestion.region, "europe-west2");
+        assert_eq!(suggestion.zone.as_deref(), Some("europe-west2-a"));
+        assert_eq!(suggestion.reason, Reason::OrgDefault);
+    }
+
+    #[test]
+    pub fn test_suggest_org_default_region_uses_known_zone() {
+        let suggestion = suggest(&settings(Some("us-central1"), None));
+
+        assert!(locations::zones_in("us-central1").contains(&suggestion.zone.as_deref().unwrap()));
+    }
+
+    #[test]
+    pub fn test_suggest_unknown_region_has_no_zone() {
+        let suggestion = suggest(&settings(Some("mars-north1"), None));
+
+        assert_eq!(suggestion.region, "mars-north1");
+        assert_eq!(suggestion.zone, None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable which points to the root of the gcloud SDK installation
+const SDK_ROOT_VAR: &str = "CLOUDSDK_ROOT_DIR";
+
+/// Number of major versions the SDK can move before a configuration is considered out of date. gcloud
+/// releases a new major version roughly every week, so this is about a year
+const MAJOR_VERSION_DRIFT: u64 = 50;
+
+/// Detect the version of the installed gcloud SDK from the VERSION file in its installation directory
+pub fn installed_version() -> Option<String> {
+    let root = match env::var_os(SDK_ROOT_VAR) {
+        Some(root) => PathBuf::from(root),
+        None => sdk_root(&fs::canonicalize(find_gcloud()?).ok()?)?.to_path_buf(),
+    };
+
+    read_version(&root)
+}
+
+/// Get the root of the SDK installation containing the given gcloud executable
+fn sdk_root(gcloud: &Path) -> Option<&Path> {
+    // the gcloud executable lives in the bin directory of the SDK root
+    gcloud.parent()?.parent()
+}
+
+/// Read the version from the VERSION file in the root of an SDK installation
+fn read_version(root: &Path) -> Option<String> {
+    let version = fs::read_to_string(root.join("VERSION")).ok()?;
+    let version = version.trim();
+
+    if version.is_empty() {
+        None
+    } else {
+        Some(version.to_owned())
+    }
+}
+
+/// Check if two SDK versions are far enough apart that a configuration may no longer behave the same
+pub fn has_drifted(recorded: &str, installed: &str) -> bool {
+    match (major_version(recorded), major_version(installed)) {
+        (Some(recorded), Some(installed)) => recorded.abs_diff(installed) >= MAJOR_VERSION_DRIFT,
+        _ => false,
+    }
+}
+
+/// Find the gcloud executable on the PATH
+fn find_gcloud() -> Option<PathBuf> {
+    let names: &[&str] = if cfg!(windows) {
+        &["gcloud.cmd", "gcloud.exe"]
+    } else {
+        &["gcloud"]
+    };
+
+    env::split_paths(&env::var_os("PATH")?)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+/// Get the major component of a version number, e.g. 400 from 400.0.1
+fn major_version(version: &str) -> Option<u64> {
+    version.split('.').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::{prelude::*, TempDir};
+
+    #[test]
+    pub fn test_sdk_root_is_parent_of_bin() {
+        let gcloud = Path::new("/opt/google-cloud-sdk/bin/gcloud");
+        assert_eq!(sdk_root(gcloud), Some(Path::new("/opt/google-cloud-sdk")));
+    }
+
+    #[test]
+    pub fn test_sdk_root_needs_bin_directory() {
+        assert_eq!(sdk_root(Path::new("gcloud")), None);
+    }
+
+    #[test]
+    pub fn test_read_version_trims_whitespace() {
+        let tmp = TempDir::new().unwrap();
+        tmp.child("VERSION").write_str("412.0.0\n").unwrap();
+
+        assert_eq!(read_version(tmp.path()), Some("412.0.0".to_owned()));
+    }
+
+    #[test]
+    pub fn test_read_version_empty_or_missing() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(read_version(tmp.path()), None);
+
+        tmp.child("VERSION").write_str("  \n").unwrap();
+        assert_eq!(read_version(tmp.path()), None);
+    }
+
+    #[test]
+    pub fn test_major_version() {
+        assert_eq!(major_version("412.0.0"), Some(412));
+        assert_eq!(major_version("412"), Some(412));
+        assert_eq!(major_version("latest"), None);
+        assert_eq!(major_version(""), None);
+    }
+
+    #[test]
+    pub fn test_has_drifted() {
+        assert!(has_drifted("350.0.0", "400.0.0"));
+        assert!(has_drifted("400.0.0", "350.0.0"));
+        assert!(!has_drifted("380.0.0", "400.0.0"));
+        assert!(!has_drifted("unknown", "400.0.0"));
+    }
+}
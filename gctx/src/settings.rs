@@ -0,0 +1,84 @@
+use anyhow::{bail, Context, Result};
+use gcloud_ctx::LocationValidation;
+
+/// Environment variable which sets the default scope for `gctx use`
+const USE_MODE_VAR: &str = "GCTX_USE_MODE";
+
+/// Environment variable which enables or disables the `gctx <name>` shortcut for `gctx activate <name>`
+const SHORTCUT_VAR: &str = "GCTX_SHORTCUT";
+
+/// Environment variable which sets the organisation default region for new configurations
+const DEFAULT_REGION_VAR: &str = "GCTX_DEFAULT_REGION";
+
+/// Environment variable which sets the organisation default zone for new configurations
+const DEFAULT_ZONE_VAR: &str = "GCTX_DEFAULT_ZONE";
+
+/// Environment variable which sets how strictly zones and regions are checked, i.e. strict, relaxed or off
+const LOCATION_VALIDATION_VAR: &str = "GCTX_LOCATION_VALIDATION";
+
+/// Scope of a configuration activation
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ActivationScope {
+    /// Activate for all terminals by updating the active configuration in the store
+    Global,
+
+    /// Activate for the current terminal only via environment variables
+    Local,
+}
+
+/// User settings for gctx
+#[derive(Debug)]
+pub struct Settings {
+    /// Default scope used by `gctx use`
+    pub use_scope: ActivationScope,
+
+    /// Whether `gctx <name>` activates a configuration, otherwise `gctx activate <name>` is required
+    pub shortcut: bool,
+
+    /// Organisation default region suggested for new configurations
+    pub default_region: Option<String>,
+
+    /// Organisation default zone suggested for new configurations
+    pub default_zone: Option<String>,
+
+    /// How strictly zones and regions are checked when creating configurations
+    pub location_validation: LocationValidation,
+}
+
+impl Settings {
+    /// Load the settings from the environment
+    pub fn from_env() -> Result<Self> {
+        let use_scope = match std::env::var(USE_MODE_VAR).as_deref() {
+            Ok("global") | Err(_) => ActivationScope::Global,
+            Ok("local") => ActivationScope::Local,
+            Ok(other) => bail!(
+                "Invalid value '{}' for {}, expected 'global' or 'local'",
+                other,
+                USE_MODE_VAR
+            ),
+        };
+
+        let shortcut = match std::env::var(SHORTCUT_VAR).as_deref() {
+            Ok("on") | Err(_) => true,
+            Ok("off") => false,
+            Ok(other) => bail!("Invalid value '{}' for {}, expected 'on' or 'off'", other, SHORTCUT_VAR),
+        };
+
+        let location_validation = match std::env::var(LOCATION_VALIDATION_VAR) {
+            Ok(value) => value
+                .parse()
+                .with_context(|| format!("Invalid value for {}", LOCATION_VALIDATION_VAR))?,
+            Err(_) => LocationValidation::default(),
+        };
+
+        let non_empty = |var| std::env::var(var).ok().filter(|value: &String| !value.is_empty());
+
+        Ok(Self {
+            use_scope,
+            shortcut,
+            default_region: non_empty(DEFAULT_REGION_VAR),
+            default_zone: non_empty(DEFAULT_ZONE_VAR),
+            location_validation,
+        })
+    }
+}
@@ -0,0 +1,64 @@
+use clap::ArgEnum;
+
+/// Environment variable which gcloud uses to override the active configuration for a single terminal
+pub const ACTIVE_CONFIG_VAR: &str = "CLOUDSDK_ACTIVE_CONFIG_NAME";
+
+/// Shell syntax used when printing environment variable assignments
+#[derive(ArgEnum, Copy, Clone, Debug, PartialEq)]
+pub enum Shell {
+    /// bash, zsh and other POSIX shells
+    Posix,
+
+    /// fish shell
+    Fish,
+
+    /// PowerShell
+    Powershell,
+}
+
+impl Default for Shell {
+    /// Detect the shell from the environment, falling back to the platform default
+    fn default() -> Self {
+        match std::env::var("SHELL") {
+            Ok(shell) if shell.ends_with("fish") => Shell::Fish,
+            Ok(_) => Shell::Posix,
+            Err(_) if cfg!(windows) => Shell::Powershell,
+            Err(_) => Shell::Posix,
+        }
+    }
+}
+
+impl Shell {
+    /// Statement which sets an environment variable in the current shell
+    pub fn set_env(&self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Posix => format!("export {}='{}'", name, value),
+            Shell::Fish => format!("set -gx {} '{}'", name, value),
+            Shell::Powershell => format!("$env:{} = '{}'", name, value),
+        }
+    }
+
+    /// Wrapper function which evaluates the statements printed by `gctx use`, to add to the shell profile
+    pub fn wrapper_function(&self) -> &'static str {
+        match self {
+            Shell::Posix => {
+                r#"gctx() { if [ "$1" = "use" ]; then eval "$(command gctx "$@")"; else command gctx "$@"; fi }"#
+            }
+            Shell::Fish => {
+                r#"function gctx; if test "$argv[1]" = use; eval (command gctx $argv); else; command gctx $argv; end; end"#
+            }
+            Shell::Powershell => {
+                r#"function gctx { if ($args[0] -eq 'use') { gctx.exe @args | Out-String | Invoke-Expression } else { gctx.exe @args } }"#
+            }
+        }
+    }
+
+    /// Statement which removes an environment variable from the current shell
+    pub fn unset_env(&self, name: &str) -> String {
+        match self {
+            Shell::Posix => format!("unset {}", name),
+            Shell::Fish => format!("set -e {}", name),
+            Shell::Powershell => format!("Remove-Item Env:{}", name),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{arguments::Opts, commands, settings::Settings};
+use anyhow::{bail, Result};
+use clap::{CommandFactory, Parser};
+use colored::Colorize;
+use dialoguer::{console::Term, theme::ColorfulTheme, Select};
+use gcloud_ctx::ConfigurationStore;
+use std::io::{self, IsTerminal};
+
+/// Activate a configuration using the `gctx <name>` shortcut
+///
+/// Names which don't match a configuration but look like a mistyped command are reported as such, rather than
+/// as an unknown configuration. When run interactively the user can pick the command they meant instead
+pub fn activate(name: &str, run_hooks: bool) -> Result<()> {
+    let similar = similar_commands(name);
+
+    if !similar.is_empty() && !configuration_exists(name) {
+        if io::stdin().is_terminal() && io::stderr().is_terminal() {
+            return disambiguate(name, &similar, run_hooks);
+        }
+
+        bail!(
+            "'{}' is neither a configuration nor a command, did you mean: {}",
+            name,
+            suggestions(&similar)
+        );
+    }
+
+    if !Settings::from_env()?.shortcut {
+        bail!(
+            "The 'gctx <name>' shortcut is disabled, use 'gctx activate {}' instead",
+            name
+        );
+    }
+
+    commands::activate(name, run_hooks, false)
+}
+
+/// Whether a configuration with the given name exists
+fn configuration_exists(name: &str) -> bool {
+    ConfigurationStore::with_default_location().is_ok_and(|store| store.find_by_name(name).is_ok_and(|c| c.is_some()))
+}
+
+/// Commands which the name is a prefix or likely typo of
+fn similar_commands(name: &str) -> Vec<String> {
+    // allow one mistake for every three characters so that short configuration names aren't mistaken for commands
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    Opts::command()
+        .get_subcommands()
+        .map(|command| command.get_name().to_owned())
+        .filter(|command| command.starts_with(name) || edit_distance(name, command) <= max_distance)
+        .collect()
+}
+
+/// Ask which of the similar commands was meant, and run it
+fn disambiguate(name: &str, similar: &[String], run_hooks: bool) -> Result<()> {
+    let items: Vec<String> = similar.iter().map(|command| format!("gctx {}", command)).collect();
+
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "'{}' is neither a configuration nor a command, did you mean",
+            name.yellow()
+        ))
+        .items(&items)
+        .default(0)
+        .interact_on_opt(&Term::stderr())?;
+
+    let command = match selection {
+        Some(index) => &similar[index],
+        None => bail!("No command selected"),
+    };
+
+    // commands which need arguments print their usage, just as if they'd been typed without them
+    let opts = Opts::try_parse_from(["gctx", command.as_str()]).unwrap_or_else(|e| e.exit());
+    crate::execute(None, opts.subcmd, run_hooks)
+}
+
+/// Format commands as a list of suggestions
+fn suggestions(commands: &[String]) -> String {
+    commands
+        .iter()
+        .map(|command| format!("'gctx {}'", command))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Number of insertions, deletions, substitutions and adjacent transpositions needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between the first i characters of a and the first j characters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_edit_distance_identical() {
+        assert_eq!(edit_distance("list", "list"), 0);
+        assert_eq!(edit_distance("", ""), 0);
+    }
+
+    #[test]
+    pub fn test_edit_distance_empty() {
+        assert_eq!(edit_distance("", "list"), 4);
+        assert_eq!(edit_distance("list", ""), 4);
+    }
+
+    #[test]
+    pub fn test_edit_distance_single_edits() {
+        assert_eq!(edit_distance("lst", "list"), 1);
+        assert_eq!(edit_distance("lists", "list"), 1);
+        assert_eq!(edit_distance("lint", "list"), 1);
+    }
+
+    #[test]
+    pub fn test_edit_distance_transposition_is_one_edit() {
+        assert_eq!(edit_distance("lsit", "list"), 1);
+        assert_eq!(edit_distance("cpoy", "copy"), 1);
+    }
+
+    #[test]
+    pub fn test_edit_distance_counts_characters_not_bytes() {
+        assert_eq!(edit_distance("lïst", "list"), 1);
+    }
+
+    #[test]
+    pub fn test_similar_commands_finds_typos_and_prefixes() {
+        assert!(similar_commands("lsit").contains(&"list".to_owned()));
+        assert!(similar_commands("desc").contains(&"describe".to_owned()));
+        assert!(similar_commands("production").is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+
+/// Timings recorded during this invocation, in the order they completed
+static TIMINGS: Mutex<Vec<(&'static str, Duration)>> = Mutex::new(Vec::new());
+
+/// Time an operation, recording the duration under the given label
+pub fn measure<T>(label: &'static str, operation: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = operation();
+    record(label, start.elapsed());
+    result
+}
+
+/// Record the duration of an operation under the given label
+fn record(label: &'static str, duration: Duration) {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.push((label, duration));
+    }
+}
+
+/// Time every configuration store operation from the spans gcloud-ctx creates for them, e.g. `activate` or
+/// `describe_all`, so the time spent in the operation itself is reported separately from opening the store
+pub fn record_store_operations() {
+    // only fails if a subscriber is already installed, in which case those timings just aren't recorded
+    let _ = tracing::subscriber::set_global_default(SpanTimer::default());
+}
+
+/// Subscriber which records how long each outermost span was entered for, ignoring the operations it calls itself
+#[derive(Default)]
+struct SpanTimer {
+    /// Source of span IDs, which must be non-zero
+    next_id: AtomicU64,
+
+    /// Spans which haven't closed yet, with when the outermost one was entered
+    spans: Mutex<SpanStack>,
+}
+
+#[derive(Default)]
+struct SpanStack {
+    /// Name of each span by ID
+    names: Vec<(u64, &'static str)>,
+
+    /// Outermost span which is currently entered, and when
+    entered: Option<(u64, Instant)>,
+
+    /// Number of spans currently entered, including the outermost one
+    depth: usize,
+}
+
+impl Subscriber for SpanTimer {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span()
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.names.push((id, span.metadata().name()));
+        }
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        if let Ok(mut spans) = self.spans.lock() {
+            if spans.depth == 0 {
+                spans.entered = Some((span.into_u64(), Instant::now()));
+            }
+
+            spans.depth += 1;
+        }
+    }
+
+    fn exit(&self, span: &Id) {
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.depth = spans.depth.saturating_sub(1);
+
+            if spans.depth > 0 {
+                return;
+            }
+
+            if let Some((id, start)) = spans.entered.take().filter(|(id, _)| *id == span.into_u64()) {
+                if let Some((_, name)) = spans.names.iter().find(|(span, _)| *span == id) {
+                    record(name, start.elapsed());
+                }
+            }
+        }
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.names.retain(|(id, _)| *id != span.into_u64());
+        }
+
+        true
+    }
+}
+
+/// Print all recorded timings to stderr
+pub fn report() {
+    if let Ok(timings) = TIMINGS.lock() {
+        for (label, duration) in timings.iter() {
+            eprintln!("{:>21}: {:>8.3}ms", label, duration.as_secs_f64() * 1000.0);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use colored::*;
+use std::{fmt::Display, sync::Mutex};
+
+/// Number of warnings emitted during this invocation
+static EMITTED: Mutex<usize> = Mutex::new(0);
+
+/// Emit a warning to stderr
+pub fn emit(warning: impl Display) {
+    eprintln!("{} {}", "Warning:".yellow(), warning);
+
+    if let Ok(mut emitted) = EMITTED.lock() {
+        *emitted += 1;
+    }
+}
+
+/// Number of warnings emitted so far
+pub fn count() -> usize {
+    EMITTED.lock().map(|emitted| *emitted).unwrap_or_default()
+}
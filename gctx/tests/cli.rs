@@ -1,5 +1,5 @@
 use assert_fs::prelude::*;
-use common::TempConfigurationStore;
+use common::{state_child, TempConfigurationStore, NEWLINE};
 use predicates::prelude::*;
 
 mod common;
@@ -29,12 +29,65 @@ fn unknown_subcommand_defaults_to_activate() {
 
     cli.arg("foo");
 
-    cli.assert().success().stdout("Successfully activated 'foo'\n");
+    cli.assert().success().stdout("Switched from 'bar' to 'foo'\n");
     tmp.child("active_config").assert("foo");
 
     tmp.close().unwrap();
 }
 
+#[test]
+fn mistyped_subcommand_suggests_command() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("lsit");
+
+    cli.assert()
+        .failure()
+        .stderr("Error: 'lsit' is neither a configuration nor a command, did you mean: 'gctx list'\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn configuration_resembling_subcommand_is_activated() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("lsit")
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("lsit");
+
+    cli.assert().success().stdout("Switched from 'foo' to 'lsit'\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn shortcut_can_be_disabled() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.env("GCTX_SHORTCUT", "off").arg("foo");
+
+    cli.assert()
+        .failure()
+        .stderr("Error: The 'gctx <name>' shortcut is disabled, use 'gctx activate foo' instead\n");
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn activate_known_configuration_succeeds() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -46,7 +99,23 @@ fn activate_known_configuration_succeeds() {
 
     cli.arg("activate").arg("foo");
 
-    cli.assert().success().stdout("Successfully activated 'foo'\n");
+    cli.assert().success().stdout("Switched from 'bar' to 'foo'\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_already_active_configuration_reports_it() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("activate").arg("foo");
+
+    cli.assert().success().stdout("Configuration 'foo' is already active\n");
     tmp.child("active_config").assert("foo");
 
     tmp.close().unwrap();
@@ -86,6 +155,81 @@ fn current_shows_active_configuration() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn list_ignores_trailing_newline_in_active_config() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    tmp.child("active_config").write_str("foo\n").unwrap();
+    cli.arg("list");
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "  bar",
+        "* foo",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn current_warns_when_active_configuration_missing() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("active_config").write_str("bar").unwrap();
+    cli.arg("current");
+
+    cli.assert().success().stdout("bar\n").stderr(
+        "Warning: The active configuration 'bar' doesn't exist. Use 'gctx activate' to activate another configuration\n",
+    );
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn deactivate_leaves_no_configuration_active() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("deactivate");
+
+    cli.assert().success().stdout("Successfully deactivated 'foo'\n");
+    tmp.child("active_config").assert("NONE");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn current_warns_when_no_configuration_active() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("active_config").write_str("NONE").unwrap();
+    cli.arg("current");
+
+    cli.assert()
+        .success()
+        .stdout("")
+        .stderr("Warning: No configuration is active. Use 'gctx activate' to activate a configuration\n");
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn list_shows_configurations() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -100,7 +244,7 @@ fn list_shows_configurations() {
     cli.arg("list");
 
     #[rustfmt::skip]
-    let expected = vec![
+    let expected = [
         "  bar",
         "* baz",
         "  foo",
@@ -149,7 +293,7 @@ fn rename_active_configuration_succeeds() {
     cli.arg("rename").arg("bar").arg("renamed");
 
     #[rustfmt::skip]
-    cli.assert().success().stdout(vec![
+    cli.assert().success().stdout([
         "Successfully renamed configuration 'bar' to 'renamed'",
         "Configuration 'renamed' is now active",
         "",
@@ -176,8 +320,9 @@ fn rename_to_existing_name_with_force_overwrites_existing() {
     cli.arg("rename").arg("bar").arg("foo").arg("--force");
 
     #[rustfmt::skip]
-    cli.assert().success().stdout(vec![
+    cli.assert().success().stdout([
         "Successfully renamed configuration 'bar' to 'foo'",
+        "The previous version of 'foo' was backed up, restore it with 'gctx restore foo'",
         "Configuration 'foo' is now active",
         "",
     ].join("\n"));
@@ -211,6 +356,71 @@ fn rename_to_existing_name_without_force_fails() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn rename_to_existing_name_with_on_conflict_keep_keeps_both() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("rename").arg("bar").arg("foo").args(["--on-conflict", "keep"]);
+
+    cli.assert().success().stdout("Kept existing configuration 'foo'\n");
+
+    tmp.child("active_config").assert("bar");
+    tmp.child("configurations/config_bar").assert(predicate::path::exists());
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn rename_to_existing_name_with_on_conflict_rename_generates_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("rename")
+        .arg("bar")
+        .arg("foo")
+        .args(["--on-conflict", "rename"]);
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::is_match("^Successfully renamed configuration 'bar' to 'foo-[a-z0-9]+'\n").unwrap());
+
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn rename_with_force_and_on_conflict_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("rename")
+        .arg("bar")
+        .arg("foo")
+        .arg("--force")
+        .args(["--on-conflict", "keep"]);
+
+    cli.assert().failure();
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn rename_to_invalid_name_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -266,10 +476,10 @@ fn create_sets_properties_successfully() {
     #[rustfmt::skip]
     cli.arg("create")
        .arg("new-config")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"]);
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"]);
 
     cli.assert()
         .success()
@@ -278,13 +488,13 @@ fn create_sets_properties_successfully() {
     #[rustfmt::skip]
     tmp.child("configurations/config_new-config").assert([
         "[core]",
-        "project=my-project",
         "account=a.user@example.org",
+        "project=my-project",
         "[compute]",
-        "zone=europe-west1-d",
         "region=us-east1",
+        "zone=europe-west1-d",
         ""
-    ].join("\n"));
+    ].join(NEWLINE));
 
     tmp.close().unwrap();
 }
@@ -300,10 +510,10 @@ fn create_without_activate_maintains_previous_activation() {
     #[rustfmt::skip]
     cli.arg("create")
        .arg("new-config")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"]);
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"]);
 
     cli.assert()
         .success()
@@ -325,10 +535,10 @@ fn create_with_activate_activates_new_configuration() {
     #[rustfmt::skip]
     cli.arg("create")
        .arg("new-config")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"])
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"])
        .arg("--activate");
 
     cli.assert().success().stdout(
@@ -352,28 +562,31 @@ fn create_with_force_succeeds() {
     #[rustfmt::skip]
     cli.arg("create")
        .arg("foo")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"])
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"])
        .arg("--force");
 
-    cli.assert()
-        .success()
-        .stdout("Successfully created configuration 'foo'\n");
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Successfully created configuration 'foo'",
+        "The previous version of 'foo' was backed up, restore it with 'gctx restore foo'",
+        "",
+    ].join("\n"));
 
     tmp.child("active_config").assert("foo");
 
     #[rustfmt::skip]
     tmp.child("configurations/config_foo").assert([
         "[core]",
-        "project=my-project",
         "account=a.user@example.org",
+        "project=my-project",
         "[compute]",
-        "zone=europe-west1-d",
         "region=us-east1",
+        "zone=europe-west1-d",
         ""
-    ].join("\n"));
+    ].join(NEWLINE));
 
     tmp.close().unwrap();
 }
@@ -389,10 +602,10 @@ fn create_with_invalid_name_fails() {
     #[rustfmt::skip]
     cli.arg("create")
        .arg("invalid_name")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"]);
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"]);
 
     cli.assert()
         .failure()
@@ -402,7 +615,7 @@ fn create_with_invalid_name_fails() {
 }
 
 #[test]
-fn create_without_force_fails() {
+fn create_with_invalid_project_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
         .unwrap()
         .with_config_activated("foo")
@@ -411,22 +624,23 @@ fn create_without_force_fails() {
 
     #[rustfmt::skip]
     cli.arg("create")
-       .arg("foo")
-       .args(&["--project", "my-project"])
-       .args(&["--account", "a.user@example.org"])
-       .args(&["--zone", "europe-west1-d"])
-       .args(&["--region", "us-east1"]);
+       .arg("bar")
+       .args(["--project", "My_Project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"]);
 
-    cli.assert()
-        .failure()
-        .stderr("Error: A configuration named 'foo' already exists. Use --force to overwrite it\n");
+    cli.assert().failure().stderr(
+        "Error: 'My_Project' is not a valid project ID. Project IDs are 6 to 30 lowercase letters, digits or hyphens, start with a letter and don't end with a hyphen\n",
+    );
+
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
 
     tmp.close().unwrap();
 }
 
 #[test]
-#[ignore] // TODO: this doesn't work because assert_cmd doesn't support interactive programs
-fn create_interactive_with_activate() {
+fn create_with_invalid_account_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
         .unwrap()
         .with_config_activated("foo")
@@ -434,37 +648,24 @@ fn create_interactive_with_activate() {
         .unwrap();
 
     #[rustfmt::skip]
-    cli.arg("create").arg("--interactive")
-       .write_stdin("bar") // name
-       .write_stdin("my-project") // project
-       .write_stdin("a.user@example.org") // account
-       .write_stdin("europe-west1-d") // zone
-       .write_stdin("us-east1") // region
-       .write_stdin("y"); // activate
-
-    cli.assert()
-        .success()
-        .stdout("Successfully created configuration 'bar'\n");
+    cli.arg("create")
+       .arg("bar")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user"])
+       .args(["--zone", "europe-west1-d"]);
 
-    tmp.child("active_config").assert("bar");
+    cli.assert().failure().stderr(
+        "Error: 'a.user' is not a valid account. Accounts are email addresses, e.g. a.user@example.org or a service account\n",
+    );
 
-    #[rustfmt::skip]
-   tmp.child("configurations/config_bar").assert([
-       "[core]",
-       "project=my-project",
-       "account=a.user@example.org",
-       "[compute]",
-       "zone=europe-west1-d",
-       "region=us-east1",
-       ""
-   ].join("\n"));
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
 
     tmp.close().unwrap();
 }
 
 #[test]
-#[ignore] // TODO: this doesn't work because assert_cmd doesn't support interactive programs
-fn create_interactive_without_activate() {
+fn create_with_unrecognised_zone_warns() {
     let (mut cli, tmp) = TempConfigurationStore::new()
         .unwrap()
         .with_config_activated("foo")
@@ -472,76 +673,215 @@ fn create_interactive_without_activate() {
         .unwrap();
 
     #[rustfmt::skip]
-    cli.arg("create").arg("--interactive")
-       .write_stdin("bar") // name
-       .write_stdin("my-project") // project
-       .write_stdin("a.user@example.org") // account
-       .write_stdin("europe-west1-d") // zone
-       .write_stdin("us-east1") // region
-       .write_stdin("n"); // activate
+    cli.arg("create")
+       .arg("bar")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "mars-north1"]);
 
     cli.assert()
         .success()
-        .stdout("Successfully created configuration 'bar'\n");
+        .stderr("Warning: 'mars-north1' doesn't look like a zone, e.g. europe-west1-d\n");
 
-    tmp.child("active_config").assert("foo");
+    tmp.child("configurations/config_bar").assert(predicate::path::exists());
 
     tmp.close().unwrap();
 }
 
 #[test]
-fn describe_with_name_shows_supported_properties() {
+fn create_with_unrecognised_zone_fails_when_validation_is_strict() {
     let (mut cli, tmp) = TempConfigurationStore::new()
         .unwrap()
         .with_config_activated("foo")
         .build()
         .unwrap();
 
-    let contents = [
-        "[core]",
-        "project=my-project",
-        "account=a.user@example.org",
-        "[compute]",
-        "zone=europe-west1-d",
-        "region=us-east1",
-        "",
-    ]
-    .join("\n");
-
-    tmp.child("configurations/config_foo").write_str(&contents).unwrap();
-
-    cli.arg("describe").arg("foo");
+    #[rustfmt::skip]
+    cli.env("GCTX_LOCATION_VALIDATION", "strict")
+       .arg("create")
+       .arg("bar")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "mars-north1"]);
+
+    cli.assert().failure().stderr(
+        "Error: 'mars-north1' doesn't look like a zone, e.g. europe-west1-d. Relax the location validation if it's a new zone\n",
+    );
 
-    cli.assert().success().stdout(contents);
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
 
     tmp.close().unwrap();
 }
 
 #[test]
-fn describe_without_name_shows_active_configuration() {
+fn create_without_force_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
         .unwrap()
         .with_config_activated("foo")
-        .with_config("bar")
         .build()
         .unwrap();
 
-    let contents = [
-        "[core]",
-        "project=my-project",
-        "account=a.user@example.org",
+    #[rustfmt::skip]
+    cli.arg("create")
+       .arg("foo")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--region", "us-east1"]);
+
+    cli.assert()
+        .failure()
+        .stderr("Error: A configuration named 'foo' already exists. Use --force to overwrite it\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn create_with_on_conflict_rename_generates_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    #[rustfmt::skip]
+    cli.arg("create")
+       .arg("foo")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .args(["--on-conflict", "rename"]);
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::is_match("^Successfully created configuration 'foo-[a-z0-9]+'\n").unwrap());
+
+    tmp.child("configurations/config_foo").assert("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[ignore] // TODO: this doesn't work because assert_cmd doesn't support interactive programs
+fn create_interactive_with_activate() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    #[rustfmt::skip]
+    cli.arg("create").arg("--interactive")
+       .write_stdin("bar") // name
+       .write_stdin("my-project") // project
+       .write_stdin("a.user@example.org") // account
+       .write_stdin("europe-west1-d") // zone
+       .write_stdin("us-east1") // region
+       .write_stdin("y"); // activate
+
+    cli.assert()
+        .success()
+        .stdout("Successfully created configuration 'bar'\n");
+
+    tmp.child("active_config").assert("bar");
+
+    #[rustfmt::skip]
+   tmp.child("configurations/config_bar").assert([
+       "[core]",
+       "project=my-project",
+       "account=a.user@example.org",
+       "[compute]",
+       "zone=europe-west1-d",
+       "region=us-east1",
+       ""
+   ].join(NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[ignore] // TODO: this doesn't work because assert_cmd doesn't support interactive programs
+fn create_interactive_without_activate() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    #[rustfmt::skip]
+    cli.arg("create").arg("--interactive")
+       .write_stdin("bar") // name
+       .write_stdin("my-project") // project
+       .write_stdin("a.user@example.org") // account
+       .write_stdin("europe-west1-d") // zone
+       .write_stdin("us-east1") // region
+       .write_stdin("n"); // activate
+
+    cli.assert()
+        .success()
+        .stdout("Successfully created configuration 'bar'\n");
+
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_with_name_shows_supported_properties() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let lines = [
+        "[core]",
+        "project=my-project",
+        "account=a.user@example.org",
         "[compute]",
         "zone=europe-west1-d",
         "region=us-east1",
         "",
-    ]
-    .join("\n");
+    ];
 
-    tmp.child("configurations/config_foo").write_str(&contents).unwrap();
+    tmp.child("configurations/config_foo")
+        .write_str(&lines.join("\n"))
+        .unwrap();
+
+    cli.arg("describe").arg("foo");
+
+    cli.assert().success().stdout(lines.join(NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_without_name_shows_active_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    let lines = [
+        "[core]",
+        "project=my-project",
+        "account=a.user@example.org",
+        "[compute]",
+        "zone=europe-west1-d",
+        "region=us-east1",
+        "",
+    ];
+
+    tmp.child("configurations/config_foo")
+        .write_str(&lines.join("\n"))
+        .unwrap();
 
     cli.arg("describe");
 
-    cli.assert().success().stdout(contents);
+    cli.assert().success().stdout(lines.join(NEWLINE));
 
     tmp.close().unwrap();
 }
@@ -598,6 +938,35 @@ fn copy_copies_all_properties() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn copy_with_project_overrides_project() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject=my-project\naccount=a.user@example.org\n[extra]\nfoo=bar\n")
+        .unwrap();
+
+    cli.args(["copy", "foo", "bar", "--project", "other-project"]);
+
+    cli.assert()
+        .success()
+        .stdout("Successfully copied configuration 'foo' to 'bar'\n");
+
+    tmp.child("configurations/config_foo")
+        .assert(predicate::str::contains("project=my-project"));
+    tmp.child("configurations/config_bar").assert(
+        predicate::str::contains("project=other-project")
+            .and(predicate::str::contains("account=a.user@example.org"))
+            .and(predicate::str::contains("foo=bar")),
+    );
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn copy_with_activation_activates_configuration() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -632,9 +1001,12 @@ fn copy_with_force_succeeds() {
 
     cli.arg("copy").arg("foo").arg("bar").arg("--force");
 
-    cli.assert()
-        .success()
-        .stdout("Successfully copied configuration 'foo' to 'bar'\n");
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Successfully copied configuration 'foo' to 'bar'",
+        "The previous version of 'bar' was backed up, restore it with 'gctx restore bar'",
+        "",
+    ].join("\n"));
 
     tmp.child("configurations/config_bar")
         .assert(predicate::path::eq_file(tmp.child("configurations/config_foo").path()));
@@ -642,6 +1014,49 @@ fn copy_with_force_succeeds() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn restore_recovers_overwritten_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_bar").write_str("replacement").unwrap();
+    state_child(&tmp, "backups/config_bar/1").write_str("bar").unwrap();
+
+    cli.arg("restore").arg("bar");
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Restored the previous version of configuration 'bar'",
+        "The version it replaced was backed up, run 'gctx restore bar' again to undo",
+        "",
+    ].join("\n"));
+
+    tmp.child("configurations/config_bar").assert("bar");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn restore_without_backups_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("restore").arg("foo");
+
+    cli.assert()
+        .failure()
+        .stderr("Error: There are no backups of configuration 'foo'\n");
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn copy_without_force_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -665,6 +1080,27 @@ fn copy_without_force_fails() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn copy_with_on_conflict_keep_keeps_existing() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo").write_str("foo").unwrap();
+    tmp.child("configurations/config_bar").write_str("bar").unwrap();
+
+    cli.arg("copy").arg("foo").arg("bar").args(["--on-conflict", "keep"]);
+
+    cli.assert().success().stdout("Kept existing configuration 'bar'\n");
+
+    tmp.child("configurations/config_bar").assert("bar");
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn copy_unknown_configuration_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -739,6 +1175,28 @@ fn delete_active_configuration_fails() {
     tmp.close().unwrap();
 }
 
+#[test]
+fn delete_active_configuration_with_fallback_succeeds() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.args(["delete", "foo", "--fallback", "bar"]);
+
+    cli.assert()
+        .success()
+        .stdout("Successfully deleted configuration 'foo'\n");
+
+    tmp.child("configurations/config_foo")
+        .assert(predicate::path::missing());
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
 #[test]
 fn delete_unknown_configuration_fails() {
     let (mut cli, tmp) = TempConfigurationStore::new()
@@ -755,3 +1213,2020 @@ fn delete_unknown_configuration_fails() {
 
     tmp.close().unwrap();
 }
+
+#[test]
+fn use_global_activates_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("use").arg("foo").arg("--global");
+
+    cli.assert()
+        .success()
+        .stdout("")
+        .stderr("Successfully activated 'foo' in all terminals\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn use_global_clears_local_activation() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.env("CLOUDSDK_ACTIVE_CONFIG_NAME", "bar");
+    cli.arg("use").arg("foo").arg("--global").args(["--shell", "posix"]);
+
+    cli.assert().success().stdout("unset CLOUDSDK_ACTIVE_CONFIG_NAME\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn use_local_prints_environment_assignment() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("use").arg("foo").arg("--local").args(["--shell", "posix"]);
+
+    cli.assert()
+        .success()
+        .stdout("export CLOUDSDK_ACTIVE_CONFIG_NAME='foo'\n")
+        .stderr("Successfully activated 'foo' in this terminal\n");
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn use_defaults_to_scope_from_settings() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.env("GCTX_USE_MODE", "local");
+    cli.arg("use").arg("foo").args(["--shell", "fish"]);
+
+    cli.assert()
+        .success()
+        .stdout("set -gx CLOUDSDK_ACTIVE_CONFIG_NAME 'foo'\n");
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn use_local_unknown_configuration_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("use").arg("unknown").arg("--local");
+
+    cli.assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: Unable to find configuration 'unknown'\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn timings_are_reported_on_stderr() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("describe").arg("--timings");
+
+    cli.assert()
+        .success()
+        .stderr(predicate::str::contains("store discovery"))
+        .stderr(predicate::str::contains("directory scan"))
+        .stderr(predicate::str::contains("describe_with_options"))
+        .stderr(predicate::str::contains("total"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn timings_report_the_store_operation_separately() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.arg("activate").arg("bar").arg("--timings");
+
+    cli.assert()
+        .success()
+        .stderr(predicate::str::contains("directory scan"))
+        .stderr(predicate::str::is_match(r"(?m)^ +activate: +[0-9.]+ms$").unwrap());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn skipped_entries_emit_warnings() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/backups").create_dir_all().unwrap();
+
+    cli.arg("list");
+
+    cli.assert()
+        .success()
+        .stdout("* foo\n")
+        .stderr(predicate::str::contains("Warning: Skipped"))
+        .stderr(predicate::str::contains("because it is a directory"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn strict_fails_when_warnings_are_emitted() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject=first\nproject=second\n")
+        .unwrap();
+
+    cli.arg("describe").arg("--strict");
+
+    cli.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Warning: Property 'core/project' is defined more than once, using the last value",
+        ))
+        .stderr(predicate::str::contains("Error: 1 warning(s) emitted in strict mode"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn strict_succeeds_without_warnings() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("list").arg("--strict");
+
+    cli.assert().success().stdout("* foo\n").stderr("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn create_with_auto_name_prints_generated_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    #[rustfmt::skip]
+    cli.arg("create")
+       .arg("--auto-name")
+       .args(["--prefix", "debug"])
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"]);
+
+    let output = cli.assert().success().get_output().stdout.clone();
+    let name = String::from_utf8(output).unwrap();
+    let name = name.trim_end();
+
+    assert!(name.starts_with("debug-"));
+    tmp.child(format!("configurations/config_{}", name))
+        .assert(predicate::path::exists());
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn create_with_auto_name_and_name_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    #[rustfmt::skip]
+    cli.arg("create")
+       .arg("bar")
+       .arg("--auto-name")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"]);
+
+    cli.assert().failure();
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn create_with_record_sdk_version_stores_metadata() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("sdk/VERSION").write_str("400.0.0\n").unwrap();
+
+    #[rustfmt::skip]
+    cli.env("CLOUDSDK_ROOT_DIR", tmp.child("sdk").path())
+       .arg("create")
+       .arg("bar")
+       .args(["--project", "my-project"])
+       .args(["--account", "a.user@example.org"])
+       .args(["--zone", "europe-west1-d"])
+       .arg("--record-sdk-version");
+
+    cli.assert()
+        .success()
+        .stdout("Successfully created configuration 'bar'\n");
+
+    state_child(&tmp, "metadata/config_bar").assert(["[gctx]", "sdk_version=400.0.0", ""].join(NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_warns_when_sdk_version_has_drifted() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    tmp.child("sdk/VERSION").write_str("400.0.0\n").unwrap();
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\nsdk_version=300.0.0\n")
+        .unwrap();
+
+    cli.env("CLOUDSDK_ROOT_DIR", tmp.child("sdk").path())
+        .arg("activate")
+        .arg("foo");
+
+    cli.assert()
+        .success()
+        .stdout("Switched from 'bar' to 'foo'\n")
+        .stderr(predicate::str::contains(
+            "Configuration 'foo' was created with gcloud SDK 300.0.0 but 400.0.0 is installed",
+        ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_does_not_warn_for_similar_sdk_version() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    tmp.child("sdk/VERSION").write_str("400.0.0\n").unwrap();
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\nsdk_version=390.1.0\n")
+        .unwrap();
+
+    cli.env("CLOUDSDK_ROOT_DIR", tmp.child("sdk").path())
+        .arg("activate")
+        .arg("foo");
+
+    cli.assert().success().stderr("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn current_warns_about_environment_overrides() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject=my-project\n")
+        .unwrap();
+
+    cli.env("CLOUDSDK_CORE_PROJECT", "other-project")
+        .env("CLOUDSDK_ACTIVE_CONFIG_NAME", "bar")
+        .arg("current");
+
+    cli.assert()
+        .success()
+        .stdout("foo\n")
+        .stderr(predicate::str::contains(
+            "Warning: CLOUDSDK_ACTIVE_CONFIG_NAME=bar overrides the active configuration in this terminal",
+        ))
+        .stderr(predicate::str::contains(
+            "Warning: CLOUDSDK_CORE_PROJECT=other-project overrides core/project (configured as 'my-project')",
+        ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_warns_about_environment_overrides() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config("foo")
+        .with_config_activated("bar")
+        .build()
+        .unwrap();
+
+    cli.env("CLOUDSDK_COMPUTE_ZONE", "europe-west1-d")
+        .arg("activate")
+        .arg("foo");
+
+    cli.assert()
+        .success()
+        .stdout("Switched from 'bar' to 'foo'\n")
+        .stderr(predicate::str::contains(
+            "Warning: CLOUDSDK_COMPUTE_ZONE=europe-west1-d overrides compute/zone (not configured)",
+        ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_new_configuration_succeeds() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let contents = "[core]\nproject = my-project\n\n[auth]\ndisable_credentials = true\n";
+    tmp.child("exported/config_bar").write_str(contents).unwrap();
+
+    cli.arg("import").arg(tmp.child("exported/config_bar").path());
+
+    cli.assert()
+        .success()
+        .stdout("Successfully imported configuration 'bar'\n");
+    tmp.child("configurations/config_bar").assert(contents);
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_from_stdin_requires_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["import", "-"]).write_stdin("[core]\nproject = my-project\n");
+
+    cli.assert()
+        .failure()
+        .stderr("Error: Use --name to name a configuration imported from stdin\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_from_stdin_succeeds() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let contents = "[core]\nproject = my-project\n";
+    cli.args(["import", "-", "--name", "bar"]).write_stdin(contents);
+
+    cli.assert()
+        .success()
+        .stdout("Successfully imported configuration 'bar'\n");
+    tmp.child("configurations/config_bar").assert(contents);
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn export_writes_configuration_to_stdout() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let contents = "# exported as-is\n[core]\nproject = my-project\n\n[auth]\ndisable_credentials = true\n";
+    tmp.child("configurations/config_foo").write_str(contents).unwrap();
+
+    cli.args(["export", "foo"]);
+
+    cli.assert().success().stdout(contents);
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn export_writes_configuration_to_file() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let contents = "[core]\nproject = my-project\n";
+    tmp.child("configurations/config_foo").write_str(contents).unwrap();
+
+    cli.arg("export").arg("foo").arg(tmp.child("config_foo").path());
+
+    cli.assert().success().stdout("");
+    tmp.child("config_foo").assert(contents);
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn archive_export_then_import_restores_store() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    let contents = "[core]\nproject = my-project\n";
+    tmp.child("configurations/config_bar").write_str(contents).unwrap();
+
+    cli.arg("archive").arg("export").arg(tmp.child("backup.tar").path());
+    cli.assert().success().stdout(format!(
+        "Exported 2 configuration(s) to {}\n",
+        tmp.child("backup.tar").path().display()
+    ));
+
+    let (mut cli, other) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("baz")
+        .build()
+        .unwrap();
+
+    cli.arg("archive").arg("import").arg(tmp.child("backup.tar").path());
+    cli.assert().success().stdout(
+        "Successfully imported configuration 'bar'\n\
+         Successfully imported configuration 'foo'\n\
+         Configuration 'bar' is now active\n",
+    );
+
+    other.child("configurations/config_bar").assert(contents);
+    other.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+    other.close().unwrap();
+}
+
+#[test]
+fn import_existing_configuration_without_policy_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("import.ini").write_str("[core]\nproject=imported\n").unwrap();
+
+    cli.arg("import")
+        .arg(tmp.child("import.ini").path())
+        .args(["--name", "foo"]);
+
+    cli.assert()
+        .failure()
+        .stderr("Error: A configuration named 'foo' already exists. Use --on-conflict to choose how to resolve it\n");
+    tmp.child("configurations/config_foo").assert("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_existing_configuration_with_keep_skips_import() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("import.ini").write_str("[core]\nproject=imported\n").unwrap();
+
+    cli.arg("import")
+        .arg(tmp.child("import.ini").path())
+        .args(["--name", "foo", "--on-conflict", "keep"]);
+
+    cli.assert().success().stdout("Kept existing configuration 'foo'\n");
+    tmp.child("configurations/config_foo").assert("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_existing_configuration_with_overwrite_replaces_it() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("import.ini").write_str("[core]\nproject=imported\n").unwrap();
+
+    cli.arg("import")
+        .arg(tmp.child("import.ini").path())
+        .args(["--name", "foo", "--on-conflict", "overwrite"]);
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Successfully imported configuration 'foo'",
+        "The previous version of 'foo' was backed up, restore it with 'gctx restore foo'",
+        "",
+    ].join("\n"));
+    tmp.child("configurations/config_foo")
+        .assert("[core]\nproject=imported\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn import_existing_configuration_with_rename_uses_new_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("import.ini").write_str("[core]\nproject=imported\n").unwrap();
+
+    cli.arg("import")
+        .arg(tmp.child("import.ini").path())
+        .args(["--name", "foo", "--on-conflict", "rename"]);
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Successfully imported configuration 'foo-"));
+    tmp.child("configurations/config_foo").assert("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn normalize_all_reports_changed_configurations() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject = my-project\naccount = a.user@example.org\n")
+        .unwrap();
+
+    cli.args(["normalize", "--all"]);
+
+    cli.assert().success().stdout(
+        "Configuration 'bar' is already normalized\n\
+         Normalized configuration 'foo'\n",
+    );
+    tmp.child("configurations/config_foo").assert(format!(
+        "[core]{0}account=a.user@example.org{0}project=my-project{0}",
+        NEWLINE
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn normalize_without_name_or_all_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("normalize");
+
+    cli.assert().failure();
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn hook_runs_after_activation_with_run_hooks() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_bar")
+        .write_str("[gctx]\non_activate_command=echo hook ran\n")
+        .unwrap();
+
+    cli.args(["--run-hooks", "activate", "bar"]);
+
+    cli.assert()
+        .success()
+        .stdout("Switched from 'foo' to 'bar'\n")
+        .stderr(predicate::str::contains("hook ran"));
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn hook_is_skipped_without_run_hooks() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_bar")
+        .write_str("[gctx]\non_activate_command=echo hook ran\n")
+        .unwrap();
+
+    cli.args(["activate", "bar"]);
+
+    cli.assert().success().stderr("");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn hook_failure_warns_without_reverting_activation() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_bar")
+        .write_str("[gctx]\non_activate_command=exit 3\n")
+        .unwrap();
+
+    cli.args(["--run-hooks", "activate", "bar"]);
+
+    cli.assert().success().stderr(predicate::str::contains(
+        "Warning: On-activate command for 'bar' failed",
+    ));
+    tmp.child("active_config").assert("bar");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn hook_sets_on_activate_command() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["hook", "foo", "echo hook ran"]);
+
+    cli.assert().success().stdout("Set the on-activate command for 'foo'\n");
+    state_child(&tmp, "metadata/config_foo").assert(format!("[gctx]{0}on_activate_command=echo hook ran{0}", NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn inspect_lists_configurations_in_other_store() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("inspect").arg("--store").arg(tmp.path());
+
+    cli.assert().success().stdout(format!(
+        "Configuration store at {} (read-only)\n* bar\n  foo\n",
+        tmp.path().display()
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_compare_shows_differences_between_stores() {
+    let (mut cli, left) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .with_config("shared")
+        .build()
+        .unwrap();
+
+    let (_, right) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("baz")
+        .build()
+        .unwrap();
+
+    right
+        .child("configurations/config_shared")
+        .write_str("[core]\nproject=other\n")
+        .unwrap();
+
+    cli.arg("list")
+        .arg("--compare")
+        .arg(format!("{}:{}", left.path().display(), right.path().display()));
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "< left only  bar",
+        "> right only baz",
+        "  same       foo",
+        "  different  shared",
+        "",
+    ].join("\n"));
+
+    left.close().unwrap();
+    right.close().unwrap();
+}
+
+#[test]
+fn list_compare_requires_two_stores() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["list", "--compare", "work"]);
+
+    cli.assert()
+        .failure()
+        .stderr("Error: Expected two configuration stores in the form LEFT:RIGHT, got 'work'\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_uses_configuration_without_activating_it() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.args(["run", "bar", "--", "sh", "-c", "echo $CLOUDSDK_ACTIVE_CONFIG_NAME"]);
+
+    cli.assert().success().stdout("bar\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_dangerous_configuration_requires_yes_prod() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_bar")
+        .write_str("[gctx]\ntags=prod\n")
+        .unwrap();
+
+    cli.args(["run", "bar", "--", "sh", "-c", "echo ran"]);
+
+    cli.assert().failure().stdout("").stderr(
+        "Error: Configuration 'bar' is dangerous, e.g. tagged prod. Use --yes-prod to run the command with it\n",
+    );
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_dangerous_configuration_with_yes_prod_succeeds() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_bar")
+        .write_str("[gctx]\ntags=prod\n")
+        .unwrap();
+
+    cli.args(["run", "bar", "--yes-prod", "--", "sh", "-c", "echo ran"]);
+
+    cli.assert().success().stdout("ran\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_ephemeral_never_saves_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args([
+        "run",
+        "--ephemeral",
+        "--project",
+        "my-project",
+        "--zone",
+        "europe-west1-d",
+        "--",
+        "sh",
+        "-c",
+        "echo $CLOUDSDK_CORE_PROJECT $CLOUDSDK_COMPUTE_ZONE",
+    ]);
+
+    cli.assert().success().stdout("my-project europe-west1-d\n");
+    assert_eq!(
+        std::fs::read_dir(tmp.child("configurations").path()).unwrap().count(),
+        1
+    );
+    tmp.child("gctx").assert(predicate::path::missing());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_adds_configuration_flag_to_gcloud() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    let gcloud = tmp.child("bin/gcloud");
+    gcloud.write_str("#!/bin/sh\necho \"$@\"\n").unwrap();
+    std::fs::set_permissions(gcloud.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    cli.args(["run", "bar", "--"])
+        .arg(gcloud.path())
+        .args(["config", "list"]);
+
+    cli.assert().success().stdout("--configuration=bar config list\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn activate_gke_fetches_cluster_credentials() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_bar")
+        .write_str("[container]\ncluster = my-cluster\n[compute]\nzone = europe-west1-d\n")
+        .unwrap();
+
+    let gcloud = tmp.child("bin/gcloud");
+    gcloud.write_str("#!/bin/sh\necho \"gcloud $@\"\n").unwrap();
+    std::fs::set_permissions(gcloud.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!(
+        "{}:{}",
+        tmp.child("bin").path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    cli.env("PATH", path).args(["activate", "bar", "--gke"]);
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Switched from 'foo' to 'bar'",
+        "gcloud --configuration=bar container clusters get-credentials my-cluster --zone=europe-west1-d",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_gke_requires_cluster() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.args(["activate", "bar", "--gke"]);
+
+    cli.assert().failure().stdout("").stderr(
+        "Error: Configuration 'bar' has no container/cluster property, which --gke needs to find the cluster\n",
+    );
+
+    // nothing is switched when the cluster can't be found
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn run_passes_on_exit_code() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["run", "foo", "--", "sh", "-c", "exit 3"]);
+
+    cli.assert().code(3);
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn suggest_region_prefers_org_default() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("suggest-region")
+        .env("GCTX_DEFAULT_REGION", "europe-west4")
+        .env("TZ", "Asia/Tokyo");
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Region: europe-west4",
+        "Zone:   europe-west4-a",
+        "Suggested based on your organisation default",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn suggest_region_uses_timezone() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("suggest-region").env("TZ", "Europe/Lisbon");
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Region: europe-southwest1",
+        "Zone:   europe-southwest1-a",
+        "Suggested based on your timezone (Europe/Lisbon)",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn refresh_data_updates_suggested_zones() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    // europe-southwest1-a has gone away, so the first remaining zone should be suggested instead
+    let zones = r#"[
+        {"name": "europe-southwest1-c", "region": "https://www.googleapis.com/compute/v1/projects/p/regions/europe-southwest1"},
+        {"name": "europe-southwest1-b", "region": "https://www.googleapis.com/compute/v1/projects/p/regions/europe-southwest1"},
+        {"name": "us-central1-a", "region": "https://www.googleapis.com/compute/v1/projects/p/regions/us-central1"}
+    ]"#;
+
+    let gcloud = tmp.child("bin/gcloud");
+    gcloud
+        .write_str(&format!("#!/bin/sh\ncat <<'EOF'\n{}\nEOF\n", zones))
+        .unwrap();
+    std::fs::set_permissions(gcloud.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!(
+        "{}:{}",
+        tmp.child("bin").path().display(),
+        std::env::var("PATH").unwrap()
+    );
+    cli.env("PATH", path).arg("refresh-data");
+
+    cli.assert().success().stdout(format!(
+        "Refreshed 2 regions in {}\n",
+        tmp.child("state/data/regions.json").path().display()
+    ));
+
+    let saved = std::fs::read_to_string(tmp.child("state/data/regions.json").path()).unwrap();
+    let saved: serde_json::Value = serde_json::from_str(&saved).unwrap();
+    let regions = &saved["items"];
+
+    assert_eq!(saved["version"], 1);
+    assert_eq!(regions.as_array().unwrap().len(), 2);
+    assert_eq!(regions[0]["name"], "us-central1");
+    assert_eq!(regions[1]["name"], "europe-southwest1");
+    assert_eq!(regions[1]["zone"], "europe-southwest1-b");
+    assert_eq!(
+        regions[1]["timezones"],
+        serde_json::json!(["Europe/Madrid", "Europe/Lisbon"])
+    );
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn suggest_region_uses_refreshed_data() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("state/data/regions.json")
+        .write_str(r#"{"version": 1, "items": [{"name": "europe-west12", "zone": "europe-west12-b", "timezones": ["Europe/Lisbon"], "countries": []}]}"#)
+        .unwrap();
+
+    cli.arg("suggest-region").env("TZ", "Europe/Lisbon");
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "Region: europe-west12",
+        "Zone:   europe-west12-b",
+        "Suggested based on your timezone (Europe/Lisbon)",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn suggest_region_ignores_data_in_other_formats() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("state/data/regions.json")
+        .write_str(r#"{"version": 999, "regions": {}}"#)
+        .unwrap();
+
+    cli.arg("suggest-region").env("TZ", "Europe/Lisbon");
+
+    #[rustfmt::skip]
+    cli.assert().success().stderr("").stdout([
+        "Region: europe-southwest1",
+        "Zone:   europe-southwest1-a",
+        "Suggested based on your timezone (Europe/Lisbon)",
+        "",
+    ].join("\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn store_env_var_pointing_to_file_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let file = tmp.child("active_config");
+    cli.env("CLOUDSDK_CONFIG", file.path()).arg("list");
+
+    cli.assert().failure().stderr(format!(
+        "Error: CLOUDSDK_CONFIG is set to '{}' which isn't a directory. It must be the gcloud configuration directory, e.g. ~/.config/gcloud\n",
+        file.path().display()
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn store_env_var_pointing_to_missing_path_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let missing = tmp.child("missing");
+    cli.env("CLOUDSDK_CONFIG", missing.path()).arg("list");
+
+    cli.assert().failure().stderr(format!(
+        "Error: CLOUDSDK_CONFIG is set to '{}' which doesn't exist. Create the directory, or unset CLOUDSDK_CONFIG to use the default location\n",
+        missing.path().display()
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn create_if_missing_initialises_store() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    let missing = tmp.child("missing");
+    cli.env("CLOUDSDK_CONFIG", missing.path())
+        .args(["--create-if-missing", "list"]);
+
+    cli.assert().success().stdout("* default\n");
+    missing
+        .child("configurations/config_default")
+        .assert(predicate::path::exists());
+    missing.child("active_config").assert("default");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn read_only_prevents_modification() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["--read-only", "delete", "foo"]);
+
+    cli.assert().failure().stderr(format!(
+        "Error: The configuration store at {} is read-only\n",
+        tmp.path().display()
+    ));
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn pin_records_pinned_metadata() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["pin", "foo"]);
+
+    cli.assert().success().stdout("Pinned configuration 'foo'\n");
+    state_child(&tmp, "metadata/config_foo").assert(format!("[gctx]{0}pinned=true{0}", NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn pin_prevents_modification() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\npinned=true\n")
+        .unwrap();
+
+    cli.args(["delete", "foo"]);
+
+    cli.assert().failure().stderr(
+        "Error: Configuration 'foo' is pinned. Use --unpin-first or unpin it with 'gctx unpin foo' to modify it\n",
+    );
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn unpin_first_allows_modification() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\npinned=true\n")
+        .unwrap();
+
+    cli.args(["--unpin-first", "delete", "foo"]);
+
+    cli.assert().success();
+    tmp.child("configurations/config_foo")
+        .assert(predicate::path::missing());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_names_only_prints_plain_names() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["list", "--names-only"]);
+
+    cli.assert().success().stdout("bar\nfoo\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_filters_by_project() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("baz")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject=shared-project\n")
+        .unwrap();
+    tmp.child("configurations/config_bar")
+        .write_str("[core]\nproject=shared-project\n")
+        .unwrap();
+    tmp.child("configurations/config_baz")
+        .write_str("[core]\nproject=other-project\n")
+        .unwrap();
+
+    cli.args(["list", "--names-only", "--project", "shared-project"]);
+
+    cli.assert().success().stdout("bar\nfoo\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_filters_by_name_and_zone() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("dev-europe")
+        .with_config("dev-us")
+        .with_config("prod-europe")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_dev-europe")
+        .write_str("[compute]\nzone=europe-west1-d\n")
+        .unwrap();
+    tmp.child("configurations/config_dev-us")
+        .write_str("[compute]\nzone=us-central1-a\n")
+        .unwrap();
+    tmp.child("configurations/config_prod-europe")
+        .write_str("[compute]\nzone=europe-west1-d\n")
+        .unwrap();
+
+    cli.args(["list", "--names-only", "--name", "dev-*", "--zone", "europe-west1-d"]);
+
+    cli.assert().success().stdout("dev-europe\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_stdin_describes_each_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject=foo-project\n")
+        .unwrap();
+    tmp.child("configurations/config_bar")
+        .write_str("[core]\nproject=bar-project\n")
+        .unwrap();
+
+    cli.args(["describe", "--stdin"]).write_stdin("foo\n\nbar\n");
+
+    cli.assert().success().stdout(format!(
+        "# foo\n[core]{0}project=foo-project{0}# bar\n[core]{0}project=bar-project{0}",
+        NEWLINE
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn delete_stdin_reports_failures_together() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .with_config("baz")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\npinned=true\n")
+        .unwrap();
+
+    cli.args(["delete", "--stdin", "--yes", "--fallback", "baz"])
+        .write_stdin("foo\nbar\n");
+
+    cli.assert()
+        .failure()
+        .stdout("Successfully deleted configuration 'bar'\n")
+        .stderr(
+            "Error: 'foo': Configuration 'foo' is pinned\n\
+             Error: 1 of 2 configuration(s) failed\n",
+        );
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
+    tmp.child("active_config").assert("baz");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn delete_stdin_including_active_configuration_deletes_nothing() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["delete", "--stdin", "--yes"]).write_stdin("foo\nbar\n");
+
+    cli.assert()
+        .failure()
+        .stdout("")
+        .stderr("Error: Unable to delete the configuration because it is currently active\n");
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn delete_several_configurations() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .with_config("baz")
+        .build()
+        .unwrap();
+
+    cli.args(["delete", "foo", "bar", "--fallback", "baz"]);
+
+    cli.assert().success().stdout(
+        "Successfully deleted configuration 'foo'\n\
+         Successfully deleted configuration 'bar'\n",
+    );
+    tmp.child("configurations/config_foo")
+        .assert(predicate::path::missing());
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
+    tmp.child("active_config").assert("baz");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn delete_several_with_unknown_name_deletes_nothing() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["delete", "foo", "unknown"]);
+
+    cli.assert()
+        .failure()
+        .stderr("Error: Unable to find configuration 'unknown'\n");
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn delete_stdin_requires_yes() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["delete", "--stdin"]).write_stdin("foo\n");
+
+    cli.assert().failure();
+    tmp.child("configurations/config_foo").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn activate_stdin_activates_single_name() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["activate", "--stdin"]).write_stdin("foo\n");
+
+    cli.assert().success().stdout("Switched from 'bar' to 'foo'\n");
+    tmp.child("active_config").assert("foo");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_shows_unknown_sections() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject = my-project\n\n[auth]\nimpersonate_service_account = sa@example.org\n")
+        .unwrap();
+
+    cli.args(["describe", "foo"]);
+
+    cli.assert().success().stdout(format!(
+        "[core]{0}project=my-project{0}[auth]{0}impersonate_service_account=sa@example.org{0}",
+        NEWLINE
+    ));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn tag_adds_and_removes_tags() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\ntags=team-a\n")
+        .unwrap();
+
+    cli.args(["tag", "foo", "prod", "team-a"]);
+
+    cli.assert().success().stdout("team-a\nprod\n");
+    state_child(&tmp, "metadata/config_foo").assert(format!("[gctx]{0}tags=team-a,prod{0}", NEWLINE));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn tags_written_inside_the_store_are_migrated() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    // earlier versions kept their files inside the gcloud configuration store
+    tmp.child("gctx/metadata/config_foo")
+        .write_str("[gctx]\ntags=team-a\n")
+        .unwrap();
+
+    cli.args(["tag", "foo"]);
+
+    cli.assert().success().stdout("team-a\n");
+    tmp.child("gctx").assert(predicate::path::missing());
+    state_child(&tmp, "metadata/config_foo").assert("[gctx]\ntags=team-a\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cleanup_advise_reports_clutter() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "backups/config_gone/1")
+        .write_str("[core]\naccount=a.user@example.org\n")
+        .unwrap();
+    state_child(&tmp, "metadata/config_gone")
+        .write_str("[gctx]\ntags=prod\n")
+        .unwrap();
+    tmp.child("legacy_credentials/a.user@example.org/adc.json")
+        .write_str(&"x".repeat(2048))
+        .unwrap();
+
+    cli.args(["cleanup", "--advise"]);
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "2.0 KiB can be reclaimed:",
+        "  old backups: 1 entry, 34 B",
+        "  metadata for deleted configurations: 1 entry, 17 B",
+        "  credentials only used by deleted configurations: 1 entry, 2.0 KiB",
+        "",
+    ].join("\n"));
+
+    state_child(&tmp, "backups/config_gone/1").assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cleanup_yes_removes_clutter() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_gone")
+        .write_str("[gctx]\ntags=prod\n")
+        .unwrap();
+
+    cli.args(["cleanup", "--yes"]);
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "17 B can be reclaimed:",
+        "  metadata for deleted configurations: 1 entry, 17 B",
+        "Removed metadata for deleted configurations",
+        "",
+    ].join("\n"));
+
+    state_child(&tmp, "metadata/config_gone").assert(predicate::path::missing());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cleanup_yes_never_removes_credentials() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "backups/config_gone/1")
+        .write_str("[core]\naccount=a.user@example.org\n")
+        .unwrap();
+    tmp.child("legacy_credentials/a.user@example.org/adc.json")
+        .write_str("{}")
+        .unwrap();
+
+    cli.args(["cleanup", "--yes"]);
+
+    #[rustfmt::skip]
+    cli.assert().success().stdout([
+        "36 B can be reclaimed:",
+        "  old backups: 1 entry, 34 B",
+        "  credentials only used by deleted configurations: 1 entry, 2 B",
+        "Removed old backups",
+        "Skipped credentials only used by deleted configurations, which are only removed after confirming each account",
+        "",
+    ].join("\n"));
+
+    tmp.child("legacy_credentials/a.user@example.org/adc.json")
+        .assert(predicate::path::exists());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cleanup_without_clutter() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("cleanup");
+
+    cli.assert().success().stdout("Nothing to clean up\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn prompt_marks_dangerous_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    state_child(&tmp, "metadata/config_foo")
+        .write_str("[gctx]\ntags=prod\n")
+        .unwrap();
+
+    cli.arg("prompt");
+
+    cli.assert().success().stdout("foo !\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn prompt_prints_normal_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("prompt");
+
+    cli.assert().success().stdout("foo\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn prompt_ignores_invalid_name_from_environment() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("prompt").env("CLOUDSDK_ACTIVE_CONFIG_NAME", "../foo");
+
+    cli.assert().success().stdout("");
+
+    tmp.close().unwrap();
+}
+
+#[cfg(not(feature = "online"))]
+#[test]
+fn create_pick_project_requires_online_feature() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.args([
+        "create",
+        "bar",
+        "--pick-project",
+        "--account",
+        "a.user@example.org",
+        "--zone",
+        "europe-west1-d",
+    ]);
+
+    cli.assert().failure().stderr(
+        "Error: Picking a project requires gctx to be built with the 'online' feature, use --project instead\n",
+    );
+    tmp.child("configurations/config_bar")
+        .assert(predicate::path::missing());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_json_output_schema() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    cli.args(["list", "--output", "json"]);
+
+    let output = cli.assert().success().get_output().stdout.clone();
+    insta::assert_snapshot!(String::from_utf8(output).unwrap());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_json_output_without_active_configuration() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("active_config").write_str("NONE").unwrap();
+    cli.args(["list", "--output", "json"]);
+
+    let output = cli.assert().success().get_output().stdout.clone();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(json["schema_version"], 2);
+    assert!(json["active"].is_null());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_json_output_schema() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject = my-project\naccount = a.user@example.org\n[compute]\nzone = europe-west1-d\n")
+        .unwrap();
+
+    cli.args(["describe", "foo", "--output", "json"]);
+
+    let output = cli.assert().success().get_output().stdout.clone();
+    insta::assert_snapshot!(String::from_utf8(output).unwrap());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_all_json_output_schema() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("[core]\nproject = foo-project\n")
+        .unwrap();
+    tmp.child("configurations/config_bar")
+        .write_str("[core]\nproject = bar-project\n[compute]\nzone = europe-west1-d\n")
+        .unwrap();
+
+    cli.args(["describe", "--all", "--output", "json"]);
+
+    let output = cli.assert().success().get_output().stdout.clone();
+    insta::assert_snapshot!(String::from_utf8(output).unwrap());
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn describe_all_warns_about_unreadable_configurations() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("bar")
+        .with_config("foo")
+        .build()
+        .unwrap();
+
+    tmp.child("configurations/config_foo")
+        .write_str("not a configuration")
+        .unwrap();
+
+    cli.args(["describe", "--all"]);
+
+    cli.assert()
+        .success()
+        .stdout("# bar\n")
+        .stderr(predicate::str::starts_with("Warning: Unable to describe 'foo'"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_sorts_numbers_naturally() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("env-1")
+        .with_config("env-10")
+        .with_config("env-2")
+        .build()
+        .unwrap();
+
+    cli.args(["list", "--names-only"]);
+
+    cli.assert().success().stdout("env-1\nenv-2\nenv-10\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn list_sort_lexical() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("env-1")
+        .with_config("env-10")
+        .with_config("env-2")
+        .build()
+        .unwrap();
+
+    cli.args(["list", "--names-only", "--sort", "lexical"]);
+
+    cli.assert().success().stdout("env-1\nenv-10\nenv-2\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cache_rebuild_writes_names() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.args(["cache", "rebuild"]);
+
+    cli.assert().success().stdout("Rebuilt the configuration name cache\n");
+    state_child(&tmp, "cache/names").assert("bar\nfoo\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cache_names_uses_up_to_date_cache() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    // written after the configurations so it's up to date, and deliberately different to prove it's used
+    state_child(&tmp, "cache/names").write_str("cached\n").unwrap();
+
+    cli.args(["cache", "names"]);
+
+    cli.assert().success().stdout("cached\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn cache_names_without_cache_scans_store() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .with_config("bar")
+        .build()
+        .unwrap();
+
+    cli.args(["cache", "names"]);
+
+    cli.assert().success().stdout("bar\nfoo\n");
+    state_child(&tmp, "cache/names").assert("bar\nfoo\n");
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn help_with_command_shows_command_help() {
+    let (mut cli, tmp) = TempConfigurationStore::new().unwrap().build().unwrap();
+
+    cli.arg("help").arg("list");
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::contains("gctx list [OPTIONS]"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn help_with_topic_shows_example() {
+    let (mut cli, tmp) = TempConfigurationStore::new().unwrap().build().unwrap();
+
+    cli.arg("help").arg("ci");
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Using gctx in CI (ci)"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn help_with_unknown_topic_fails() {
+    let (mut cli, tmp) = TempConfigurationStore::new().unwrap().build().unwrap();
+
+    cli.arg("help").arg("unknown");
+
+    cli.assert().failure().stderr(
+        "Error: No help for 'unknown', expected a command or one of the example topics: onboarding, prod-staging, ci\n",
+    );
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn examples_as_markdown() {
+    let (mut cli, tmp) = TempConfigurationStore::new().unwrap().build().unwrap();
+
+    cli.args(["examples", "onboarding", "--output", "markdown"]);
+
+    cli.assert()
+        .success()
+        .stdout(predicate::str::starts_with("## Onboarding a new project\n"))
+        .stdout(predicate::str::contains("```bash\n# Create a configuration"))
+        .stdout(predicate::str::ends_with("```\n"));
+
+    tmp.close().unwrap();
+}
+
+#[test]
+fn onboard_requires_a_terminal() {
+    let (mut cli, tmp) = TempConfigurationStore::new()
+        .unwrap()
+        .with_config_activated("foo")
+        .build()
+        .unwrap();
+
+    cli.arg("onboard").write_stdin("");
+
+    cli.assert()
+        .failure()
+        .stderr("Error: gctx onboard is interactive, run it from a terminal\n");
+
+    tmp.close().unwrap();
+}
@@ -1,7 +1,9 @@
 use assert_cmd::Command;
-use assert_fs::{prelude::*, TempDir};
+use assert_fs::{fixture::ChildPath, prelude::*, TempDir};
+use gcloud_ctx::StateDirectory;
 
 const CLOUDSDK_CONFIG: &str = "CLOUDSDK_CONFIG";
+const GCTX_STATE_DIR: &str = "GCTX_STATE_DIR";
 
 pub struct TempConfigurationStore {
     active: Option<String>,
@@ -22,7 +24,16 @@ impl TempConfigurationStore {
         std::fs::create_dir(dir.path().join("configurations"))?;
 
         let mut command = Command::cargo_bin("gctx")?;
+
+        // isolate tests from any gctx/gcloud settings in the environment running them
+        for (name, _) in std::env::vars() {
+            if name.starts_with("CLOUDSDK_") || name.starts_with("GCTX_") {
+                command.env_remove(name);
+            }
+        }
+
         command.env(CLOUDSDK_CONFIG, dir.path());
+        command.env(GCTX_STATE_DIR, dir.path().join("state"));
 
         if let Some(active) = &self.active {
             dir.child("active_config").write_str(active)?;
@@ -47,3 +58,16 @@ impl TempConfigurationStore {
         self
     }
 }
+
+/// A file in the gctx state directory of a temporary store
+pub fn state_child(dir: &TempDir, path: &str) -> ChildPath {
+    let state = dir
+        .path()
+        .join("state")
+        .join("stores")
+        .join(StateDirectory::key(dir.path()));
+    ChildPath::new(state.join(path))
+}
+
+/// Line ending used by gctx when writing properties on this platform
+pub const NEWLINE: &str = if cfg!(windows) { "\r\n" } else { "\n" };